@@ -127,13 +127,7 @@ fn criterion_benchmark(c: &mut Criterion) {
     }
 
     const SIGMA: usize = 1;
-    fn prover(
-        num_blocks: usize,
-    ) -> (
-        Sha256Circuit,
-        Proof<u32, Keccak256, SIGMA>,
-        Vec<GF2Word<u32>>,
-    ) {
+    fn prover(num_blocks: usize) -> (Sha256Circuit, Proof<u32, Keccak256>, Vec<GF2Word<u32>>) {
         let mut rng = thread_rng();
 
         let string =
@@ -162,10 +156,10 @@ fn criterion_benchmark(c: &mut Criterion) {
 
     fn verifier(
         circuit: &Sha256Circuit,
-        proof: &Proof<u32, Keccak256, SIGMA>,
+        proof: &Proof<u32, Keccak256>,
         output: &Vec<GF2Word<u32>>,
     ) {
-        Verifier::<u32, ChaCha20Rng, Keccak256>::verify(proof, circuit, output).unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(proof, circuit, output).unwrap();
     }
 
     let num_blocks_range = 1..=10;
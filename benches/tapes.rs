@@ -0,0 +1,55 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{BenchmarkId, Criterion};
+use rand_chacha::ChaCha20Rng;
+use zkboo::{
+    expander::tape_label,
+    tape::{expand_tapes, Tape},
+};
+
+// compare with `cargo bench --bench tapes --features parallel`
+fn criterion_benchmark(c: &mut Criterion) {
+    fn expand(len: usize) {
+        let keys = [
+            ([1u8; 32], tape_label(0, 0)),
+            ([2u8; 32], tape_label(0, 1)),
+            ([3u8; 32], tape_label(0, 2)),
+        ];
+        for mut tape in expand_tapes::<u32, ChaCha20Rng, 3>(keys, len) {
+            for _ in 0..len {
+                tape.read_next();
+            }
+        }
+    }
+
+    fn read(len: usize) {
+        let mut tape = Tape::<u32>::from_key::<ChaCha20Rng>([1u8; 32], &tape_label(0, 0), len);
+        for _ in 0..len {
+            tape.read_next();
+        }
+    }
+
+    let lens = [1 << 12, 1 << 16, 1 << 20];
+
+    let mut group = c.benchmark_group("tapes-expand-3");
+    group.sample_size(10);
+    for len in lens {
+        group.bench_with_input(BenchmarkId::from_parameter(len), &len, |b, &len| {
+            b.iter(|| expand(len))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("tape-read");
+    group.sample_size(10);
+    for len in lens {
+        group.bench_with_input(BenchmarkId::from_parameter(len), &len, |b, &len| {
+            b.iter(|| read(len))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
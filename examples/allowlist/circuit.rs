@@ -0,0 +1,184 @@
+//! The statement proven at login.
+//!
+//! The witness is a credential secret followed by the path of its leaf in the
+//! allowlist. The circuit outputs the root reached from `SHA-256(secret)` along
+//! the path, and the nullifier of the secret in the scope of the service:
+//!
+//! ```text
+//! witness = secret: [u8; 32] || (sibling: [u8; 32] || is_right: u8) * depth
+//! output  = root: 8 words || nullifier: 8 words
+//! ```
+
+use zkboo::{
+    circuit::{Circuit, Output, TwoThreeDecOutput},
+    error::Error,
+    gadgets::{
+        merkle::{
+            merkle_num_of_mul_gates, merkle_root_verify, mpc_merkle_root, MerklePath, Node,
+            PATH_LEVEL_LEN,
+        },
+        nullifier::{nullifier, NullifierGadget},
+        sha256::{
+            digest_to_bytes, mpc_sha256_padded, mpc_sha256_padded_verify, sha256,
+            sha256_num_of_mul_gates, PaddingTemplate,
+        },
+    },
+    gf2_word::GF2Word,
+    party::Party,
+    statement::{CircuitFingerprint, Statement},
+};
+
+pub const SECRET_LEN: usize = 32;
+
+/// Soundness of login proofs, in bits.
+pub const SIGMA: usize = 40;
+
+/// Name under which statements refer to `AllowlistCircuit`.
+pub const CIRCUIT_NAME: &str = "allowlist-login";
+
+/// Separates login proofs from other proofs with the same circuit.
+const DOMAIN: &[u8] = b"zkboo allowlist login";
+
+pub fn leaf_of(secret: &[u8]) -> Node {
+    digest_to_bytes(&sha256(secret)).try_into().unwrap()
+}
+
+pub fn to_words(node: &Node) -> Vec<GF2Word<u32>> {
+    node.chunks(4)
+        .map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap()).into())
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct AllowlistCircuit {
+    depth: usize,
+    scope: Vec<u8>,
+    leaf_template: PaddingTemplate,
+    nullifier: NullifierGadget,
+}
+
+impl AllowlistCircuit {
+    pub fn new(depth: usize, scope: &[u8]) -> Self {
+        Self {
+            depth,
+            scope: scope.to_vec(),
+            leaf_template: PaddingTemplate::new(SECRET_LEN, &[]),
+            nullifier: NullifierGadget::new(SECRET_LEN, scope),
+        }
+    }
+
+    pub fn witness(secret: &[u8], path: &MerklePath) -> Vec<u8> {
+        [secret, &path.to_witness()].concat()
+    }
+
+    /// The statement of a login with `nullifier` to a service whose allowlist
+    /// has `root`, bound to `message`.
+    pub fn statement(&self, root: &Node, nullifier: &Node, message: &[u8]) -> Statement<u32> {
+        Statement::new::<SIGMA>(
+            CircuitFingerprint::of(CIRCUIT_NAME, self),
+            [to_words(root), to_words(nullifier)].concat(),
+            &[DOMAIN, message].concat(),
+        )
+    }
+}
+
+impl Circuit<u32> for AllowlistCircuit {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+        assert_eq!(input.len(), self.party_input_len());
+        let (secret, path) = input.split_at(SECRET_LEN);
+
+        let path = MerklePath {
+            siblings: path
+                .chunks(PATH_LEVEL_LEN)
+                .map(|level| level[..32].try_into().unwrap())
+                .collect(),
+            is_right: path
+                .chunks(PATH_LEVEL_LEN)
+                .map(|level| level[32] & 1 == 1)
+                .collect(),
+        };
+        let root = path.root(&leaf_of(secret));
+
+        [to_words(&root), nullifier(secret, &self.scope)].concat()
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<u32>,
+        p2: &mut Party<u32>,
+        p3: &mut Party<u32>,
+    ) -> TwoThreeDecOutput<u32> {
+        let (w1, w2, w3) = (
+            p1.view.input.clone(),
+            p2.view.input.clone(),
+            p3.view.input.clone(),
+        );
+        let (secret_p1, path_p1) = w1.split_at(SECRET_LEN);
+        let (secret_p2, path_p2) = w2.split_at(SECRET_LEN);
+        let (secret_p3, path_p3) = w3.split_at(SECRET_LEN);
+
+        let leaf = mpc_sha256_padded(
+            &self.leaf_template.apply(secret_p1),
+            &self.leaf_template.apply(secret_p2),
+            &self.leaf_template.apply(secret_p3),
+            p1,
+            p2,
+            p3,
+        );
+        let root = mpc_merkle_root(leaf, (path_p1, path_p2, path_p3), p1, p2, p3);
+        let nullifier = self
+            .nullifier
+            .mpc_nullifier((secret_p1, secret_p2, secret_p3), p1, p2, p3);
+
+        (
+            [root.0, nullifier.0].concat(),
+            [root.1, nullifier.1].concat(),
+            [root.2, nullifier.2].concat(),
+        )
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<u32>,
+        p_next: &mut Party<u32>,
+    ) -> Result<(Output<u32>, Output<u32>), Error> {
+        if p.view.input.len() != self.party_input_len()
+            || p_next.view.input.len() != self.party_input_len()
+        {
+            return Err(Error::VerificationError);
+        }
+        let (w, w_next) = (p.view.input.clone(), p_next.view.input.clone());
+        let (secret_p, path_p) = w.split_at(SECRET_LEN);
+        let (secret_p_next, path_p_next) = w_next.split_at(SECRET_LEN);
+
+        let leaf = mpc_sha256_padded_verify(
+            &self.leaf_template.apply(secret_p),
+            &self.leaf_template.apply(secret_p_next),
+            p,
+            p_next,
+        )?;
+        let root = merkle_root_verify(leaf, (path_p, path_p_next), p, p_next)?;
+        let nullifier = self
+            .nullifier
+            .nullifier_verify((secret_p, secret_p_next), p, p_next)?;
+
+        Ok((
+            [root.0, nullifier.0].concat(),
+            [root.1, nullifier.1].concat(),
+        ))
+    }
+
+    fn party_input_len(&self) -> usize {
+        SECRET_LEN + self.depth * PATH_LEVEL_LEN
+    }
+
+    fn party_output_len(&self) -> usize {
+        16
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        sha256_num_of_mul_gates(SECRET_LEN)
+            + merkle_num_of_mul_gates(self.depth)
+            + self.nullifier.num_of_mul_gates()
+    }
+}
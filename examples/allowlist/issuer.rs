@@ -0,0 +1,49 @@
+//! The issuer enrolls members and publishes the allowlist.
+
+use zkboo::gadgets::merkle::{MerklePath, MerkleTree, Node};
+
+/// Enrolls members by the leaf of their credential. The issuer never sees the
+/// credential secrets.
+#[derive(Default)]
+pub struct Issuer {
+    leaves: Vec<Node>,
+}
+
+impl Issuer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `leaf` to the next allowlist and returns its position in it.
+    pub fn enroll(&mut self, leaf: Node) -> usize {
+        self.leaves.push(leaf);
+        self.leaves.len() - 1
+    }
+
+    pub fn publish(&self) -> Allowlist {
+        Allowlist {
+            tree: MerkleTree::new(&self.leaves),
+        }
+    }
+}
+
+/// A published allowlist. Services only need its root and depth; members
+/// fetch the path of their leaf from it.
+#[derive(Clone, Debug)]
+pub struct Allowlist {
+    tree: MerkleTree,
+}
+
+impl Allowlist {
+    pub fn root(&self) -> Node {
+        self.tree.root()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.tree.depth()
+    }
+
+    pub fn path(&self, index: usize) -> Option<MerklePath> {
+        self.tree.path(index)
+    }
+}
@@ -0,0 +1,178 @@
+//! Anonymous allowlist login.
+//!
+//! An issuer enrolls members by the SHA-256 digest of a secret credential and
+//! publishes the Merkle tree of those digests. To log in to a service, a member
+//! proves that it knows the secret of some leaf of the tree, without saying
+//! which, and reveals the nullifier of its secret in the scope of the service.
+//! The service accepts each nullifier once, so a member logs in once per scope,
+//! and logins to services with different scopes cannot be linked. The proof is
+//! bound to a login message, so it cannot be replayed for another login.
+//!
+//! - `issuer`: enrollment and publication of the allowlist;
+//! - `member`: credentials and login requests;
+//! - `service`: verification of login requests and the audit log;
+//! - `circuit`: the statement they agree on.
+//!
+//! Run with `cargo run --release --example allowlist`.
+
+mod circuit;
+mod issuer;
+mod member;
+mod service;
+
+use rand::thread_rng;
+
+use issuer::Issuer;
+use member::Credential;
+use service::{LoginError, Service};
+
+fn main() {
+    let mut rng = thread_rng();
+
+    let credentials: Vec<_> = (0..5).map(|_| Credential::generate(&mut rng)).collect();
+    let mut issuer = Issuer::new();
+    let positions: Vec<_> = credentials
+        .iter()
+        .map(|credential| issuer.enroll(credential.leaf()))
+        .collect();
+    let allowlist = issuer.publish();
+    println!(
+        "allowlist of {} members, depth {}",
+        credentials.len(),
+        allowlist.depth()
+    );
+
+    let scope = b"forum.example, 2024 vote";
+    let mut service = Service::new(&allowlist, scope);
+
+    let request = credentials[3]
+        .login(&mut rng, &allowlist, positions[3], scope, b"session 1")
+        .expect("proving failed")
+        .to_bytes();
+    println!("login request of {} bytes", request.len());
+    service
+        .accept(b"session 1", &request)
+        .expect("login rejected");
+    println!("login accepted");
+
+    let request = credentials[3]
+        .login(&mut rng, &allowlist, positions[3], scope, b"session 2")
+        .expect("proving failed")
+        .to_bytes();
+    match service.accept(b"session 2", &request) {
+        Err(LoginError::Replayed) => println!("second login of the same member refused"),
+        other => panic!("unexpected outcome {:?}", other),
+    }
+
+    let outsider = Credential::generate(&mut rng);
+    let request = outsider
+        .login(&mut rng, &allowlist, positions[0], scope, b"session 3")
+        .expect("proving failed")
+        .to_bytes();
+    match service.accept(b"session 3", &request) {
+        Err(LoginError::Rejected(err)) => println!("login of an outsider rejected: {}", err),
+        other => panic!("unexpected outcome {:?}", other),
+    }
+
+    for digest in service.audit_log() {
+        let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        println!("audit log: {}", hex);
+    }
+}
+
+#[cfg(test)]
+mod allowlist_tests {
+    use rand::thread_rng;
+
+    use super::{
+        issuer::Issuer,
+        member::Credential,
+        service::{LoginError, LoginRequest, Service},
+    };
+
+    #[test]
+    fn test_login() {
+        let mut rng = thread_rng();
+
+        let credentials: Vec<_> = (0..3).map(|_| Credential::generate(&mut rng)).collect();
+        let mut issuer = Issuer::new();
+        for credential in &credentials {
+            issuer.enroll(credential.leaf());
+        }
+        let allowlist = issuer.publish();
+
+        let scope = b"service a";
+        let mut service = Service::new(&allowlist, scope);
+
+        let request = credentials[1]
+            .login(&mut rng, &allowlist, 1, scope, b"challenge 1")
+            .unwrap();
+
+        // bound to the login message
+        assert!(matches!(
+            service.accept(b"challenge 2", &request.to_bytes()),
+            Err(LoginError::Rejected(_))
+        ));
+
+        // and to the nullifier
+        let mut forged = request.clone();
+        forged.nullifier[0] ^= 1;
+        assert!(matches!(
+            service.accept(b"challenge 1", &forged.to_bytes()),
+            Err(LoginError::Rejected(_))
+        ));
+
+        service.accept(b"challenge 1", &request.to_bytes()).unwrap();
+        assert_eq!(service.audit_log().len(), 1);
+
+        // one login per member and scope
+        let again = credentials[1]
+            .login(&mut rng, &allowlist, 1, scope, b"challenge 3")
+            .unwrap();
+        assert!(matches!(
+            service.accept(b"challenge 3", &again.to_bytes()),
+            Err(LoginError::Replayed)
+        ));
+
+        // other members and other scopes are not affected
+        let other = credentials[2]
+            .login(&mut rng, &allowlist, 2, scope, b"challenge 4")
+            .unwrap();
+        service.accept(b"challenge 4", &other.to_bytes()).unwrap();
+
+        let other_scope = credentials[1]
+            .login(&mut rng, &allowlist, 1, b"service b", b"challenge 5")
+            .unwrap();
+        assert_ne!(other_scope.nullifier, request.nullifier);
+        Service::new(&allowlist, b"service b")
+            .accept(b"challenge 5", &other_scope.to_bytes())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_not_enrolled() {
+        let mut rng = thread_rng();
+
+        let enrolled = Credential::generate(&mut rng);
+        let outsider = Credential::generate(&mut rng);
+        let mut issuer = Issuer::new();
+        issuer.enroll(enrolled.leaf());
+        issuer.enroll(Credential::generate(&mut rng).leaf());
+        let allowlist = issuer.publish();
+
+        let scope = b"service";
+        let mut service = Service::new(&allowlist, scope);
+
+        // an outsider can prove along the path of an enrolled leaf, but reaches
+        // another root
+        let request = outsider
+            .login(&mut rng, &allowlist, 0, scope, b"challenge")
+            .unwrap();
+        assert!(matches!(
+            service.accept(b"challenge", &request.to_bytes()),
+            Err(LoginError::Rejected(_))
+        ));
+
+        assert!(LoginRequest::from_bytes(&[0; 31]).is_err());
+    }
+}
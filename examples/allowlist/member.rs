@@ -0,0 +1,67 @@
+//! A member holds a credential and logs in with it.
+
+use rand::{CryptoRng, RngCore};
+use rand_chacha::ChaCha20Rng;
+use sha3::Keccak256;
+use zkboo::{
+    circuit::Circuit, error::Error, gadgets::merkle::Node, prover::Prover, witness::Witness,
+};
+
+use crate::{
+    circuit::{leaf_of, AllowlistCircuit, SECRET_LEN, SIGMA},
+    issuer::Allowlist,
+    service::LoginRequest,
+};
+
+/// The secret of a member. Its leaf is what the issuer enrolls.
+#[derive(Debug)]
+pub struct Credential(Witness);
+
+impl Credential {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut secret = vec![0u8; SECRET_LEN];
+        rng.fill_bytes(&mut secret);
+        Self(Witness::new(secret))
+    }
+
+    pub fn leaf(&self) -> Node {
+        leaf_of(self.0.expose_secret())
+    }
+
+    /// Proves membership in `allowlist` at position `index` to the service of
+    /// `scope`, bound to the login `message` (e.g. a challenge of the service).
+    pub fn login<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        allowlist: &Allowlist,
+        index: usize,
+        scope: &[u8],
+        message: &[u8],
+    ) -> Result<LoginRequest, Error> {
+        let path = allowlist.path(index).ok_or(Error::OutOfRangeError)?;
+        let circuit = AllowlistCircuit::new(allowlist.depth(), scope);
+
+        let witness = Witness::new(AllowlistCircuit::witness(self.0.expose_secret(), &path));
+        let output = circuit.compute(witness.expose_secret());
+        let nullifier: Node = output[8..]
+            .iter()
+            .flat_map(|word| word.value.to_be_bytes())
+            .collect::<Vec<u8>>()
+            .try_into()
+            .unwrap();
+
+        let statement = circuit.statement(&allowlist.root(), &nullifier, message);
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove_with_context::<R, SIGMA>(
+            rng,
+            witness.expose_secret(),
+            &circuit,
+            &statement.public_output,
+            &statement.context,
+        )?;
+
+        Ok(LoginRequest {
+            nullifier,
+            proof: proof.to_bytes()?,
+        })
+    }
+}
@@ -0,0 +1,96 @@
+//! A service accepts at most one login per member, without learning who logs in.
+
+use std::collections::HashSet;
+
+use rand_chacha::ChaCha20Rng;
+use sha3::Keccak256;
+use zkboo::{
+    config::HASH_LEN,
+    data_structures::TypedProof,
+    error::Error,
+    gadgets::merkle::{Node, NODE_LEN},
+};
+
+use crate::{
+    circuit::{AllowlistCircuit, SIGMA},
+    issuer::Allowlist,
+};
+
+/// What a member sends to log in: the nullifier of its credential in the scope
+/// of the service and a proof that it is correctly derived from an enrolled
+/// credential.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoginRequest {
+    pub nullifier: Node,
+    pub proof: Vec<u8>,
+}
+
+impl LoginRequest {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [self.nullifier.as_slice(), &self.proof].concat()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < NODE_LEN {
+            return Err(Error::SerializationError);
+        }
+        let (nullifier, proof) = bytes.split_at(NODE_LEN);
+        Ok(Self {
+            nullifier: nullifier.try_into().unwrap(),
+            proof: proof.to_vec(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum LoginError {
+    /// The proof does not verify.
+    Rejected(Error),
+    /// A login with this credential was already accepted.
+    Replayed,
+}
+
+pub struct Service {
+    root: Node,
+    circuit: AllowlistCircuit,
+    nullifiers: HashSet<Node>,
+    audit_log: Vec<[u8; HASH_LEN]>,
+}
+
+impl Service {
+    pub fn new(allowlist: &Allowlist, scope: &[u8]) -> Self {
+        Self {
+            root: allowlist.root(),
+            circuit: AllowlistCircuit::new(allowlist.depth(), scope),
+            nullifiers: HashSet::new(),
+            audit_log: vec![],
+        }
+    }
+
+    /// Accepts a serialized `LoginRequest` made for `message`, and records it
+    /// in the audit log.
+    pub fn accept(&mut self, message: &[u8], request: &[u8]) -> Result<(), LoginError> {
+        let request = LoginRequest::from_bytes(request).map_err(LoginError::Rejected)?;
+        if self.nullifiers.contains(&request.nullifier) {
+            return Err(LoginError::Replayed);
+        }
+
+        let statement = self
+            .circuit
+            .statement(&self.root, &request.nullifier, message);
+        let proof = TypedProof::<u32, Keccak256, SIGMA>::from_bytes(&request.proof)
+            .map_err(LoginError::Rejected)?;
+        let digest = statement
+            .verify_for_audit::<ChaCha20Rng, Keccak256, SIGMA>(&proof, &self.circuit)
+            .map_err(LoginError::Rejected)?;
+
+        self.nullifiers.insert(request.nullifier);
+        self.audit_log.push(digest);
+        Ok(())
+    }
+
+    /// Digests of the accepted logins, in order.
+    pub fn audit_log(&self) -> &[[u8; HASH_LEN]] {
+        &self.audit_log
+    }
+}
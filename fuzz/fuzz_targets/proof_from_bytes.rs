@@ -0,0 +1,19 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rand_chacha::ChaCha20Rng;
+use sha3::Keccak256;
+use zkboo::{
+    data_structures::Proof, gadgets::add_mod::AdderCircuit, gf2_word::GF2Word, verifier::Verifier,
+};
+
+const SIGMA: usize = 1;
+
+// any bytes either fail to decode or fail to verify, without panicking
+fuzz_target!(|data: &[u8]| {
+    if let Ok(proof) = Proof::<u32, Keccak256>::from_bytes(data) {
+        let circuit = AdderCircuit::<u32>::new();
+        let output = vec![GF2Word::from(11u32)];
+        let _ = Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output);
+    }
+});
@@ -0,0 +1,25 @@
+#![no_main]
+
+use arbitrary::{Arbitrary, Unstructured};
+use libfuzzer_sys::fuzz_target;
+use rand_chacha::ChaCha20Rng;
+use sha3::Keccak256;
+use zkboo::{
+    data_structures::Proof, gadgets::add_mod::AdderCircuit, gf2_word::GF2Word, verifier::Verifier,
+};
+
+const SIGMA: usize = 1;
+
+// well-formed proofs of any shape are rejected, and survive serialization
+fuzz_target!(|data: &[u8]| {
+    let Ok(proof) = Proof::<u32, Keccak256>::arbitrary(&mut Unstructured::new(data)) else {
+        return;
+    };
+    let bytes = proof.to_bytes().unwrap();
+    let decoded = Proof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.to_bytes().unwrap(), bytes);
+
+    let circuit = AdderCircuit::<u32>::new();
+    let output = vec![GF2Word::from(11u32)];
+    let _ = Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output);
+});
@@ -0,0 +1,234 @@
+//! Bulk XOR and AND over slices of bytes and of words.
+//!
+//! The prover splits the witness into shares and the prover and the verifier
+//! derive the output of a third party from two others and the public output,
+//! a slice at a time. With the `simd` feature the byte kernels use AVX2 on
+//! x86_64 processors which support it, detected at run time, and NEON on
+//! aarch64. Other targets, and the bytes past the last full vector, take the
+//! scalar path, which computes the same bytes.
+//!
+//! A `GF2Word` keeps its size next to its value, so the values of a word slice
+//! are not contiguous: the word kernels combine them word by word, with the
+//! gate counting of `GF2Word` under the `bench` feature.
+
+use crate::gf2_word::{GF2Word, Value};
+
+#[derive(Clone, Copy)]
+enum Op {
+    Xor,
+    And,
+}
+
+fn apply_scalar(op: Op, dst: &mut [u8], src: &[u8]) {
+    let pairs = dst.iter_mut().zip(src);
+    match op {
+        Op::Xor => pairs.for_each(|(d, s)| *d ^= s),
+        Op::And => pairs.for_each(|(d, s)| *d &= s),
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod avx2 {
+    use std::arch::x86_64::{
+        __m256i, _mm256_and_si256, _mm256_loadu_si256, _mm256_storeu_si256, _mm256_xor_si256,
+    };
+
+    use super::Op;
+
+    const LANES: usize = 32;
+
+    /// Applies `op` to the longest prefix of whole vectors and returns its
+    /// length.
+    ///
+    /// # Safety
+    ///
+    /// The processor supports AVX2 and `dst` and `src` have the same length.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn apply(op: Op, dst: &mut [u8], src: &[u8]) -> usize {
+        let len = dst.len() - dst.len() % LANES;
+        for i in (0..len).step_by(LANES) {
+            let d = dst.as_mut_ptr().add(i) as *mut __m256i;
+            let s = src.as_ptr().add(i) as *const __m256i;
+            let (a, b) = (_mm256_loadu_si256(d), _mm256_loadu_si256(s));
+            let c = match op {
+                Op::Xor => _mm256_xor_si256(a, b),
+                Op::And => _mm256_and_si256(a, b),
+            };
+            _mm256_storeu_si256(d, c);
+        }
+        len
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+mod neon {
+    use std::arch::aarch64::{vandq_u8, veorq_u8, vld1q_u8, vst1q_u8};
+
+    use super::Op;
+
+    const LANES: usize = 16;
+
+    /// Applies `op` to the longest prefix of whole vectors and returns its
+    /// length.
+    ///
+    /// # Safety
+    ///
+    /// `dst` and `src` have the same length.
+    #[target_feature(enable = "neon")]
+    pub unsafe fn apply(op: Op, dst: &mut [u8], src: &[u8]) -> usize {
+        let len = dst.len() - dst.len() % LANES;
+        for i in (0..len).step_by(LANES) {
+            let d = dst.as_mut_ptr().add(i);
+            let (a, b) = (vld1q_u8(d), vld1q_u8(src.as_ptr().add(i)));
+            let c = match op {
+                Op::Xor => veorq_u8(a, b),
+                Op::And => vandq_u8(a, b),
+            };
+            vst1q_u8(d, c);
+        }
+        len
+    }
+}
+
+/// Applies `op` to a prefix of the slices with vector instructions, if any,
+/// and returns its length.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn apply_vectorized(op: Op, dst: &mut [u8], src: &[u8]) -> usize {
+    if is_x86_feature_detected!("avx2") {
+        // safe since AVX2 is supported and the caller checked the lengths
+        unsafe { avx2::apply(op, dst, src) }
+    } else {
+        0
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+fn apply_vectorized(op: Op, dst: &mut [u8], src: &[u8]) -> usize {
+    // safe since NEON is part of the aarch64 baseline and the caller checked
+    // the lengths
+    unsafe { neon::apply(op, dst, src) }
+}
+
+#[cfg(not(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+fn apply_vectorized(_op: Op, _dst: &mut [u8], _src: &[u8]) -> usize {
+    0
+}
+
+fn apply(op: Op, dst: &mut [u8], src: &[u8]) {
+    assert_eq!(dst.len(), src.len(), "slices of different lengths");
+    let done = apply_vectorized(op, dst, src);
+    apply_scalar(op, &mut dst[done..], &src[done..]);
+}
+
+/// Sets `dst` to `dst ^ src`. Panics if the slices have different lengths.
+pub fn xor_bytes(dst: &mut [u8], src: &[u8]) {
+    apply(Op::Xor, dst, src)
+}
+
+/// Sets `dst` to `dst & src`. Panics if the slices have different lengths.
+pub fn and_bytes(dst: &mut [u8], src: &[u8]) {
+    apply(Op::And, dst, src)
+}
+
+/// `a ^ b ^ c`. Panics if the slices have different lengths.
+pub fn xor3_bytes(a: &[u8], b: &[u8], c: &[u8]) -> Vec<u8> {
+    let mut out = a.to_vec();
+    xor_bytes(&mut out, b);
+    xor_bytes(&mut out, c);
+    out
+}
+
+fn check_words<T: Value>(dst: &[GF2Word<T>], src: &[GF2Word<T>]) {
+    assert_eq!(dst.len(), src.len(), "slices of different lengths");
+}
+
+/// Sets `dst` to `dst ^ src`. Panics if the slices have different lengths.
+pub fn xor_words<T: Value>(dst: &mut [GF2Word<T>], src: &[GF2Word<T>]) {
+    check_words(dst, src);
+    dst.iter_mut().zip(src).for_each(|(d, &s)| *d = *d ^ s);
+}
+
+/// Sets `dst` to `dst & src`. Panics if the slices have different lengths.
+pub fn and_words<T: Value>(dst: &mut [GF2Word<T>], src: &[GF2Word<T>]) {
+    check_words(dst, src);
+    dst.iter_mut().zip(src).for_each(|(d, &s)| *d = *d & s);
+}
+
+/// `a ^ b ^ c`. Panics if the slices have different lengths.
+pub fn xor3_words<T: Value>(
+    a: &[GF2Word<T>],
+    b: &[GF2Word<T>],
+    c: &[GF2Word<T>],
+) -> Vec<GF2Word<T>> {
+    let mut out = a.to_vec();
+    xor_words(&mut out, b);
+    xor_words(&mut out, c);
+    out
+}
+
+#[cfg(test)]
+mod batch_tests {
+    use rand::{thread_rng, RngCore};
+
+    use crate::gf2_word::{GF2Word, GenRand};
+
+    use super::{
+        and_bytes, and_words, apply_scalar, xor3_bytes, xor3_words, xor_bytes, xor_words, Op,
+    };
+
+    #[test]
+    fn test_bytes_match_scalar() {
+        let mut rng = thread_rng();
+        // lengths around the vector sizes, to cover the tails
+        for len in (0..=100).chain([1023, 1024, 1025]) {
+            let mut a = vec![0u8; len];
+            let mut b = vec![0u8; len];
+            rng.fill_bytes(&mut a);
+            rng.fill_bytes(&mut b);
+
+            let (mut xor, mut expected) = (a.clone(), a.clone());
+            xor_bytes(&mut xor, &b);
+            apply_scalar(Op::Xor, &mut expected, &b);
+            assert_eq!(xor, expected);
+
+            let (mut and, mut expected) = (a.clone(), a.clone());
+            and_bytes(&mut and, &b);
+            apply_scalar(Op::And, &mut expected, &b);
+            assert_eq!(and, expected);
+
+            // xoring the same slice twice cancels out
+            assert_eq!(xor3_bytes(&a, &b, &b), a);
+        }
+    }
+
+    #[test]
+    fn test_words() {
+        let mut rng = thread_rng();
+        let words = |rng: &mut _| -> Vec<GF2Word<u32>> {
+            u32::gen_rand_packed(rng, 10)
+                .into_iter()
+                .map(GF2Word::from)
+                .collect()
+        };
+        let (a, b, c) = (words(&mut rng), words(&mut rng), words(&mut rng));
+
+        let xor = xor3_words(&a, &b, &c);
+        let mut and = a.clone();
+        and_words(&mut and, &b);
+        for i in 0..a.len() {
+            assert_eq!(xor[i].value, a[i].value ^ b[i].value ^ c[i].value);
+            assert_eq!(and[i].value, a[i].value & b[i].value);
+        }
+
+        let mut back = xor;
+        xor_words(&mut back, &c);
+        xor_words(&mut back, &b);
+        assert_eq!(back, a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_lengths_differ() {
+        xor_bytes(&mut [0u8; 3], &[0u8; 4]);
+    }
+}
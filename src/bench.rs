@@ -0,0 +1,162 @@
+//! Cost of proving a circuit, for comparing gadget implementations.
+//!
+//! `CircuitCost::measure` runs a circuit once as three parties to count its
+//! gates, then proves and verifies it once to time it and size its proof. AND
+//! gates are the messages each party sends; XOR gates are the XORs of
+//! `GF2Word`s each party computes, including those inside AND gates, and are
+//! only counted while this feature is enabled.
+
+use std::{cell::Cell, fmt, time::Instant};
+
+use rand::{CryptoRng, RngCore};
+use sha3::{digest::FixedOutputReset, Digest};
+
+use crate::{
+    circuit::Circuit, error::Error, expander::TapeExpander, gf2_word::Value, key::Key,
+    num_of_repetitions_given_desired_security, prover::Prover, verifier::Verifier,
+};
+
+thread_local! {
+    static XOR_GATES: Cell<usize> = const { Cell::new(0) };
+}
+
+pub(crate) fn count_xor() {
+    XOR_GATES.with(|gates| gates.set(gates.get() + 1));
+}
+
+/// Serialized size of a proof header, see `Proof::write_to`.
+const HEADER_LEN: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitCost {
+    /// AND gates run by each party.
+    pub and_gates: usize,
+    /// XORs computed by each party, averaged over the three.
+    pub xor_gates: usize,
+    /// Values on the tape of each party.
+    pub tape_len: usize,
+    /// The security level `proof_len` and the timings were measured at.
+    pub security_param: usize,
+    /// Serialized size of the measured proof in bytes.
+    pub proof_len: usize,
+    /// Serialized size of a single repetition in bytes.
+    pub repetition_len: usize,
+    pub prove_time: std::time::Duration,
+    pub verify_time: std::time::Duration,
+}
+
+impl CircuitCost {
+    /// Measure the cost of proving `witness` for `circuit` at security level
+    /// `SIGMA`.
+    pub fn measure<T, TapeR, D, R, const SIGMA: usize>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+    ) -> Result<Self, Error>
+    where
+        T: Value + PartialEq,
+        TapeR: TapeExpander,
+        D: fmt::Debug + Default + Digest + FixedOutputReset + Clone,
+        R: RngCore + CryptoRng,
+    {
+        let mut keys = [Key::default(); 3];
+        for key in &mut keys {
+            rng.fill_bytes(key);
+        }
+
+        let xor_gates_before = XOR_GATES.with(Cell::get);
+        let repetition = Prover::<T, TapeR, D>::prove_repetition(
+            rng,
+            witness,
+            (keys[0], keys[1], keys[2]),
+            0,
+            circuit,
+        )?;
+        let xor_gates = (XOR_GATES.with(Cell::get) - xor_gates_before) / 3;
+
+        let public_output = circuit.compute(witness);
+        let start = Instant::now();
+        let proof =
+            Prover::<T, TapeR, D>::prove::<R, SIGMA>(rng, witness, circuit, &public_output)?;
+        let prove_time = start.elapsed();
+
+        let start = Instant::now();
+        Verifier::<T, TapeR, D>::verify::<SIGMA>(&proof, circuit, &public_output)?;
+        let verify_time = start.elapsed();
+
+        // every repetition of a circuit serializes to the same size
+        let proof_len = proof.to_bytes()?.len();
+        Ok(Self {
+            and_gates: repetition.party_views.0.messages.len(),
+            xor_gates,
+            tape_len: circuit.num_of_mul_gates(),
+            security_param: SIGMA,
+            proof_len,
+            repetition_len: (proof_len - HEADER_LEN) / proof.num_of_repetitions(),
+            prove_time,
+            verify_time,
+        })
+    }
+
+    /// Serialized size of a proof at security level `sigma` in bytes.
+    pub fn proof_len_at(&self, sigma: usize) -> usize {
+        HEADER_LEN + num_of_repetitions_given_desired_security(sigma) * self.repetition_len
+    }
+}
+
+impl fmt::Display for CircuitCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "AND gates:   {}", self.and_gates)?;
+        writeln!(f, "XOR gates:   {}", self.xor_gates)?;
+        writeln!(f, "tape length: {}", self.tape_len)?;
+        writeln!(
+            f,
+            "proof size:  {} bytes at SIGMA = {}",
+            self.proof_len, self.security_param
+        )?;
+        writeln!(f, "prove time:  {:?}", self.prove_time)?;
+        write!(f, "verify time: {:?}", self.verify_time)
+    }
+}
+
+#[cfg(test)]
+mod bench_tests {
+    use rand::thread_rng;
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::Circuit,
+        gadgets::{add_mod::AdderCircuit, sha256::Sha256Circuit},
+    };
+
+    use super::CircuitCost;
+
+    const SIGMA: usize = 40;
+
+    #[test]
+    fn test_circuit_cost() {
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+
+        let cost = CircuitCost::measure::<u32, ChaCha20Rng, Keccak256, _, SIGMA>(
+            &mut rng, &input, &circuit,
+        )
+        .unwrap();
+        assert_eq!(cost.and_gates, circuit.num_of_mul_gates());
+        assert_eq!(cost.tape_len, circuit.num_of_mul_gates());
+        assert!(cost.xor_gates > 0);
+        assert_eq!(cost.proof_len_at(SIGMA), cost.proof_len);
+        assert!(cost.proof_len_at(80) > cost.proof_len);
+        assert!(cost.to_string().contains("AND gates:   1"));
+
+        // SHA-256 runs far more gates on a single block
+        let sha = Sha256Circuit::new(3);
+        let sha_cost =
+            CircuitCost::measure::<u32, ChaCha20Rng, Keccak256, _, SIGMA>(&mut rng, b"abc", &sha)
+                .unwrap();
+        assert_eq!(sha_cost.and_gates, sha.num_of_mul_gates());
+        assert!(sha_cost.xor_gates > 100 * cost.xor_gates);
+    }
+}
@@ -0,0 +1,218 @@
+//! Command line prover and verifier of hash preimages, behind the `cli`
+//! feature.
+//!
+//! ```text
+//! zkboo prove --circuit sha256 --witness-file w.bin --out proof.bin
+//! zkboo verify --circuit sha256 --digest <hex> proof.bin
+//! ```
+//!
+//! `prove` prints the hex digest of the witness, which is what `verify` is
+//! given. Proofs are made with the parameters of `zkboo::statements` and
+//! written with `Proof::to_bytes`. Circuits are `sha256` and `blake2s`.
+//!
+//! Run with `cargo run --release --features cli -- <args>`.
+
+use std::{fs, process::ExitCode};
+
+use rand::thread_rng;
+
+use zkboo::statements::{
+    prove_blake2s_preimage, prove_sha256_preimage, verify_blake2s_preimage, verify_sha256_preimage,
+    Digest, StatementProof,
+};
+
+const USAGE: &str = "usage:
+    zkboo prove --circuit <sha256|blake2s> --witness-file <path> --out <path>
+    zkboo verify --circuit <sha256|blake2s> --digest <hex> <proof>";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HashCircuit {
+    Sha256,
+    Blake2s,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Prove {
+        circuit: HashCircuit,
+        witness_file: String,
+        out: String,
+    },
+    Verify {
+        circuit: HashCircuit,
+        digest: Digest,
+        proof_file: String,
+    },
+}
+
+fn parse_circuit(name: &str) -> Result<HashCircuit, String> {
+    match name {
+        "sha256" => Ok(HashCircuit::Sha256),
+        "blake2s" => Ok(HashCircuit::Blake2s),
+        _ => Err(format!("unknown circuit {name}")),
+    }
+}
+
+fn parse_digest(hex: &str) -> Result<Digest, String> {
+    let invalid = || format!("invalid digest {hex}, expected 64 hex digits");
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let bytes = (0..32)
+        .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(bytes.try_into().unwrap())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (command, args) = args.split_first().ok_or("missing command")?;
+
+    let mut circuit = None;
+    let mut options = Vec::new();
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix("--") {
+            Some(name) => {
+                let value = args.next().ok_or(format!("missing value of --{name}"))?;
+                if name == "circuit" {
+                    circuit = Some(parse_circuit(value)?);
+                } else {
+                    options.push((name, value.clone()));
+                }
+            }
+            None => positional.push(arg.clone()),
+        }
+    }
+    let circuit = circuit.ok_or("missing --circuit")?;
+    let mut option = |name: &str| -> Result<String, String> {
+        let index = options
+            .iter()
+            .position(|(option, _)| *option == name)
+            .ok_or(format!("missing --{name}"))?;
+        Ok(options.swap_remove(index).1)
+    };
+
+    let parsed = match command.as_str() {
+        "prove" => Command::Prove {
+            circuit,
+            witness_file: option("witness-file")?,
+            out: option("out")?,
+        },
+        "verify" => Command::Verify {
+            circuit,
+            digest: parse_digest(&option("digest")?)?,
+            proof_file: positional.pop().ok_or("missing proof file")?,
+        },
+        _ => return Err(format!("unknown command {command}")),
+    };
+    if let Some((name, _)) = options.first() {
+        return Err(format!("unexpected option --{name}"));
+    }
+    if !positional.is_empty() {
+        return Err(format!("unexpected argument {}", positional[0]));
+    }
+    Ok(parsed)
+}
+
+fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Prove {
+            circuit,
+            witness_file,
+            out,
+        } => {
+            let witness =
+                fs::read(&witness_file).map_err(|e| format!("cannot read {witness_file}: {e}"))?;
+            let prove = match circuit {
+                HashCircuit::Sha256 => prove_sha256_preimage,
+                HashCircuit::Blake2s => prove_blake2s_preimage,
+            };
+            let (digest, proof) = prove(&mut thread_rng(), &witness).map_err(|e| e.to_string())?;
+            let bytes = proof.to_bytes().map_err(|e| e.to_string())?;
+            fs::write(&out, bytes).map_err(|e| format!("cannot write {out}: {e}"))?;
+            println!("{}", to_hex(&digest));
+        }
+        Command::Verify {
+            circuit,
+            digest,
+            proof_file,
+        } => {
+            let bytes =
+                fs::read(&proof_file).map_err(|e| format!("cannot read {proof_file}: {e}"))?;
+            let proof = StatementProof::from_bytes(&bytes).map_err(|e| e.to_string())?;
+            let verify = match circuit {
+                HashCircuit::Sha256 => verify_sha256_preimage,
+                HashCircuit::Blake2s => verify_blake2s_preimage,
+            };
+            verify(&digest, &proof).map_err(|e| e.to_string())?;
+            println!("ok");
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match parse_args(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("error: {e}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::{parse_args, parse_digest, to_hex, Command, HashCircuit};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(&args(
+                "prove --circuit sha256 --witness-file w.bin --out proof.bin"
+            )),
+            Ok(Command::Prove {
+                circuit: HashCircuit::Sha256,
+                witness_file: "w.bin".to_string(),
+                out: "proof.bin".to_string(),
+            })
+        );
+
+        let digest = [0xab; 32];
+        let line = format!(
+            "verify --circuit blake2s --digest {} p.bin",
+            to_hex(&digest)
+        );
+        assert_eq!(
+            parse_args(&args(&line)),
+            Ok(Command::Verify {
+                circuit: HashCircuit::Blake2s,
+                digest,
+                proof_file: "p.bin".to_string(),
+            })
+        );
+
+        assert!(parse_args(&args("prove --circuit md5 --witness-file w --out p")).is_err());
+        assert!(parse_args(&args("prove --circuit sha256 --out p")).is_err());
+        assert!(parse_args(&args("prove --circuit sha256 --witness-file w --out p x")).is_err());
+        assert!(parse_args(&args("verify --circuit sha256 --digest 00 p")).is_err());
+        assert!(parse_digest(&"é".repeat(32)).is_err());
+    }
+}
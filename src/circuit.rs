@@ -1,14 +1,38 @@
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+use rand_chacha::ChaCha20Rng;
+
 use crate::{
+    data_structures::Instance,
+    encoding::{encode_circuit_id, encode_members},
     error::Error,
+    expander::tape_label,
+    gadgets::{
+        assertion::{
+            assert_eq_num_of_mul_gates, assert_eq_verify, mpc_assert_eq, mpc_success_bit,
+            no_failures, success_bit_verify,
+        },
+        mpc_and, mpc_and_verify, mpc_public, public_verify,
+    },
     gf2_word::{GF2Word, Value},
+    key::Key,
     party::Party,
+    tape::Tape,
+    view::View,
+    witness::{WitnessMask, WitnessRange},
 };
 
 pub type Output<T> = Vec<GF2Word<T>>;
 pub type TwoThreeDecOutput<T> = (Output<T>, Output<T>, Output<T>);
 
 pub trait Circuit<T: Value> {
-    fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>>;
+    /// Evaluates the circuit on `input` in the clear. The default runs
+    /// `compute_23_decomposition`, see `evaluate`; circuits with a faster plain
+    /// implementation, e.g. a native hash, override it.
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+        evaluate(self, input)
+    }
 
     /// Decompose this circuit into 3 branches such that the values computed in
     /// 2 branches reveals no information about the input x.
@@ -25,134 +49,2148 @@ pub trait Circuit<T: Value> {
     ) -> Result<(Output<T>, Output<T>), Error>;
     fn party_input_len(&self) -> usize;
     fn party_output_len(&self) -> usize;
+    /// The number of values each party reads from its tape, one per AND gate.
+    /// The prover and the verifier reject a repetition which reads more or
+    /// fewer values, see `count_mul_gates` for deriving it.
     fn num_of_mul_gates(&self) -> usize;
+
+    /// Public values the gates use besides the witness, such as a message or a
+    /// counter, encoded as bytes. They are absorbed by the Fiat-Shamir oracle
+    /// along the public output but are not shared nor part of the views: each
+    /// party mixes them into its shares, see `gadgets::mpc_public`.
+    fn public_input(&self) -> Vec<u8> {
+        vec![]
+    }
+
+    /// Witness bytes which only take some values, checked by
+    /// `Prover::check_witness`. The circuit itself does not enforce them.
+    fn witness_ranges(&self) -> Vec<WitnessRange> {
+        vec![]
+    }
+
+    /// Bytes identifying this circuit, absorbed by the Fiat-Shamir oracle so
+    /// that a proof does not verify against another circuit with the same
+    /// outputs. The default only records the sizes of the circuit: circuits
+    /// of the same sizes tell themselves apart with a name, see `named_id`.
+    fn id(&self) -> Vec<u8> {
+        named_id("", self, &[])
+    }
 }
 
-#[cfg(test)]
-mod circuit_tests {
-    use std::marker::PhantomData;
+/// The `Circuit::id` of `circuit` under `name`, made of the circuits with ids
+/// `parts`, see `encoding::encode_circuit_id`.
+pub fn named_id<T: Value>(
+    name: &str,
+    circuit: &(impl Circuit<T> + ?Sized),
+    parts: &[Vec<u8>],
+) -> Vec<u8> {
+    encode_circuit_id(
+        name.as_bytes(),
+        circuit.party_input_len(),
+        circuit.party_output_len(),
+        circuit.num_of_mul_gates(),
+        parts,
+    )
+}
 
-    use rand::{rngs::ThreadRng, thread_rng};
-    use rand_chacha::ChaCha20Rng;
-    use sha3::Keccak256;
+/// Boxed circuits, e.g. those built at run time by a `CircuitRegistry`, are
+/// circuits.
+impl<T: Value, C: Circuit<T> + ?Sized> Circuit<T> for Box<C> {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+        (**self).compute(input)
+    }
 
-    use super::{Circuit, Output, TwoThreeDecOutput};
-    use crate::{
-        error::Error,
-        gadgets::{mpc_and, mpc_and_verify, mpc_xor, prepare::generic_parse},
-        gf2_word::{GF2Word, Value},
-        party::Party,
-        prover::Prover,
-        verifier::Verifier,
-    };
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<T>,
+        p2: &mut Party<T>,
+        p3: &mut Party<T>,
+    ) -> TwoThreeDecOutput<T> {
+        (**self).compute_23_decomposition(p1, p2, p3)
+    }
 
-    // computes: (x1 ^ x2) & (x3 ^ x4) & x5
-    #[derive(Clone, Copy)]
-    struct SimpleCircuit1<T>(PhantomData<T>);
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<T>,
+        p_next: &mut Party<T>,
+    ) -> Result<(Output<T>, Output<T>), Error> {
+        (**self).simulate_two_parties(p, p_next)
+    }
 
-    impl<T: Value> Circuit<T> for SimpleCircuit1<T> {
-        fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
-            let x = generic_parse(input, self.party_input_len());
-            vec![(x[0] ^ x[1]) & (x[2] ^ x[3]) & x[4]]
-        }
+    fn party_input_len(&self) -> usize {
+        (**self).party_input_len()
+    }
 
-        fn compute_23_decomposition(
-            &self,
-            p1: &mut Party<T>,
-            p2: &mut Party<T>,
-            p3: &mut Party<T>,
-        ) -> TwoThreeDecOutput<T> {
-            // prepare
-            let x = generic_parse(&p1.view.input, 5);
-            let y = generic_parse(&p2.view.input, 5);
-            let z = generic_parse(&p3.view.input, 5);
+    fn party_output_len(&self) -> usize {
+        (**self).party_output_len()
+    }
 
-            let (x1, x2, x3, x4, x5) = (x[0], x[1], x[2], x[3], x[4]);
-            let (y1, y2, y3, y4, y5) = (y[0], y[1], y[2], y[3], y[4]);
-            let (z1, z2, z3, z4, z5) = (z[0], z[1], z[2], z[3], z[4]);
+    fn num_of_mul_gates(&self) -> usize {
+        (**self).num_of_mul_gates()
+    }
 
-            let (a1, a2, a3) = mpc_xor((x1, x2), (y1, y2), (z1, z2));
-            let (b1, b2, b3) = mpc_xor((x3, x4), (y3, y4), (z3, z4));
+    fn public_input(&self) -> Vec<u8> {
+        (**self).public_input()
+    }
 
-            let (ab1, ab2, ab3) = mpc_and((a1, b1), (a2, b2), (a3, b3), p1, p2, p3);
+    fn witness_ranges(&self) -> Vec<WitnessRange> {
+        (**self).witness_ranges()
+    }
 
-            let (o1, o2, o3) = mpc_and((ab1, x5), (ab2, y5), (ab3, z5), p1, p2, p3);
+    fn id(&self) -> Vec<u8> {
+        (**self).id()
+    }
+}
 
-            (vec![o1], vec![o2], vec![o3])
+/// Runs `second` on the output of `first`, see `compose`.
+#[derive(Clone, Debug)]
+pub struct Compose<A, B> {
+    pub first: A,
+    pub second: B,
+}
+
+/// Chains two circuits: the output words of `first` are passed to `second` as
+/// its input, in big-endian bytes as for digests.
+///
+/// Each party runs `second` on its shares of the output of `first`, so the
+/// intermediate value is never reconstructed. Both circuits read from the same
+/// tapes, one after the other.
+pub fn compose<A, B>(first: A, second: B) -> Compose<A, B> {
+    Compose { first, second }
+}
+
+fn to_input<T: Value>(output: &[GF2Word<T>]) -> Vec<u8> {
+    output
+        .iter()
+        .flat_map(|word| word.value.to_bytes())
+        .collect()
+}
+
+impl<T: Value, A: Circuit<T>, B: Circuit<T>> Circuit<T> for Compose<A, B> {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+        self.second.compute(&to_input(&self.first.compute(input)))
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<T>,
+        p2: &mut Party<T>,
+        p3: &mut Party<T>,
+    ) -> TwoThreeDecOutput<T> {
+        let (o1, o2, o3) = self.first.compute_23_decomposition(p1, p2, p3);
+
+        // the views keep the input of `first`, which is what they commit to
+        let input_p1 = std::mem::replace(&mut p1.view.input, to_input(&o1));
+        let input_p2 = std::mem::replace(&mut p2.view.input, to_input(&o2));
+        let input_p3 = std::mem::replace(&mut p3.view.input, to_input(&o3));
+        let output = self.second.compute_23_decomposition(p1, p2, p3);
+        (p1.view.input, p2.view.input, p3.view.input) = (input_p1, input_p2, input_p3);
+
+        output
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<T>,
+        p_next: &mut Party<T>,
+    ) -> Result<(Output<T>, Output<T>), Error> {
+        let (o, o_next) = self.first.simulate_two_parties(p, p_next)?;
+
+        let input_p = std::mem::replace(&mut p.view.input, to_input(&o));
+        let input_p_next = std::mem::replace(&mut p_next.view.input, to_input(&o_next));
+        let output = self.second.simulate_two_parties(p, p_next);
+        (p.view.input, p_next.view.input) = (input_p, input_p_next);
+
+        output
+    }
+
+    fn party_input_len(&self) -> usize {
+        self.first.party_input_len()
+    }
+
+    fn party_output_len(&self) -> usize {
+        self.second.party_output_len()
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        self.first.num_of_mul_gates() + self.second.num_of_mul_gates()
+    }
+
+    fn public_input(&self) -> Vec<u8> {
+        [self.first.public_input(), self.second.public_input()].concat()
+    }
+
+    fn witness_ranges(&self) -> Vec<WitnessRange> {
+        self.first.witness_ranges()
+    }
+
+    fn id(&self) -> Vec<u8> {
+        named_id("compose", self, &[self.first.id(), self.second.id()])
+    }
+}
+
+/// Runs `first` and `second` on the same input, see `join`.
+#[derive(Clone, Debug)]
+pub struct Join<A, B> {
+    pub first: A,
+    pub second: B,
+}
+
+/// Runs two circuits on a shared input and outputs the words of `first`
+/// followed by those of `second`, e.g. to prove that one secret is a preimage
+/// of two digests.
+///
+/// Both circuits must read inputs of the same length. Each party runs both on
+/// its share of the input, reading from the same tapes one after the other.
+pub fn join<A, B>(first: A, second: B) -> Join<A, B> {
+    Join { first, second }
+}
+
+impl<T: Value, A: Circuit<T>, B: Circuit<T>> Circuit<T> for Join<A, B> {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+        [self.first.compute(input), self.second.compute(input)].concat()
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<T>,
+        p2: &mut Party<T>,
+        p3: &mut Party<T>,
+    ) -> TwoThreeDecOutput<T> {
+        let first = self.first.compute_23_decomposition(p1, p2, p3);
+        let second = self.second.compute_23_decomposition(p1, p2, p3);
+        (
+            [first.0, second.0].concat(),
+            [first.1, second.1].concat(),
+            [first.2, second.2].concat(),
+        )
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<T>,
+        p_next: &mut Party<T>,
+    ) -> Result<(Output<T>, Output<T>), Error> {
+        if self.first.party_input_len() != self.second.party_input_len() {
+            return Err(Error::VerificationError);
         }
+        let first = self.first.simulate_two_parties(p, p_next)?;
+        let second = self.second.simulate_two_parties(p, p_next)?;
+        Ok(([first.0, second.0].concat(), [first.1, second.1].concat()))
+    }
 
-        fn simulate_two_parties(
-            &self,
-            p: &mut Party<T>,
-            p_next: &mut Party<T>,
-        ) -> Result<(Output<T>, Output<T>), Error> {
-            let p_inputs = generic_parse(&p.view.input, self.party_input_len());
-            let p_next_inputs = generic_parse(&p_next.view.input, self.party_input_len());
+    fn party_input_len(&self) -> usize {
+        self.first.party_input_len()
+    }
 
-            let (x1, x2, x3, x4, x5) = (
-                p_inputs[0],
-                p_inputs[1],
-                p_inputs[2],
-                p_inputs[3],
-                p_inputs[4],
-            );
+    fn party_output_len(&self) -> usize {
+        self.first.party_output_len() + self.second.party_output_len()
+    }
 
-            let (y1, y2, y3, y4, y5) = (
-                p_next_inputs[0],
-                p_next_inputs[1],
-                p_next_inputs[2],
-                p_next_inputs[3],
-                p_next_inputs[4],
-            );
+    fn num_of_mul_gates(&self) -> usize {
+        self.first.num_of_mul_gates() + self.second.num_of_mul_gates()
+    }
 
-            let a1 = x1 ^ x2;
-            let b1 = x3 ^ x4;
+    fn public_input(&self) -> Vec<u8> {
+        [self.first.public_input(), self.second.public_input()].concat()
+    }
 
-            let a2 = y1 ^ y2;
-            let b2 = y3 ^ y4;
+    fn witness_ranges(&self) -> Vec<WitnessRange> {
+        [self.first.witness_ranges(), self.second.witness_ranges()].concat()
+    }
 
-            let (ab1, ab2) = mpc_and_verify((a1, b1), (a2, b2), p, p_next)?;
-            let (o1, o2) = mpc_and_verify((ab1, x5), (ab2, y5), p, p_next)?;
+    fn id(&self) -> Vec<u8> {
+        named_id("join", self, &[self.first.id(), self.second.id()])
+    }
+}
 
-            Ok((vec![o1], vec![o2]))
+/// Runs `first` and `second` on separate parts of the input, see `and`.
+#[derive(Clone, Debug)]
+pub struct And<A, B> {
+    pub first: A,
+    pub second: B,
+    /// Number of bytes of the input read by `first`, the rest is read by
+    /// `second`.
+    pub first_input_len: usize,
+}
+
+/// Proves two independent statements at once: `first` runs on the first
+/// `first_input_len` bytes of the input, `second` on the rest, and the output
+/// is the words of `first` followed by those of `second`.
+///
+/// A proof for the combined circuit has a single challenge, drawn from the
+/// commitments and outputs of both, so it only verifies for both statements
+/// together. Its views are about as large as those of the two proofs it
+/// replaces. Each party runs both on its shares, reading from the same tapes
+/// one after the other.
+pub fn and<A, B>(first: A, first_input_len: usize, second: B) -> And<A, B> {
+    And {
+        first,
+        second,
+        first_input_len,
+    }
+}
+
+impl<A, B> And<A, B> {
+    /// The parts of `input` read by `first` and by `second`.
+    fn split<'a>(&self, input: &'a [u8]) -> (&'a [u8], &'a [u8]) {
+        input.split_at(self.first_input_len.min(input.len()))
+    }
+}
+
+impl<T: Value, A: Circuit<T>, B: Circuit<T>> Circuit<T> for And<A, B> {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+        let (x, y) = self.split(input);
+        [self.first.compute(x), self.second.compute(y)].concat()
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<T>,
+        p2: &mut Party<T>,
+        p3: &mut Party<T>,
+    ) -> TwoThreeDecOutput<T> {
+        // the views keep the whole input, which is what they commit to
+        let inputs = [&mut *p1, &mut *p2, &mut *p3].map(|p| std::mem::take(&mut p.view.input));
+
+        for (p, input) in [&mut *p1, &mut *p2, &mut *p3].into_iter().zip(&inputs) {
+            p.view.input = self.split(input).0.to_vec();
         }
+        let first = self.first.compute_23_decomposition(p1, p2, p3);
 
-        fn party_input_len(&self) -> usize {
-            5
+        for (p, input) in [&mut *p1, &mut *p2, &mut *p3].into_iter().zip(&inputs) {
+            p.view.input = self.split(input).1.to_vec();
         }
+        let second = self.second.compute_23_decomposition(p1, p2, p3);
 
-        fn party_output_len(&self) -> usize {
-            1
+        for (p, input) in [p1, p2, p3].into_iter().zip(inputs) {
+            p.view.input = input;
         }
+        (
+            [first.0, second.0].concat(),
+            [first.1, second.1].concat(),
+            [first.2, second.2].concat(),
+        )
+    }
 
-        fn num_of_mul_gates(&self) -> usize {
-            2
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<T>,
+        p_next: &mut Party<T>,
+    ) -> Result<(Output<T>, Output<T>), Error> {
+        let input_p = std::mem::take(&mut p.view.input);
+        let input_p_next = std::mem::take(&mut p_next.view.input);
+
+        p.view.input = self.split(&input_p).0.to_vec();
+        p_next.view.input = self.split(&input_p_next).0.to_vec();
+        let first = self.first.simulate_two_parties(p, p_next);
+
+        p.view.input = self.split(&input_p).1.to_vec();
+        p_next.view.input = self.split(&input_p_next).1.to_vec();
+        let second = self.second.simulate_two_parties(p, p_next);
+
+        (p.view.input, p_next.view.input) = (input_p, input_p_next);
+        let (first, second) = (first?, second?);
+        Ok(([first.0, second.0].concat(), [first.1, second.1].concat()))
+    }
+
+    fn party_input_len(&self) -> usize {
+        self.first.party_input_len() + self.second.party_input_len()
+    }
+
+    fn party_output_len(&self) -> usize {
+        self.first.party_output_len() + self.second.party_output_len()
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        self.first.num_of_mul_gates() + self.second.num_of_mul_gates()
+    }
+
+    fn public_input(&self) -> Vec<u8> {
+        [self.first.public_input(), self.second.public_input()].concat()
+    }
+
+    fn witness_ranges(&self) -> Vec<WitnessRange> {
+        let offset = self.first_input_len;
+        let second = self.second.witness_ranges().into_iter().map(|range| {
+            WitnessRange::new(
+                range.bytes.start + offset..range.bytes.end + offset,
+                range.allowed,
+            )
+        });
+        self.first
+            .witness_ranges()
+            .into_iter()
+            .chain(second)
+            .collect()
+    }
+
+    fn id(&self) -> Vec<u8> {
+        named_id("and", self, &[self.first.id(), self.second.id()])
+    }
+}
+
+/// Runs every member on the same input and outputs whether one of them
+/// computes its public output, see `any_of`.
+pub struct AnyOf<'a, T: Value> {
+    pub members: &'a [Instance<'a, T>],
+}
+
+/// Proves that the input is a witness for one of `members`, without revealing
+/// which: the only output is a bit, 1 iff one of the members outputs its
+/// public output, see `gadgets::assertion::success_public_output`.
+///
+/// Each party runs every member on its share of the input, as for `join`, and
+/// compares the outputs with the public output of the member inside the
+/// circuit, so a proof for it is a regular proof with the soundness of any
+/// other. Its views are about as large as the proofs of all members together.
+/// All members must read inputs of the same length.
+pub fn any_of<'a, T: Value>(members: &'a [Instance<'a, T>]) -> AnyOf<'a, T> {
+    AnyOf { members }
+}
+
+impl<T: Value> Circuit<T> for AnyOf<'_, T> {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+        let any = self
+            .members
+            .iter()
+            .any(|member| member.circuit.compute(input) == *member.public_output);
+        vec![T::from(u8::from(any)).into()]
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<T>,
+        p2: &mut Party<T>,
+        p3: &mut Party<T>,
+    ) -> TwoThreeDecOutput<T> {
+        // shares of a word which is all ones iff every member so far failed
+        let mut all_failed: Option<(GF2Word<T>, GF2Word<T>, GF2Word<T>)> = None;
+        for member in self.members {
+            let (o1, o2, o3) = member.circuit.compute_23_decomposition(p1, p2, p3);
+            let mut failures = no_failures();
+            for (i, &expected) in member.public_output.iter().enumerate() {
+                let output = (o1[i], o2[i], o3[i]);
+                failures = mpc_assert_eq(failures, output, mpc_public(expected), p1, p2, p3);
+            }
+
+            all_failed = Some(match all_failed {
+                None => failures,
+                Some(f) => mpc_and(
+                    (f.0, failures.0),
+                    (f.1, failures.1),
+                    (f.2, failures.2),
+                    p1,
+                    p2,
+                    p3,
+                ),
+            });
         }
+
+        let all_failed = all_failed.unwrap_or_else(|| mpc_public((!T::zero()).into()));
+        let (s1, s2, s3) = mpc_success_bit(all_failed);
+        (vec![s1], vec![s2], vec![s3])
     }
 
-    #[test]
-    fn test_full_run() {
-        let mut rng = thread_rng();
-        const SIGMA: usize = 40;
-        let input: Vec<u8> = [
-            5u32.to_le_bytes(),
-            4u32.to_le_bytes(),
-            7u32.to_le_bytes(),
-            2u32.to_le_bytes(),
-            9u32.to_le_bytes(),
-        ]
-        .into_iter()
-        .flatten()
-        .collect();
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<T>,
+        p_next: &mut Party<T>,
+    ) -> Result<(Output<T>, Output<T>), Error> {
+        let mut all_failed: Option<(GF2Word<T>, GF2Word<T>)> = None;
+        for member in self.members {
+            if member.circuit.party_input_len() != self.party_input_len() {
+                return Err(Error::VerificationError);
+            }
+            let (o, o_next) = member.circuit.simulate_two_parties(p, p_next)?;
+            if o.len() != member.public_output.len() || o_next.len() != o.len() {
+                return Err(Error::VerificationError);
+            }
 
-        let circuit = SimpleCircuit1(PhantomData);
-        let output = circuit.compute(&input);
+            let mut failures = public_verify(T::zero().into());
+            for (i, &expected) in member.public_output.iter().enumerate() {
+                let output = (o[i], o_next[i]);
+                failures = assert_eq_verify(failures, output, public_verify(expected), p, p_next)?;
+            }
 
-        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
-            &mut rng, &input, &circuit, &output,
-        )
-        .unwrap();
+            all_failed = Some(match all_failed {
+                None => failures,
+                Some(f) => mpc_and_verify((f.0, failures.0), (f.1, failures.1), p, p_next)?,
+            });
+        }
+
+        let all_failed = all_failed.unwrap_or_else(|| public_verify((!T::zero()).into()));
+        let (s, s_next) = success_bit_verify(all_failed);
+        Ok((vec![s], vec![s_next]))
+    }
+
+    fn party_input_len(&self) -> usize {
+        self.members
+            .first()
+            .map_or(0, |member| member.circuit.party_input_len())
+    }
+
+    fn party_output_len(&self) -> usize {
+        1
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        let members: usize = self
+            .members
+            .iter()
+            .map(|member| {
+                member.circuit.num_of_mul_gates()
+                    + member.public_output.len() * assert_eq_num_of_mul_gates::<T>()
+            })
+            .sum();
+        // one gate combines each member after the first
+        members + self.members.len().saturating_sub(1)
+    }
+
+    /// The public inputs and outputs of the members, which the circuit
+    /// compares with, see `encoding::encode_members`.
+    fn public_input(&self) -> Vec<u8> {
+        encode_members(self.members)
+    }
+
+    fn id(&self) -> Vec<u8> {
+        let parts: Vec<_> = self
+            .members
+            .iter()
+            .map(|member| member.circuit.id())
+            .collect();
+        named_id("any-of", self, &parts)
+    }
+}
 
-        Verifier::<u32, ChaCha20Rng, Keccak256>::verify(&proof, &circuit, &output).unwrap();
+/// Runs `circuit` and discloses the witness bytes selected by `mask`, see
+/// `disclose`.
+#[derive(Clone, Debug)]
+pub struct Disclose<C> {
+    pub circuit: C,
+    pub mask: WitnessMask,
+}
+
+/// Proves the statement of `circuit` about a witness some bytes of which are
+/// public: the output is the words of `circuit` followed by the bytes selected
+/// by `mask`, packed into words from the least significant bit of the first
+/// byte, see `pack_bytes`.
+///
+/// Each party outputs its own shares of the selected bytes, so disclosing them
+/// costs no AND gate, and a verifier holding the public bytes checks them as
+/// part of the public output. The other bytes stay as hidden as without the
+/// mask.
+pub fn disclose<C>(circuit: C, mask: WitnessMask) -> Disclose<C> {
+    Disclose { circuit, mask }
+}
+
+/// `bytes` as words of `T`, filled from their least significant bit with the
+/// bits of `bytes` from the least significant bit of the first byte, the last
+/// word padded with zeros. For `u32` words this is the little-endian encoding.
+pub fn pack_bytes<T: Value>(bytes: &[u8]) -> Vec<GF2Word<T>> {
+    let bits: Vec<bool> = bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+        .collect();
+    bits.chunks(T::bits_len())
+        .map(|chunk| {
+            let word = chunk
+                .iter()
+                .enumerate()
+                .fold(T::zero(), |word, (pos, &bit)| word.set_bit(pos, bit));
+            word.into()
+        })
+        .collect()
+}
+
+impl<C> Disclose<C> {
+    fn disclosed<T: Value>(&self, input: &[u8]) -> Result<Output<T>, Error> {
+        Ok(pack_bytes(&self.mask.disclosed(input)?))
+    }
+}
+
+impl<T: Value, C: Circuit<T>> Circuit<T> for Disclose<C> {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+        // a witness too short for the mask yields an output of the wrong
+        // length, which no proof verifies against
+        let disclosed = self.disclosed(input).unwrap_or_default();
+        [self.circuit.compute(input), disclosed].concat()
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<T>,
+        p2: &mut Party<T>,
+        p3: &mut Party<T>,
+    ) -> TwoThreeDecOutput<T> {
+        let (o1, o2, o3) = self.circuit.compute_23_decomposition(p1, p2, p3);
+        let [d1, d2, d3] =
+            [&*p1, &*p2, &*p3].map(|p| self.disclosed(&p.view.input).unwrap_or_default());
+        ([o1, d1].concat(), [o2, d2].concat(), [o3, d3].concat())
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<T>,
+        p_next: &mut Party<T>,
+    ) -> Result<(Output<T>, Output<T>), Error> {
+        let (o, o_next) = self.circuit.simulate_two_parties(p, p_next)?;
+        let d = self.disclosed(&p.view.input)?;
+        let d_next = self.disclosed(&p_next.view.input)?;
+        Ok(([o, d].concat(), [o_next, d_next].concat()))
+    }
+
+    fn party_input_len(&self) -> usize {
+        self.circuit.party_input_len()
+    }
+
+    fn party_output_len(&self) -> usize {
+        self.circuit.party_output_len() + (8 * self.mask.len()).div_ceil(T::bits_len())
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        self.circuit.num_of_mul_gates()
+    }
+
+    fn public_input(&self) -> Vec<u8> {
+        self.circuit.public_input()
+    }
+
+    fn witness_ranges(&self) -> Vec<WitnessRange> {
+        // the disclosed bytes take any value but must be in the witness
+        let disclosed = self
+            .mask
+            .ranges
+            .iter()
+            .map(|range| WitnessRange::new(range.clone(), u8::MIN..=u8::MAX));
+        self.circuit
+            .witness_ranges()
+            .into_iter()
+            .chain(disclosed)
+            .collect()
+    }
+
+    fn id(&self) -> Vec<u8> {
+        named_id("disclose", self, &[self.circuit.id(), self.mask.to_bytes()])
+    }
+}
+
+/// The number of values a party reads from its tape when running `circuit`,
+/// counted by running its decomposition on an all-zero witness of
+/// `input_len` bytes.
+///
+/// The count does not depend on the witness for circuits built from gadgets,
+/// whose gates are fixed by the sizes of their inputs.
+pub fn count_mul_gates<T: Value>(circuit: &(impl Circuit<T> + ?Sized), input_len: usize) -> usize {
+    let (mut p1, mut p2, mut p3) = (
+        unbounded_party(0, vec![0u8; input_len]),
+        unbounded_party(1, vec![0u8; input_len]),
+        unbounded_party(2, vec![0u8; input_len]),
+    );
+    circuit.compute_23_decomposition(&mut p1, &mut p2, &mut p3);
+
+    p1.tape.offset()
+}
+
+/// Evaluates `circuit` on `input` by running `compute_23_decomposition` on a
+/// sharing where the first party holds `input` and the others zeros. The
+/// outputs of any sharing add up to the output of the circuit whatever the
+/// tapes, so this needs no plain implementation of the gadgets, at the cost of
+/// running the three parties.
+pub fn evaluate<T: Value>(circuit: &(impl Circuit<T> + ?Sized), input: &[u8]) -> Vec<GF2Word<T>> {
+    let (mut p1, mut p2, mut p3) = (
+        unbounded_party(0, input.to_vec()),
+        unbounded_party(1, vec![0u8; input.len()]),
+        unbounded_party(2, vec![0u8; input.len()]),
+    );
+    let (o1, o2, o3) = circuit.compute_23_decomposition(&mut p1, &mut p2, &mut p3);
+
+    o1.iter()
+        .zip(&o2)
+        .zip(&o3)
+        .map(|((&w1, &w2), &w3)| w1 ^ w2 ^ w3)
+        .collect()
+}
+
+/// A party holding `share` with a tape which never runs out.
+fn unbounded_party<T: Value>(index: usize, share: Vec<u8>) -> Party<T> {
+    let tape = Tape::from_key::<ChaCha20Rng>(Key::default(), &tape_label(0, index), usize::MAX);
+    Party::from_tape_and_view(View::new(share), tape)
+}
+
+/// A circuit with its sizes, public input, witness ranges and id computed once.
+///
+/// The prover and the verifier query these on every repetition, and a circuit
+/// built from gadgets recomputes them each time. A service proving or
+/// verifying the same circuit many times prepares it once and passes the
+/// prepared circuit to `prove` and `verify` in its place: proofs of either are
+/// interchangeable.
+#[derive(Clone, Debug)]
+pub struct PreparedCircuit<C> {
+    circuit: C,
+    party_input_len: usize,
+    party_output_len: usize,
+    num_of_mul_gates: usize,
+    public_input: Vec<u8>,
+    witness_ranges: Vec<WitnessRange>,
+    id: Vec<u8>,
+}
+
+impl<C> PreparedCircuit<C> {
+    pub fn new<T: Value>(circuit: C) -> Self
+    where
+        C: Circuit<T>,
+    {
+        Self {
+            party_input_len: circuit.party_input_len(),
+            party_output_len: circuit.party_output_len(),
+            num_of_mul_gates: circuit.num_of_mul_gates(),
+            public_input: circuit.public_input(),
+            witness_ranges: circuit.witness_ranges(),
+            id: circuit.id(),
+            circuit,
+        }
+    }
+
+    /// Like `new`, with the number of AND gates counted by `count_mul_gates`
+    /// on a witness of `input_len` bytes, in place of the one the circuit
+    /// declares.
+    pub fn with_counted_gates<T: Value>(circuit: C, input_len: usize) -> Self
+    where
+        C: Circuit<T>,
+    {
+        Self {
+            num_of_mul_gates: count_mul_gates(&circuit, input_len),
+            ..Self::new(circuit)
+        }
+    }
+
+    pub fn circuit(&self) -> &C {
+        &self.circuit
+    }
+
+    pub fn into_inner(self) -> C {
+        self.circuit
+    }
+}
+
+impl<T: Value, C: Circuit<T>> Circuit<T> for PreparedCircuit<C> {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+        self.circuit.compute(input)
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<T>,
+        p2: &mut Party<T>,
+        p3: &mut Party<T>,
+    ) -> TwoThreeDecOutput<T> {
+        self.circuit.compute_23_decomposition(p1, p2, p3)
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<T>,
+        p_next: &mut Party<T>,
+    ) -> Result<(Output<T>, Output<T>), Error> {
+        self.circuit.simulate_two_parties(p, p_next)
+    }
+
+    fn party_input_len(&self) -> usize {
+        self.party_input_len
+    }
+
+    fn party_output_len(&self) -> usize {
+        self.party_output_len
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        self.num_of_mul_gates
+    }
+
+    fn public_input(&self) -> Vec<u8> {
+        self.public_input.clone()
+    }
+
+    fn witness_ranges(&self) -> Vec<WitnessRange> {
+        self.witness_ranges.clone()
+    }
+
+    fn id(&self) -> Vec<u8> {
+        self.id.clone()
+    }
+}
+
+#[cfg(test)]
+mod circuit_tests {
+    use std::{cell::Cell, marker::PhantomData};
+
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use super::{
+        and, any_of, count_mul_gates, disclose, evaluate, named_id, pack_bytes, AnyOf, Circuit,
+        Output, PreparedCircuit, TwoThreeDecOutput,
+    };
+    use crate::{
+        data_structures::{AggregateProof, Instance, MerkleProof, Proof, RingProof},
+        error::{Error, VerificationCheck},
+        gadgets::{
+            add_mod::AdderCircuit, assertion::success_public_output, mpc_and, mpc_and_verify,
+            mpc_xor, prepare::generic_parse,
+        },
+        gf2_word::{Bit, GF2Word, Value},
+        party::Party,
+        prover::Prover,
+        verifier::Verifier,
+        witness::WitnessMask,
+    };
+
+    // computes: (x1 ^ x2) & (x3 ^ x4) & x5
+    #[derive(Clone, Copy)]
+    struct SimpleCircuit1<T>(PhantomData<T>);
+
+    impl<T: Value> Circuit<T> for SimpleCircuit1<T> {
+        fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+            let x = generic_parse(input, self.party_input_len()).unwrap();
+            vec![(x[0] ^ x[1]) & (x[2] ^ x[3]) & x[4]]
+        }
+
+        fn compute_23_decomposition(
+            &self,
+            p1: &mut Party<T>,
+            p2: &mut Party<T>,
+            p3: &mut Party<T>,
+        ) -> TwoThreeDecOutput<T> {
+            // prepare
+            let x = generic_parse(&p1.view.input, 5).unwrap();
+            let y = generic_parse(&p2.view.input, 5).unwrap();
+            let z = generic_parse(&p3.view.input, 5).unwrap();
+
+            let (x1, x2, x3, x4, x5) = (x[0], x[1], x[2], x[3], x[4]);
+            let (y1, y2, y3, y4, y5) = (y[0], y[1], y[2], y[3], y[4]);
+            let (z1, z2, z3, z4, z5) = (z[0], z[1], z[2], z[3], z[4]);
+
+            let (a1, a2, a3) = mpc_xor((x1, x2), (y1, y2), (z1, z2));
+            let (b1, b2, b3) = mpc_xor((x3, x4), (y3, y4), (z3, z4));
+
+            let (ab1, ab2, ab3) = mpc_and((a1, b1), (a2, b2), (a3, b3), p1, p2, p3);
+
+            let (o1, o2, o3) = mpc_and((ab1, x5), (ab2, y5), (ab3, z5), p1, p2, p3);
+
+            (vec![o1], vec![o2], vec![o3])
+        }
+
+        fn simulate_two_parties(
+            &self,
+            p: &mut Party<T>,
+            p_next: &mut Party<T>,
+        ) -> Result<(Output<T>, Output<T>), Error> {
+            let p_inputs = generic_parse(&p.view.input, self.party_input_len())?;
+            let p_next_inputs = generic_parse(&p_next.view.input, self.party_input_len())?;
+
+            let (x1, x2, x3, x4, x5) = (
+                p_inputs[0],
+                p_inputs[1],
+                p_inputs[2],
+                p_inputs[3],
+                p_inputs[4],
+            );
+
+            let (y1, y2, y3, y4, y5) = (
+                p_next_inputs[0],
+                p_next_inputs[1],
+                p_next_inputs[2],
+                p_next_inputs[3],
+                p_next_inputs[4],
+            );
+
+            let a1 = x1 ^ x2;
+            let b1 = x3 ^ x4;
+
+            let a2 = y1 ^ y2;
+            let b2 = y3 ^ y4;
+
+            let (ab1, ab2) = mpc_and_verify((a1, b1), (a2, b2), p, p_next)?;
+            let (o1, o2) = mpc_and_verify((ab1, x5), (ab2, y5), p, p_next)?;
+
+            Ok((vec![o1], vec![o2]))
+        }
+
+        fn party_input_len(&self) -> usize {
+            5
+        }
+
+        fn party_output_len(&self) -> usize {
+            1
+        }
+
+        fn num_of_mul_gates(&self) -> usize {
+            2
+        }
+    }
+
+    #[test]
+    fn test_full_run() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+        let input: Vec<u8> = [
+            5u32.to_le_bytes(),
+            4u32.to_le_bytes(),
+            7u32.to_le_bytes(),
+            2u32.to_le_bytes(),
+            9u32.to_le_bytes(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let circuit = SimpleCircuit1(PhantomData);
+        let output = circuit.compute(&input);
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_prepared_circuit() {
+        const SIGMA: usize = 40;
+
+        // counts the queries of the tape length
+        struct Counted(SimpleCircuit1<u32>, Cell<usize>);
+
+        impl Circuit<u32> for Counted {
+            fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+                self.0.compute(input)
+            }
+
+            fn compute_23_decomposition(
+                &self,
+                p1: &mut Party<u32>,
+                p2: &mut Party<u32>,
+                p3: &mut Party<u32>,
+            ) -> TwoThreeDecOutput<u32> {
+                self.0.compute_23_decomposition(p1, p2, p3)
+            }
+
+            fn simulate_two_parties(
+                &self,
+                p: &mut Party<u32>,
+                p_next: &mut Party<u32>,
+            ) -> Result<(Output<u32>, Output<u32>), Error> {
+                self.0.simulate_two_parties(p, p_next)
+            }
+
+            fn party_input_len(&self) -> usize {
+                self.0.party_input_len()
+            }
+
+            fn party_output_len(&self) -> usize {
+                self.0.party_output_len()
+            }
+
+            fn num_of_mul_gates(&self) -> usize {
+                self.1.set(self.1.get() + 1);
+                self.0.num_of_mul_gates()
+            }
+        }
+
+        let mut rng = thread_rng();
+        let input: Vec<u8> = [5u32, 4, 7, 2, 9]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let prepared = PreparedCircuit::new(Counted(SimpleCircuit1(PhantomData), Cell::new(0)));
+        // once for the tape length and once for the default id
+        assert_eq!(prepared.circuit().1.get(), 2);
+        let output = prepared.compute(&input);
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &prepared, &output,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &prepared, &output)
+            .unwrap();
+        assert_eq!(prepared.circuit().1.get(), 2);
+
+        // the proof is the same as one of the circuit itself
+        let circuit = prepared.into_inner();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+        assert!(circuit.1.get() > 2);
+    }
+
+    #[test]
+    fn test_circuit_id_is_bound() {
+        const SIGMA: usize = 40;
+
+        // computes the same as `SimpleCircuit1` under another name
+        struct Named(SimpleCircuit1<u32>, &'static str);
+
+        impl Circuit<u32> for Named {
+            fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+                self.0.compute(input)
+            }
+
+            fn compute_23_decomposition(
+                &self,
+                p1: &mut Party<u32>,
+                p2: &mut Party<u32>,
+                p3: &mut Party<u32>,
+            ) -> TwoThreeDecOutput<u32> {
+                self.0.compute_23_decomposition(p1, p2, p3)
+            }
+
+            fn simulate_two_parties(
+                &self,
+                p: &mut Party<u32>,
+                p_next: &mut Party<u32>,
+            ) -> Result<(Output<u32>, Output<u32>), Error> {
+                self.0.simulate_two_parties(p, p_next)
+            }
+
+            fn party_input_len(&self) -> usize {
+                self.0.party_input_len()
+            }
+
+            fn party_output_len(&self) -> usize {
+                self.0.party_output_len()
+            }
+
+            fn num_of_mul_gates(&self) -> usize {
+                self.0.num_of_mul_gates()
+            }
+
+            fn id(&self) -> Vec<u8> {
+                named_id(self.1, self, &[])
+            }
+        }
+
+        let mut rng = thread_rng();
+        let input: Vec<u8> = [5u32, 4, 7, 2, 9]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let simple = SimpleCircuit1::<u32>(PhantomData);
+        let (a, b) = (Named(simple, "a"), Named(simple, "b"));
+        let output = a.compute(&input);
+        assert_eq!(output, b.compute(&input));
+        assert_ne!(a.id(), b.id());
+        // circuits of the same sizes share the default id
+        assert_eq!(simple.id(), Named(simple, "").id());
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &a, &output,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &a, &output).unwrap();
+        assert!(matches!(
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &b, &output),
+            Err(Error::FiatShamirOutputsMatchingError)
+        ));
+
+        // combinators are told apart by their parts
+        assert_ne!(and(a, 20, simple).id(), and(b, 20, simple).id());
+    }
+
+    #[test]
+    fn test_disclose() {
+        const SIGMA: usize = 40;
+
+        let bytes = [0x01, 0x80, 0xff, 0x12, 0x34];
+        let words: Vec<u32> = pack_bytes::<u32>(&bytes).iter().map(|w| w.value).collect();
+        assert_eq!(words, vec![0x12ff8001, 0x34]);
+        let bits: Vec<Bit> = pack_bytes::<Bit>(&[0x05]).iter().map(|w| w.value).collect();
+        assert_eq!(bits, [1, 0, 1, 0, 0, 0, 0, 0].map(Bit::from));
+
+        let mut rng = thread_rng();
+        let input = [5u64.to_le_bytes(), 6u64.to_le_bytes()].concat();
+        // the low bytes of the second summand
+        let circuit = disclose(AdderCircuit::<u64>::new(), WitnessMask::range(8..11));
+        assert_eq!(Circuit::<u64>::party_output_len(&circuit), 2);
+
+        let output = circuit.compute(&input);
+        assert_eq!(output, vec![11u64.into(), 6u64.into()]);
+        let proof = Prover::<u64, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+        Verifier::<u64, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+
+        // the disclosed bytes are checked
+        let wrong = vec![11u64.into(), 7u64.into()];
+        assert!(
+            Verifier::<u64, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &wrong)
+                .is_err()
+        );
+
+        // the mask is part of the id
+        let other = disclose(AdderCircuit::<u64>::new(), WitnessMask::prefix(3));
+        assert_ne!(Circuit::<u64>::id(&circuit), Circuit::<u64>::id(&other));
+    }
+
+    /// Leaves `compute` to its default.
+    struct Decomposed<C>(C);
+
+    impl<T: Value, C: Circuit<T>> Circuit<T> for Decomposed<C> {
+        fn compute_23_decomposition(
+            &self,
+            p1: &mut Party<T>,
+            p2: &mut Party<T>,
+            p3: &mut Party<T>,
+        ) -> TwoThreeDecOutput<T> {
+            self.0.compute_23_decomposition(p1, p2, p3)
+        }
+
+        fn simulate_two_parties(
+            &self,
+            p: &mut Party<T>,
+            p_next: &mut Party<T>,
+        ) -> Result<(Output<T>, Output<T>), Error> {
+            self.0.simulate_two_parties(p, p_next)
+        }
+
+        fn party_input_len(&self) -> usize {
+            self.0.party_input_len()
+        }
+
+        fn party_output_len(&self) -> usize {
+            self.0.party_output_len()
+        }
+
+        fn num_of_mul_gates(&self) -> usize {
+            self.0.num_of_mul_gates()
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        use crate::gadgets::sha256::Sha256Circuit;
+
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+
+        let input: Vec<u8> = [3u32, 8, 1, 6, 5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let simple = SimpleCircuit1::<u32>(PhantomData);
+        assert_eq!(evaluate(&simple, &input), simple.compute(&input));
+
+        let adder_input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let adder = AdderCircuit::<u32>::new();
+        assert_eq!(evaluate(&adder, &adder_input), adder.compute(&adder_input));
+
+        let preimage = b"plain evaluation";
+        let sha256 = Sha256Circuit::new(preimage.len());
+        assert_eq!(evaluate(&sha256, preimage), sha256.compute(preimage));
+
+        // a circuit without a plain implementation proves as usual
+        let circuit = Decomposed(sha256);
+        let output = circuit.compute(preimage);
+        assert_eq!(output, Sha256Circuit::new(preimage.len()).compute(preimage));
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, preimage, &circuit, &output,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_counted_mul_gates() {
+        const SIGMA: usize = 40;
+
+        // declares a wrong number of AND gates
+        struct Misdeclared(SimpleCircuit1<u32>, usize);
+
+        impl Circuit<u32> for Misdeclared {
+            fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+                self.0.compute(input)
+            }
+
+            fn compute_23_decomposition(
+                &self,
+                p1: &mut Party<u32>,
+                p2: &mut Party<u32>,
+                p3: &mut Party<u32>,
+            ) -> TwoThreeDecOutput<u32> {
+                self.0.compute_23_decomposition(p1, p2, p3)
+            }
+
+            fn simulate_two_parties(
+                &self,
+                p: &mut Party<u32>,
+                p_next: &mut Party<u32>,
+            ) -> Result<(Output<u32>, Output<u32>), Error> {
+                self.0.simulate_two_parties(p, p_next)
+            }
+
+            fn party_input_len(&self) -> usize {
+                self.0.party_input_len()
+            }
+
+            fn party_output_len(&self) -> usize {
+                self.0.party_output_len()
+            }
+
+            fn num_of_mul_gates(&self) -> usize {
+                self.1
+            }
+        }
+
+        let mut rng = thread_rng();
+        let input: Vec<u8> = [5u32, 4, 7, 2, 9]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let simple = SimpleCircuit1::<u32>(PhantomData);
+        let output = simple.compute(&input);
+        assert_eq!(
+            count_mul_gates(&simple, input.len()),
+            simple.num_of_mul_gates()
+        );
+        assert_eq!(
+            count_mul_gates(&AdderCircuit::<u32>::new(), 8),
+            AdderCircuit::<u32>::new().num_of_mul_gates()
+        );
+
+        let prove = |declared: usize| {
+            Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+                &mut thread_rng(),
+                &input,
+                &Misdeclared(simple, declared),
+                &output,
+            )
+        };
+        assert!(matches!(prove(3), Err(Error::TapeLengthError(3, 2))));
+        assert!(matches!(prove(1), Err(Error::TapeExhaustedError)));
+
+        let prepared = PreparedCircuit::with_counted_gates(Misdeclared(simple, 3), input.len());
+        assert_eq!(prepared.num_of_mul_gates(), 2);
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &prepared, &output,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &prepared, &output)
+            .unwrap();
+
+        // the verifier checks the declared count as well
+        assert!(matches!(
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+                &proof,
+                &Misdeclared(simple, 3),
+                &output
+            ),
+            Err(Error::RepetitionError {
+                repetition: 0,
+                check: VerificationCheck::Tape,
+                source,
+                ..
+            }) if matches!(*source, Error::TapeLengthError(3, 2))
+        ));
+    }
+
+    #[test]
+    fn test_public_output_len() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+        let input: Vec<u8> = [5u32, 4, 7, 2, 9]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+
+        let circuit = SimpleCircuit1(PhantomData);
+        let output = circuit.compute(&input);
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+
+        // outputs of another length are rejected instead of read out of bounds
+        assert!(matches!(
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &vec![]),
+            Err(Error::PublicOutputLenError(1, 0))
+        ));
+        let long = [output.clone(), output].concat();
+        assert!(matches!(
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &long),
+            Err(Error::PublicOutputLenError(1, 2))
+        ));
+    }
+
+    #[test]
+    fn test_deterministic_proving() {
+        type P = Prover<u32, ChaCha20Rng, Keccak256>;
+        const SIGMA: usize = 40;
+        let input: Vec<u8> = [5u32, 4, 7, 2, 9]
+            .into_iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+
+        let circuit = SimpleCircuit1(PhantomData);
+        let output = circuit.compute(&input);
+
+        let prove = |seed: &[u8], context: &[u8]| {
+            P::prove_deterministic::<SIGMA>(seed, &input, &circuit, &output, context)
+                .unwrap()
+                .to_bytes()
+                .unwrap()
+        };
+        let proof = prove(b"seed", b"context");
+        assert_eq!(proof, prove(b"seed", b"context"));
+        assert_ne!(proof, prove(b"other seed", b"context"));
+        assert_ne!(proof, prove(b"seed", b"other context"));
+
+        let proof = Proof::<u32, Keccak256>::from_bytes(&proof).unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify_with_context::<SIGMA>(
+            &proof, &circuit, &output, b"context",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_bit_circuit() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+        let circuit = SimpleCircuit1::<Bit>(PhantomData);
+
+        for input in [[1, 0, 0, 1, 1], [1, 1, 0, 1, 1], [0, 1, 1, 0, 0]] {
+            let output = circuit.compute(&input);
+            assert_eq!(
+                output[0].value,
+                Bit::from((input[0] ^ input[1]) & (input[2] ^ input[3]) & input[4])
+            );
+
+            let proof = Prover::<Bit, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+                &mut rng, &input, &circuit, &output,
+            )
+            .unwrap();
+            let proof = Proof::<Bit, Keccak256>::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+
+            Verifier::<Bit, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_streaming_verification() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+        let input: Vec<u8> = [3u32, 8, 1, 6, 5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+
+        let circuit = SimpleCircuit1(PhantomData);
+        let output = circuit.compute(&input);
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+        let bytes = proof.to_bytes().unwrap();
+
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify_from_reader::<SIGMA, _>(
+            &mut bytes.as_slice(),
+            &circuit,
+            &output,
+        )
+        .unwrap();
+
+        let proof = Proof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+
+        // truncated proofs are rejected
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify_from_reader::<SIGMA, _>(
+                &mut &truncated[..],
+                &circuit,
+                &output,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_split_verification() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+        let input: Vec<u8> = [3u32, 8, 1, 6, 5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+
+        let circuit = SimpleCircuit1(PhantomData);
+        let output = circuit.compute(&input);
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+        let pieces = proof.split();
+
+        // each piece is verified on its own, in any order
+        let transcripts: Vec<_> = pieces
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, piece)| {
+                Verifier::<u32, ChaCha20Rng, Keccak256>::verify_repetition_proof(
+                    &circuit, &output, i, piece,
+                )
+                .unwrap()
+            })
+            .collect();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::finalize::<SIGMA>(
+            &circuit,
+            &output,
+            transcripts.clone(),
+            &[],
+        )
+        .unwrap();
+
+        // missing and duplicated repetitions are rejected
+        let mut missing = transcripts.clone();
+        missing.pop();
+        assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::finalize::<SIGMA>(
+            &circuit,
+            &output,
+            missing,
+            &[]
+        )
+        .is_err());
+        let mut duplicated = transcripts.clone();
+        duplicated[0] = duplicated[1].clone();
+        assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::finalize::<SIGMA>(
+            &circuit,
+            &output,
+            duplicated,
+            &[]
+        )
+        .is_err());
+
+        // a repetition corrupted in transit fails the final check, until it
+        // is retransmitted
+        let mut received = pieces.clone();
+        received[7].keys.0[0] ^= 1;
+        let proof = Proof::<u32, Keccak256>::recombine(SIGMA, received.clone());
+        assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+            &proof, &circuit, &output
+        )
+        .is_err());
+
+        received[7] = pieces[7].clone();
+        let proof = Proof::<u32, Keccak256>::recombine(SIGMA, received);
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_malformed_views() {
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+        let input: Vec<u8> = [3u32, 8, 1, 6, 5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let circuit = SimpleCircuit1(PhantomData);
+        let output = circuit.compute(&input);
+        let adder = AdderCircuit::<u32>::new();
+        let adder_input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let adder_output = adder.compute(&adder_input);
+
+        let mut proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+        let mut adder_proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng,
+            &adder_input,
+            &adder,
+            &adder_output,
+        )
+        .unwrap();
+
+        // views missing messages are rejected instead of read out of bounds
+        proof.views[5].messages.truncate(1);
+        adder_proof.views[0].messages.clear();
+        assert!(matches!(
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output),
+            Err(Error::RepetitionError {
+                repetition: 5,
+                check: VerificationCheck::Simulation,
+                source,
+                ..
+            }) if matches!(*source, Error::ViewOffsetOutOfBoundsError(1))
+        ));
+        assert!(matches!(
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+                &adder_proof,
+                &adder,
+                &adder_output
+            ),
+            Err(Error::RepetitionError {
+                repetition: 0,
+                check: VerificationCheck::Simulation,
+                source,
+                ..
+            }) if matches!(*source, Error::ViewOffsetOutOfBoundsError(0))
+        ));
+    }
+
+    #[test]
+    fn test_verification_report() {
+        const SIGMA: usize = 40;
+        type V = Verifier<u32, ChaCha20Rng, Keccak256>;
+        let input: Vec<u8> = [3u32, 8, 1, 6, 5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let circuit = SimpleCircuit1(PhantomData);
+        let output = circuit.compute(&input);
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut thread_rng(),
+            &input,
+            &circuit,
+            &output,
+        )
+        .unwrap();
+
+        let report = V::verify_with_report::<SIGMA>(&proof, &circuit, &output, &[]);
+        assert!(report.is_valid());
+        assert!(report.into_result().is_ok());
+
+        // every failing repetition is reported
+        let mut broken = proof.clone();
+        broken.views[2].messages.clear();
+        broken.views[7].messages.clear();
+        let report = V::verify_with_report::<SIGMA>(&broken, &circuit, &output, &[]);
+        assert_eq!(report.failed_repetitions(), vec![2, 7]);
+        assert_eq!(report.challenge_matches, None);
+        assert!(matches!(
+            report.into_result(),
+            Err(Error::RepetitionError { repetition: 2, .. })
+        ));
+
+        // another public output derives other outputs, so another challenge
+        let mut other = output.clone();
+        other[0] = other[0] ^ GF2Word::from(1);
+        let report = V::verify_with_report::<SIGMA>(&proof, &circuit, &other, &[]);
+        assert!(report.failures.is_empty());
+        assert_eq!(report.challenge_matches, Some(false));
+        assert!(matches!(
+            report.into_result(),
+            Err(Error::FiatShamirOutputsMatchingError)
+        ));
+
+        let report = V::verify_with_report::<80>(&proof, &circuit, &output, &[]);
+        assert!(report.malformed.is_some());
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn test_verify_any() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+        let input: Vec<u8> = [3u32, 8, 1, 6, 5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+
+        let circuit = SimpleCircuit1(PhantomData);
+        let output = circuit.compute(&input);
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+
+        let allowed = vec![vec![GF2Word::from(7u32)], output.clone(), vec![]];
+        assert_eq!(
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify_any::<SIGMA>(
+                &proof, &circuit, &allowed
+            )
+            .unwrap(),
+            1
+        );
+
+        let not_allowed = vec![vec![GF2Word::from(7u32)], vec![GF2Word::from(9u32)]];
+        assert!(matches!(
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify_any::<SIGMA>(
+                &proof,
+                &circuit,
+                &not_allowed
+            ),
+            Err(Error::FiatShamirOutputsMatchingError)
+        ));
+    }
+
+    #[test]
+    fn test_aggregate_proof() {
+        type P = Prover<u32, ChaCha20Rng, Keccak256>;
+        type V = Verifier<u32, ChaCha20Rng, Keccak256>;
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        let simple = SimpleCircuit1(PhantomData);
+        let simple_input: Vec<u8> = [3u32, 8, 1, 6, 5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let simple_output = simple.compute(&simple_input);
+
+        let adder = AdderCircuit::<u32>::new();
+        let adder_input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let adder_output = adder.compute(&adder_input);
+
+        let instances = [
+            Instance {
+                circuit: &simple,
+                public_output: &simple_output,
+            },
+            Instance {
+                circuit: &adder,
+                public_output: &adder_output,
+            },
+        ];
+
+        let proof = P::prove_many::<_, SIGMA>(
+            &mut rng,
+            &[&simple_input, &adder_input],
+            &instances,
+            b"context",
+        )
+        .unwrap();
+        let bytes = proof.to_bytes().unwrap();
+        let proof = AggregateProof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        V::verify_aggregate::<SIGMA>(&proof, &instances, b"context").unwrap();
+
+        assert!(V::verify_aggregate::<SIGMA>(&proof, &instances, b"other context").is_err());
+        assert!(V::verify_aggregate::<SIGMA>(&proof, &instances[..1], b"context").is_err());
+
+        // statements are bound to their position
+        let mut swapped = AggregateProof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        swapped.proofs.swap(0, 1);
+        let swapped_instances = [
+            Instance {
+                circuit: &adder,
+                public_output: &adder_output,
+            },
+            Instance {
+                circuit: &simple,
+                public_output: &simple_output,
+            },
+        ];
+        assert!(V::verify_aggregate::<SIGMA>(&swapped, &swapped_instances, b"context").is_err());
+
+        let wrong_output = vec![GF2Word::from(12u32)];
+        let wrong = [
+            Instance {
+                circuit: &simple,
+                public_output: &simple_output,
+            },
+            Instance {
+                circuit: &adder,
+                public_output: &wrong_output,
+            },
+        ];
+        assert!(V::verify_aggregate::<SIGMA>(&proof, &wrong, b"context").is_err());
+
+        // a proof taken out of the aggregate does not verify on its own
+        let mut proof = proof;
+        let first = proof.proofs.remove(0);
+        assert!(
+            V::verify_with_context::<SIGMA>(&first, &simple, &simple_output, b"context").is_err()
+        );
+
+        // mismatching witnesses and instances are rejected
+        assert!(matches!(
+            P::prove_many::<_, SIGMA>(&mut rng, &[&adder_input], &instances, b""),
+            Err(Error::StatementMismatchError)
+        ));
+    }
+
+    #[test]
+    fn test_and_composition() {
+        type P = Prover<u32, ChaCha20Rng, Keccak256>;
+        type V = Verifier<u32, ChaCha20Rng, Keccak256>;
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        let adder_input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let simple_input: Vec<u8> = [3u32, 8, 1, 6, 5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let circuit = and(
+            AdderCircuit::<u32>::new(),
+            adder_input.len(),
+            SimpleCircuit1(PhantomData),
+        );
+        let input = [adder_input.clone(), simple_input.clone()].concat();
+
+        let output = circuit.compute(&input);
+        assert_eq!(
+            output,
+            [
+                AdderCircuit::<u32>::new().compute(&adder_input),
+                SimpleCircuit1(PhantomData).compute(&simple_input)
+            ]
+            .concat()
+        );
+
+        let proof = P::prove::<ThreadRng, SIGMA>(&mut rng, &input, &circuit, &output).unwrap();
+        V::verify::<SIGMA>(&proof, &circuit, &output).unwrap();
+
+        // a single proof for both statements, binding both outputs
+        for i in 0..output.len() {
+            let mut wrong = output.clone();
+            wrong[i] = wrong[i] ^ GF2Word::from(1u32);
+            assert!(V::verify::<SIGMA>(&proof, &circuit, &wrong).is_err());
+        }
+        assert_eq!(
+            proof.views[0].messages.len(),
+            AdderCircuit::<u32>::new().num_of_mul_gates()
+                + Circuit::<u32>::num_of_mul_gates(&SimpleCircuit1(PhantomData))
+        );
+    }
+
+    /// Runs the circuit of a ring honestly, except that the third party sends a
+    /// wrong share from the last AND gate, which combines the last member, and
+    /// outputs accordingly. Only the challenge which recomputes the third party
+    /// catches it, so each repetition passes 2 of the 3 challenges.
+    struct CheatingRing<'a>(AnyOf<'a, u32>);
+
+    impl Circuit<u32> for CheatingRing<'_> {
+        fn compute_23_decomposition(
+            &self,
+            p1: &mut Party<u32>,
+            p2: &mut Party<u32>,
+            p3: &mut Party<u32>,
+        ) -> TwoThreeDecOutput<u32> {
+            let (o1, o2, mut o3) = self.0.compute_23_decomposition(p1, p2, p3);
+            let flip = GF2Word::from(1u32);
+            let message = p3.view.messages.last_mut().unwrap();
+            *message = *message ^ flip;
+            o3[0] = o3[0] ^ flip;
+            (o1, o2, o3)
+        }
+
+        fn simulate_two_parties(
+            &self,
+            p: &mut Party<u32>,
+            p_next: &mut Party<u32>,
+        ) -> Result<(Output<u32>, Output<u32>), Error> {
+            self.0.simulate_two_parties(p, p_next)
+        }
+
+        fn party_input_len(&self) -> usize {
+            self.0.party_input_len()
+        }
+
+        fn party_output_len(&self) -> usize {
+            self.0.party_output_len()
+        }
+
+        fn num_of_mul_gates(&self) -> usize {
+            self.0.num_of_mul_gates()
+        }
+
+        fn public_input(&self) -> Vec<u8> {
+            self.0.public_input()
+        }
+
+        fn id(&self) -> Vec<u8> {
+            self.0.id()
+        }
+    }
+
+    #[test]
+    fn test_ring_proof() {
+        type P = Prover<u32, ChaCha20Rng, Keccak256>;
+        type V = Verifier<u32, ChaCha20Rng, Keccak256>;
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        let adder = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let outputs = [
+            vec![GF2Word::from(7u32)],
+            adder.compute(&input),
+            vec![GF2Word::from(9u32)],
+        ];
+        let instances: Vec<_> = outputs
+            .iter()
+            .map(|public_output| Instance {
+                circuit: &adder,
+                public_output,
+            })
+            .collect();
+
+        let proof = P::prove_ring::<_, SIGMA>(&mut rng, &input, &instances, b"context").unwrap();
+        let bytes = proof.to_bytes().unwrap();
+        let proof = RingProof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        V::verify_ring::<SIGMA>(&proof, &instances, b"context").unwrap();
+
+        assert!(V::verify_ring::<SIGMA>(&proof, &instances, b"other context").is_err());
+        assert!(V::verify_ring::<SIGMA>(&proof, &instances[..2], b"context").is_err());
+        assert!(matches!(
+            V::verify_ring::<SIGMA>(&proof, &[], b"context"),
+            Err(Error::StatementMismatchError)
+        ));
+        assert!(matches!(
+            P::prove_ring::<_, SIGMA>(&mut rng, &input, &[], b"context"),
+            Err(Error::StatementMismatchError)
+        ));
+
+        // the circuit only tells whether one of the members holds
+        let ring = any_of(&instances);
+        let wrong_input = [1u32.to_le_bytes(), 1u32.to_le_bytes()].concat();
+        assert_eq!(ring.compute(&input), success_public_output());
+        assert_eq!(ring.compute(&wrong_input), vec![GF2Word::from(0u32)]);
+        assert_eq!(evaluate(&ring, &input), success_public_output());
+        assert_eq!(count_mul_gates(&ring, input.len()), ring.num_of_mul_gates());
+
+        // without a witness for any member, an honest run does not verify
+        let proof =
+            P::prove_ring::<_, SIGMA>(&mut rng, &wrong_input, &instances, b"context").unwrap();
+        assert!(V::verify_ring::<SIGMA>(&proof, &instances, b"context").is_err());
+
+        // nor does a cheating run which opens consistently in every repetition,
+        // as an OR of proofs of the members would, since recomputing the third
+        // party under the last challenge no longer matches its commitment
+        let proof = P::prove_with_context::<_, SIGMA>(
+            &mut rng,
+            &wrong_input,
+            &CheatingRing(any_of(&instances)),
+            &success_public_output(),
+            b"context",
+        )
+        .unwrap();
+        assert!(proof.claimed_trits.contains(&2));
+        for repetition in 0..proof.num_of_repetitions() {
+            V::verify_repetition_proof(
+                &ring,
+                &success_public_output(),
+                repetition,
+                &proof.repetition(repetition),
+            )
+            .unwrap();
+        }
+        assert!(matches!(
+            V::verify_ring::<SIGMA>(&RingProof { proof }, &instances, b"context"),
+            Err(Error::FiatShamirOutputsMatchingError)
+        ));
+
+        // simulated repetitions pass every check but the challenge
+        let trits = vec![2u8; crate::num_of_repetitions_given_desired_security(SIGMA)];
+        let simulated = P::simulate::<_, SIGMA>(&mut rng, &adder, &outputs[0], 8, trits).unwrap();
+        for repetition in 0..simulated.num_of_repetitions() {
+            V::verify_repetition_proof(
+                &adder,
+                &outputs[0],
+                repetition,
+                &simulated.repetition(repetition),
+            )
+            .unwrap();
+        }
+        assert!(matches!(
+            V::verify::<SIGMA>(&simulated, &adder, &outputs[0]),
+            Err(Error::FiatShamirOutputsMatchingError)
+        ));
+    }
+
+    #[test]
+    fn test_merkle_proof() {
+        type P = Prover<u32, ChaCha20Rng, Keccak256>;
+        type V = Verifier<u32, ChaCha20Rng, Keccak256>;
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+        let input: Vec<u8> = [3u32, 8, 1, 6, 5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+
+        let circuit = SimpleCircuit1(PhantomData);
+        let output = circuit.compute(&input);
+
+        let proof =
+            P::prove_merkle::<ThreadRng, SIGMA>(&mut rng, &input, &circuit, &output, b"context")
+                .unwrap();
+        let bytes = proof.to_bytes().unwrap();
+        let proof = MerkleProof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        V::verify_merkle::<SIGMA>(&proof, &circuit, &output, b"context").unwrap();
+
+        // every party of every repetition carries a path
+        assert_eq!(proof.paths.len(), 3 * proof.proof.num_of_repetitions());
+
+        // any subset of the repetitions is checked against the root alone
+        V::verify_merkle_repetitions::<SIGMA>(&proof, &circuit, &output, b"context", [3, 50])
+            .unwrap();
+        assert!(V::verify_merkle_repetitions::<SIGMA>(
+            &proof,
+            &circuit,
+            &output,
+            b"context",
+            [proof.paths.len()]
+        )
+        .is_err());
+        // checking no repetition, or one repetition several times, is not
+        // a partial verification
+        for repetitions in [vec![], vec![0, 0, 0]] {
+            assert!(matches!(
+                V::verify_merkle_repetitions::<SIGMA>(
+                    &proof,
+                    &circuit,
+                    &output,
+                    b"context",
+                    repetitions
+                ),
+                Err(Error::VerificationError)
+            ));
+        }
+
+        assert!(V::verify_merkle::<SIGMA>(&proof, &circuit, &output, b"other").is_err());
+        let wrong_output = vec![GF2Word::from(7u32)];
+        assert!(V::verify_merkle::<SIGMA>(&proof, &circuit, &wrong_output, b"context").is_err());
+
+        // a proof made for an output the witness does not compute is rejected,
+        // even on one repetition, by the leaf of the unopened party
+        let mut forged_output = output.clone();
+        forged_output[0] = forged_output[0] ^ GF2Word::from(1u32);
+        let forged = P::prove_merkle::<ThreadRng, SIGMA>(
+            &mut rng,
+            &input,
+            &circuit,
+            &forged_output,
+            b"context",
+        )
+        .unwrap();
+        for repetitions in [0..forged.proof.num_of_repetitions(), 0..1] {
+            assert!(matches!(
+                V::verify_merkle_repetitions::<SIGMA>(
+                    &forged,
+                    &circuit,
+                    &forged_output,
+                    b"context",
+                    repetitions
+                ),
+                Err(Error::RepetitionError {
+                    check: VerificationCheck::Commitment,
+                    ..
+                })
+            ));
+        }
+
+        // a repetition with a tampered view or path no longer leads to the root
+        let mut tampered = MerkleProof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        tampered.proof.views[7].input[0] ^= 1;
+        assert!(V::verify_merkle_repetitions::<SIGMA>(
+            &tampered,
+            &circuit,
+            &output,
+            b"context",
+            [7]
+        )
+        .is_err());
+        V::verify_merkle_repetitions::<SIGMA>(&tampered, &circuit, &output, b"context", [8])
+            .unwrap();
+
+        let mut tampered = MerkleProof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        tampered.paths[7][0][0] ^= 1;
+        assert!(V::verify_merkle::<SIGMA>(&tampered, &circuit, &output, b"context").is_err());
+
+        // the inner proof is not a regular proof
+        assert!(V::verify::<SIGMA>(&proof.proof, &circuit, &output).is_err());
+    }
+
+    #[cfg(feature = "constant-time")]
+    #[test]
+    fn test_constant_time_verification() {
+        const SIGMA: usize = 40;
+        type V = Verifier<u32, ChaCha20Rng, Keccak256>;
+
+        let mut rng = thread_rng();
+        let input: Vec<u8> = [3u32, 8, 1, 6, 5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let circuit = SimpleCircuit1(PhantomData);
+        let output = circuit.compute(&input);
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove_with_context::<_, SIGMA>(
+            &mut rng, &input, &circuit, &output, b"context",
+        )
+        .unwrap();
+        V::verify_constant_time::<SIGMA>(&proof, &circuit, &output, b"context").unwrap();
+
+        // every failure is reported the same way
+        assert!(matches!(
+            V::verify_constant_time::<SIGMA>(&proof, &circuit, &output, b"other"),
+            Err(Error::VerificationError)
+        ));
+        let bytes = proof.to_bytes().unwrap();
+        let mut tampered = Proof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        tampered.views[3].messages[0].value ^= 1;
+        assert!(matches!(
+            V::verify_constant_time::<SIGMA>(&tampered, &circuit, &output, b"context"),
+            Err(Error::VerificationError)
+        ));
+        let mut tampered = Proof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        tampered.claimed_trits[0] = 3;
+        assert!(matches!(
+            V::verify_constant_time::<SIGMA>(&tampered, &circuit, &output, b"context"),
+            Err(Error::VerificationError)
+        ));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing() {
+        use std::sync::{Arc, Mutex};
+
+        use tracing::{
+            field::{Field, Visit},
+            span::{Attributes, Id, Record},
+            Event, Metadata, Subscriber,
+        };
+
+        /// Records the names of spans and sums the counters of events.
+        #[derive(Default)]
+        struct Recorder {
+            spans: Mutex<Vec<&'static str>>,
+            counters: Mutex<(u64, u64)>,
+        }
+
+        impl Visit for &Recorder {
+            fn record_u64(&mut self, field: &Field, value: u64) {
+                let mut counters = self.counters.lock().unwrap();
+                match field.name() {
+                    "tape_bytes" => counters.0 += value,
+                    "and_gates" => counters.1 += value,
+                    _ => {}
+                }
+            }
+
+            fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut spans = self.spans.lock().unwrap();
+                spans.push(span.metadata().name());
+                Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                event.record(&mut &*self);
+            }
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
+        }
+
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+        let input: Vec<u8> = [3u32, 8, 1, 6, 5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let circuit = SimpleCircuit1(PhantomData);
+        let output = circuit.compute(&input);
+
+        let recorder = Arc::new(Recorder::default());
+        let proof = tracing::subscriber::with_default(recorder.clone(), || {
+            Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+                &mut rng, &input, &circuit, &output,
+            )
+            .unwrap()
+        });
+
+        // three parties read two words each and run two AND gates per repetition
+        let spans = recorder.spans.lock().unwrap().clone();
+        assert_eq!(*recorder.counters.lock().unwrap(), (69 * 3 * 2 * 4, 69 * 2));
+        let count = |name| spans.iter().filter(|&&span| span == name).count();
+        assert_eq!(count("prove_repetition"), 69);
+        assert_eq!(count("commit"), 1 + 3 * 69);
+        assert_eq!(count("digest_prover_message"), 1);
+        assert_eq!(count("sample_trits"), 1);
+
+        let recorder = Arc::new(Recorder::default());
+        tracing::subscriber::with_default(recorder.clone(), || {
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+                .unwrap()
+        });
+        let spans = recorder.spans.lock().unwrap().clone();
+        assert_eq!(*recorder.counters.lock().unwrap(), (69 * 2 * 2 * 4, 69 * 2));
+        assert_eq!(
+            spans
+                .iter()
+                .filter(|&&span| span == "verify_repetition")
+                .count(),
+            69
+        );
+        assert!(spans.contains(&"check_fiat_shamir"));
+    }
+
+    #[cfg(feature = "debug-trace")]
+    #[test]
+    fn test_proof_trace() {
+        use crate::commitment::{Commitment, CommitmentScheme, HashCommitment};
+
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+        let input: Vec<u8> = [3u32, 8, 1, 6, 5]
+            .iter()
+            .flat_map(|x| x.to_le_bytes())
+            .collect();
+        let circuit = SimpleCircuit1(PhantomData);
+        let output = circuit.compute(&input);
+
+        let (proof, trace) = Prover::<u32, ChaCha20Rng, Keccak256>::prove_with_trace::<_, SIGMA>(
+            &mut rng, &input, &circuit, &output, b"context",
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify_with_context::<SIGMA>(
+            &proof, &circuit, &output, b"context",
+        )
+        .unwrap();
+
+        assert_eq!(trace.views.len(), 3 * 69);
+        assert_eq!(trace.challenge, proof.claimed_trits);
+        for (i, view) in trace.views.iter().enumerate() {
+            let commitment: Commitment<Keccak256> =
+                HashCommitment::commit_view(&trace.keys[i], view).unwrap();
+            assert_eq!(commitment.data, trace.commitments[i].data);
+        }
+
+        // the proof opens the views and keys of the trace
+        for (repetition, &party) in trace.challenge.iter().enumerate() {
+            let i0 = 3 * repetition + party as usize;
+            let i1 = 3 * repetition + (party as usize + 1) % 3;
+            assert_eq!(proof.party_inputs[repetition], trace.views[i0].input);
+            assert_eq!(proof.views[repetition].messages, trace.views[i1].messages);
+            assert_eq!(proof.keys[2 * repetition], trace.keys[i0]);
+            assert_eq!(proof.keys[2 * repetition + 1], trace.keys[i1]);
+        }
     }
 }
@@ -0,0 +1,231 @@
+//! Consistency checks between the two ways a circuit is run.
+//!
+//! The prover runs `Circuit::compute_23_decomposition` on three parties and
+//! the verifier runs `Circuit::simulate_two_parties` on two of them. A gadget
+//! whose two halves disagree, e.g. a gate computed differently by the
+//! simulation, gives a circuit for which honest proofs fail to verify or, worse,
+//! for which the verifier checks something else than the prover computed.
+//! `check_circuit` catches such circuits on random witnesses.
+
+use rand::{CryptoRng, RngCore};
+use rand_chacha::ChaCha20Rng;
+
+use crate::{
+    circuit::{Circuit, TwoThreeDecOutput},
+    error::Error,
+    expander::tape_label,
+    gf2_word::{GenRand, Value},
+    key::Key,
+    party::Party,
+    tape::Tape,
+    view::View,
+};
+
+/// Run `circuit` on `num_of_witnesses` random witnesses of `witness_len`
+/// bytes, see `check_witness`.
+pub fn check_circuit<T, R>(
+    rng: &mut R,
+    circuit: &impl Circuit<T>,
+    witness_len: usize,
+    num_of_witnesses: usize,
+) -> Result<(), Error>
+where
+    T: Value + PartialEq,
+    R: RngCore + CryptoRng,
+{
+    (0..num_of_witnesses).try_for_each(|_| {
+        let witness = u8::gen_rand_packed(rng, witness_len);
+        check_witness(rng, circuit, &witness)
+    })
+}
+
+/// Share `witness` among three parties with random tapes and run `circuit` on
+/// them, then simulate each pair of consecutive parties from the view of the
+/// second. Fails with `Error::CircuitConsistencyError` naming the first check
+/// which does not hold:
+///
+/// - `"mul gates"`: a party read more or less tape than `num_of_mul_gates`;
+/// - `"output len"`: a party output other than `party_output_len` words;
+/// - `"reconstruction"`: the outputs of the parties do not add up to
+///   `compute`;
+/// - `"simulated output"`, `"simulated view"`, `"simulated tape"`: a simulated
+///   party output, sent or read from its tape something else than it did when
+///   run by the prover;
+/// - `"unread view"`: the simulation skipped messages of the second party.
+pub fn check_witness<T, R>(
+    rng: &mut R,
+    circuit: &impl Circuit<T>,
+    witness: &[u8],
+) -> Result<(), Error>
+where
+    T: Value + PartialEq,
+    R: RngCore + CryptoRng,
+{
+    let check = |holds: bool, name: &'static str| {
+        if holds {
+            Ok(())
+        } else {
+            Err(Error::CircuitConsistencyError(name))
+        }
+    };
+
+    let share_1 = u8::gen_rand_packed(rng, witness.len());
+    let share_2 = u8::gen_rand_packed(rng, witness.len());
+    let share_3: Vec<u8> = witness
+        .iter()
+        .zip(&share_1)
+        .zip(&share_2)
+        .map(|((&x, &s1), &s2)| x ^ s1 ^ s2)
+        .collect();
+    let shares = [share_1, share_2, share_3];
+
+    let mut keys = [Key::default(); 3];
+    for key in keys.iter_mut() {
+        rng.fill_bytes(key);
+    }
+    let tape = |party: usize| {
+        Tape::<T>::from_key::<ChaCha20Rng>(
+            keys[party],
+            &tape_label(0, party),
+            circuit.num_of_mul_gates(),
+        )
+    };
+
+    let mut parties: Vec<Party<T>> = (0..3)
+        .map(|party| Party::from_share_and_tape(shares[party].clone(), tape(party)))
+        .collect();
+    let [p1, p2, p3] = &mut parties[..] else {
+        unreachable!()
+    };
+    let (o1, o2, o3): TwoThreeDecOutput<T> = circuit.compute_23_decomposition(p1, p2, p3);
+
+    check(
+        parties.iter().all(|p| p.tape.check_consumed().is_ok()),
+        "mul gates",
+    )?;
+    let outputs = [o1, o2, o3];
+    check(
+        outputs
+            .iter()
+            .all(|output| output.len() == circuit.party_output_len()),
+        "output len",
+    )?;
+    let reconstructed: Vec<_> = outputs[0]
+        .iter()
+        .zip(&outputs[1])
+        .zip(&outputs[2])
+        .map(|((&w1, &w2), &w3)| w1 ^ w2 ^ w3)
+        .collect();
+    check(reconstructed == circuit.compute(witness), "reconstruction")?;
+
+    for party in 0..3 {
+        let next = (party + 1) % 3;
+        let mut p = Party::from_share_and_tape(shares[party].clone(), tape(party));
+        let view_next: &View<T> = &parties[next].view;
+        let mut p_next = Party::from_tape_and_view(view_next.clone(), tape(next));
+
+        let simulated = circuit
+            .simulate_two_parties(&mut p, &mut p_next)
+            .map_err(|_| Error::CircuitConsistencyError("simulated output"))?;
+        check(
+            simulated == (outputs[party].clone(), outputs[next].clone()),
+            "simulated output",
+        )?;
+        check(
+            p.view.messages == parties[party].view.messages,
+            "simulated view",
+        )?;
+        check(
+            p.tape.offset() == parties[party].tape.offset()
+                && p_next.tape.offset() == parties[next].tape.offset(),
+            "simulated tape",
+        )?;
+        check(
+            p_next.view.offset() == view_next.messages.len(),
+            "unread view",
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod testing_tests {
+    use std::marker::PhantomData;
+
+    use rand::thread_rng;
+
+    use super::check_circuit;
+    use crate::{
+        circuit::{Circuit, Output, TwoThreeDecOutput},
+        error::Error,
+        gadgets::{
+            add_mod::AdderCircuit, mpc_and, mpc_xor, prepare::generic_parse, sha256::Sha256Circuit,
+        },
+        gf2_word::{GF2Word, Value},
+        party::Party,
+    };
+
+    #[test]
+    fn test_gadgets_are_consistent() {
+        let mut rng = thread_rng();
+        check_circuit::<u32, _>(&mut rng, &AdderCircuit::<u32>::new(), 8, 20).unwrap();
+        check_circuit::<u32, _>(&mut rng, &Sha256Circuit::new(3), 3, 2).unwrap();
+    }
+
+    // computes x1 & x2, but the simulation computes x1 ^ x2
+    struct MismatchedCircuit<T>(PhantomData<T>);
+
+    impl<T: Value> Circuit<T> for MismatchedCircuit<T> {
+        fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+            let x = generic_parse(input, 2).unwrap();
+            vec![x[0] & x[1]]
+        }
+
+        fn compute_23_decomposition(
+            &self,
+            p1: &mut Party<T>,
+            p2: &mut Party<T>,
+            p3: &mut Party<T>,
+        ) -> TwoThreeDecOutput<T> {
+            let x1 = generic_parse(&p1.view.input, 2).unwrap();
+            let x2 = generic_parse(&p2.view.input, 2).unwrap();
+            let x3 = generic_parse(&p3.view.input, 2).unwrap();
+            let (o1, o2, o3) = mpc_and((x1[0], x1[1]), (x2[0], x2[1]), (x3[0], x3[1]), p1, p2, p3);
+            (vec![o1], vec![o2], vec![o3])
+        }
+
+        fn simulate_two_parties(
+            &self,
+            p: &mut Party<T>,
+            p_next: &mut Party<T>,
+        ) -> Result<(Output<T>, Output<T>), Error> {
+            let x = generic_parse(&p.view.input, 2)?;
+            let x_next = generic_parse(&p_next.view.input, 2)?;
+            let (o, o_next, _) = mpc_xor((x[0], x[1]), (x_next[0], x_next[1]), (x[0], x[1]));
+            Ok((vec![o], vec![o_next]))
+        }
+
+        fn party_input_len(&self) -> usize {
+            2
+        }
+
+        fn party_output_len(&self) -> usize {
+            1
+        }
+
+        fn num_of_mul_gates(&self) -> usize {
+            1
+        }
+    }
+
+    #[test]
+    fn test_mismatched_circuit() {
+        let mut rng = thread_rng();
+        let circuit = MismatchedCircuit::<u32>(PhantomData);
+        assert!(matches!(
+            check_circuit::<u32, _>(&mut rng, &circuit, 8, 1),
+            Err(Error::CircuitConsistencyError("simulated output"))
+        ));
+    }
+}
@@ -1,11 +1,136 @@
-use std::marker::PhantomData;
+//! Hash commitments, `H(blinding || message)` with bincode encodings.
+//!
+//! The prover commits to the views of the parties with them, through a
+//! `CommitmentScheme`. Behind the
+//! `commitment` feature they are also usable on their own: `Commitment::commit_random`
+//! commits to a message under a fresh random blinding and returns the `Opening`,
+//! commitments serialize to `HASH_LEN` bytes, and `Opening::verify` checks an
+//! opening in constant time.
+//!
+//! A commitment can also be made to the digest of its message, `H(blinding ||
+//! H(message))`, and opened by a `DigestOpening` without revealing the message.
+//! `DigestCommitment` commits to views that way, so that the commitment of the
+//! unopened party could be opened by other means than its key, as in the
+//! Merkle and KKW variants of the protocol.
+//!
+//! Behind the `blake3` feature, `Blake3Commitment` commits to views with BLAKE3
+//! in keyed mode, which is much faster than SHA-3 on the long views of large
+//! circuits. Proofs made with it use `Blake3` as their hash, which also drives
+//! the default Fiat-Shamir oracle.
+
+use std::{
+    io::{self, Write},
+    marker::PhantomData,
+};
 
 use serde::{Deserialize, Serialize};
 use sha3::Digest;
 
-use crate::{config::HASH_LEN, error::Error};
+use crate::{
+    config::HASH_LEN,
+    data_structures::PartyExecution,
+    error::Error,
+    gf2_word::Value,
+    key::{derive_blinding, Key},
+    view::View,
+};
+
+/// How the prover commits to the view of each party, and the verifier
+/// recomputes the commitments of the opened ones.
+///
+/// Proofs only carry the keys of the opened parties, so a scheme derives any
+/// blinding it needs from the key, under a label that keeps it apart from the
+/// tape seeded with the same key. The prover and the verifier must use the
+/// same scheme, `HashCommitment` unless chosen otherwise.
+pub trait CommitmentScheme<D: Default + Digest + Clone> {
+    fn commit_view<T: Value>(key: &Key, view: &View<T>) -> Result<Commitment<D>, Error>;
+}
 
-#[derive(Default, Serialize, Deserialize)]
+/// Hash of the input and messages of the party, blinded with a value derived
+/// from its key, see `PartyExecution::commit`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HashCommitment;
+
+impl<D: Default + Digest + Clone> CommitmentScheme<D> for HashCommitment {
+    fn commit_view<T: Value>(key: &Key, view: &View<T>) -> Result<Commitment<D>, Error> {
+        PartyExecution { key, view }.commit()
+    }
+}
+
+/// Commits to the digest of the view of a party, `H(blinding || H(len: u64 ||
+/// view))`, with the blinding of `HashCommitment`. See `DigestOpening::of_view`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DigestCommitment;
+
+impl<D: Default + Digest + Clone> CommitmentScheme<D> for DigestCommitment {
+    fn commit_view<T: Value>(key: &Key, view: &View<T>) -> Result<Commitment<D>, Error> {
+        DigestOpening::<D>::of_view(key, view)?.commit()
+    }
+}
+
+/// What opens a commitment to the digest of a message: the blinding and the
+/// digest, but not the message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DigestOpening<D> {
+    pub blinding: [u8; HASH_LEN],
+    pub digest: [u8; HASH_LEN],
+    _digest: PhantomData<D>,
+}
+
+impl<D: Default + Digest + Clone> DigestOpening<D> {
+    pub fn new(blinding: [u8; HASH_LEN], digest: [u8; HASH_LEN]) -> Self {
+        Self {
+            blinding,
+            digest,
+            _digest: PhantomData,
+        }
+    }
+
+    /// The opening of the commitment of `DigestCommitment` to `view`, for the
+    /// party holding `key`.
+    pub fn of_view<T: Value>(key: &Key, view: &View<T>) -> Result<Self, Error> {
+        let blinding = derive_blinding::<D>(key)?;
+
+        let mut hasher = D::new();
+        Digest::update(&mut hasher, (view.encoded_len() as u64).to_le_bytes());
+        view.write_be(&mut HashWriter(&mut hasher))
+            .map_err(|_| Error::SerializationError)?;
+        // safe to unwrap since `derive_blinding` checked the digest length
+        let digest = hasher.finalize().to_vec().try_into().unwrap();
+
+        Ok(Self::new(blinding, digest))
+    }
+
+    /// The commitment this opens.
+    pub fn commit(&self) -> Result<Commitment<D>, Error> {
+        Commitment::commit(&Blinding(self.blinding), &self.digest)
+    }
+}
+
+/// BLAKE3, with the `Digest` traits this crate's hashes implement.
+#[cfg(feature = "blake3")]
+pub type Blake3 = blake3::Hasher;
+
+/// BLAKE3 keyed with the blinding derived from the key of the party, over the
+/// bytes `HashCommitment` hashes after its blinding: `keyed_hash(blinding,
+/// len: u64 || view)`.
+#[cfg(feature = "blake3")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Blake3Commitment;
+
+#[cfg(feature = "blake3")]
+impl CommitmentScheme<Blake3> for Blake3Commitment {
+    fn commit_view<T: Value>(key: &Key, view: &View<T>) -> Result<Commitment<Blake3>, Error> {
+        let blinding = derive_blinding::<Blake3>(key)?;
+        let mut hasher = Blake3::new_keyed(&blinding);
+        hasher.update(&(view.encoded_len() as u64).to_le_bytes());
+        view.write_be(&mut hasher)
+            .map_err(|_| Error::SerializationError)?;
+        Ok(Commitment::from_data(*Blake3::finalize(&hasher).as_bytes()))
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Blinding<T: Serialize>(pub T);
 impl<T: Serialize> AsRef<T> for Blinding<T> {
     fn as_ref(&self) -> &T {
@@ -20,29 +145,44 @@ pub struct Commitment<D: Default + Digest + Clone> {
 }
 
 impl<D: Default + Digest + Clone> Commitment<D> {
+    pub(crate) fn from_data(data: [u8; HASH_LEN]) -> Self {
+        Self {
+            data,
+            _digest: PhantomData,
+        }
+    }
+
     /// Commit to a given `message` using by hashing it with some `blinding`.
     pub fn commit<U: Serialize, T: Serialize>(
         blinding: &Blinding<U>,
         message: &T,
     ) -> Result<Self, Error> {
-        let digest_len = <D as Digest>::output_size();
-        if HASH_LEN != digest_len {
-            return Err(Error::HashLenError(HASH_LEN, digest_len));
-        }
-
-        let blinding =
-            bincode::serialize(blinding.as_ref()).map_err(|_| Error::SerializationError)?;
-        let message = bincode::serialize(message).map_err(|_| Error::SerializationError)?;
+        let mut hasher = Self::blinded_hasher(blinding)?;
+        bincode::serialize_into(HashWriter(&mut hasher), message)
+            .map_err(|_| Error::SerializationError)?;
+        Ok(Self::from_hasher(hasher))
+    }
 
-        let mut hasher: D = Digest::new_with_prefix(blinding);
-        hasher.update(&message);
+    /// Like `commit` of the `len` bytes written to the `Absorber` by `message`
+    /// as a `Vec<u8>`, hashing them as they are written rather than gathering
+    /// them first. Fails if `message` writes another number of bytes.
+    pub fn commit_bytes<U: Serialize>(
+        blinding: &Blinding<U>,
+        len: usize,
+        message: impl FnOnce(&mut Absorber<D>) -> io::Result<()>,
+    ) -> Result<Self, Error> {
+        let mut hasher = Self::blinded_hasher(blinding)?;
+        Digest::update(&mut hasher, (len as u64).to_le_bytes());
 
-        // safe to unwrap since we check digest output is of right side
-        let data = hasher.finalize().to_vec().try_into().unwrap();
-        Ok(Self {
-            data,
-            _digest: PhantomData,
-        })
+        let mut absorber = Absorber {
+            hasher,
+            remaining: Some(len),
+        };
+        message(&mut absorber).map_err(|_| Error::SerializationError)?;
+        if absorber.remaining != Some(0) {
+            return Err(Error::SerializationError);
+        }
+        Ok(Self::from_hasher(absorber.hasher))
     }
 
     /// Verify that the `Commitment` is consistent with a given opening.
@@ -51,28 +191,162 @@ impl<D: Default + Digest + Clone> Commitment<D> {
         blinding: &Blinding<U>,
         message: &T,
     ) -> Result<bool, Error> {
+        Ok(Self::commit(blinding, message)?.data == self.data)
+    }
+
+    /// Verify that the `Commitment` is to the digest opened by `opening`.
+    pub fn verify_digest_opening(&self, opening: &DigestOpening<D>) -> Result<bool, Error> {
+        Ok(opening.commit()?.data == self.data)
+    }
+
+    fn blinded_hasher<U: Serialize>(blinding: &Blinding<U>) -> Result<D, Error> {
         let digest_len = <D as Digest>::output_size();
         if HASH_LEN != digest_len {
             return Err(Error::HashLenError(HASH_LEN, digest_len));
         }
-        let blinding =
-            bincode::serialize(blinding.as_ref()).map_err(|_| Error::SerializationError)?;
-        let message = bincode::serialize(message).map_err(|_| Error::SerializationError)?;
 
-        let mut hasher: D = Digest::new_with_prefix(blinding);
-        hasher.update(&message);
+        let mut hasher = D::new();
+        bincode::serialize_into(HashWriter(&mut hasher), blinding.as_ref())
+            .map_err(|_| Error::SerializationError)?;
+        Ok(hasher)
+    }
 
+    fn from_hasher(hasher: D) -> Self {
         // safe to unwrap since we check digest output is of right side
-        let claimed_data: [u8; HASH_LEN] = hasher.finalize().to_vec().try_into().unwrap();
-        Ok(claimed_data == self.data)
+        Self::from_data(hasher.finalize().to_vec().try_into().unwrap())
+    }
+}
+
+/// Feeds what bincode writes to a hasher.
+struct HashWriter<'a, D>(&'a mut D);
+
+impl<D: Digest> Write for HashWriter<'_, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Digest::update(self.0, buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The message of `Commitment::commit_bytes`, hashed as it is written.
+pub struct Absorber<D> {
+    hasher: D,
+    /// `None` once more bytes than announced were written.
+    remaining: Option<usize>,
+}
+
+impl<D: Digest> Absorber<D> {
+    pub fn absorb(&mut self, bytes: &[u8]) {
+        self.remaining = self
+            .remaining
+            .and_then(|remaining| remaining.checked_sub(bytes.len()));
+        Digest::update(&mut self.hasher, bytes);
+    }
+}
+
+impl<D: Digest> Write for Absorber<D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.absorb(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "commitment")]
+pub use standalone::*;
+
+#[cfg(feature = "commitment")]
+mod standalone {
+    use rand_core::{CryptoRng, RngCore};
+    use serde::{Deserialize, Serialize};
+    use sha3::Digest;
+    use subtle::ConstantTimeEq;
+
+    use super::{Blinding, Commitment};
+    use crate::{config::HASH_LEN, error::Error};
+
+    pub const BLINDING_LEN: usize = 32;
+
+    impl Blinding<[u8; BLINDING_LEN]> {
+        pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+            let mut blinding = [0u8; BLINDING_LEN];
+            rng.fill_bytes(&mut blinding);
+            Self(blinding)
+        }
+    }
+
+    /// What a committer reveals to open a `Commitment`.
+    #[derive(Clone, Serialize, Deserialize)]
+    pub struct Opening<T: Serialize> {
+        pub blinding: Blinding<[u8; BLINDING_LEN]>,
+        pub message: T,
+    }
+
+    impl<T: Serialize> Opening<T> {
+        /// Checks that `commitment` opens to this message, comparing the
+        /// digests in constant time.
+        pub fn verify<D: Default + Digest + Clone>(
+            &self,
+            commitment: &Commitment<D>,
+        ) -> Result<(), Error> {
+            let claimed = Commitment::<D>::commit(&self.blinding, &self.message)?;
+            if bool::from(claimed.data.ct_eq(&commitment.data)) {
+                Ok(())
+            } else {
+                Err(Error::VerificationError)
+            }
+        }
+    }
+
+    impl<D: Default + Digest + Clone> Commitment<D> {
+        /// Commits to `message` under a fresh blinding drawn from `rng`.
+        pub fn commit_random<R: RngCore + CryptoRng, T: Serialize>(
+            rng: &mut R,
+            message: T,
+        ) -> Result<(Self, Opening<T>), Error> {
+            let blinding = Blinding::random(rng);
+            let commitment = Self::commit(&blinding, &message)?;
+            Ok((commitment, Opening { blinding, message }))
+        }
+
+        pub fn to_bytes(&self) -> [u8; HASH_LEN] {
+            self.data
+        }
+
+        pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+            let digest_len = <D as Digest>::output_size();
+            if HASH_LEN != digest_len {
+                return Err(Error::HashLenError(HASH_LEN, digest_len));
+            }
+            let data = bytes.try_into().map_err(|_| Error::SerializationError)?;
+            Ok(Self {
+                data,
+                _digest: Default::default(),
+            })
+        }
     }
 }
 
 #[cfg(test)]
 mod commitment_tests {
-    use sha3::{Keccak224, Keccak256};
+    use std::io::Write;
 
-    use super::{Blinding, Commitment};
+    use rand::thread_rng;
+    use rand_chacha::ChaCha20Rng;
+    use sha3::{Digest, Keccak224, Keccak256};
+
+    use crate::{
+        circuit::Circuit, error::Error, gadgets::add_mod::AdderCircuit, gf2_word::Value, key::Key,
+        prover::Prover, verifier::Verifier, view::View,
+    };
+
+    use super::{Blinding, Commitment, CommitmentScheme, DigestCommitment, DigestOpening};
 
     #[test]
     fn test_commitment() {
@@ -83,6 +357,32 @@ mod commitment_tests {
         assert!(c.verify_opening(&blinding, &message).unwrap())
     }
 
+    #[test]
+    fn test_commit_bytes() {
+        let blinding = Blinding([7u8; 32]);
+        let message = b"streamed in pieces".to_vec();
+
+        // the same commitment as to the gathered bytes
+        let c = Commitment::<Keccak256>::commit_bytes(&blinding, message.len(), |absorber| {
+            for chunk in message.chunks(5) {
+                absorber.absorb(chunk);
+            }
+            Ok(())
+        })
+        .unwrap();
+        assert!(c.verify_opening(&blinding, &message).unwrap());
+
+        // fewer or more bytes than announced
+        for len in [message.len() - 1, message.len() + 1] {
+            assert!(
+                Commitment::<Keccak256>::commit_bytes(&blinding, len, |absorber| {
+                    absorber.write_all(&message)
+                })
+                .is_err()
+            );
+        }
+    }
+
     #[test]
     #[should_panic]
     fn test_wrong_hash_len() {
@@ -91,4 +391,145 @@ mod commitment_tests {
 
         let _ = Commitment::<Keccak224>::commit(&blinding, &message).unwrap();
     }
+
+    /// Binds the views under a domain separation tag.
+    struct TaggedCommitment;
+
+    impl CommitmentScheme<Keccak256> for TaggedCommitment {
+        fn commit_view<T: Value>(
+            key: &Key,
+            view: &View<T>,
+        ) -> Result<Commitment<Keccak256>, Error> {
+            Commitment::commit(&Blinding((b"tagged", key)), view)
+        }
+    }
+
+    #[test]
+    fn test_commitment_scheme() {
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256, TaggedCommitment>::prove::<_, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256, TaggedCommitment>::verify::<SIGMA>(
+            &proof, &circuit, &output,
+        )
+        .unwrap();
+
+        // proofs only verify under the scheme they were made with
+        assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+            &proof, &circuit, &output
+        )
+        .is_err());
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<_, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+        assert!(
+            Verifier::<u32, ChaCha20Rng, Keccak256, TaggedCommitment>::verify::<SIGMA>(
+                &proof, &circuit, &output,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_digest_opening() {
+        const SIGMA: usize = 40;
+        type P = Prover<u32, ChaCha20Rng, Keccak256, DigestCommitment>;
+        type V = Verifier<u32, ChaCha20Rng, Keccak256, DigestCommitment>;
+
+        let key: Key = [3u8; 32];
+        let mut view = View::<u32>::new(vec![1, 2, 3]);
+        view.send_msg(9u32.into());
+
+        let c = DigestCommitment::commit_view::<u32>(&key, &view).unwrap();
+        let opening = DigestOpening::<Keccak256>::of_view(&key, &view).unwrap();
+        assert!(c.verify_digest_opening(&opening).unwrap());
+        let digest = Keccak256::new()
+            .chain_update((view.encoded_len() as u64).to_le_bytes())
+            .chain_update(view.to_bytes_be())
+            .finalize();
+        assert_eq!(opening.digest, digest.as_slice());
+
+        // another view or blinding does not open it
+        view.send_msg(1u32.into());
+        let other = DigestOpening::<Keccak256>::of_view(&key, &view).unwrap();
+        assert!(!c.verify_digest_opening(&other).unwrap());
+        let other = DigestOpening::new([0; 32], opening.digest);
+        assert!(!c.verify_digest_opening(&other).unwrap());
+
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+        let proof = P::prove::<_, SIGMA>(&mut rng, &input, &circuit, &output).unwrap();
+        V::verify::<SIGMA>(&proof, &circuit, &output).unwrap();
+        assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+            &proof, &circuit, &output
+        )
+        .is_err());
+    }
+
+    #[cfg(feature = "commitment")]
+    #[test]
+    fn test_standalone_commitment() {
+        use super::Opening;
+
+        let (c, opening) =
+            Commitment::<Keccak256>::commit_random(&mut thread_rng(), b"bid: 42".to_vec()).unwrap();
+        opening.verify(&c).unwrap();
+
+        let c = Commitment::<Keccak256>::from_bytes(&c.to_bytes()).unwrap();
+        opening.verify(&c).unwrap();
+        assert!(Commitment::<Keccak256>::from_bytes(&[0; 31]).is_err());
+
+        let forged = Opening {
+            blinding: opening.blinding.clone(),
+            message: b"bid: 43".to_vec(),
+        };
+        assert!(forged.verify(&c).is_err());
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_blake3_commitment() {
+        use super::{Blake3, Blake3Commitment, HashCommitment};
+        use crate::key::derive_blinding;
+
+        const SIGMA: usize = 40;
+        type P<C> = Prover<u32, ChaCha20Rng, Blake3, C>;
+        type V<C> = Verifier<u32, ChaCha20Rng, Blake3, C>;
+
+        let key: Key = [3u8; 32];
+        let mut view = View::<u32>::new(vec![1, 2, 3]);
+        view.send_msg(9u32.into());
+
+        let blinding = derive_blinding::<Blake3>(&key).unwrap();
+        let encoding = [
+            (view.encoded_len() as u64).to_le_bytes().to_vec(),
+            view.to_bytes_be(),
+        ]
+        .concat();
+        assert_eq!(
+            Blake3Commitment::commit_view(&key, &view).unwrap().data,
+            *blake3::keyed_hash(&blinding, &encoding).as_bytes()
+        );
+
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        // BLAKE3 for the commitments and the oracle
+        let proof =
+            P::<Blake3Commitment>::prove::<_, SIGMA>(&mut rng, &input, &circuit, &output).unwrap();
+        V::<Blake3Commitment>::verify::<SIGMA>(&proof, &circuit, &output).unwrap();
+        assert!(V::<HashCommitment>::verify::<SIGMA>(&proof, &circuit, &output).is_err());
+    }
 }
@@ -0,0 +1,319 @@
+//! Conformance suite for transports carrying the interactive protocol.
+//!
+//! Integrators plug their network glue in as a [`Transport`] and let
+//! [`ConformanceSuite`] drive the crate's `InteractiveProver` and
+//! `InteractiveVerifier` through it. Besides an honest session the suite
+//! replays scripted attacks which both state machines must reject.
+//!
+//! Messages travel as bytes:
+//! - first message: bincode of `FirstMessageA`;
+//! - challenge: one byte per repetition, each in `{0, 1, 2}`;
+//! - proof: `Proof::to_bytes`.
+
+use std::{collections::VecDeque, fmt::Debug, io, marker::PhantomData};
+
+use rand_core::{CryptoRng, RngCore};
+use serde::de::DeserializeOwned;
+use sha3::{digest::FixedOutputReset, Digest};
+
+use crate::{
+    circuit::Circuit,
+    data_structures::{FirstMessageA, Proof},
+    error::Error,
+    expander::TapeExpander,
+    gf2_word::Value,
+    prover::InteractiveProver,
+    verifier::InteractiveVerifier,
+};
+
+/// A byte channel between a prover endpoint and a verifier endpoint.
+///
+/// Every message sent by one endpoint must be received unchanged and in order
+/// by the other one.
+pub trait Transport {
+    fn send_to_verifier(&mut self, msg: &[u8]) -> io::Result<()>;
+    fn recv_from_prover(&mut self) -> io::Result<Vec<u8>>;
+    fn send_to_prover(&mut self, msg: &[u8]) -> io::Result<()>;
+    fn recv_from_verifier(&mut self) -> io::Result<Vec<u8>>;
+}
+
+/// In-memory transport, useful as a reference implementation.
+#[derive(Default)]
+pub struct LoopbackTransport {
+    to_verifier: VecDeque<Vec<u8>>,
+    to_prover: VecDeque<Vec<u8>>,
+}
+
+fn pop(queue: &mut VecDeque<Vec<u8>>) -> io::Result<Vec<u8>> {
+    queue
+        .pop_front()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "no message in flight"))
+}
+
+impl Transport for LoopbackTransport {
+    fn send_to_verifier(&mut self, msg: &[u8]) -> io::Result<()> {
+        self.to_verifier.push_back(msg.to_vec());
+        Ok(())
+    }
+
+    fn recv_from_prover(&mut self) -> io::Result<Vec<u8>> {
+        pop(&mut self.to_verifier)
+    }
+
+    fn send_to_prover(&mut self, msg: &[u8]) -> io::Result<()> {
+        self.to_prover.push_back(msg.to_vec());
+        Ok(())
+    }
+
+    fn recv_from_verifier(&mut self) -> io::Result<Vec<u8>> {
+        pop(&mut self.to_prover)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scenario {
+    /// Both endpoints follow the protocol; the proof must be accepted.
+    Honest,
+    /// The verifier's challenge is one trit short; the prover must refuse to answer.
+    WrongChallengeLength,
+    /// The first message of an earlier session is replayed in a fresh one.
+    ReplayedFirstMessage,
+    /// A single commitment of the first message is altered in transit.
+    MutatedCommitment,
+}
+
+impl Scenario {
+    pub fn all() -> [Scenario; 4] {
+        [
+            Scenario::Honest,
+            Scenario::WrongChallengeLength,
+            Scenario::ReplayedFirstMessage,
+            Scenario::MutatedCommitment,
+        ]
+    }
+
+    pub fn expects_acceptance(&self) -> bool {
+        matches!(self, Scenario::Honest)
+    }
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    Accepted,
+    Rejected(Error),
+    TransportFailure(io::Error),
+}
+
+impl From<Error> for Outcome {
+    fn from(err: Error) -> Self {
+        Outcome::Rejected(err)
+    }
+}
+
+impl From<io::Error> for Outcome {
+    fn from(err: io::Error) -> Self {
+        Outcome::TransportFailure(err)
+    }
+}
+
+#[derive(Debug)]
+pub struct ScenarioResult {
+    pub scenario: Scenario,
+    pub outcome: Outcome,
+}
+
+impl ScenarioResult {
+    /// Honest sessions must be accepted, adversarial ones rejected by the
+    /// protocol. A broken transport never passes.
+    pub fn passed(&self) -> bool {
+        match self.outcome {
+            Outcome::Accepted => self.scenario.expects_acceptance(),
+            Outcome::Rejected(_) => !self.scenario.expects_acceptance(),
+            Outcome::TransportFailure(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ConformanceReport {
+    pub results: Vec<ScenarioResult>,
+}
+
+impl ConformanceReport {
+    pub fn all_passed(&self) -> bool {
+        self.results.iter().all(|result| result.passed())
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &ScenarioResult> {
+        self.results.iter().filter(|result| !result.passed())
+    }
+}
+
+pub struct ConformanceSuite<T, TapeR, D>(PhantomData<(T, TapeR, D)>);
+
+impl<T, TapeR, D> ConformanceSuite<T, TapeR, D>
+where
+    T: Value + PartialEq + DeserializeOwned,
+    TapeR: TapeExpander,
+    D: Debug + Default + Digest + FixedOutputReset + Clone,
+{
+    /// Run every scenario over `transport`, proving knowledge of `witness`.
+    pub fn run<R: RngCore + CryptoRng, Tr: Transport, const SIGMA: usize>(
+        rng: &mut R,
+        transport: &mut Tr,
+        circuit: &impl Circuit<T>,
+        witness: &[u8],
+    ) -> ConformanceReport {
+        let results = Scenario::all()
+            .into_iter()
+            .map(|scenario| ScenarioResult {
+                scenario,
+                outcome: Self::run_scenario::<R, Tr, SIGMA>(
+                    scenario, rng, transport, circuit, witness,
+                ),
+            })
+            .collect();
+
+        ConformanceReport { results }
+    }
+
+    pub fn run_scenario<R: RngCore + CryptoRng, Tr: Transport, const SIGMA: usize>(
+        scenario: Scenario,
+        rng: &mut R,
+        transport: &mut Tr,
+        circuit: &impl Circuit<T>,
+        witness: &[u8],
+    ) -> Outcome {
+        match Self::session::<R, Tr, SIGMA>(scenario, rng, transport, circuit, witness) {
+            Ok(()) => Outcome::Accepted,
+            Err(outcome) => outcome,
+        }
+    }
+
+    fn session<R: RngCore + CryptoRng, Tr: Transport, const SIGMA: usize>(
+        scenario: Scenario,
+        rng: &mut R,
+        transport: &mut Tr,
+        circuit: &impl Circuit<T>,
+        witness: &[u8],
+    ) -> Result<(), Outcome> {
+        let public_output = circuit.compute(witness);
+
+        let mut prover = InteractiveProver::<T, TapeR, D>::new();
+        let mut verifier = InteractiveVerifier::<T, TapeR, D>::new();
+
+        // round 1: prover -> verifier
+        let mut fm = prover.round1::<R, SIGMA>(rng, witness, circuit, &public_output)?;
+        match scenario {
+            Scenario::ReplayedFirstMessage => {
+                let mut previous = InteractiveProver::<T, TapeR, D>::new();
+                fm = previous.round1::<R, SIGMA>(rng, witness, circuit, &public_output)?;
+            }
+            Scenario::MutatedCommitment => fm.all_commitments[0].data[0] ^= 1,
+            _ => {}
+        }
+        let fm_bytes = bincode::serialize(&fm).map_err(|_| Error::SerializationError)?;
+        transport.send_to_verifier(&fm_bytes)?;
+
+        // round 2: verifier -> prover
+        let fm: FirstMessageA<T, D> = bincode::deserialize(&transport.recv_from_prover()?)
+            .map_err(|_| Error::SerializationError)?;
+        let mut challenge = verifier.round2::<R, SIGMA>(rng, fm)?;
+        if scenario == Scenario::WrongChallengeLength {
+            challenge.pop();
+        }
+        transport.send_to_prover(&challenge)?;
+
+        // round 3: prover -> verifier
+        let challenge = transport.recv_from_verifier()?;
+        let proof = prover.round3::<SIGMA>(challenge)?;
+        transport.send_to_verifier(&proof.to_bytes()?)?;
+
+        let proof = Proof::<T, D>::from_bytes(&transport.recv_from_prover()?)?;
+        verifier.verify::<SIGMA>(&proof, circuit, &public_output)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod conformance_tests {
+    use std::io;
+
+    use rand::thread_rng;
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::gadgets::add_mod::AdderCircuit;
+
+    use super::{ConformanceSuite, LoopbackTransport, Outcome, Scenario, Transport};
+
+    const SIGMA: usize = 40;
+
+    fn witness() -> Vec<u8> {
+        [1234u32.to_le_bytes(), 5678u32.to_le_bytes()].concat()
+    }
+
+    #[test]
+    fn test_loopback_conforms() {
+        let mut rng = thread_rng();
+        let mut transport = LoopbackTransport::default();
+
+        let report = ConformanceSuite::<u32, ChaCha20Rng, Keccak256>::run::<_, _, SIGMA>(
+            &mut rng,
+            &mut transport,
+            &AdderCircuit::<u32>::new(),
+            &witness(),
+        );
+
+        assert_eq!(report.results.len(), Scenario::all().len());
+        assert!(report.all_passed(), "{:?}", report);
+    }
+
+    /// Flips a bit of every proof it carries.
+    #[derive(Default)]
+    struct CorruptingTransport {
+        inner: LoopbackTransport,
+        sent_to_verifier: usize,
+    }
+
+    impl Transport for CorruptingTransport {
+        fn send_to_verifier(&mut self, msg: &[u8]) -> io::Result<()> {
+            self.sent_to_verifier += 1;
+            let mut msg = msg.to_vec();
+            if self.sent_to_verifier.is_multiple_of(2) {
+                let last = msg.len() - 1;
+                msg[last] ^= 1;
+            }
+            self.inner.send_to_verifier(&msg)
+        }
+
+        fn recv_from_prover(&mut self) -> io::Result<Vec<u8>> {
+            self.inner.recv_from_prover()
+        }
+
+        fn send_to_prover(&mut self, msg: &[u8]) -> io::Result<()> {
+            self.inner.send_to_prover(msg)
+        }
+
+        fn recv_from_verifier(&mut self) -> io::Result<Vec<u8>> {
+            self.inner.recv_from_verifier()
+        }
+    }
+
+    #[test]
+    fn test_corrupting_transport_fails_honest_session() {
+        let mut rng = thread_rng();
+        let mut transport = CorruptingTransport::default();
+
+        let outcome = ConformanceSuite::<u32, ChaCha20Rng, Keccak256>::run_scenario::<_, _, SIGMA>(
+            Scenario::Honest,
+            &mut rng,
+            &mut transport,
+            &AdderCircuit::<u32>::new(),
+            &witness(),
+        );
+
+        assert!(matches!(outcome, Outcome::Rejected(_)));
+    }
+}
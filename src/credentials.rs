@@ -0,0 +1,282 @@
+//! Credentials as proofs of knowledge of a committed secret key.
+//!
+//! Issuing a credential draws a secret key `sk`, or takes an existing one such
+//! as the secret key of an ECDSA or Schnorr key pair, and records its
+//! commitment `H(sk)` for a hash `H` chosen by the application among the
+//! gadgets of this crate. Presenting the credential proves knowledge of `sk`
+//! without revealing it. A presentation is bound to a context chosen by the
+//! verifier, e.g. a fresh nonce, so that it cannot be replayed to another
+//! verifier or in another session.
+//!
+//! The commitment is deterministic, so presentations of the same credential are
+//! linkable through it. The length of the secret key is public.
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::{CryptoRng, RngCore};
+use sha3::Keccak256;
+
+use crate::{
+    circuit::Circuit,
+    data_structures::TypedProof,
+    error::Error,
+    gadgets::{
+        blake2s::{blake2s, Blake2sCircuit},
+        ripemd160::{ripemd160, Ripemd160Circuit},
+        sha256::{sha256, Sha256Circuit},
+    },
+    gf2_word::GF2Word,
+    prover::Prover,
+    verifier::Verifier,
+    witness::Witness,
+};
+
+pub const SECRET_KEY_LEN: usize = 32;
+
+/// Separates presentations from other proofs about the same commitment.
+const DOMAIN: &[u8] = b"zkboo credential";
+
+pub type Presentation<const SIGMA: usize> = TypedProof<u32, Keccak256, SIGMA>;
+
+/// The hash a secret key is committed with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CredentialHash {
+    Sha256,
+    Blake2s,
+    Ripemd160,
+}
+
+impl CredentialHash {
+    /// Length of a commitment in bytes.
+    pub fn digest_len(self) -> usize {
+        match self {
+            Self::Sha256 | Self::Blake2s => 32,
+            Self::Ripemd160 => 20,
+        }
+    }
+
+    /// The commitment to `secret`, with the byte order of the hash.
+    pub fn digest(self, secret: &[u8]) -> Vec<u8> {
+        let (words, to_bytes): (_, fn(u32) -> [u8; 4]) = match self {
+            Self::Sha256 => (sha256(secret), u32::to_be_bytes),
+            Self::Blake2s => (blake2s(secret), u32::to_le_bytes),
+            Self::Ripemd160 => (ripemd160(secret), u32::to_le_bytes),
+        };
+        words.iter().flat_map(|word| to_bytes(word.value)).collect()
+    }
+
+    fn to_words(self, digest: &[u8]) -> Result<Vec<GF2Word<u32>>, Error> {
+        if digest.len() != self.digest_len() {
+            return Err(Error::HashLenError(self.digest_len(), digest.len()));
+        }
+        let from_bytes = match self {
+            Self::Sha256 => u32::from_be_bytes,
+            Self::Blake2s | Self::Ripemd160 => u32::from_le_bytes,
+        };
+        Ok(digest
+            .chunks(4)
+            .map(|chunk| from_bytes(chunk.try_into().unwrap()).into())
+            .collect())
+    }
+}
+
+/// The secret key of a credential, held by its owner.
+#[derive(Debug)]
+pub struct Credential {
+    hash: CredentialHash,
+    secret: Witness,
+}
+
+/// The public commitment to the secret key of a credential, recorded by the
+/// issuer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CredentialCommitment {
+    pub hash: CredentialHash,
+    pub secret_len: usize,
+    pub digest: Vec<u8>,
+}
+
+impl Credential {
+    /// The credential for an existing secret key.
+    pub fn from_secret(hash: CredentialHash, secret: Vec<u8>) -> Self {
+        Self {
+            hash,
+            secret: Witness::new(secret),
+        }
+    }
+
+    pub fn commitment(&self) -> CredentialCommitment {
+        CredentialCommitment {
+            hash: self.hash,
+            secret_len: self.secret.len(),
+            digest: self.hash.digest(self.secret.expose_secret()),
+        }
+    }
+}
+
+fn context(context: &[u8]) -> Vec<u8> {
+    [DOMAIN, context].concat()
+}
+
+/// Issue a credential for a random secret key of `SECRET_KEY_LEN` bytes,
+/// committed with `hash`.
+pub fn issue<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    hash: CredentialHash,
+) -> (Credential, CredentialCommitment) {
+    let mut secret = vec![0u8; SECRET_KEY_LEN];
+    rng.fill_bytes(&mut secret);
+
+    let credential = Credential::from_secret(hash, secret);
+    let commitment = credential.commitment();
+    (credential, commitment)
+}
+
+/// Prove knowledge of the secret key of `credential`, for the verifier
+/// context `ctx`.
+pub fn present<R: RngCore + CryptoRng, const SIGMA: usize>(
+    rng: &mut R,
+    credential: &Credential,
+    ctx: &[u8],
+) -> Result<Presentation<SIGMA>, Error> {
+    let secret = credential.secret.expose_secret();
+    let commitment = credential.commitment();
+    let public_output = commitment.hash.to_words(&commitment.digest)?;
+
+    match commitment.hash {
+        CredentialHash::Sha256 => prove::<R, SIGMA>(
+            rng,
+            secret,
+            &Sha256Circuit::new(secret.len()),
+            &public_output,
+            ctx,
+        ),
+        CredentialHash::Blake2s => prove::<R, SIGMA>(
+            rng,
+            secret,
+            &Blake2sCircuit::new(secret.len()),
+            &public_output,
+            ctx,
+        ),
+        CredentialHash::Ripemd160 => prove::<R, SIGMA>(
+            rng,
+            secret,
+            &Ripemd160Circuit::new(secret.len()),
+            &public_output,
+            ctx,
+        ),
+    }
+}
+
+/// Verify that `presentation` shows knowledge of the secret key committed to
+/// by `commitment`, for the verifier context `ctx`.
+pub fn verify<const SIGMA: usize>(
+    commitment: &CredentialCommitment,
+    ctx: &[u8],
+    presentation: &Presentation<SIGMA>,
+) -> Result<(), Error> {
+    let public_output = commitment.hash.to_words(&commitment.digest)?;
+    let secret_len = commitment.secret_len;
+
+    match commitment.hash {
+        CredentialHash::Sha256 => verify_with::<SIGMA>(
+            presentation,
+            &Sha256Circuit::new(secret_len),
+            &public_output,
+            ctx,
+        ),
+        CredentialHash::Blake2s => verify_with::<SIGMA>(
+            presentation,
+            &Blake2sCircuit::new(secret_len),
+            &public_output,
+            ctx,
+        ),
+        CredentialHash::Ripemd160 => verify_with::<SIGMA>(
+            presentation,
+            &Ripemd160Circuit::new(secret_len),
+            &public_output,
+            ctx,
+        ),
+    }
+}
+
+fn prove<R: RngCore + CryptoRng, const SIGMA: usize>(
+    rng: &mut R,
+    secret: &[u8],
+    circuit: &impl Circuit<u32>,
+    public_output: &Vec<GF2Word<u32>>,
+    ctx: &[u8],
+) -> Result<Presentation<SIGMA>, Error> {
+    Prover::<u32, ChaCha20Rng, Keccak256>::prove_with_context::<R, SIGMA>(
+        rng,
+        secret,
+        circuit,
+        public_output,
+        &context(ctx),
+    )?
+    .try_into()
+}
+
+fn verify_with<const SIGMA: usize>(
+    presentation: &Presentation<SIGMA>,
+    circuit: &impl Circuit<u32>,
+    public_output: &Vec<GF2Word<u32>>,
+    ctx: &[u8],
+) -> Result<(), Error> {
+    Verifier::<u32, ChaCha20Rng, Keccak256>::verify_with_context::<SIGMA>(
+        presentation,
+        circuit,
+        public_output,
+        &context(ctx),
+    )
+}
+
+#[cfg(test)]
+mod credentials_tests {
+    use rand::thread_rng;
+
+    use super::{issue, present, verify, Credential, CredentialHash, Presentation};
+
+    const SIGMA: usize = 40;
+
+    #[test]
+    fn test_issue_and_present() {
+        let mut rng = thread_rng();
+        for hash in [
+            CredentialHash::Sha256,
+            CredentialHash::Blake2s,
+            CredentialHash::Ripemd160,
+        ] {
+            let (credential, commitment) = issue(&mut rng, hash);
+            assert_eq!(commitment.digest.len(), hash.digest_len());
+
+            let presentation = present::<_, SIGMA>(&mut rng, &credential, b"nonce").unwrap();
+            let bytes = presentation.to_bytes().unwrap();
+            let presentation = Presentation::<SIGMA>::from_bytes(&bytes).unwrap();
+            verify(&commitment, b"nonce", &presentation).unwrap();
+
+            // the presentation is bound to the context and the commitment
+            assert!(verify(&commitment, b"other nonce", &presentation).is_err());
+            let (_, other) = issue(&mut rng, hash);
+            assert!(verify(&other, b"nonce", &presentation).is_err());
+        }
+    }
+
+    #[test]
+    fn test_existing_secret_key() {
+        let mut rng = thread_rng();
+        // e.g. the 32-byte secret scalar of a Schnorr key pair
+        let credential = Credential::from_secret(CredentialHash::Sha256, vec![7u8; 32]);
+        let commitment = credential.commitment();
+        assert_eq!(commitment.digest, CredentialHash::Sha256.digest(&[7u8; 32]));
+
+        let presentation = present::<_, SIGMA>(&mut rng, &credential, b"session").unwrap();
+        verify(&commitment, b"session", &presentation).unwrap();
+
+        // a commitment under another hash does not match
+        let mut other = commitment.clone();
+        other.hash = CredentialHash::Blake2s;
+        assert!(verify(&other, b"session", &presentation).is_err());
+        other.hash = CredentialHash::Ripemd160;
+        assert!(verify(&other, b"session", &presentation).is_err());
+    }
+}
@@ -1,11 +1,21 @@
-use serde::Serialize;
+use std::{
+    io::{Read, Write},
+    ops::Deref,
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sha3::Digest;
 
 use crate::{
+    circuit::Circuit,
     commitment::{Blinding, Commitment},
+    config::HASH_LEN,
     error::Error,
+    expander::TapeExpander,
     gf2_word::{GF2Word, Value},
-    key::Key,
+    header::ProofHeader,
+    key::{derive_blinding, Key},
+    merkle::Node,
     view::View,
 };
 
@@ -19,38 +29,36 @@ pub struct PartyExecution<'a, T: Value> {
    Based on: O4 of (https://eprint.iacr.org/2017/279.pdf)
 */
 impl<'a, T: Value> PartyExecution<'a, T> {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     pub fn commit<D: Default + Digest + Clone>(&self) -> Result<Commitment<D>, Error> {
-        let blinding = Blinding(self.key);
-        let messages_bytes: Vec<u8> = self
-            .view
-            .messages
-            .iter()
-            .flat_map(|msg| msg.value.to_bytes())
-            .collect();
+        let blinding = Blinding(derive_blinding::<D>(self.key)?);
 
-        // TODO: consider more optimal way to prepare message for committing
-        // we omit commiting to full view to make sure that offset is not included which is just helper variable
-        let commitment = Commitment::<D>::commit(
-            &blinding,
-            &[self.view.input.clone(), messages_bytes].concat(),
-        )?;
-        Ok(commitment)
+        // the canonical encoding leaves out the offset, which is just a helper variable
+        Commitment::<D>::commit_bytes(&blinding, self.view.encoded_len(), |absorber| {
+            self.view.write_be(absorber)
+        })
     }
 }
 
 #[derive(Serialize)]
 pub struct PublicInput<'a, T: Value> {
-    pub hash_len: usize,
     pub security_param: usize,
     pub public_output: &'a Vec<GF2Word<T>>,
+    pub public_input: &'a [u8],
     pub outputs: &'a Vec<Vec<GF2Word<T>>>,
+    /// `Circuit::id` of the circuit the proof is for.
+    pub circuit_id: &'a [u8],
 }
 
 // TODO: add methods for computing proofs size, etc.
-pub struct Proof<T: Value, D, const SIGMA: usize>
+#[derive(Clone)]
+pub struct Proof<T: Value, D>
 where
     D: Default + Digest + Clone,
 {
+    /// The security level the proof was made for. Verifiers check it against
+    /// the level they expect.
+    pub security_param: usize,
     pub party_inputs: Vec<Vec<u8>>,
     pub commitments: Vec<Commitment<D>>,
     pub views: Vec<View<T>>,
@@ -58,8 +66,599 @@ where
     pub claimed_trits: Vec<u8>,
 }
 
-#[derive(Clone, Default)]
+/// Everything the prover computed for a proof, returned by
+/// `Prover::prove_with_trace`. Parties are indexed as `3 * repetition + party`.
+#[cfg(feature = "debug-trace")]
+#[derive(Clone)]
+pub struct ProofTrace<T: Value, D: Default + Digest + Clone> {
+    pub views: Vec<View<T>>,
+    pub keys: Vec<Key>,
+    pub commitments: Vec<Commitment<D>>,
+    pub outputs: Vec<Vec<GF2Word<T>>>,
+    /// The party opened first in each repetition.
+    pub challenge: Vec<u8>,
+}
+
+/// The part of a `Proof` which belongs to a single repetition.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "", deserialize = "T: Deserialize<'de>"))]
+pub struct RepetitionProof<T: Value, D: Default + Digest + Clone> {
+    pub claimed_trit: u8,
+    pub party_input: Vec<u8>,
+    pub keys: (Key, Key),
+    pub view: View<T>,
+    pub commitment: Commitment<D>,
+}
+
+#[derive(Serialize)]
+#[serde(bound = "")]
+struct RepetitionProofRef<'a, T: Value, D: Default + Digest + Clone> {
+    claimed_trit: u8,
+    party_input: &'a Vec<u8>,
+    keys: (&'a Key, &'a Key),
+    view: &'a View<T>,
+    commitment: &'a Commitment<D>,
+}
+
+/*
+    Proofs are serialized repetition by repetition: the security parameter and
+    the number of repetitions followed by one `RepetitionProof` per repetition.
+    This allows verifiers to check a proof without holding all views in memory
+    at once.
+*/
+impl<T: Value, D> Proof<T, D>
+where
+    D: Default + Digest + Clone,
+{
+    pub fn num_of_repetitions(&self) -> usize {
+        self.claimed_trits.len()
+    }
+
+    /// The security level in bits the proof claims to be made for. It is only
+    /// trusted once the proof verifies under it, e.g. with `Verifier::verify_dyn`.
+    pub fn security_level(&self) -> usize {
+        self.security_param
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        let header = (self.security_param as u64, self.num_of_repetitions() as u64);
+        bincode::serialize_into(&mut *writer, &header).map_err(|_| Error::SerializationError)?;
+
+        for repetition in 0..self.num_of_repetitions() {
+            let repetition_proof = RepetitionProofRef {
+                claimed_trit: self.claimed_trits[repetition],
+                party_input: &self.party_inputs[repetition],
+                keys: (&self.keys[2 * repetition], &self.keys[2 * repetition + 1]),
+                view: &self.views[repetition],
+                commitment: &self.commitments[repetition],
+            };
+            bincode::serialize_into(&mut *writer, &repetition_proof)
+                .map_err(|_| Error::SerializationError)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![];
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Write the proof after the `ProofHeader` describing it as a proof of
+    /// `circuit` with tapes expanded by `TapeR`, see
+    /// `Verifier::verify_with_header`.
+    pub fn write_with_header_to<TapeR: TapeExpander, W: Write>(
+        &self,
+        writer: &mut W,
+        circuit: &(impl Circuit<T> + ?Sized),
+    ) -> Result<(), Error> {
+        let mut header = ProofHeader::new::<T, TapeR, D>(circuit, self.security_param)?;
+        header.num_of_repetitions = self.num_of_repetitions();
+        header.write_to(writer)?;
+        self.write_to(writer)
+    }
+
+    pub fn to_bytes_with_header<TapeR: TapeExpander>(
+        &self,
+        circuit: &(impl Circuit<T> + ?Sized),
+    ) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![];
+        self.write_with_header_to::<TapeR, _>(&mut bytes, circuit)?;
+        Ok(bytes)
+    }
+
+    /// Clone the proof of a single repetition.
+    pub fn repetition(&self, repetition: usize) -> RepetitionProof<T, D> {
+        RepetitionProof {
+            claimed_trit: self.claimed_trits[repetition],
+            party_input: self.party_inputs[repetition].clone(),
+            keys: (self.keys[2 * repetition], self.keys[2 * repetition + 1]),
+            view: self.views[repetition].clone(),
+            commitment: self.commitments[repetition].clone(),
+        }
+    }
+
+    /// Split the proof into independent per-repetition pieces, in repetition order.
+    pub fn split(self) -> Vec<RepetitionProof<T, D>> {
+        let mut keys = self.keys.chunks(2);
+
+        self.claimed_trits
+            .into_iter()
+            .zip(self.party_inputs)
+            .zip(self.views)
+            .zip(self.commitments)
+            .map(|(((claimed_trit, party_input), view), commitment)| {
+                let k = keys.next().unwrap();
+                RepetitionProof {
+                    claimed_trit,
+                    party_input,
+                    keys: (k[0], k[1]),
+                    view,
+                    commitment,
+                }
+            })
+            .collect()
+    }
+
+    /// Inverse of `split`. The pieces must be in repetition order.
+    pub fn recombine(security_param: usize, pieces: Vec<RepetitionProof<T, D>>) -> Self {
+        let mut proof = Self {
+            security_param,
+            party_inputs: Vec::with_capacity(pieces.len()),
+            commitments: Vec::with_capacity(pieces.len()),
+            views: Vec::with_capacity(pieces.len()),
+            keys: Vec::with_capacity(2 * pieces.len()),
+            claimed_trits: Vec::with_capacity(pieces.len()),
+        };
+
+        for piece in pieces {
+            proof.push_repetition(piece);
+        }
+
+        proof
+    }
+
+    fn push_repetition(&mut self, repetition_proof: RepetitionProof<T, D>) {
+        self.claimed_trits.push(repetition_proof.claimed_trit);
+        self.party_inputs.push(repetition_proof.party_input);
+        self.keys.push(repetition_proof.keys.0);
+        self.keys.push(repetition_proof.keys.1);
+        self.views.push(repetition_proof.view);
+        self.commitments.push(repetition_proof.commitment);
+    }
+}
+
+impl<T: Value + DeserializeOwned, D> Proof<T, D>
+where
+    D: Default + Digest + Clone,
+{
+    /// Read the security parameter and the number of repetitions that a
+    /// serialized proof starts with.
+    pub fn read_header<R: Read>(reader: &mut R) -> Result<(usize, usize), Error> {
+        let (security_param, num_of_repetitions): (u64, u64) =
+            bincode::deserialize_from(reader).map_err(|_| Error::SerializationError)?;
+        Ok((
+            usize::try_from(security_param).map_err(|_| Error::SerializationError)?,
+            usize::try_from(num_of_repetitions).map_err(|_| Error::SerializationError)?,
+        ))
+    }
+
+    /// Read the proof of a single repetition from a serialized proof.
+    pub fn read_repetition<R: Read>(reader: &mut R) -> Result<RepetitionProof<T, D>, Error> {
+        bincode::deserialize_from(reader).map_err(|_| Error::SerializationError)
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let (security_param, num_of_repetitions) = Self::read_header(reader)?;
+
+        let mut proof = Self {
+            security_param,
+            party_inputs: vec![],
+            commitments: vec![],
+            views: vec![],
+            keys: vec![],
+            claimed_trits: vec![],
+        };
+
+        for _ in 0..num_of_repetitions {
+            proof.push_repetition(Self::read_repetition(reader)?);
+        }
+
+        Ok(proof)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = bytes;
+        Self::read_from(&mut reader)
+    }
+}
+
+/// A `Proof` whose security parameter is part of its type, as it was before
+/// proofs carried it at runtime.
+pub struct TypedProof<T: Value, D: Default + Digest + Clone, const SIGMA: usize>(Proof<T, D>);
+
+impl<T: Value, D: Default + Digest + Clone, const SIGMA: usize> TypedProof<T, D, SIGMA> {
+    pub fn into_inner(self) -> Proof<T, D> {
+        self.0
+    }
+}
+
+impl<T: Value, D: Default + Digest + Clone, const SIGMA: usize> TryFrom<Proof<T, D>>
+    for TypedProof<T, D, SIGMA>
+{
+    type Error = Error;
+
+    fn try_from(proof: Proof<T, D>) -> Result<Self, Error> {
+        if proof.security_param != SIGMA {
+            return Err(Error::VerificationError);
+        }
+        Ok(Self(proof))
+    }
+}
+
+impl<T: Value, D: Default + Digest + Clone, const SIGMA: usize> Deref for TypedProof<T, D, SIGMA> {
+    type Target = Proof<T, D>;
+
+    fn deref(&self) -> &Proof<T, D> {
+        &self.0
+    }
+}
+
+impl<T: Value + DeserializeOwned, D: Default + Digest + Clone, const SIGMA: usize>
+    TypedProof<T, D, SIGMA>
+{
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Proof::from_bytes(bytes)?.try_into()
+    }
+}
+
+/// A proof whose parties are committed to with a Merkle tree.
+///
+/// `proof` is made as usual, except that its challenge is derived from `root`,
+/// the root of a tree with a leaf per party of every repetition, instead of
+/// from all the commitments and outputs. `paths[3 * j]` and `paths[3 * j + 1]`
+/// authenticate the leaves of the two opened parties of repetition `j`, in the
+/// order they are opened, and `paths[3 * j + 2]` the leaf of the unopened
+/// party, whose output is derived from the public output. Any subset of the
+/// repetitions can then be checked on its own.
+pub struct MerkleProof<T: Value, D: Default + Digest + Clone> {
+    pub root: Node,
+    pub proof: Proof<T, D>,
+    pub paths: Vec<Vec<Node>>,
+}
+
+#[derive(Serialize)]
+#[serde(bound = "")]
+struct MerkleRepetitionRef<'a, T: Value, D: Default + Digest + Clone> {
+    claimed_trit: u8,
+    party_input: &'a Vec<u8>,
+    keys: (&'a Key, &'a Key),
+    view: &'a View<T>,
+    commitment: &'a Commitment<D>,
+    paths: [&'a Vec<Node>; 3],
+}
+
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
+struct MerkleRepetition<T: Value, D: Default + Digest + Clone> {
+    claimed_trit: u8,
+    party_input: Vec<u8>,
+    keys: (Key, Key),
+    view: View<T>,
+    commitment: Commitment<D>,
+    paths: [Vec<Node>; 3],
+}
+
+/*
+    Merkle proofs are serialized as the root, the security parameter and the
+    number of repetitions, then repetition by repetition as in
+    `Proof::write_to`, followed by the paths of its three parties.
+*/
+impl<T: Value, D: Default + Digest + Clone> MerkleProof<T, D> {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        let proof = &self.proof;
+        let num_of_repetitions = proof.num_of_repetitions();
+        if proof.commitments.len() != num_of_repetitions
+            || proof.party_inputs.len() != num_of_repetitions
+            || proof.views.len() != num_of_repetitions
+            || proof.keys.len() != 2 * num_of_repetitions
+            || self.paths.len() != 3 * num_of_repetitions
+        {
+            return Err(Error::SerializationError);
+        }
+
+        writer
+            .write_all(&self.root)
+            .map_err(|_| Error::SerializationError)?;
+        let header = (proof.security_param as u64, num_of_repetitions as u64);
+        bincode::serialize_into(&mut *writer, &header).map_err(|_| Error::SerializationError)?;
+
+        for repetition in 0..num_of_repetitions {
+            let repetition_proof = MerkleRepetitionRef {
+                claimed_trit: proof.claimed_trits[repetition],
+                party_input: &proof.party_inputs[repetition],
+                keys: (&proof.keys[2 * repetition], &proof.keys[2 * repetition + 1]),
+                view: &proof.views[repetition],
+                commitment: &proof.commitments[repetition],
+                paths: [0, 1, 2].map(|k| &self.paths[3 * repetition + k]),
+            };
+            bincode::serialize_into(&mut *writer, &repetition_proof)
+                .map_err(|_| Error::SerializationError)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![];
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl<T: Value + DeserializeOwned, D: Default + Digest + Clone> MerkleProof<T, D> {
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let mut root = [0u8; HASH_LEN];
+        reader
+            .read_exact(&mut root)
+            .map_err(|_| Error::SerializationError)?;
+        let (security_param, num_of_repetitions) = Proof::<T, D>::read_header(reader)?;
+
+        let mut proof = Proof {
+            security_param,
+            party_inputs: vec![],
+            commitments: vec![],
+            views: vec![],
+            keys: vec![],
+            claimed_trits: vec![],
+        };
+        let mut paths = vec![];
+        for _ in 0..num_of_repetitions {
+            let repetition: MerkleRepetition<T, D> =
+                bincode::deserialize_from(&mut *reader).map_err(|_| Error::SerializationError)?;
+            proof.claimed_trits.push(repetition.claimed_trit);
+            proof.party_inputs.push(repetition.party_input);
+            proof.keys.extend([repetition.keys.0, repetition.keys.1]);
+            proof.views.push(repetition.view);
+            proof.commitments.push(repetition.commitment);
+            paths.extend(repetition.paths);
+        }
+
+        Ok(Self { root, proof, paths })
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = bytes;
+        Self::read_from(&mut reader)
+    }
+}
+
+/// A circuit together with the public output it is claimed to compute, one of
+/// the statements of an `AggregateProof`.
+pub struct Instance<'a, T: Value> {
+    pub circuit: &'a dyn Circuit<T>,
+    pub public_output: &'a Vec<GF2Word<T>>,
+}
+
+/// Proofs of several statements whose challenges come from one shared
+/// transcript. None of them verifies on its own.
+pub struct AggregateProof<T: Value, D: Default + Digest + Clone> {
+    pub proofs: Vec<Proof<T, D>>,
+}
+
+/*
+    Aggregate proofs are serialized as the number of proofs followed by each
+    proof in the format of `Proof::write_to`.
+*/
+impl<T: Value, D: Default + Digest + Clone> AggregateProof<T, D> {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        bincode::serialize_into(&mut *writer, &(self.proofs.len() as u64))
+            .map_err(|_| Error::SerializationError)?;
+        for proof in &self.proofs {
+            proof.write_to(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![];
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl<T: Value + DeserializeOwned, D: Default + Digest + Clone> AggregateProof<T, D> {
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let num_of_proofs: u64 =
+            bincode::deserialize_from(&mut *reader).map_err(|_| Error::SerializationError)?;
+        let proofs = (0..num_of_proofs)
+            .map(|_| Proof::read_from(reader))
+            .collect::<Result<_, Error>>()?;
+        Ok(Self { proofs })
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = bytes;
+        Self::read_from(&mut reader)
+    }
+}
+
+/// A proof that one of several statements holds, without revealing which.
+///
+/// `proof` is a regular proof that the witness makes the `circuit::AnyOf` of
+/// the members output 1. Splitting one challenge among proofs of the members
+/// instead, as in OR-proofs of 2-special-sound protocols, would be unsound:
+/// a cheating prover can pass 2 of the 3 challenges of every repetition of
+/// ZKBoo, and any two such pairs of trits cover every challenge.
+pub struct RingProof<T: Value, D: Default + Digest + Clone> {
+    pub proof: Proof<T, D>,
+}
+
+/*
+    Ring proofs are serialized as their proof, see `Proof::write_to`.
+*/
+impl<T: Value, D: Default + Digest + Clone> RingProof<T, D> {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        self.proof.write_to(writer)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![];
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl<T: Value + DeserializeOwned, D: Default + Digest + Clone> RingProof<T, D> {
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        Ok(Self {
+            proof: Proof::read_from(reader)?,
+        })
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = bytes;
+        Self::read_from(&mut reader)
+    }
+}
+
+/// Repetitions of any shape, for fuzzing verifiers. Messages are read from
+/// raw bytes so that they take every value of `T`, and the claimed trit may be
+/// out of range.
+#[cfg(feature = "arbitrary")]
+impl<'a, T: Value, D: Default + Digest + Clone> arbitrary::Arbitrary<'a> for RepetitionProof<T, D> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let claimed_trit = u.int_in_range(0..=3)?;
+        let party_input = u.arbitrary()?;
+        let keys = u.arbitrary()?;
+
+        let mut view = View::new(u.arbitrary()?);
+        let num_of_messages = u.arbitrary_len::<u8>()? / T::bytes_len();
+        view.messages = u
+            .bytes(num_of_messages * T::bytes_len())?
+            .chunks(T::bytes_len())
+            .map(|bytes| T::from_le_bytes(bytes).into())
+            .collect();
+
+        Ok(Self {
+            claimed_trit,
+            party_input,
+            keys,
+            view,
+            commitment: Commitment::from_data(u.arbitrary()?),
+        })
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: Value, D: Default + Digest + Clone> arbitrary::Arbitrary<'a> for Proof<T, D> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let security_param = u.arbitrary::<u8>()? as usize;
+        let pieces = u.arbitrary_iter()?.collect::<arbitrary::Result<_>>()?;
+        Ok(Self::recombine(security_param, pieces))
+    }
+}
+
+/// What verifying a single repetition yields: the commitments and outputs of
+/// all three parties, in party order. Transcripts of all repetitions are
+/// enough to run the Fiat-Shamir check.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(serialize = "", deserialize = "T: Deserialize<'de>"))]
+pub struct RepetitionTranscript<T: Value, D: Default + Digest + Clone> {
+    pub repetition: usize,
+    pub claimed_trit: u8,
+    pub commitments: Vec<Commitment<D>>,
+    pub outputs: Vec<Vec<GF2Word<T>>>,
+}
+
+/// How far an interactive session has gone. Each round runs at most once and
+/// in order, calls out of order fail with `Error::ProtocolStateError`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SessionState {
+    /// Nothing was sent yet.
+    #[default]
+    Start,
+    /// The first message was sent and the challenge is awaited.
+    Committed,
+    /// The proof was sent, or checked. A new session needs a new verifier, or
+    /// a new or reset prover.
+    Finished,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(bound(serialize = "", deserialize = "T: Deserialize<'de>"))]
 pub struct FirstMessageA<T: Value, D: Default + Digest + Clone> {
     pub outputs: Vec<Vec<GF2Word<T>>>,
     pub all_commitments: Vec<Commitment<D>>,
 }
+
+#[cfg(test)]
+mod data_structures_tests {
+    use rand::{thread_rng, Rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use super::Proof;
+    use crate::{
+        circuit::Circuit, gadgets::add_mod::AdderCircuit, prover::Prover, verifier::Verifier,
+    };
+
+    const SIGMA: usize = 10;
+
+    #[test]
+    fn test_corrupted_bytes_are_rejected() {
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+        let bytes = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<_, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap()
+        .to_bytes()
+        .unwrap();
+
+        // every outcome is an error rather than a panic
+        for _ in 0..500 {
+            let mut corrupted = bytes.clone();
+            for _ in 0..rng.gen_range(1..4) {
+                let i = rng.gen_range(0..corrupted.len());
+                corrupted[i] = rng.gen();
+            }
+            corrupted.truncate(rng.gen_range(0..=corrupted.len()));
+
+            if let Ok(proof) = Proof::<u32, Keccak256>::from_bytes(&corrupted) {
+                assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+                    &proof, &circuit, &output
+                )
+                .is_err());
+            }
+        }
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary_proofs_are_rejected() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let output = vec![11u32.into()];
+        for _ in 0..500 {
+            let data: Vec<u8> = (0..rng.gen_range(0..4096)).map(|_| rng.gen()).collect();
+            let Ok(proof) = Proof::<u32, Keccak256>::arbitrary(&mut Unstructured::new(&data))
+            else {
+                continue;
+            };
+
+            let bytes = proof.to_bytes().unwrap();
+            let decoded = Proof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded.to_bytes().unwrap(), bytes);
+            assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+                &proof, &circuit, &output
+            )
+            .is_err());
+        }
+    }
+}
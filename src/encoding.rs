@@ -0,0 +1,430 @@
+//! Byte encodings absorbed by the Fiat-Shamir oracle and by the key derivation
+//! of deterministic proving.
+//!
+//! These are written out explicitly (rather than delegated to a serializer)
+//! so that the transcript format is pinned down and can be implemented
+//! independently. Any change here invalidates every previously produced proof.
+//!
+//! All integers are little-endian and every length prefix is a `u64`:
+//!
+//! ```text
+//! word(T)          = value: T as LE bytes (T::bytes_len() bytes) || size: u64
+//! words            = len: u64 || word(T) * len
+//! public_input     = security_param: u64 || public_output: words
+//!                    || len: u64 || public_input
+//!                    || len: u64 || outputs: words * len
+//!                    || len: u64 || circuit_id
+//! circuit_id       = len: u64 || name || party_input_len: u64
+//!                    || party_output_len: u64 || num_of_mul_gates: u64
+//!                    || len: u64 || (len: u64 || circuit_id) * len
+//! commitments      = len: u64 || data: [u8; HASH_LEN] * len
+//! seed(context)    = 0x00                                  if context is empty
+//!                    0x01 || len: u64 || context            otherwise
+//! derivation       = domain || len: u64 || seed
+//!                    || len: u64 || witness
+//!                    || public_output: words
+//!                    || len: u64 || context
+//!                    || security_param: u64 || num_of_repetitions: u64
+//!                    || len: u64 || public_input
+//!                    || len: u64 || circuit_id
+//! aggregate_seed   = domain || num_of_statements: u64 || seed(context)
+//! merkle_seed      = domain || seed(context)
+//! interactive_seed = domain
+//! verifier_randomness = len: u64 || randomness
+//! members          = len: u64 || (len: u64 || public_input
+//!                    || public_output: words) * len
+//! party_leaf       = commitment: [u8; HASH_LEN] || output: words
+//! ```
+//!
+//! The oracle hashes `seed || public_input || commitments`, where
+//! `public_input` ends with the `Circuit::id` of the circuit so that a proof
+//! only verifies against the circuit it was made for. Deterministic
+//! proving hashes `derivation` into the seed of its random generator. The
+//! oracle of an aggregate proof of `k` statements hashes `aggregate_seed`
+//! followed by `public_input || commitments` of each statement in order. The
+//! oracle of a Merkle proof hashes `merkle_seed || public_input ||
+//! commitments`, where `public_input` holds no outputs and `commitments` only
+//! the root of the tree over the `party_leaf` of every party of every
+//! repetition, the party `j` of repetition `r` at leaf `3 * r + j`.
+//! The challenge of an interactive session hashes `interactive_seed ||
+//! verifier_randomness || public_input || commitments`, where `public_input`
+//! and `commitments` are those of the first message and `public_input` holds
+//! the outputs of the parties, but no public output, public input nor circuit
+//! id, which only the final check binds. A ring proof is a proof of the
+//! `circuit::AnyOf` of its members, whose own public input is `members`.
+
+use sha3::Digest;
+
+use crate::{
+    commitment::Commitment,
+    data_structures::{Instance, PublicInput},
+    gf2_word::{GF2Word, Value},
+};
+
+fn encode_u64(x: usize, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(x as u64).to_le_bytes());
+}
+
+fn encode_word<T: Value>(word: &GF2Word<T>, out: &mut Vec<u8>) {
+    // `to_bytes` is big-endian
+    out.extend(word.value.to_bytes().into_iter().rev());
+    encode_u64(word.size, out);
+}
+
+fn encode_words<T: Value>(words: &[GF2Word<T>], out: &mut Vec<u8>) {
+    encode_u64(words.len(), out);
+    for word in words {
+        encode_word(word, out);
+    }
+}
+
+/// Seed of the oracle, binding the proof to an application defined context.
+pub fn encode_seed(context: &[u8]) -> Vec<u8> {
+    if context.is_empty() {
+        return vec![0u8];
+    }
+    let mut out = vec![1u8];
+    encode_u64(context.len(), &mut out);
+    out.extend_from_slice(context);
+    out
+}
+
+/// Domain separating aggregate proofs from proofs of a single statement.
+pub const AGGREGATE_DOMAIN: &[u8] = b"zkboo-aggregate-v1";
+
+/// Seed of the oracle shared by the statements of an aggregate proof.
+pub fn encode_aggregate_seed(num_of_statements: usize, context: &[u8]) -> Vec<u8> {
+    let mut out = AGGREGATE_DOMAIN.to_vec();
+    encode_u64(num_of_statements, &mut out);
+    out.extend(encode_seed(context));
+    out
+}
+
+/// Domain separating Merkle proofs from proofs absorbing every commitment.
+pub const MERKLE_DOMAIN: &[u8] = b"zkboo-merkle-v1";
+
+/// Seed of the oracle of a Merkle proof.
+pub fn encode_merkle_seed(context: &[u8]) -> Vec<u8> {
+    let mut out = MERKLE_DOMAIN.to_vec();
+    out.extend(encode_seed(context));
+    out
+}
+
+/// Domain separating the challenges of interactive sessions from
+/// non-interactive proofs.
+pub const INTERACTIVE_DOMAIN: &[u8] = b"zkboo-interactive-v1";
+
+/// Seed of the oracle of an interactive session.
+pub fn encode_interactive_seed() -> Vec<u8> {
+    INTERACTIVE_DOMAIN.to_vec()
+}
+
+/// Encoding of the randomness the verifier of an interactive session adds to
+/// the transcript.
+pub fn encode_verifier_randomness(randomness: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + randomness.len());
+    encode_u64(randomness.len(), &mut out);
+    out.extend_from_slice(randomness);
+    out
+}
+
+/// Public input of the `circuit::AnyOf` of `members`: the public input and
+/// the public output of each of them, in order.
+pub fn encode_members<T: Value>(members: &[Instance<T>]) -> Vec<u8> {
+    let mut out = vec![];
+    encode_u64(members.len(), &mut out);
+    for member in members {
+        let public_input = member.circuit.public_input();
+        encode_u64(public_input.len(), &mut out);
+        out.extend(public_input);
+        encode_words(member.public_output, &mut out);
+    }
+    out
+}
+
+/// Data of the Merkle leaf of a party, from its commitment and output.
+pub fn encode_party_leaf<T: Value, D: Default + Digest + Clone>(
+    commitment: &Commitment<D>,
+    output: &[GF2Word<T>],
+) -> Vec<u8> {
+    let mut out = commitment.data.to_vec();
+    encode_words(output, &mut out);
+    out
+}
+
+/// Encoding of the public data absorbed before the prover's message.
+pub fn encode_public_input<T: Value>(pi: &PublicInput<T>) -> Vec<u8> {
+    let mut out = vec![];
+    encode_u64(pi.security_param, &mut out);
+    encode_words(pi.public_output, &mut out);
+    encode_u64(pi.public_input.len(), &mut out);
+    out.extend_from_slice(pi.public_input);
+    encode_u64(pi.outputs.len(), &mut out);
+    for output in pi.outputs {
+        encode_words(output, &mut out);
+    }
+    encode_u64(pi.circuit_id.len(), &mut out);
+    out.extend_from_slice(pi.circuit_id);
+    out
+}
+
+/// `Circuit::id` of a circuit named `name` with the given sizes, made of the
+/// circuits with ids `parts`.
+pub fn encode_circuit_id(
+    name: &[u8],
+    party_input_len: usize,
+    party_output_len: usize,
+    num_of_mul_gates: usize,
+    parts: &[Vec<u8>],
+) -> Vec<u8> {
+    let mut out = vec![];
+    encode_u64(name.len(), &mut out);
+    out.extend_from_slice(name);
+    encode_u64(party_input_len, &mut out);
+    encode_u64(party_output_len, &mut out);
+    encode_u64(num_of_mul_gates, &mut out);
+    encode_u64(parts.len(), &mut out);
+    for part in parts {
+        encode_u64(part.len(), &mut out);
+        out.extend_from_slice(part);
+    }
+    out
+}
+
+/// Encoding of what deterministic proving derives its randomness from: the
+/// whole statement, so that no two proofs with different challenges share
+/// their keys and input shares.
+#[allow(clippy::too_many_arguments)]
+pub fn encode_derivation<T: Value>(
+    domain: &[u8],
+    seed: &[u8],
+    witness: &[u8],
+    public_output: &[GF2Word<T>],
+    context: &[u8],
+    (security_param, num_of_repetitions): (usize, usize),
+    public_input: &[u8],
+    circuit_id: &[u8],
+) -> Vec<u8> {
+    let mut out = domain.to_vec();
+    for data in [seed, witness] {
+        encode_u64(data.len(), &mut out);
+        out.extend_from_slice(data);
+    }
+    encode_words(public_output, &mut out);
+    encode_u64(context.len(), &mut out);
+    out.extend_from_slice(context);
+    encode_u64(security_param, &mut out);
+    encode_u64(num_of_repetitions, &mut out);
+    for data in [public_input, circuit_id] {
+        encode_u64(data.len(), &mut out);
+        out.extend_from_slice(data);
+    }
+    out
+}
+
+/// Encoding of the commitments of all parties in all repetitions.
+pub fn encode_commitments<D: Default + Digest + Clone>(commitments: &[Commitment<D>]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + commitments.len() * crate::config::HASH_LEN);
+    encode_u64(commitments.len(), &mut out);
+    for commitment in commitments {
+        out.extend_from_slice(&commitment.data);
+    }
+    out
+}
+
+#[cfg(test)]
+mod encoding_tests {
+    use sha3::{Digest, Keccak256};
+
+    use crate::{
+        commitment::{Blinding, Commitment},
+        config::HASH_LEN,
+        data_structures::PublicInput,
+        fs::SigmaFS,
+        gf2_word::GF2Word,
+    };
+
+    use super::{
+        encode_aggregate_seed, encode_circuit_id, encode_commitments, encode_derivation,
+        encode_merkle_seed, encode_party_leaf, encode_public_input, encode_seed,
+        encode_verifier_randomness, AGGREGATE_DOMAIN, MERKLE_DOMAIN,
+    };
+
+    fn hex(data: &[u8]) -> String {
+        data.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn keccak_hex(data: &[u8]) -> String {
+        hex(&Keccak256::digest(data))
+    }
+
+    fn sample_commitments() -> Vec<Commitment<Keccak256>> {
+        (0u8..3)
+            .map(|i| Commitment::commit(&Blinding([i; 4]), &vec![i; 5]).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_public_input_encoding() {
+        let public_output: Vec<GF2Word<u32>> = vec![0xdeadbeefu32.into(), 7u32.into()];
+        let outputs = vec![
+            vec![1u32.into(), 2u32.into()],
+            vec![3u32.into(), 0xffffffffu32.into()],
+        ];
+        let pi = PublicInput {
+            security_param: 40,
+            public_output: &public_output,
+            public_input: b"msg",
+            outputs: &outputs,
+            circuit_id: b"id",
+        };
+
+        let encoded = encode_public_input(&pi);
+        assert_eq!(
+            encoded.len(),
+            8 + (8 + 2 * 12) + (8 + 3) + 8 + 2 * (8 + 2 * 12) + (8 + 2)
+        );
+        assert_eq!(&encoded[..8], &40u64.to_le_bytes());
+        assert_eq!(&encoded[16..20], &0xdeadbeefu32.to_le_bytes());
+        assert_eq!(encoded, bincode::serialize(&pi).unwrap());
+        assert_eq!(
+            keccak_hex(&encoded),
+            "e228712a5ad0d7ef9bd80a028ae3289b7c4dc564fcb580e3096838d405ae501a"
+        );
+    }
+
+    #[test]
+    fn test_public_input_encoding_u8() {
+        let public_output: Vec<GF2Word<u8>> = vec![0xabu8.into()];
+        let outputs = vec![vec![0x01u8.into()]];
+        let pi = PublicInput {
+            security_param: 80,
+            public_output: &public_output,
+            public_input: &[],
+            outputs: &outputs,
+            circuit_id: &[],
+        };
+
+        let encoded = encode_public_input(&pi);
+        assert_eq!(encoded, bincode::serialize(&pi).unwrap());
+        assert_eq!(
+            keccak_hex(&encoded),
+            "12cddcf2e542b18830a6ee0ca8217a7daaae0177f522d3f48afdd03ae0af4270"
+        );
+    }
+
+    #[test]
+    fn test_sigma_fs_transcript() {
+        let public_output: Vec<GF2Word<u32>> = vec![5u32.into()];
+        let outputs = vec![vec![1u32.into()], vec![2u32.into()], vec![6u32.into()]];
+        let pi = PublicInput {
+            security_param: 40,
+            public_output: &public_output,
+            public_input: &[],
+            outputs: &outputs,
+            circuit_id: &[],
+        };
+
+        let mut fs_oracle = SigmaFS::<Keccak256>::initialize(&[0u8]);
+        fs_oracle.digest_public_data(&pi).unwrap();
+        fs_oracle
+            .digest_prover_message(&sample_commitments())
+            .unwrap();
+
+        assert_eq!(
+            fs_oracle.sample_trits(16),
+            vec![1, 2, 0, 0, 2, 0, 0, 2, 0, 0, 1, 1, 1, 1, 1, 1]
+        );
+    }
+
+    #[test]
+    fn test_circuit_id_encoding() {
+        let part = encode_circuit_id(b"", 1, 2, 3, &[]);
+        let mut expected = vec![0u8; 8];
+        for x in [1u64, 2, 3, 0] {
+            expected.extend(x.to_le_bytes());
+        }
+        assert_eq!(part, expected);
+
+        let encoded = encode_circuit_id(b"ab", 4, 5, 6, std::slice::from_ref(&part));
+        let mut expected = vec![2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b'];
+        for x in [4u64, 5, 6, 1, part.len() as u64] {
+            expected.extend(x.to_le_bytes());
+        }
+        expected.extend(part);
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_seed_encoding() {
+        assert_eq!(encode_seed(&[]), vec![0u8]);
+        assert_eq!(
+            encode_seed(b"ab"),
+            vec![1u8, 2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b']
+        );
+    }
+
+    #[test]
+    fn test_derivation_encoding() {
+        let public_output: Vec<GF2Word<u8>> = vec![0xabu8.into()];
+        let encoded =
+            encode_derivation(b"d", b"s", b"w", &public_output, b"c", (40, 2), b"p", b"i");
+
+        let mut expected = vec![b'd'];
+        expected.extend([1, 0, 0, 0, 0, 0, 0, 0, b's']);
+        expected.extend([1, 0, 0, 0, 0, 0, 0, 0, b'w']);
+        expected.extend([1, 0, 0, 0, 0, 0, 0, 0, 0xab, 8, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend([1, 0, 0, 0, 0, 0, 0, 0, b'c']);
+        expected.extend([40, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+        expected.extend([1, 0, 0, 0, 0, 0, 0, 0, b'p']);
+        expected.extend([1, 0, 0, 0, 0, 0, 0, 0, b'i']);
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn test_aggregate_seed_encoding() {
+        let mut expected = AGGREGATE_DOMAIN.to_vec();
+        expected.extend([2, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0, b'c']);
+        assert_eq!(encode_aggregate_seed(2, b"c"), expected);
+        assert_ne!(encode_aggregate_seed(1, b""), encode_seed(b""));
+    }
+
+    #[test]
+    fn test_merkle_encoding() {
+        let mut expected = MERKLE_DOMAIN.to_vec();
+        expected.push(0);
+        assert_eq!(encode_merkle_seed(b""), expected);
+
+        let commitment = &sample_commitments()[0];
+        let output: Vec<GF2Word<u8>> = vec![1u8.into()];
+        let mut expected = commitment.data.to_vec();
+        expected.extend([1, 0, 0, 0, 0, 0, 0, 0, 1, 8, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(encode_party_leaf(commitment, &output), expected);
+    }
+
+    #[test]
+    fn test_verifier_randomness_encoding() {
+        assert_eq!(
+            encode_verifier_randomness(&[7, 8]),
+            [2, 0, 0, 0, 0, 0, 0, 0, 7, 8]
+        );
+        assert_eq!(encode_verifier_randomness(&[]), [0; 8]);
+    }
+
+    #[test]
+    fn test_commitments_encoding() {
+        let commitments = sample_commitments();
+        assert_eq!(
+            hex(&commitments[0].data),
+            "aaf9c904c5023cad5d4813985f69a5f63e7fbe5d8b4705695806d9f2ceebcf5e"
+        );
+
+        let encoded = encode_commitments(&commitments);
+        assert_eq!(encoded.len(), 8 + 3 * HASH_LEN);
+        assert_eq!(encoded, bincode::serialize(&commitments).unwrap());
+        assert_eq!(
+            keccak_hex(&encoded),
+            "43dd04d7fc7c2cce76ff20f9b1e9f93ff301978a1a4d6571a39ade2c957e1138"
+        );
+    }
+}
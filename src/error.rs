@@ -1,4 +1,23 @@
 use thiserror::Error;
+
+use crate::progress::CancelStats;
+
+/// A check the verifier runs on every repetition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationCheck {
+    /// The claimed index of the first opened party is one of 0, 1 and 2.
+    PartyIndex,
+    /// The two opened parties are simulated without error.
+    Simulation,
+    /// The simulation reads exactly the tape of a party.
+    Tape,
+    /// The view of an opened party is committed to.
+    Commitment,
+    /// The output of the third party is derived from the outputs of the opened
+    /// parties and the public output.
+    Output,
+}
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("zkboo serialization error")]
@@ -13,4 +32,60 @@ pub enum Error {
     FiatShamirOutputsMatchingError,
     #[error("zkboo bit error")]
     BitError,
+    #[error("zkboo key seed error: the generator output a constant seed")]
+    KeySeedError,
+    #[error("zkboo challenge length error")]
+    ChallengeLenError(usize, usize),
+    #[error("zkboo integrity error")]
+    IntegrityError,
+    #[error("zkboo statement mismatch error")]
+    StatementMismatchError,
+    #[error("zkboo out of range error")]
+    OutOfRangeError,
+    #[error("zkboo witness byte {0} out of range")]
+    WitnessRangeError(usize),
+    #[error("zkboo witness output error: word {0} differs from the public output")]
+    WitnessOutputError(usize),
+    #[error("zkboo tape exhausted error")]
+    TapeExhaustedError,
+    #[error("zkboo tape length error: declared {0} values, read {1}")]
+    TapeLengthError(usize, usize),
+    #[error("zkboo view offset {0} out of bounds")]
+    ViewOffsetOutOfBoundsError(usize),
+    #[error("zkboo view store error")]
+    ViewStoreError,
+    #[error("zkboo parse length error: expected {0} bytes, got {1}")]
+    ParseLengthError(usize, usize),
+    #[error("zkboo transport error")]
+    TransportError,
+    #[error("zkboo protocol state error")]
+    ProtocolStateError,
+    #[error("zkboo public output length error: expected {0} words, got {1}")]
+    PublicOutputLenError(usize, usize),
+    #[error("zkboo test vector mismatch in {0}")]
+    TestVectorMismatchError(&'static str),
+    #[error("zkboo circuit simulation mismatch: {0}")]
+    CircuitConsistencyError(&'static str),
+    #[error("zkboo parameter mismatch in {0}: expected {1}, got {2}")]
+    ParameterMismatchError(&'static str, String, String),
+    #[error("zkboo memory budget error: needs {0} bytes, the budget is {1}")]
+    MemoryBudgetError(usize, usize),
+    #[error("zkboo security level error: the proof has {0} bits, at least {1} are required")]
+    SecurityLevelError(usize, usize),
+    #[error("zkboo unknown circuit {0}")]
+    UnknownCircuitError(String),
+    #[error("zkboo circuit {0} is already registered")]
+    DuplicateCircuitError(String),
+    #[error("zkboo share consistency error")]
+    ShareConsistencyError,
+    #[error("zkboo job cancelled in {:?} after {} of {} steps", .0.at.phase, .0.at.completed, .0.at.total)]
+    CancelledError(Box<CancelStats>),
+    #[error("zkboo verification error in repetition {repetition}, party {party}: {check:?} check failed")]
+    RepetitionError {
+        repetition: usize,
+        party: u8,
+        check: VerificationCheck,
+        #[source]
+        source: Box<Error>,
+    },
 }
@@ -0,0 +1,253 @@
+//! Generators expanding a party key into its random tape.
+//!
+//! A `TapeExpander` turns a key and a label into a stream of randomness, read
+//! by `Tape` a chunk at a time. The prover and the verifier must expand keys
+//! with the same generator, which is why it is a parameter of `Prover`,
+//! `Verifier` and `ProofParams` and is identified by `TapeExpander::ID`.
+//!
+//! An empty label uses the key as is. Any other label keys the generator with
+//! `Keccak256(domain || key || len: u64 || label)`, except for SHAKE128 which
+//! absorbs the label itself.
+//!
+//! The prover and the verifier label the tape of each party with its
+//! repetition and party index, see `tape_label`, so that the same key used
+//! twice by mistake still yields independent tapes. The `n`-th value of a tape
+//! is the `n`-th value of its stream, which makes the gate counter the position
+//! in the stream. `LegacyChaCha20Rng` ignores labels, for proofs made before
+//! tapes were labeled.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{impls, CryptoRng, CryptoRngCore, RngCore};
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Digest, Keccak256, Shake128, Shake128Reader,
+};
+
+use crate::{
+    gf2_word::{GF2Word, Value},
+    key::Key,
+};
+
+/// Separates labeled keys from the keys they are derived from.
+const LABEL_DOMAIN: &[u8] = b"zkboo-tape-label-v1";
+
+/// The key of the generator for `key` under `label`.
+pub fn labeled_key(key: &Key, label: &[u8]) -> Key {
+    if label.is_empty() {
+        return *key;
+    }
+    let mut hasher = Keccak256::new_with_prefix(LABEL_DOMAIN);
+    Digest::update(&mut hasher, key);
+    Digest::update(&mut hasher, (label.len() as u64).to_le_bytes());
+    Digest::update(&mut hasher, label);
+    hasher.finalize().into()
+}
+
+/// The label of the tape of party `party_index` in `repetition`:
+/// `repetition: u64 || party_index: u8`.
+pub fn tape_label(repetition: usize, party_index: usize) -> Vec<u8> {
+    debug_assert!(party_index < 3);
+    let mut label = (repetition as u64).to_le_bytes().to_vec();
+    label.push(party_index as u8);
+    label
+}
+
+pub trait TapeExpander: 'static {
+    /// Name of the generator, recorded in `ProofParams`.
+    const ID: &'static str;
+
+    /// The stream of randomness for `key` under `label`.
+    fn stream(key: &Key, label: &[u8]) -> Box<dyn CryptoRngCore>;
+
+    /// The first `len` values of the stream for `key` under `label`.
+    fn expand<T: Value>(key: &Key, label: &[u8], len: usize) -> Vec<GF2Word<T>> {
+        T::gen_rand_packed(&mut Self::stream(key, label), len)
+            .into_iter()
+            .map(GF2Word::from)
+            .collect()
+    }
+}
+
+impl TapeExpander for ChaCha20Rng {
+    const ID: &'static str = "chacha20";
+
+    fn stream(key: &Key, label: &[u8]) -> Box<dyn CryptoRngCore> {
+        Box::new(ChaCha20Rng::from_seed(labeled_key(key, label)))
+    }
+}
+
+/// ChaCha20 seeded with the key whatever the label, as tapes were expanded
+/// before they were labeled.
+pub struct LegacyChaCha20Rng;
+
+impl TapeExpander for LegacyChaCha20Rng {
+    const ID: &'static str = "chacha20-legacy";
+
+    fn stream(key: &Key, _label: &[u8]) -> Box<dyn CryptoRngCore> {
+        Box::new(ChaCha20Rng::from_seed(*key))
+    }
+}
+
+/// SHAKE128 of `domain || key || label`.
+pub struct Shake128Expander(Shake128Reader);
+
+/// Separates tapes from other uses of SHAKE128 on the same key.
+const SHAKE_DOMAIN: &[u8] = b"zkboo-tape-shake128-v1";
+
+impl RngCore for Shake128Expander {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.read(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for Shake128Expander {}
+
+impl TapeExpander for Shake128Expander {
+    const ID: &'static str = "shake128";
+
+    fn stream(key: &Key, label: &[u8]) -> Box<dyn CryptoRngCore> {
+        let mut xof = Shake128::default();
+        xof.update(SHAKE_DOMAIN);
+        xof.update(key);
+        xof.update(label);
+        Box::new(Self(xof.finalize_xof()))
+    }
+}
+
+/// AES-256 in counter mode from a zero IV, with AES-NI when the CPU has it.
+#[cfg(feature = "aes")]
+pub struct AesCtrExpander(ctr::Ctr128BE<aes::Aes256>);
+
+#[cfg(feature = "aes")]
+impl RngCore for AesCtrExpander {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        use ctr::cipher::StreamCipher;
+
+        dest.fill(0);
+        self.0.apply_keystream(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "aes")]
+impl CryptoRng for AesCtrExpander {}
+
+#[cfg(feature = "aes")]
+impl TapeExpander for AesCtrExpander {
+    const ID: &'static str = "aes256-ctr";
+
+    fn stream(key: &Key, label: &[u8]) -> Box<dyn CryptoRngCore> {
+        use ctr::cipher::KeyIvInit;
+
+        let key = labeled_key(key, label);
+        Box::new(Self(ctr::Ctr128BE::new(&key.into(), &[0u8; 16].into())))
+    }
+}
+
+#[cfg(test)]
+mod expander_tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::gf2_word::GenRand;
+
+    use super::*;
+
+    fn check_expander<E: TapeExpander>() {
+        let key = [5u8; 32];
+        let values = E::expand::<u32>(&key, b"label", 50);
+        assert_eq!(values, E::expand::<u32>(&key, b"label", 50));
+        // a prefix of a longer expansion
+        assert_eq!(values[..20], E::expand::<u32>(&key, b"label", 20));
+
+        assert_ne!(values, E::expand::<u32>(&key, b"other", 50));
+        assert_ne!(values, E::expand::<u32>(&[6u8; 32], b"label", 50));
+        assert_ne!(values, E::expand::<u32>(&key, b"", 50));
+    }
+
+    #[test]
+    fn test_expanders() {
+        check_expander::<ChaCha20Rng>();
+        check_expander::<Shake128Expander>();
+        #[cfg(feature = "aes")]
+        check_expander::<AesCtrExpander>();
+    }
+
+    #[test]
+    fn test_chacha20_without_label() {
+        // tapes of an empty label are those of the key as a seed
+        let key = [9u8; 32];
+        let mut rng = ChaCha20Rng::from_seed(key);
+        let expected: Vec<u32> = (0..10).map(|_| u32::gen_rand(&mut rng)).collect();
+        let values = ChaCha20Rng::expand::<u32>(&key, &[], 10);
+        assert_eq!(
+            values.iter().map(|word| word.value).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_tape_labels() {
+        let key = [3u8; 32];
+        let labels = [tape_label(0, 0), tape_label(0, 1), tape_label(1, 0)];
+        assert!(labels.iter().all(|label| label.len() == 9));
+
+        // a key reused across parties or repetitions yields unrelated tapes
+        let tapes: Vec<_> = labels
+            .iter()
+            .map(|label| ChaCha20Rng::expand::<u32>(&key, label, 10))
+            .collect();
+        assert_ne!(tapes[0], tapes[1]);
+        assert_ne!(tapes[0], tapes[2]);
+
+        // unless labels are ignored
+        let legacy = LegacyChaCha20Rng::expand::<u32>(&key, &labels[1], 10);
+        assert_eq!(
+            legacy,
+            LegacyChaCha20Rng::expand::<u32>(&key, &labels[2], 10)
+        );
+        assert_eq!(legacy, ChaCha20Rng::expand::<u32>(&key, &[], 10));
+    }
+
+    #[cfg(feature = "aes")]
+    #[test]
+    fn test_aes_ctr_keystream() {
+        use aes::cipher::{BlockEncrypt, KeyInit};
+
+        // the first block is the encryption of a zero counter
+        let key = [1u8; 32];
+        let mut block = [0u8; 16].into();
+        aes::Aes256::new(&key.into()).encrypt_block(&mut block);
+
+        let mut stream = AesCtrExpander::stream(&key, &[]);
+        let mut bytes = [0u8; 16];
+        stream.fill_bytes(&mut bytes);
+        assert_eq!(bytes, block.as_slice());
+    }
+}
@@ -0,0 +1,143 @@
+//! Fault injection for resilience testing, behind the `fault-injection` feature.
+//!
+//! A `FaultInjector` is called by `Prover::prove_with_faults` at fixed points of
+//! the proving pipeline and may corrupt whatever passes through them. This
+//! gives systems built on top of the prover realistic partially broken proofs
+//! to test their error handling against.
+
+use sha3::Digest;
+
+use crate::{data_structures::Proof, gf2_word::Value, tape::Tape, view::View};
+
+pub trait FaultInjector<T: Value, D: Default + Digest + Clone> {
+    /// Called with the tape of `party` before the circuit runs.
+    fn tape(&mut self, _repetition: usize, _party: usize, _tape: &mut Tape<T>) {}
+
+    /// Called with the view of `party` after the circuit ran, before it is
+    /// committed to.
+    fn view(&mut self, _repetition: usize, _party: usize, _view: &mut View<T>) {}
+
+    /// Called with the finished proof.
+    fn proof(&mut self, _proof: &mut Proof<T, D>) {}
+}
+
+/// Injects no fault.
+pub struct NoFaults;
+
+impl<T: Value, D: Default + Digest + Clone> FaultInjector<T, D> for NoFaults {}
+
+#[cfg(feature = "fault-injection")]
+pub use injectors::*;
+
+#[cfg(feature = "fault-injection")]
+mod injectors {
+    use sha3::Digest;
+
+    use crate::{data_structures::Proof, gf2_word::Value, tape::Tape, view::View};
+
+    use super::FaultInjector;
+
+    /// Flips `bit` of message `message` in the view of `party`, or of every
+    /// party if `party` is `None`.
+    pub struct FlipViewBit {
+        pub repetition: usize,
+        pub party: Option<usize>,
+        pub message: usize,
+        pub bit: usize,
+    }
+
+    impl<T: Value, D: Default + Digest + Clone> FaultInjector<T, D> for FlipViewBit {
+        fn view(&mut self, repetition: usize, party: usize, view: &mut View<T>) {
+            if repetition != self.repetition || self.party.is_some_and(|p| p != party) {
+                return;
+            }
+            if let Some(msg) = view.messages.get_mut(self.message) {
+                let bit = self.bit % T::bits_len();
+                msg.value = msg.value.set_bit(bit, !msg.value.get_bit(bit).inner());
+            }
+        }
+    }
+
+    /// Removes the commitment of `repetition` from the proof.
+    pub struct DropCommitment {
+        pub repetition: usize,
+    }
+
+    impl<T: Value, D: Default + Digest + Clone> FaultInjector<T, D> for DropCommitment {
+        fn proof(&mut self, proof: &mut Proof<T, D>) {
+            if self.repetition < proof.commitments.len() {
+                proof.commitments.remove(self.repetition);
+            }
+        }
+    }
+
+    /// Cuts the tape of `party` down to `len` values.
+    pub struct TruncateTape {
+        pub repetition: usize,
+        pub party: usize,
+        pub len: usize,
+    }
+
+    impl<T: Value, D: Default + Digest + Clone> FaultInjector<T, D> for TruncateTape {
+        fn tape(&mut self, repetition: usize, party: usize, tape: &mut Tape<T>) {
+            if repetition == self.repetition && party == self.party {
+                tape.truncate(self.len);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "fault-injection"))]
+mod fault_tests {
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::Circuit, error::Error, gadgets::add_mod::AdderCircuit, prover::Prover,
+        verifier::Verifier,
+    };
+
+    use super::{DropCommitment, FaultInjector, FlipViewBit, NoFaults, TruncateTape};
+
+    const SIGMA: usize = 40;
+
+    fn prove_and_verify(faults: &mut impl FaultInjector<u32, Keccak256>) -> Result<(), Error> {
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove_with_faults::<ThreadRng, SIGMA>(
+            &mut rng, &input, &circuit, &output, faults,
+        )?;
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+    }
+
+    #[test]
+    fn test_faults() {
+        prove_and_verify(&mut NoFaults).unwrap();
+
+        assert!(prove_and_verify(&mut FlipViewBit {
+            repetition: 3,
+            party: None,
+            message: 0,
+            bit: 5,
+        })
+        .is_err());
+
+        assert!(matches!(
+            prove_and_verify(&mut DropCommitment { repetition: 0 }),
+            Err(Error::VerificationError)
+        ));
+
+        assert!(matches!(
+            prove_and_verify(&mut TruncateTape {
+                repetition: 1,
+                party: 2,
+                len: 0,
+            }),
+            Err(Error::TapeExhaustedError)
+        ));
+    }
+}
@@ -2,14 +2,37 @@
 //!
 //! This is used to derive verifier opening queries non-interactively, after the
 //! prover commits to all its views.
+//!
+//! The prover and verifier talk to the oracle through the `Transcript` trait.
+//! `SigmaFS` is the hash-based oracle used by default; behind the `merlin`
+//! feature a `merlin::Transcript` can be passed instead, so that a ZKBoo proof
+//! is bound to the transcript of a larger protocol.
+//!
+//! The oracle hashes with its own function, which need not be the one the
+//! views are committed with: `Prover` and `Verifier` take the oracle as a type
+//! parameter, `SigmaFS<D>` by default. `XofFS` derives challenges from an
+//! extendable output function such as SHAKE256.
+//!
+//! Challenges are vectors of trits. Every oracle reads them from its output
+//! bytes with `decode_trits`, which rejects the pair of bits that does not
+//! encode a trit instead of reducing it modulo 3, so trits are uniform when
+//! the bytes are. Challenges picked at random, e.g. to be simulated with
+//! `Prover::simulate`, are decoded the same way from `random_trits`.
 
+use rand_core::{CryptoRng, RngCore};
 use sha3::{
-    digest::{FixedOutputReset, OutputSizeUser},
+    digest::{ExtendableOutput, FixedOutputReset, Update, XofReader},
     Digest,
 };
 use std::marker::PhantomData;
 
-use crate::{commitment::Commitment, data_structures::PublicInput, error::Error, gf2_word::Value};
+use crate::{
+    commitment::Commitment,
+    data_structures::PublicInput,
+    encoding::{encode_commitments, encode_public_input, encode_seed, encode_verifier_randomness},
+    error::Error,
+    gf2_word::Value,
+};
 
 pub struct SigmaProtocolStatelessFiatShamir<D: Clone + Digest + Clone>(PhantomData<D>);
 
@@ -21,43 +44,92 @@ impl<D: Clone + Digest> SigmaProtocolStatelessFiatShamir<D> {
             hasher.update(public_data);
             hasher.update(prover_msg);
 
-            hasher.finalize()
+            hasher.finalize().to_vec()
         };
 
-        // local closure for which pos always < 8
-        let get_bit = |x: u8, pos: usize| -> u8 { (x >> pos) & 1 };
-
-        let mut trits = vec![0u8; r];
-
-        let mut sampled: usize = 0;
         let mut prefix: u8 = 0;
-        let mut pos = 0;
+        sample_trits_from(
+            with_prefix(prefix),
+            || {
+                prefix += 1;
+                with_prefix(prefix)
+            },
+            r,
+        )
+    }
+}
 
-        let mut hash = with_prefix(prefix);
+/// A Fiat-Shamir oracle. The prover and the verifier absorb the same messages
+/// in the same order, then squeeze one challenge trit per repetition.
+pub trait Transcript<D: Default + Digest + Clone> {
+    fn absorb_public_input<T: Value>(&mut self, pi: &PublicInput<T>) -> Result<(), Error>;
+    fn absorb_commitments(&mut self, commitments: &[Commitment<D>]) -> Result<(), Error>;
+    /// Absorbs the randomness the verifier of an interactive session sends
+    /// with its challenge.
+    fn absorb_verifier_randomness(&mut self, randomness: &[u8]) -> Result<(), Error>;
+    fn challenge_trits(&mut self, r: usize) -> Vec<u8>;
+}
 
-        while sampled < r {
-            if pos >= <D as OutputSizeUser>::output_size() * 8 {
-                prefix += 1;
-                hash = with_prefix(prefix);
-                pos = 0;
-            }
+/// A `Transcript` started from a seed, as the prover and the verifier start
+/// the oracle of the proofs they make and check on their own.
+pub trait Oracle<D: Default + Digest + Clone>: Transcript<D> {
+    fn from_seed(seed: &[u8]) -> Self;
 
-            let b1 = get_bit(hash[(pos / 8)], pos % 8);
-            let b2 = get_bit(hash[((pos + 1) / 8)], (pos + 1) % 8);
+    /// Oracle bound to `context`. The empty context gives the oracle of plain
+    /// proofs.
+    fn from_context(context: &[u8]) -> Self
+    where
+        Self: Sized,
+    {
+        Self::from_seed(&encode_seed(context))
+    }
+}
 
+/// The trits encoded by `bytes`, in order.
+///
+/// Each byte is read from its least significant bit up, two bits at a time.
+/// The pair of bits `(b1, b2)`, with `b1` read first, encodes the trit
+/// `2 * b1 + b2`, except for `(1, 1)` which is skipped. A byte thus gives zero
+/// to four trits, and uniform bytes give uniform trits, e.g. `0x9b` gives
+/// `[1, 2, 1]`.
+pub fn decode_trits(bytes: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    bytes.iter().flat_map(|&byte| {
+        (0..4).filter_map(move |pair| {
+            let b1 = (byte >> (2 * pair)) & 1;
+            let b2 = (byte >> (2 * pair + 1)) & 1;
             let trit = (b1 << 1) | b2;
-            if trit < 3 {
-                trits[sampled] = trit;
-                sampled += 1;
-            }
+            (trit < 3).then_some(trit)
+        })
+    })
+}
 
-            pos += 2;
+/// The first `r` trits decoded from `bytes`, followed by those decoded from
+/// `next` each time the previous bytes run out.
+fn sample_trits_from(mut bytes: Vec<u8>, mut next: impl FnMut() -> Vec<u8>, r: usize) -> Vec<u8> {
+    let mut trits = Vec::with_capacity(r);
+    loop {
+        trits.extend(decode_trits(&bytes).take(r - trits.len()));
+        if trits.len() == r {
+            return trits;
         }
-
-        trits
+        bytes = next();
     }
 }
 
+/// Number of random bytes `random_trits` draws at a time.
+const RANDOM_BLOCK_LEN: usize = 64;
+
+/// `r` uniform trits decoded from bytes drawn from `rng`.
+pub fn random_trits<R: RngCore + CryptoRng>(rng: &mut R, r: usize) -> Vec<u8> {
+    let mut draw = || {
+        let mut bytes = vec![0u8; RANDOM_BLOCK_LEN];
+        rng.fill_bytes(&mut bytes);
+        bytes
+    };
+    let bytes = draw();
+    sample_trits_from(bytes, draw, r)
+}
+
 pub struct SigmaFS<D: Digest + FixedOutputReset + Clone> {
     hasher: D,
 }
@@ -68,59 +140,172 @@ impl<D: Default + Digest + FixedOutputReset + Clone> SigmaFS<D> {
         Self { hasher }
     }
 
+    /// Oracle bound to `context`. The empty context gives the oracle of plain proofs.
+    pub fn initialize_with_context(context: &[u8]) -> Self {
+        Self::initialize(&encode_seed(context))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub fn digest_public_data<T: Value>(&mut self, pi: &PublicInput<T>) -> Result<(), Error> {
-        let data = bincode::serialize(pi).map_err(|_| Error::SerializationError)?;
-        Digest::update(&mut self.hasher, &data);
+        Digest::update(&mut self.hasher, encode_public_input(pi));
         Ok(())
     }
 
-    pub fn digest_prover_message(
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn digest_prover_message<DC: Default + Digest + Clone>(
         &mut self,
-        single_run_commitments: &Vec<Commitment<D>>,
+        single_run_commitments: &[Commitment<DC>],
     ) -> Result<(), Error> {
-        let data =
-            bincode::serialize(single_run_commitments).map_err(|_| Error::SerializationError)?;
-        Digest::update(&mut self.hasher, data);
+        Digest::update(&mut self.hasher, encode_commitments(single_run_commitments));
         Ok(())
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
     pub fn sample_trits(&mut self, r: usize) -> Vec<u8> {
-        let mut hash = self.hasher.finalize_reset();
+        let mut last = self.hasher.finalize_reset().to_vec();
+
+        sample_trits_from(
+            last.clone(),
+            || {
+                Digest::update(&mut self.hasher, &last);
+                last = self.hasher.finalize_reset().to_vec();
+                last.clone()
+            },
+            r,
+        )
+    }
+}
 
-        // local closure for which pos always < 8
-        let get_bit = |x: u8, pos: usize| -> u8 { (x >> pos) & 1 };
+// the oracle hashes with `F`, whatever hash `D` the views are committed with
+impl<D, F> Transcript<D> for SigmaFS<F>
+where
+    D: Default + Digest + Clone,
+    F: Default + Digest + FixedOutputReset + Clone,
+{
+    fn absorb_public_input<T: Value>(&mut self, pi: &PublicInput<T>) -> Result<(), Error> {
+        self.digest_public_data(pi)
+    }
 
-        let mut sampled: usize = 0;
-        let mut pos = 0;
+    fn absorb_commitments(&mut self, commitments: &[Commitment<D>]) -> Result<(), Error> {
+        self.digest_prover_message(commitments)
+    }
 
-        let mut trits = vec![0u8; r];
+    fn absorb_verifier_randomness(&mut self, randomness: &[u8]) -> Result<(), Error> {
+        Digest::update(&mut self.hasher, encode_verifier_randomness(randomness));
+        Ok(())
+    }
 
-        while sampled < r {
-            if pos >= <D as OutputSizeUser>::output_size() * 8 {
-                Digest::update(&mut self.hasher, &hash);
-                hash = self.hasher.finalize_reset();
-                pos = 0;
-            }
+    fn challenge_trits(&mut self, r: usize) -> Vec<u8> {
+        self.sample_trits(r)
+    }
+}
 
-            let b1 = get_bit(hash[(pos / 8)], pos % 8);
-            let b2 = get_bit(hash[((pos + 1) / 8)], (pos + 1) % 8);
+impl<D, F> Oracle<D> for SigmaFS<F>
+where
+    D: Default + Digest + Clone,
+    F: Default + Digest + FixedOutputReset + Clone,
+{
+    fn from_seed(seed: &[u8]) -> Self {
+        Self::initialize(seed)
+    }
+}
 
-            let trit = (b1 << 1) | b2;
-            if trit < 3 {
-                trits[sampled] = trit;
-                sampled += 1;
-            }
+/// Number of bytes `XofFS` squeezes at a time.
+const XOF_BLOCK_LEN: usize = 64;
 
-            pos += 2;
-        }
+/// Oracle absorbing the same messages as `SigmaFS` into an extendable output
+/// function `X`, e.g. `sha3::Shake256`, and squeezing its challenges from it.
+#[derive(Clone)]
+pub struct XofFS<X: Default + Update + ExtendableOutput + Clone> {
+    hasher: X,
+}
+
+impl<X: Default + Update + ExtendableOutput + Clone> XofFS<X> {
+    pub fn initialize(seed: &[u8]) -> Self {
+        let mut hasher = X::default();
+        hasher.update(seed);
+        Self { hasher }
+    }
+}
+
+impl<D, X> Transcript<D> for XofFS<X>
+where
+    D: Default + Digest + Clone,
+    X: Default + Update + ExtendableOutput + Clone,
+{
+    fn absorb_public_input<T: Value>(&mut self, pi: &PublicInput<T>) -> Result<(), Error> {
+        self.hasher.update(&encode_public_input(pi));
+        Ok(())
+    }
+
+    fn absorb_commitments(&mut self, commitments: &[Commitment<D>]) -> Result<(), Error> {
+        self.hasher.update(&encode_commitments(commitments));
+        Ok(())
+    }
+
+    fn absorb_verifier_randomness(&mut self, randomness: &[u8]) -> Result<(), Error> {
+        self.hasher.update(&encode_verifier_randomness(randomness));
+        Ok(())
+    }
+
+    fn challenge_trits(&mut self, r: usize) -> Vec<u8> {
+        let mut reader = self.hasher.clone().finalize_xof();
+        let mut squeeze = || {
+            let mut bytes = vec![0u8; XOF_BLOCK_LEN];
+            reader.read(&mut bytes);
+            bytes
+        };
+        let first = squeeze();
+        // a later challenge depends on this one
+        self.hasher.update(&first);
+        sample_trits_from(first, squeeze, r)
+    }
+}
+
+impl<D, X> Oracle<D> for XofFS<X>
+where
+    D: Default + Digest + Clone,
+    X: Default + Update + ExtendableOutput + Clone,
+{
+    fn from_seed(seed: &[u8]) -> Self {
+        Self::initialize(seed)
+    }
+}
+
+#[cfg(feature = "merlin")]
+impl<D: Default + Digest + Clone> Transcript<D> for merlin::Transcript {
+    fn absorb_public_input<T: Value>(&mut self, pi: &PublicInput<T>) -> Result<(), Error> {
+        self.append_message(b"zkboo-public-input", &encode_public_input(pi));
+        Ok(())
+    }
 
-        trits
+    fn absorb_commitments(&mut self, commitments: &[Commitment<D>]) -> Result<(), Error> {
+        self.append_message(b"zkboo-commitments", &encode_commitments(commitments));
+        Ok(())
+    }
+
+    fn absorb_verifier_randomness(&mut self, randomness: &[u8]) -> Result<(), Error> {
+        self.append_message(
+            b"zkboo-verifier-randomness",
+            &encode_verifier_randomness(randomness),
+        );
+        Ok(())
+    }
+
+    fn challenge_trits(&mut self, r: usize) -> Vec<u8> {
+        let mut challenge = || {
+            let mut bytes = vec![0u8; 64];
+            self.challenge_bytes(b"zkboo-trits", &mut bytes);
+            bytes
+        };
+        let bytes = challenge();
+        sample_trits_from(bytes, challenge, r)
     }
 }
 
 #[cfg(test)]
 mod test_fs {
-    use super::SigmaProtocolStatelessFiatShamir;
+    use super::{decode_trits, random_trits, SigmaProtocolStatelessFiatShamir};
     use sha3::Keccak256;
 
     #[test]
@@ -140,4 +325,114 @@ mod test_fs {
             assert!(trit == 0 || trit == 1 || trit == 2);
         }
     }
+
+    #[test]
+    fn test_decode_trits() {
+        let decode = |bytes: &[u8]| decode_trits(bytes).collect::<Vec<_>>();
+
+        // pairs are read from the least significant bit, (1, 1) is skipped
+        assert_eq!(decode(&[0x00]), vec![0, 0, 0, 0]);
+        assert_eq!(decode(&[0xff]), Vec::<u8>::new());
+        assert_eq!(decode(&[0x9b]), vec![1, 2, 1]);
+        assert_eq!(decode(&[0x01, 0x02]), vec![2, 0, 0, 0, 1, 0, 0, 0]);
+        assert_eq!(decode(&[0xe4]), vec![0, 2, 1]);
+
+        // every trit is decoded from as many bytes
+        let mut counts = [0usize; 3];
+        for byte in 0..=255u8 {
+            decode(&[byte])
+                .into_iter()
+                .for_each(|t| counts[t as usize] += 1);
+        }
+        assert_eq!(counts[0], counts[1]);
+        assert_eq!(counts[1], counts[2]);
+
+        let trits = random_trits(&mut rand::thread_rng(), 1000);
+        assert_eq!(trits.len(), 1000);
+        assert!(trits.iter().all(|&t| t < 3));
+    }
+
+    #[test]
+    fn test_separate_oracle_hash() {
+        use rand::{rngs::ThreadRng, thread_rng};
+        use rand_chacha::ChaCha20Rng;
+        use sha3::{Sha3_256, Shake256};
+
+        use super::{SigmaFS, XofFS};
+        use crate::{
+            circuit::Circuit, commitment::HashCommitment, gadgets::add_mod::AdderCircuit,
+            prover::Prover, verifier::Verifier,
+        };
+
+        const SIGMA: usize = 40;
+        type P<F> = Prover<u32, ChaCha20Rng, Sha3_256, HashCommitment, F>;
+        type V<F> = Verifier<u32, ChaCha20Rng, Sha3_256, HashCommitment, F>;
+
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        // commitments with SHA3-256, challenges with SHAKE256
+        let proof = P::<XofFS<Shake256>>::prove::<ThreadRng, SIGMA>(
+            &mut thread_rng(),
+            &input,
+            &circuit,
+            &output,
+        )
+        .unwrap();
+        V::<XofFS<Shake256>>::verify::<SIGMA>(&proof, &circuit, &output).unwrap();
+        assert!(V::<SigmaFS<Sha3_256>>::verify::<SIGMA>(&proof, &circuit, &output).is_err());
+
+        let proof = P::<SigmaFS<Keccak256>>::prove::<ThreadRng, SIGMA>(
+            &mut thread_rng(),
+            &input,
+            &circuit,
+            &output,
+        )
+        .unwrap();
+        V::<SigmaFS<Keccak256>>::verify::<SIGMA>(&proof, &circuit, &output).unwrap();
+        assert!(V::<SigmaFS<Sha3_256>>::verify::<SIGMA>(&proof, &circuit, &output).is_err());
+    }
+
+    #[cfg(feature = "merlin")]
+    #[test]
+    fn test_merlin_transcript() {
+        use rand::{rngs::ThreadRng, thread_rng};
+        use rand_chacha::ChaCha20Rng;
+
+        use crate::{
+            circuit::Circuit, gadgets::add_mod::AdderCircuit, prover::Prover, verifier::Verifier,
+        };
+
+        const SIGMA: usize = 40;
+        type P = Prover<u32, ChaCha20Rng, Keccak256>;
+        type V = Verifier<u32, ChaCha20Rng, Keccak256>;
+
+        // a ZKBoo proof as one step of an enclosing protocol
+        let transcript = || {
+            let mut transcript = merlin::Transcript::new(b"enclosing protocol");
+            transcript.append_message(b"round", b"1");
+            transcript
+        };
+
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        let proof = P::prove_with_transcript::<ThreadRng, SIGMA>(
+            &mut thread_rng(),
+            &input,
+            &circuit,
+            &output,
+            &mut transcript(),
+        )
+        .unwrap();
+
+        V::verify_with_transcript::<SIGMA>(&proof, &circuit, &output, &mut transcript()).unwrap();
+
+        let mut other = transcript();
+        other.append_message(b"round", b"2");
+        assert!(V::verify_with_transcript::<SIGMA>(&proof, &circuit, &output, &mut other).is_err());
+        assert!(V::verify::<SIGMA>(&proof, &circuit, &output).is_err());
+    }
 }
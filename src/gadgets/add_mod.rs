@@ -1,4 +1,9 @@
+use std::marker::PhantomData;
+
 use crate::{
+    circuit::{named_id, Circuit, Output, TwoThreeDecOutput},
+    error::Error,
+    gadgets::prepare::{generic_parse, Prepare},
     gf2_word::{Bit, GF2Word, Value},
     party::Party,
 };
@@ -32,7 +37,7 @@ pub fn adder<T: Value>(x: T, y: T) -> T {
 // }
 
 /// Binary multiplication gate from p.12 of https://eprint.iacr.org/2016/163.pdf
-fn bit_and(input_p1: (Bit, Bit), input_p2: (Bit, Bit), r_p1: Bit, r_p2: Bit) -> Bit {
+pub(crate) fn bit_and(input_p1: (Bit, Bit), input_p2: (Bit, Bit), r_p1: Bit, r_p2: Bit) -> Bit {
     (input_p1.0 & input_p1.1)
         ^ (input_p1.0 & input_p2.1)
         ^ (input_p1.1 & input_p2.0)
@@ -147,12 +152,12 @@ pub fn add_mod_verify<T: Value>(
     input_p_next: (GF2Word<T>, GF2Word<T>),
     p: &mut Party<T>,
     p_next: &mut Party<T>,
-) -> (GF2Word<T>, GF2Word<T>) {
+) -> Result<(GF2Word<T>, GF2Word<T>), Error> {
     let ri = p.read_tape();
     let ri_next = p_next.read_tape();
 
     let mut carry_p = T::zero().into();
-    let carry_p_next = p_next.view.read_next();
+    let carry_p_next = p_next.view.read_next()?;
 
     for i in 0..T::bytes_len() * 8 - 1 {
         let ri_p = ri.value.get_bit(i);
@@ -175,7 +180,7 @@ pub fn add_mod_verify<T: Value>(
     let o1 = input_p.0 ^ input_p.1 ^ carry_p;
     let o2 = input_p_next.0 ^ input_p_next.1 ^ carry_p_next;
 
-    (o1, o2)
+    Ok((o1, o2))
 }
 
 pub fn add_mod_verify_k<T: Value>(
@@ -184,12 +189,12 @@ pub fn add_mod_verify_k<T: Value>(
     k: GF2Word<T>,
     p: &mut Party<T>,
     p_next: &mut Party<T>,
-) -> (GF2Word<T>, GF2Word<T>) {
+) -> Result<(GF2Word<T>, GF2Word<T>), Error> {
     let ri = p.read_tape();
     let ri_next = p_next.read_tape();
 
     let mut carry_p = T::zero().into();
-    let carry_p_next = p_next.view.read_next();
+    let carry_p_next = p_next.view.read_next()?;
 
     for i in 0..T::bytes_len() * 8 - 1 {
         let ri_p = ri.value.get_bit(i);
@@ -212,7 +217,87 @@ pub fn add_mod_verify_k<T: Value>(
     let o1 = input_p ^ k ^ carry_p;
     let o2 = input_p_next ^ k ^ carry_p_next;
 
-    (o1, o2)
+    Ok((o1, o2))
+}
+
+/// Computes `x + y` modulo 2^(T::bits_size) for two secret words.
+///
+/// The witness is `x || y` with both words little-endian encoded.
+#[derive(Clone, Copy, Default)]
+pub struct AdderCircuit<T: Value>(PhantomData<T>);
+
+impl<T: Value> AdderCircuit<T> {
+    pub fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: Value> Circuit<T> for AdderCircuit<T> {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+        let words = generic_parse::<T>(input, self.party_input_len()).unwrap();
+        vec![adder(words[0].value, words[1].value).into()]
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<T>,
+        p2: &mut Party<T>,
+        p3: &mut Party<T>,
+    ) -> TwoThreeDecOutput<T> {
+        let words_p1 = generic_parse(&p1.view.input, self.party_input_len()).unwrap();
+        let words_p2 = generic_parse(&p2.view.input, self.party_input_len()).unwrap();
+        let words_p3 = generic_parse(&p3.view.input, self.party_input_len()).unwrap();
+
+        let (o1, o2, o3) = mpc_add_mod(
+            (words_p1[0], words_p1[1]),
+            (words_p2[0], words_p2[1]),
+            (words_p3[0], words_p3[1]),
+            p1,
+            p2,
+            p3,
+        );
+        (vec![o1], vec![o2], vec![o3])
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<T>,
+        p_next: &mut Party<T>,
+    ) -> Result<(Output<T>, Output<T>), Error> {
+        let words_p = generic_parse(&p.view.input, self.party_input_len())?;
+        let words_p_next = generic_parse(&p_next.view.input, self.party_input_len())?;
+
+        let (o1, o2) = add_mod_verify(
+            (words_p[0], words_p[1]),
+            (words_p_next[0], words_p_next[1]),
+            p,
+            p_next,
+        )?;
+        Ok((vec![o1], vec![o2]))
+    }
+
+    fn party_input_len(&self) -> usize {
+        2
+    }
+
+    fn party_output_len(&self) -> usize {
+        1
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        1
+    }
+
+    fn id(&self) -> Vec<u8> {
+        named_id("adder", self, &[])
+    }
+}
+
+impl<T: Value> Prepare<T> for AdderCircuit<T> {
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        generic_parse::<T>(bytes, self.party_input_len())?;
+        Ok(bytes.to_vec())
+    }
 }
 
 #[cfg(test)]
@@ -235,7 +320,7 @@ mod adder_tests {
 
     impl<T: Value> Circuit<T> for AddModKCircuit<T> {
         fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
-            let input = generic_parse(input, self.party_input_len())[0];
+            let input = generic_parse(input, self.party_input_len()).unwrap()[0];
             let res = adder(input.value, self.k.value);
             vec![res.into()]
         }
@@ -246,9 +331,9 @@ mod adder_tests {
             p2: &mut Party<T>,
             p3: &mut Party<T>,
         ) -> (Vec<GF2Word<T>>, Vec<GF2Word<T>>, Vec<GF2Word<T>>) {
-            let input_p1 = generic_parse(&p1.view.input, self.party_input_len())[0];
-            let input_p2 = generic_parse(&p2.view.input, self.party_input_len())[0];
-            let input_p3 = generic_parse(&p3.view.input, self.party_input_len())[0];
+            let input_p1 = generic_parse(&p1.view.input, self.party_input_len()).unwrap()[0];
+            let input_p2 = generic_parse(&p2.view.input, self.party_input_len()).unwrap()[0];
+            let input_p3 = generic_parse(&p3.view.input, self.party_input_len()).unwrap()[0];
 
             let (o1, o2, o3) = mpc_add_mod_k(input_p1, input_p2, input_p3, self.k, p1, p2, p3);
             (vec![o1], vec![o2], vec![o3])
@@ -259,10 +344,10 @@ mod adder_tests {
             p: &mut Party<T>,
             p_next: &mut Party<T>,
         ) -> Result<(Output<T>, Output<T>), Error> {
-            let input_p = generic_parse(&p.view.input, self.party_input_len())[0];
-            let input_p_next = generic_parse(&p_next.view.input, self.party_input_len())[0];
+            let input_p = generic_parse(&p.view.input, self.party_input_len())?[0];
+            let input_p_next = generic_parse(&p_next.view.input, self.party_input_len())?[0];
 
-            let (o1, o2) = add_mod_verify_k(input_p, input_p_next, self.k, p, p_next);
+            let (o1, o2) = add_mod_verify_k(input_p, input_p_next, self.k, p, p_next)?;
             Ok((vec![o1], vec![o2]))
         }
 
@@ -281,14 +366,64 @@ mod adder_tests {
 
     #[cfg(test)]
     mod test_adder {
-        use rand::{rngs::ThreadRng, thread_rng};
+        use rand::{rngs::ThreadRng, thread_rng, Rng};
         use rand_chacha::ChaCha20Rng;
         use sha3::Keccak256;
 
-        use crate::{circuit::Circuit, prover::Prover, verifier::Verifier};
+        use crate::{
+            circuit::Circuit,
+            gadgets::add_mod::{adder, AdderCircuit},
+            gf2_word::Value,
+            prover::Prover,
+            verifier::Verifier,
+        };
 
         use super::AddModKCircuit;
 
+        #[test]
+        fn test_adder_matches_wrapping_add() {
+            let mut rng = thread_rng();
+            for _ in 0..100 {
+                let (x, y) = (rng.gen::<u8>(), rng.gen::<u8>());
+                assert_eq!(adder(x, y), x.wrapping_add(y));
+
+                let (x, y) = (rng.gen::<u16>(), rng.gen::<u16>());
+                assert_eq!(adder(x, y), x.wrapping_add(y));
+
+                let (x, y) = (rng.gen::<u32>(), rng.gen::<u32>());
+                assert_eq!(adder(x, y), x.wrapping_add(y));
+
+                let (x, y) = (rng.gen::<u64>(), rng.gen::<u64>());
+                assert_eq!(adder(x, y), x.wrapping_add(y));
+            }
+            assert_eq!(adder(u32::MAX, 1), 0);
+        }
+
+        fn prove_and_verify_sum<T: Value + PartialEq>(x: &[u8], y: &[u8]) {
+            let mut rng = thread_rng();
+            const SIGMA: usize = 40;
+            let input = [x, y].concat();
+
+            let circuit = AdderCircuit::<T>::new();
+            let output = circuit.compute(&input);
+
+            let proof = Prover::<T, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+                &mut rng, &input, &circuit, &output,
+            )
+            .unwrap();
+
+            Verifier::<T, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+                .unwrap();
+        }
+
+        #[test]
+        fn test_adder_circuit() {
+            prove_and_verify_sum::<u8>(&[200], &[100]);
+            prove_and_verify_sum::<u16>(&60000u16.to_le_bytes(), &7000u16.to_le_bytes());
+            prove_and_verify_sum::<u32>(&u32::MAX.to_le_bytes(), &17u32.to_le_bytes());
+            prove_and_verify_sum::<u64>(&(1u64 << 63).to_le_bytes(), &(3u64 << 62).to_le_bytes());
+        }
+
         #[test]
         fn test_circuit() {
             let mut rng = thread_rng();
@@ -306,7 +441,8 @@ mod adder_tests {
             )
             .unwrap();
 
-            Verifier::<u32, ChaCha20Rng, Keccak256>::verify(&proof, &circuit, &output).unwrap();
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+                .unwrap();
         }
     }
 }
@@ -0,0 +1,324 @@
+//! In-circuit equality assertions.
+//!
+//! Instead of revealing the compared values, every assertion folds `x != y`
+//! into a shared failure word. Once all assertions are made the accumulated
+//! failures are turned into a single success bit (1 iff every assertion held),
+//! which can be used as the only public output of predicate-style circuits.
+//!
+//! Public values can be compared against by passing them as replicated shares
+//! `(c, c, c)`: since an odd number of parties hold `c`, the shares
+//! reconstruct to `c`.
+
+use crate::{
+    error::Error,
+    gadgets::{mpc_and, mpc_and_verify},
+    gf2_word::{GF2Word, Value},
+    party::Party,
+};
+
+/// Number of multiplication gates used by a single `mpc_assert_eq` over `T`.
+pub fn assert_eq_num_of_mul_gates<T: Value>() -> usize {
+    // log2(bits) gates for OR-reduction and one gate for accumulation
+    T::bits_len().trailing_zeros() as usize + 1
+}
+
+/// Initial value of the failure shares, before any assertion is made.
+pub fn no_failures<T: Value>() -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    let zero: GF2Word<T> = T::zero().into();
+    (zero, zero, zero)
+}
+
+/// Public output of a predicate circuit whose assertions all hold.
+pub fn success_public_output<T: Value>() -> Vec<GF2Word<T>> {
+    vec![T::from(1).into()]
+}
+
+pub fn mpc_or<T: Value>(
+    input_p1: (GF2Word<T>, GF2Word<T>),
+    input_p2: (GF2Word<T>, GF2Word<T>),
+    input_p3: (GF2Word<T>, GF2Word<T>),
+    p1: &mut Party<T>,
+    p2: &mut Party<T>,
+    p3: &mut Party<T>,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    // a or b = a xor b xor (a and b)
+    let (ab_1, ab_2, ab_3) = mpc_and(input_p1, input_p2, input_p3, p1, p2, p3);
+
+    (
+        input_p1.0 ^ input_p1.1 ^ ab_1,
+        input_p2.0 ^ input_p2.1 ^ ab_2,
+        input_p3.0 ^ input_p3.1 ^ ab_3,
+    )
+}
+
+pub fn or_verify<T: Value>(
+    input_p: (GF2Word<T>, GF2Word<T>),
+    input_p_next: (GF2Word<T>, GF2Word<T>),
+    p: &mut Party<T>,
+    p_next: &mut Party<T>,
+) -> Result<(GF2Word<T>, GF2Word<T>), Error> {
+    let (ab_p, ab_p_next) = mpc_and_verify(input_p, input_p_next, p, p_next)?;
+
+    Ok((
+        input_p.0 ^ input_p.1 ^ ab_p,
+        input_p_next.0 ^ input_p_next.1 ^ ab_p_next,
+    ))
+}
+
+/// Sets every bit of the output to the OR of all bits of the input.
+/// Uses log2(bits) multiplication gates.
+pub fn mpc_or_reduce<T: Value>(
+    input: (GF2Word<T>, GF2Word<T>, GF2Word<T>),
+    p1: &mut Party<T>,
+    p2: &mut Party<T>,
+    p3: &mut Party<T>,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    let (mut x1, mut x2, mut x3) = input;
+
+    let mut shift = 1;
+    while shift < T::bits_len() {
+        (x1, x2, x3) = mpc_or(
+            (x1, x1.rotl(shift)),
+            (x2, x2.rotl(shift)),
+            (x3, x3.rotl(shift)),
+            p1,
+            p2,
+            p3,
+        );
+        shift <<= 1;
+    }
+
+    (x1, x2, x3)
+}
+
+pub fn or_reduce_verify<T: Value>(
+    input: (GF2Word<T>, GF2Word<T>),
+    p: &mut Party<T>,
+    p_next: &mut Party<T>,
+) -> Result<(GF2Word<T>, GF2Word<T>), Error> {
+    let (mut x, mut x_next) = input;
+
+    let mut shift = 1;
+    while shift < T::bits_len() {
+        (x, x_next) = or_verify((x, x.rotl(shift)), (x_next, x_next.rotl(shift)), p, p_next)?;
+        shift <<= 1;
+    }
+
+    Ok((x, x_next))
+}
+
+/// Accumulates `x != y` into `failures`.
+pub fn mpc_assert_eq<T: Value>(
+    failures: (GF2Word<T>, GF2Word<T>, GF2Word<T>),
+    x: (GF2Word<T>, GF2Word<T>, GF2Word<T>),
+    y: (GF2Word<T>, GF2Word<T>, GF2Word<T>),
+    p1: &mut Party<T>,
+    p2: &mut Party<T>,
+    p3: &mut Party<T>,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    let diff = (x.0 ^ y.0, x.1 ^ y.1, x.2 ^ y.2);
+    let (d1, d2, d3) = mpc_or_reduce(diff, p1, p2, p3);
+
+    mpc_or(
+        (failures.0, d1),
+        (failures.1, d2),
+        (failures.2, d3),
+        p1,
+        p2,
+        p3,
+    )
+}
+
+pub fn assert_eq_verify<T: Value>(
+    failures: (GF2Word<T>, GF2Word<T>),
+    x: (GF2Word<T>, GF2Word<T>),
+    y: (GF2Word<T>, GF2Word<T>),
+    p: &mut Party<T>,
+    p_next: &mut Party<T>,
+) -> Result<(GF2Word<T>, GF2Word<T>), Error> {
+    let diff = (x.0 ^ y.0, x.1 ^ y.1);
+    let (d, d_next) = or_reduce_verify(diff, p, p_next)?;
+
+    or_verify((failures.0, d), (failures.1, d_next), p, p_next)
+}
+
+fn success_bit<T: Value>(failure: GF2Word<T>) -> GF2Word<T> {
+    // every party negates its share, which negates the shared value since
+    // there are an odd number of parties
+    (!failure.value & T::from(1)).into()
+}
+
+/// Converts accumulated failures into shares of a single bit that is 1 iff
+/// every assertion held. No multiplication gates are used.
+pub fn mpc_success_bit<T: Value>(
+    failures: (GF2Word<T>, GF2Word<T>, GF2Word<T>),
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    (
+        success_bit(failures.0),
+        success_bit(failures.1),
+        success_bit(failures.2),
+    )
+}
+
+pub fn success_bit_verify<T: Value>(
+    failures: (GF2Word<T>, GF2Word<T>),
+) -> (GF2Word<T>, GF2Word<T>) {
+    (success_bit(failures.0), success_bit(failures.1))
+}
+
+#[cfg(test)]
+mod test_assertion {
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::{Circuit, Output, TwoThreeDecOutput},
+        gadgets::prepare::generic_parse,
+        gf2_word::BitUtils,
+        prover::Prover,
+        verifier::Verifier,
+    };
+
+    use super::*;
+
+    /// Proves knowledge of two words whose xor is `expected_xor` and whose
+    /// first word is `expected_first`, revealing only the success bit.
+    pub struct PredicateCircuit {
+        expected_first: GF2Word<u32>,
+        expected_xor: GF2Word<u32>,
+    }
+
+    impl Circuit<u32> for PredicateCircuit {
+        fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+            let x = generic_parse::<u32>(input, self.party_input_len()).unwrap();
+            let holds = x[0] == self.expected_first && (x[0] ^ x[1]) == self.expected_xor;
+            vec![u32::from(holds).into()]
+        }
+
+        fn compute_23_decomposition(
+            &self,
+            p1: &mut Party<u32>,
+            p2: &mut Party<u32>,
+            p3: &mut Party<u32>,
+        ) -> TwoThreeDecOutput<u32> {
+            let x = generic_parse(&p1.view.input, self.party_input_len()).unwrap();
+            let y = generic_parse(&p2.view.input, self.party_input_len()).unwrap();
+            let z = generic_parse(&p3.view.input, self.party_input_len()).unwrap();
+
+            let first = (
+                self.expected_first,
+                self.expected_first,
+                self.expected_first,
+            );
+            let xor = (self.expected_xor, self.expected_xor, self.expected_xor);
+
+            let failures = no_failures();
+            let failures = mpc_assert_eq(failures, (x[0], y[0], z[0]), first, p1, p2, p3);
+            let failures = mpc_assert_eq(
+                failures,
+                (x[0] ^ x[1], y[0] ^ y[1], z[0] ^ z[1]),
+                xor,
+                p1,
+                p2,
+                p3,
+            );
+
+            let (o1, o2, o3) = mpc_success_bit(failures);
+            (vec![o1], vec![o2], vec![o3])
+        }
+
+        fn simulate_two_parties(
+            &self,
+            p: &mut Party<u32>,
+            p_next: &mut Party<u32>,
+        ) -> Result<(Output<u32>, Output<u32>), Error> {
+            let x = generic_parse(&p.view.input, self.party_input_len())?;
+            let y = generic_parse(&p_next.view.input, self.party_input_len())?;
+
+            let first = (self.expected_first, self.expected_first);
+            let xor = (self.expected_xor, self.expected_xor);
+
+            let zero = u32::zero().into();
+            let failures = assert_eq_verify((zero, zero), (x[0], y[0]), first, p, p_next)?;
+            let failures = assert_eq_verify(failures, (x[0] ^ x[1], y[0] ^ y[1]), xor, p, p_next)?;
+
+            let (o1, o2) = success_bit_verify(failures);
+            Ok((vec![o1], vec![o2]))
+        }
+
+        fn party_input_len(&self) -> usize {
+            2
+        }
+
+        fn party_output_len(&self) -> usize {
+            1
+        }
+
+        fn num_of_mul_gates(&self) -> usize {
+            2 * assert_eq_num_of_mul_gates::<u32>()
+        }
+    }
+
+    fn witness(first: u32, second: u32) -> Vec<u8> {
+        [first.to_le_bytes(), second.to_le_bytes()]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn test_assertions_hold() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        let circuit = PredicateCircuit {
+            expected_first: 0xdeadbeefu32.into(),
+            expected_xor: (0xdeadbeefu32 ^ 12345).into(),
+        };
+        let input = witness(0xdeadbeef, 12345);
+
+        let output = circuit.compute(&input);
+        assert_eq!(output, success_public_output::<u32>());
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_failed_assertion_is_rejected() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        let circuit = PredicateCircuit {
+            expected_first: 0xdeadbeefu32.into(),
+            expected_xor: (0xdeadbeefu32 ^ 12345).into(),
+        };
+        // only the highest bit of the second word is wrong
+        let input = witness(0xdeadbeef, 12345 | (1 << 31));
+
+        assert_eq!(circuit.compute(&input), vec![0u32.into()]);
+
+        // claim success although the second assertion fails
+        let claimed_output = success_public_output::<u32>();
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng,
+            &input,
+            &circuit,
+            &claimed_output,
+        )
+        .unwrap();
+
+        assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+            &proof,
+            &circuit,
+            &claimed_output
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! Bit-sliced packing of independent blocks.
+//!
+//! A bit-sliced witness holds bit `k` of block `j` in lane `j` of word `k`, so
+//! a circuit written for single bits processes up to `T::bits_len()` blocks at
+//! once: every XOR and AND gate applies to all lanes, and an AND gate costs one
+//! tape value and one view message whatever the number of blocks. Over `u128`,
+//! 128 blocks share the cost of each repetition.
+//!
+//! Bits of a block are numbered from the least significant bit of its first
+//! byte.
+
+use crate::{
+    error::Error,
+    gf2_word::{GF2Word, Value},
+};
+
+/// Witness bytes of `blocks` in bit-sliced layout, as little-endian words.
+pub fn bitslice<T: Value>(blocks: &[&[u8]]) -> Result<Vec<u8>, Error> {
+    if blocks.len() > T::bits_len() {
+        return Err(Error::OutOfRangeError);
+    }
+    let block_len = blocks.first().map_or(0, |block| block.len());
+    if let Some(block) = blocks.iter().find(|block| block.len() != block_len) {
+        return Err(Error::ParseLengthError(block_len, block.len()));
+    }
+
+    let mut witness = Vec::with_capacity(8 * block_len * T::bytes_len());
+    for k in 0..8 * block_len {
+        let word = blocks
+            .iter()
+            .enumerate()
+            .fold(T::zero(), |word, (lane, block)| {
+                word.set_bit(lane, (block[k / 8] >> (k % 8)) & 1 == 1)
+            });
+        // `to_bytes` is big-endian
+        witness.extend(word.to_bytes().into_iter().rev());
+    }
+    Ok(witness)
+}
+
+/// The `num_of_blocks` blocks packed in bit-sliced `words`.
+pub fn unbitslice<T: Value>(words: &[GF2Word<T>], num_of_blocks: usize) -> Vec<Vec<u8>> {
+    assert!(num_of_blocks <= T::bits_len());
+    assert_eq!(words.len() % 8, 0);
+
+    (0..num_of_blocks)
+        .map(|lane| {
+            words
+                .chunks(8)
+                .map(|byte| {
+                    byte.iter().enumerate().fold(0u8, |acc, (i, word)| {
+                        acc | (word.value.get_bit(lane).inner() as u8) << i
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod bitslice_tests {
+    use rand::{thread_rng, Rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::{Circuit, Output, TwoThreeDecOutput},
+        data_structures::Proof,
+        error::Error,
+        gadgets::{mpc_and, mpc_and_verify, prepare::generic_parse},
+        gf2_word::GF2Word,
+        party::Party,
+        prover::Prover,
+        verifier::Verifier,
+    };
+
+    use super::{bitslice, unbitslice};
+
+    const BLOCK_LEN: usize = 2;
+    const WORDS: usize = 8 * BLOCK_LEN;
+
+    /// ANDs two bit-sliced sets of blocks.
+    struct AndCircuit;
+
+    impl Circuit<u128> for AndCircuit {
+        fn compute(&self, input: &[u8]) -> Vec<GF2Word<u128>> {
+            let words = generic_parse::<u128>(input, self.party_input_len()).unwrap();
+            let (x, y) = words.split_at(WORDS);
+            x.iter().zip(y).map(|(&x, &y)| x & y).collect()
+        }
+
+        fn compute_23_decomposition(
+            &self,
+            p1: &mut Party<u128>,
+            p2: &mut Party<u128>,
+            p3: &mut Party<u128>,
+        ) -> TwoThreeDecOutput<u128> {
+            let w1 = generic_parse(&p1.view.input, self.party_input_len()).unwrap();
+            let w2 = generic_parse(&p2.view.input, self.party_input_len()).unwrap();
+            let w3 = generic_parse(&p3.view.input, self.party_input_len()).unwrap();
+
+            let (mut o1, mut o2, mut o3) = (vec![], vec![], vec![]);
+            for k in 0..WORDS {
+                let (a, b, c) = mpc_and(
+                    (w1[k], w1[WORDS + k]),
+                    (w2[k], w2[WORDS + k]),
+                    (w3[k], w3[WORDS + k]),
+                    p1,
+                    p2,
+                    p3,
+                );
+                o1.push(a);
+                o2.push(b);
+                o3.push(c);
+            }
+            (o1, o2, o3)
+        }
+
+        fn simulate_two_parties(
+            &self,
+            p: &mut Party<u128>,
+            p_next: &mut Party<u128>,
+        ) -> Result<(Output<u128>, Output<u128>), Error> {
+            let w = generic_parse(&p.view.input, self.party_input_len())?;
+            let w_next = generic_parse(&p_next.view.input, self.party_input_len())?;
+
+            let (mut o, mut o_next) = (vec![], vec![]);
+            for k in 0..WORDS {
+                let (a, b) = mpc_and_verify(
+                    (w[k], w[WORDS + k]),
+                    (w_next[k], w_next[WORDS + k]),
+                    p,
+                    p_next,
+                )?;
+                o.push(a);
+                o_next.push(b);
+            }
+            Ok((o, o_next))
+        }
+
+        fn party_input_len(&self) -> usize {
+            2 * WORDS
+        }
+
+        fn party_output_len(&self) -> usize {
+            WORDS
+        }
+
+        fn num_of_mul_gates(&self) -> usize {
+            WORDS
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut rng = thread_rng();
+        let blocks: Vec<[u8; 3]> = (0..100).map(|_| rng.gen()).collect();
+        let refs: Vec<&[u8]> = blocks.iter().map(|block| block.as_slice()).collect();
+
+        let witness = bitslice::<u128>(&refs).unwrap();
+        let words = generic_parse::<u128>(&witness, 24).unwrap();
+        assert_eq!(unbitslice(&words, blocks.len()), blocks);
+
+        assert!(bitslice::<u32>(&refs).is_err());
+        assert!(bitslice::<u128>(&[&[1, 2], &[3]]).is_err());
+    }
+
+    #[test]
+    fn test_bitsliced_circuit() {
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+
+        let x: Vec<[u8; BLOCK_LEN]> = (0..128).map(|_| rng.gen()).collect();
+        let y: Vec<[u8; BLOCK_LEN]> = (0..128).map(|_| rng.gen()).collect();
+        let witness = [
+            bitslice::<u128>(&x.iter().map(|b| b.as_slice()).collect::<Vec<_>>()).unwrap(),
+            bitslice::<u128>(&y.iter().map(|b| b.as_slice()).collect::<Vec<_>>()).unwrap(),
+        ]
+        .concat();
+
+        let circuit = AndCircuit;
+        let output = circuit.compute(&witness);
+        for (j, block) in unbitslice(&output, 128).iter().enumerate() {
+            assert_eq!(block[0], x[j][0] & y[j][0]);
+            assert_eq!(block[1], x[j][1] & y[j][1]);
+        }
+
+        let proof = Prover::<u128, ChaCha20Rng, Keccak256>::prove::<_, SIGMA>(
+            &mut rng, &witness, &circuit, &output,
+        )
+        .unwrap();
+        let proof = Proof::<u128, Keccak256>::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+        Verifier::<u128, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+    }
+}
@@ -0,0 +1,472 @@
+//! BLAKE2s-256 over 32-bit words, unkeyed.
+//!
+//! Only the additions of the G function use gates: 6 per call, so 480 per
+//! block against more than 700 for SHA-256. The block counters and the last
+//! block flag only depend on the input length, and are XORed into the shares
+//! as public values.
+
+use crate::{
+    circuit::{join, named_id, Circuit, Join, Output, TwoThreeDecOutput},
+    error::Error,
+    gadgets::{
+        add_mod::{add_mod_verify, mpc_add_mod},
+        prepare::{try_parse_exact, Prepare},
+        sha256::Sha256Circuit,
+    },
+    gf2_word::GF2Word,
+    party::Party,
+};
+
+pub type State = [GF2Word<u32>; 8];
+type WorkingVector = [GF2Word<u32>; 16];
+
+pub const BLOCK_LEN: usize = 64;
+const DIGEST_LEN: u32 = 32;
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// Words of the column and diagonal G calls of a round.
+const G_WORDS: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+fn num_of_blocks(input_len: usize) -> usize {
+    // the empty input is hashed as a single zero block
+    input_len.div_ceil(BLOCK_LEN).max(1)
+}
+
+/// Number of multiplication gates used to hash an input of `input_len` bytes.
+pub fn blake2s_num_of_mul_gates(input_len: usize) -> usize {
+    // 10 rounds of 8 G calls with 6 additions
+    10 * 8 * 6 * num_of_blocks(input_len)
+}
+
+/// The input zero-padded to whole blocks, as little-endian words.
+fn to_words(input: &[u8]) -> Vec<GF2Word<u32>> {
+    let mut padded = input.to_vec();
+    padded.resize(num_of_blocks(input.len()) * BLOCK_LEN, 0);
+    // safe to unwrap since the padded input is a whole number of words
+    try_parse_exact(&padded).unwrap()
+}
+
+fn init_state() -> State {
+    let mut state = IV.map(GF2Word::from);
+    // parameter block: digest length, no key, fanout and depth 1
+    state[0] = state[0] ^ (0x01010000 ^ DIGEST_LEN).into();
+    state
+}
+
+/// The working vector of the block ending at byte `offset` of the input.
+fn init_working_vector(state: &State, offset: usize, last: bool) -> WorkingVector {
+    let mut v = [GF2Word::from(0u32); 16];
+    v[..8].copy_from_slice(state);
+    for (word, iv) in v[8..].iter_mut().zip(IV) {
+        *word = iv.into();
+    }
+    v[12] = v[12] ^ (offset as u64 as u32).into();
+    v[13] = v[13] ^ ((offset as u64 >> 32) as u32).into();
+    if last {
+        v[14] = v[14] ^ u32::MAX.into();
+    }
+    v
+}
+
+fn finalize(state: &State, v: &WorkingVector) -> State {
+    std::array::from_fn(|i| state[i] ^ v[i] ^ v[i + 8])
+}
+
+/// Block offsets and last block flags of an input of `input_len` bytes.
+fn blocks(input_len: usize) -> impl Iterator<Item = (usize, bool)> {
+    let num_of_blocks = num_of_blocks(input_len);
+    (0..num_of_blocks).map(move |i| {
+        let last = i + 1 == num_of_blocks;
+        let offset = if last { input_len } else { (i + 1) * BLOCK_LEN };
+        (offset, last)
+    })
+}
+
+fn compress(state: &State, block: &[GF2Word<u32>], offset: usize, last: bool) -> State {
+    let mut v = init_working_vector(state, offset, last).map(|word| word.value);
+    let m: Vec<u32> = block.iter().map(|word| word.value).collect();
+
+    for s in SIGMA {
+        for (i, [a, b, c, d]) in G_WORDS.into_iter().enumerate() {
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(m[s[2 * i]]);
+            v[d] = (v[d] ^ v[a]).rotate_right(16);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(12);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(m[s[2 * i + 1]]);
+            v[d] = (v[d] ^ v[a]).rotate_right(8);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(7);
+        }
+    }
+
+    finalize(state, &v.map(GF2Word::from))
+}
+
+/// Computes the BLAKE2s-256 digest of `input`, as little-endian words.
+pub fn blake2s(input: &[u8]) -> Vec<GF2Word<u32>> {
+    let words = to_words(input);
+    words
+        .chunks(16)
+        .zip(blocks(input.len()))
+        .fold(init_state(), |state, (block, (offset, last))| {
+            compress(&state, block, offset, last)
+        })
+        .to_vec()
+}
+
+/// x := x ^ y rotated right by `n`, on each party's working vector.
+fn xor_rotr(vs: &mut [WorkingVector], x: usize, y: usize, n: usize) {
+    for v in vs {
+        v[x] = (v[x] ^ v[y]).rotr(n);
+    }
+}
+
+/// x := x + y, for a word of the message.
+fn mpc_add_word(
+    vs: &mut [WorkingVector; 3],
+    x: usize,
+    y: [GF2Word<u32>; 3],
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) {
+    let [v1, v2, v3] = vs;
+    (v1[x], v2[x], v3[x]) = mpc_add_mod((v1[x], y[0]), (v2[x], y[1]), (v3[x], y[2]), p1, p2, p3);
+}
+
+/// x := x + y, for words of the working vector.
+fn mpc_add(
+    vs: &mut [WorkingVector; 3],
+    x: usize,
+    y: usize,
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) {
+    let y = vs.each_ref().map(|v| v[y]);
+    mpc_add_word(vs, x, y, p1, p2, p3);
+}
+
+fn add_word_verify(
+    vs: &mut [WorkingVector; 2],
+    x: usize,
+    y: [GF2Word<u32>; 2],
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<(), Error> {
+    let [v, v_next] = vs;
+    (v[x], v_next[x]) = add_mod_verify((v[x], y[0]), (v_next[x], y[1]), p, p_next)?;
+    Ok(())
+}
+
+fn add_verify(
+    vs: &mut [WorkingVector; 2],
+    x: usize,
+    y: usize,
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<(), Error> {
+    let y = vs.each_ref().map(|v| v[y]);
+    add_word_verify(vs, x, y, p, p_next)
+}
+
+pub fn mpc_compress(
+    states: [&State; 3],
+    blocks: [&[GF2Word<u32>]; 3],
+    offset: usize,
+    last: bool,
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) -> [State; 3] {
+    // the counters and the flag are replicated shares
+    let mut vs = states.map(|state| init_working_vector(state, offset, last));
+
+    for s in SIGMA {
+        for (i, [a, b, c, d]) in G_WORDS.into_iter().enumerate() {
+            let (x, y) = (blocks.map(|m| m[s[2 * i]]), blocks.map(|m| m[s[2 * i + 1]]));
+            for (m, r1, r2) in [(x, 16, 12), (y, 8, 7)] {
+                mpc_add(&mut vs, a, b, p1, p2, p3);
+                mpc_add_word(&mut vs, a, m, p1, p2, p3);
+                xor_rotr(&mut vs, d, a, r1);
+                mpc_add(&mut vs, c, d, p1, p2, p3);
+                xor_rotr(&mut vs, b, c, r2);
+            }
+        }
+    }
+
+    std::array::from_fn(|j| finalize(states[j], &vs[j]))
+}
+
+pub fn compress_verify(
+    states: [&State; 2],
+    blocks: [&[GF2Word<u32>]; 2],
+    offset: usize,
+    last: bool,
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<[State; 2], Error> {
+    let mut vs = states.map(|state| init_working_vector(state, offset, last));
+
+    for s in SIGMA {
+        for (i, [a, b, c, d]) in G_WORDS.into_iter().enumerate() {
+            let (x, y) = (blocks.map(|m| m[s[2 * i]]), blocks.map(|m| m[s[2 * i + 1]]));
+            for (m, r1, r2) in [(x, 16, 12), (y, 8, 7)] {
+                add_verify(&mut vs, a, b, p, p_next)?;
+                add_word_verify(&mut vs, a, m, p, p_next)?;
+                xor_rotr(&mut vs, d, a, r1);
+                add_verify(&mut vs, c, d, p, p_next)?;
+                xor_rotr(&mut vs, b, c, r2);
+            }
+        }
+    }
+
+    Ok([finalize(states[0], &vs[0]), finalize(states[1], &vs[1])])
+}
+
+/// Hashes the shares of an input, which must all have the same length.
+pub fn mpc_blake2s(
+    input_p1: &[u8],
+    input_p2: &[u8],
+    input_p3: &[u8],
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) -> TwoThreeDecOutput<u32> {
+    let words = [input_p1, input_p2, input_p3].map(to_words);
+
+    let mut states = [init_state(); 3];
+    for (i, (offset, last)) in blocks(input_p1.len()).enumerate() {
+        let block = words.each_ref().map(|words| &words[16 * i..16 * (i + 1)]);
+        states = mpc_compress(states.each_ref(), block, offset, last, p1, p2, p3);
+    }
+
+    let [o1, o2, o3] = states.map(|state| state.to_vec());
+    (o1, o2, o3)
+}
+
+pub fn mpc_blake2s_verify(
+    input_p: &[u8],
+    input_p_next: &[u8],
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<(Output<u32>, Output<u32>), Error> {
+    if input_p.len() != input_p_next.len() {
+        return Err(Error::VerificationError);
+    }
+    let words = [input_p, input_p_next].map(to_words);
+
+    let mut states = [init_state(); 2];
+    for (i, (offset, last)) in blocks(input_p.len()).enumerate() {
+        let block = words.each_ref().map(|words| &words[16 * i..16 * (i + 1)]);
+        states = compress_verify(states.each_ref(), block, offset, last, p, p_next)?;
+    }
+
+    let [o, o_next] = states.map(|state| state.to_vec());
+    Ok((o, o_next))
+}
+
+/// Proves knowledge of a preimage of `input_len` bytes for a BLAKE2s-256
+/// digest.
+#[derive(Clone, Copy, Debug)]
+pub struct Blake2sCircuit {
+    pub input_len: usize,
+}
+
+impl Blake2sCircuit {
+    pub fn new(input_len: usize) -> Self {
+        Self { input_len }
+    }
+}
+
+impl Circuit<u32> for Blake2sCircuit {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+        assert_eq!(input.len(), self.input_len);
+        blake2s(input)
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<u32>,
+        p2: &mut Party<u32>,
+        p3: &mut Party<u32>,
+    ) -> TwoThreeDecOutput<u32> {
+        let (input_p1, input_p2, input_p3) = (
+            p1.view.input.clone(),
+            p2.view.input.clone(),
+            p3.view.input.clone(),
+        );
+        mpc_blake2s(&input_p1, &input_p2, &input_p3, p1, p2, p3)
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<u32>,
+        p_next: &mut Party<u32>,
+    ) -> Result<(Output<u32>, Output<u32>), Error> {
+        if p.view.input.len() != self.input_len || p_next.view.input.len() != self.input_len {
+            return Err(Error::VerificationError);
+        }
+        let (input_p, input_p_next) = (p.view.input.clone(), p_next.view.input.clone());
+        mpc_blake2s_verify(&input_p, &input_p_next, p, p_next)
+    }
+
+    fn party_input_len(&self) -> usize {
+        self.input_len
+    }
+
+    fn party_output_len(&self) -> usize {
+        8
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        blake2s_num_of_mul_gates(self.input_len)
+    }
+
+    fn id(&self) -> Vec<u8> {
+        named_id("blake2s", self, &[])
+    }
+}
+
+impl Prepare<u32> for Blake2sCircuit {
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        if bytes.len() != self.input_len {
+            return Err(Error::ParseLengthError(self.input_len, bytes.len()));
+        }
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Proves knowledge of a single preimage of `input_len` bytes for both a
+/// SHA-256 and a BLAKE2s-256 digest. The output is the 8 words of each.
+pub type Sha256Blake2sCircuit = Join<Sha256Circuit, Blake2sCircuit>;
+
+impl Sha256Blake2sCircuit {
+    pub fn new(input_len: usize) -> Self {
+        join(
+            Sha256Circuit::new(input_len),
+            Blake2sCircuit::new(input_len),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_blake2s {
+    use blake2::{Blake2s256, Digest};
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::Circuit,
+        gadgets::sha256::{sha256, sha256_num_of_mul_gates},
+        prover::Prover,
+        verifier::Verifier,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_plain_blake2s() {
+        for len in [0, 3, 63, 64, 65, 128, 200] {
+            let input: Vec<u8> = (0..len).map(|i| (i * 7 + 1) as u8).collect();
+            let digest: Vec<u8> = blake2s(&input)
+                .iter()
+                .flat_map(|word| word.value.to_le_bytes())
+                .collect();
+            assert_eq!(digest, Blake2s256::digest(&input).to_vec());
+        }
+    }
+
+    #[test]
+    fn test_num_of_mul_gates() {
+        assert_eq!(blake2s_num_of_mul_gates(0), blake2s_num_of_mul_gates(64));
+        assert_eq!(
+            blake2s_num_of_mul_gates(65),
+            2 * blake2s_num_of_mul_gates(1)
+        );
+        assert!(blake2s_num_of_mul_gates(32) < sha256_num_of_mul_gates(32));
+        assert!(
+            Blake2sCircuit::new(32).num_of_mul_gates() < Sha256Circuit::new(32).num_of_mul_gates()
+        );
+    }
+
+    #[test]
+    fn test_blake2s_circuit() {
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+
+        for preimage in [&b""[..], b"abc", &[0x42; 100]] {
+            let circuit = Blake2sCircuit::new(preimage.len());
+            let output = circuit.compute(preimage);
+
+            let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+                &mut rng, preimage, &circuit, &output,
+            )
+            .unwrap();
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+                .unwrap();
+
+            let mut wrong_output = output.clone();
+            wrong_output[7] = wrong_output[7] ^ 1u32.into();
+            assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+                &proof,
+                &circuit,
+                &wrong_output
+            )
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_sha256_blake2s_circuit() {
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+
+        let preimage = b"the same secret";
+        let circuit = Sha256Blake2sCircuit::new(preimage.len());
+        let output = circuit.compute(preimage);
+        assert_eq!(output, [sha256(preimage), blake2s(preimage)].concat());
+        assert_eq!(circuit.party_output_len(), 16);
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, preimage, &circuit, &output,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+
+        // digests of two different preimages
+        let other = b"another secret!";
+        let mixed = [sha256(preimage), blake2s(other)].concat();
+        assert!(
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &mixed)
+                .is_err()
+        );
+    }
+}
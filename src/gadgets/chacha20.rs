@@ -0,0 +1,310 @@
+//! The ChaCha20 block function of RFC 8439.
+//!
+//! `ChaCha20Circuit` proves knowledge of a key and nonce for which the block at
+//! a public counter is a given keystream block. The state is 16 little-endian
+//! words, and every addition of the quarter rounds and of the final feed
+//! forward is one `add_mod` gate; rotations and XORs are free.
+
+use crate::{
+    circuit::{named_id, Circuit, Output, TwoThreeDecOutput},
+    error::Error,
+    gadgets::{
+        add_mod::{add_mod_verify, mpc_add_mod},
+        prepare::{generic_parse, Prepare},
+    },
+    gf2_word::GF2Word,
+    party::Party,
+};
+
+pub type State = [GF2Word<u32>; 16];
+
+/// "expand 32-byte k"
+const CONSTANTS: [u32; 4] = [0x61707865, 0x3320646e, 0x79622d32, 0x6b206574];
+
+pub const KEY_LEN: usize = 32;
+pub const NONCE_LEN: usize = 12;
+
+/// Words of the column and diagonal quarter rounds of a double round.
+const QUARTER_ROUNDS: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+/// Number of multiplication gates used by a block.
+pub fn chacha20_num_of_mul_gates() -> usize {
+    // 10 double rounds of 8 quarter rounds with 4 additions, and the feed forward
+    10 * 8 * 4 + 16
+}
+
+/// The initial state, with the key and nonce given as 11 words.
+fn init_state(key_nonce: &[GF2Word<u32>], counter: u32) -> State {
+    let mut state = [GF2Word::from(0u32); 16];
+    for (word, constant) in state.iter_mut().zip(CONSTANTS) {
+        *word = constant.into();
+    }
+    state[4..12].copy_from_slice(&key_nonce[..8]);
+    state[12] = counter.into();
+    state[13..].copy_from_slice(&key_nonce[8..]);
+    state
+}
+
+/// Computes the keystream block at `counter`, as little-endian words.
+pub fn chacha20_block(key: &[u8], nonce: &[u8], counter: u32) -> Vec<GF2Word<u32>> {
+    assert_eq!((key.len(), nonce.len()), (KEY_LEN, NONCE_LEN));
+    let key_nonce = generic_parse(&[key, nonce].concat(), 11).unwrap();
+    let initial = init_state(&key_nonce, counter).map(|word| word.value);
+
+    let mut x = initial;
+    for _ in 0..10 {
+        for [a, b, c, d] in QUARTER_ROUNDS {
+            x[a] = x[a].wrapping_add(x[b]);
+            x[d] = (x[d] ^ x[a]).rotate_left(16);
+            x[c] = x[c].wrapping_add(x[d]);
+            x[b] = (x[b] ^ x[c]).rotate_left(12);
+            x[a] = x[a].wrapping_add(x[b]);
+            x[d] = (x[d] ^ x[a]).rotate_left(8);
+            x[c] = x[c].wrapping_add(x[d]);
+            x[b] = (x[b] ^ x[c]).rotate_left(7);
+        }
+    }
+
+    x.iter()
+        .zip(initial)
+        .map(|(&word, initial)| word.wrapping_add(initial).into())
+        .collect()
+}
+
+/// x := x ^ y rotated left by `n`, on each party's state.
+fn xor_rotl(states: &mut [State], x: usize, y: usize, n: usize) {
+    for state in states {
+        state[x] = (state[x] ^ state[y]).rotl(n);
+    }
+}
+
+/// x := x + y
+fn mpc_add(
+    states: &mut [State; 3],
+    x: usize,
+    y: usize,
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) {
+    let [s1, s2, s3] = states;
+    (s1[x], s2[x], s3[x]) = mpc_add_mod((s1[x], s1[y]), (s2[x], s2[y]), (s3[x], s3[y]), p1, p2, p3);
+}
+
+fn add_verify(
+    states: &mut [State; 2],
+    x: usize,
+    y: usize,
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<(), Error> {
+    let [s, s_next] = states;
+    (s[x], s_next[x]) = add_mod_verify((s[x], s[y]), (s_next[x], s_next[y]), p, p_next)?;
+    Ok(())
+}
+
+pub fn mpc_chacha20_block(
+    initial: [State; 3],
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) -> TwoThreeDecOutput<u32> {
+    let mut states = initial;
+    for _ in 0..10 {
+        for [a, b, c, d] in QUARTER_ROUNDS {
+            mpc_add(&mut states, a, b, p1, p2, p3);
+            xor_rotl(&mut states, d, a, 16);
+            mpc_add(&mut states, c, d, p1, p2, p3);
+            xor_rotl(&mut states, b, c, 12);
+            mpc_add(&mut states, a, b, p1, p2, p3);
+            xor_rotl(&mut states, d, a, 8);
+            mpc_add(&mut states, c, d, p1, p2, p3);
+            xor_rotl(&mut states, b, c, 7);
+        }
+    }
+
+    let (mut o1, mut o2, mut o3) = (vec![], vec![], vec![]);
+    for i in 0..16 {
+        let (w1, w2, w3) = mpc_add_mod(
+            (states[0][i], initial[0][i]),
+            (states[1][i], initial[1][i]),
+            (states[2][i], initial[2][i]),
+            p1,
+            p2,
+            p3,
+        );
+        o1.push(w1);
+        o2.push(w2);
+        o3.push(w3);
+    }
+    (o1, o2, o3)
+}
+
+pub fn mpc_chacha20_block_verify(
+    initial: [State; 2],
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<(Output<u32>, Output<u32>), Error> {
+    let mut states = initial;
+    for _ in 0..10 {
+        for [a, b, c, d] in QUARTER_ROUNDS {
+            add_verify(&mut states, a, b, p, p_next)?;
+            xor_rotl(&mut states, d, a, 16);
+            add_verify(&mut states, c, d, p, p_next)?;
+            xor_rotl(&mut states, b, c, 12);
+            add_verify(&mut states, a, b, p, p_next)?;
+            xor_rotl(&mut states, d, a, 8);
+            add_verify(&mut states, c, d, p, p_next)?;
+            xor_rotl(&mut states, b, c, 7);
+        }
+    }
+
+    let (mut o, mut o_next) = (vec![], vec![]);
+    for i in 0..16 {
+        let (w, w_next) = add_mod_verify(
+            (states[0][i], initial[0][i]),
+            (states[1][i], initial[1][i]),
+            p,
+            p_next,
+        )?;
+        o.push(w);
+        o_next.push(w_next);
+    }
+    Ok((o, o_next))
+}
+
+/// Proves knowledge of a key and nonce, the 44 byte witness `key || nonce`,
+/// for which the keystream block at `counter` is the public output.
+#[derive(Clone, Copy, Debug)]
+pub struct ChaCha20Circuit {
+    pub counter: u32,
+}
+
+impl ChaCha20Circuit {
+    pub fn new(counter: u32) -> Self {
+        Self { counter }
+    }
+}
+
+impl Circuit<u32> for ChaCha20Circuit {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+        assert_eq!(input.len(), KEY_LEN + NONCE_LEN);
+        chacha20_block(&input[..KEY_LEN], &input[KEY_LEN..], self.counter)
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<u32>,
+        p2: &mut Party<u32>,
+        p3: &mut Party<u32>,
+    ) -> TwoThreeDecOutput<u32> {
+        // the constants and the counter are replicated shares
+        let initial = [&p1.view.input, &p2.view.input, &p3.view.input]
+            .map(|input| init_state(&generic_parse(input, 11).unwrap(), self.counter));
+        mpc_chacha20_block(initial, p1, p2, p3)
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<u32>,
+        p_next: &mut Party<u32>,
+    ) -> Result<(Output<u32>, Output<u32>), Error> {
+        let initial = [
+            init_state(&generic_parse(&p.view.input, 11)?, self.counter),
+            init_state(&generic_parse(&p_next.view.input, 11)?, self.counter),
+        ];
+        mpc_chacha20_block_verify(initial, p, p_next)
+    }
+
+    fn party_input_len(&self) -> usize {
+        KEY_LEN + NONCE_LEN
+    }
+
+    fn party_output_len(&self) -> usize {
+        16
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        chacha20_num_of_mul_gates()
+    }
+
+    fn public_input(&self) -> Vec<u8> {
+        self.counter.to_le_bytes().to_vec()
+    }
+
+    fn id(&self) -> Vec<u8> {
+        named_id("chacha20", self, &[])
+    }
+}
+
+impl Prepare<u32> for ChaCha20Circuit {
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        if bytes.len() != KEY_LEN + NONCE_LEN {
+            return Err(Error::ParseLengthError(KEY_LEN + NONCE_LEN, bytes.len()));
+        }
+        Ok(bytes.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod test_chacha20 {
+    use rand::{rngs::ThreadRng, thread_rng, Rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{circuit::Circuit, prover::Prover, verifier::Verifier};
+
+    use super::*;
+
+    #[test]
+    fn test_plain_chacha20() {
+        // RFC 8439, section 2.3.2
+        let key: Vec<u8> = (0..32).collect();
+        let nonce = [0, 0, 0, 9, 0, 0, 0, 0x4a, 0, 0, 0, 0];
+        let expected = [
+            0xe4e7f110, 0x15593bd1, 0x1fdd0f50, 0xc47120a3, 0xc7f4d1c7, 0x0368c033, 0x9aaa2204,
+            0x4e6cd4c3, 0x466482d2, 0x09aa9f07, 0x05d7c214, 0xa2028bd9, 0xd19c12b5, 0xb94e16de,
+            0xe883d0cb, 0x4e3c50a2,
+        ];
+        let block = chacha20_block(&key, &nonce, 1);
+        assert_eq!(
+            block.iter().map(|word| word.value).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_chacha20_circuit() {
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+
+        let mut witness = [0u8; KEY_LEN + NONCE_LEN];
+        rng.fill(&mut witness[..]);
+        let circuit = ChaCha20Circuit::new(7);
+        let output = circuit.compute(&witness);
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &witness, &circuit, &output,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+
+        // the block at another counter is another statement
+        assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+            &proof,
+            &ChaCha20Circuit::new(8),
+            &output
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,412 @@
+//! Equality and unsigned comparison of shared words.
+//!
+//! Every gadget outputs shares of a single bit (in the least significant
+//! position of the word), which reconstructs to 1 iff the relation holds.
+//! A public threshold can be used as an operand by passing it as replicated
+//! shares `(c, c, c)`.
+
+use crate::{
+    error::Error,
+    gadgets::{
+        add_mod::bit_and,
+        assertion::{mpc_or_reduce, or_reduce_verify},
+    },
+    gf2_word::{GF2Word, Value},
+    party::Party,
+};
+
+/// Number of multiplication gates used by `mpc_eq` over `T`.
+pub fn eq_num_of_mul_gates<T: Value>() -> usize {
+    T::bits_len().trailing_zeros() as usize
+}
+
+/// Number of multiplication gates used by `mpc_lt` (or `mpc_gt`) over `T`.
+pub fn lt_num_of_mul_gates<T: Value>() -> usize {
+    1
+}
+
+/// Returns 1 if `x == y` and 0 otherwise.
+pub fn equal<T: Value>(x: T, y: T) -> T {
+    let diff = x ^ y;
+    T::from(u8::from(diff == T::zero()))
+}
+
+/*
+    x < y iff adding !x and y overflows, since !x + y = 2^n - 1 - x + y.
+    The carries are computed as in `add_mod`, except that the carry out of the
+    most significant bit is also computed. It is stored in bit 0 of the carry
+    word, which is otherwise always 0 (there is no carry into the lowest bit).
+*/
+
+/// Returns 1 if `x < y` and 0 otherwise, comparing as unsigned integers.
+pub fn less_than<T: Value>(x: T, y: T) -> T {
+    let x = !x;
+    let mut carry = T::zero();
+
+    for i in 0..T::bits_len() {
+        let a = (x ^ carry).get_bit(i);
+        let b = (y ^ carry).get_bit(i);
+
+        let ci = (a & b) ^ carry.get_bit(i);
+        carry = carry.set_bit((i + 1) % T::bits_len(), ci.inner());
+    }
+
+    carry & T::from(1)
+}
+
+fn lsb<T: Value>(x: GF2Word<T>) -> GF2Word<T> {
+    (x.value & T::from(1)).into()
+}
+
+fn negate<T: Value>(x: GF2Word<T>) -> GF2Word<T> {
+    // an odd number of parties negate their shares, which negates the shared value
+    (!x.value).into()
+}
+
+pub fn mpc_eq<T: Value>(
+    input_p1: (GF2Word<T>, GF2Word<T>),
+    input_p2: (GF2Word<T>, GF2Word<T>),
+    input_p3: (GF2Word<T>, GF2Word<T>),
+    p1: &mut Party<T>,
+    p2: &mut Party<T>,
+    p3: &mut Party<T>,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    let diff = (
+        input_p1.0 ^ input_p1.1,
+        input_p2.0 ^ input_p2.1,
+        input_p3.0 ^ input_p3.1,
+    );
+    let (d1, d2, d3) = mpc_or_reduce(diff, p1, p2, p3);
+
+    (lsb(negate(d1)), lsb(negate(d2)), lsb(negate(d3)))
+}
+
+pub fn eq_verify<T: Value>(
+    input_p: (GF2Word<T>, GF2Word<T>),
+    input_p_next: (GF2Word<T>, GF2Word<T>),
+    p: &mut Party<T>,
+    p_next: &mut Party<T>,
+) -> Result<(GF2Word<T>, GF2Word<T>), Error> {
+    let diff = (input_p.0 ^ input_p.1, input_p_next.0 ^ input_p_next.1);
+    let (d, d_next) = or_reduce_verify(diff, p, p_next)?;
+
+    Ok((lsb(negate(d)), lsb(negate(d_next))))
+}
+
+/// Shares of `x < y`, where the inputs are `(x, y)`.
+/// Uses a single multiplication gate.
+pub fn mpc_lt<T: Value>(
+    input_p1: (GF2Word<T>, GF2Word<T>),
+    input_p2: (GF2Word<T>, GF2Word<T>),
+    input_p3: (GF2Word<T>, GF2Word<T>),
+    p1: &mut Party<T>,
+    p2: &mut Party<T>,
+    p3: &mut Party<T>,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    let rand_p1 = p1.read_tape();
+    let rand_p2 = p2.read_tape();
+    let rand_p3 = p3.read_tape();
+
+    let (x_p1, y_p1) = (negate(input_p1.0), input_p1.1);
+    let (x_p2, y_p2) = (negate(input_p2.0), input_p2.1);
+    let (x_p3, y_p3) = (negate(input_p3.0), input_p3.1);
+
+    let mut carry_p1: GF2Word<T> = T::zero().into();
+    let mut carry_p2: GF2Word<T> = T::zero().into();
+    let mut carry_p3: GF2Word<T> = T::zero().into();
+
+    for i in 0..T::bits_len() {
+        let ri_p1 = rand_p1.value.get_bit(i);
+        let ri_p2 = rand_p2.value.get_bit(i);
+        let ri_p3 = rand_p3.value.get_bit(i);
+
+        let a_p1 = (x_p1 ^ carry_p1).value.get_bit(i);
+        let b_p1 = (y_p1 ^ carry_p1).value.get_bit(i);
+
+        let a_p2 = (x_p2 ^ carry_p2).value.get_bit(i);
+        let b_p2 = (y_p2 ^ carry_p2).value.get_bit(i);
+
+        let a_p3 = (x_p3 ^ carry_p3).value.get_bit(i);
+        let b_p3 = (y_p3 ^ carry_p3).value.get_bit(i);
+
+        let ci_p1 = bit_and((a_p1, b_p1), (a_p2, b_p2), ri_p1, ri_p2) ^ carry_p1.value.get_bit(i);
+        let ci_p2 = bit_and((a_p2, b_p2), (a_p3, b_p3), ri_p2, ri_p3) ^ carry_p2.value.get_bit(i);
+        let ci_p3 = bit_and((a_p3, b_p3), (a_p1, b_p1), ri_p3, ri_p1) ^ carry_p3.value.get_bit(i);
+
+        let pos = (i + 1) % T::bits_len();
+        carry_p1 = carry_p1.value.set_bit(pos, ci_p1.inner()).into();
+        carry_p2 = carry_p2.value.set_bit(pos, ci_p2.inner()).into();
+        carry_p3 = carry_p3.value.set_bit(pos, ci_p3.inner()).into();
+    }
+
+    p1.view.send_msg(carry_p1);
+    p2.view.send_msg(carry_p2);
+    p3.view.send_msg(carry_p3);
+
+    (lsb(carry_p1), lsb(carry_p2), lsb(carry_p3))
+}
+
+pub fn lt_verify<T: Value>(
+    input_p: (GF2Word<T>, GF2Word<T>),
+    input_p_next: (GF2Word<T>, GF2Word<T>),
+    p: &mut Party<T>,
+    p_next: &mut Party<T>,
+) -> Result<(GF2Word<T>, GF2Word<T>), Error> {
+    let ri = p.read_tape();
+    let ri_next = p_next.read_tape();
+
+    let (x_p, y_p) = (negate(input_p.0), input_p.1);
+    let (x_p_next, y_p_next) = (negate(input_p_next.0), input_p_next.1);
+
+    let mut carry_p: GF2Word<T> = T::zero().into();
+    let carry_p_next = p_next.read_view()?;
+    // bit 0 holds the carry out, which is not used while computing the carries
+    let carries_p_next: GF2Word<T> = (carry_p_next.value & !T::from(1)).into();
+
+    for i in 0..T::bits_len() {
+        let ri_p = ri.value.get_bit(i);
+        let ri_p_next = ri_next.value.get_bit(i);
+
+        let a_p = (x_p ^ carry_p).value.get_bit(i);
+        let b_p = (y_p ^ carry_p).value.get_bit(i);
+
+        let a_p_next = (x_p_next ^ carries_p_next).value.get_bit(i);
+        let b_p_next = (y_p_next ^ carries_p_next).value.get_bit(i);
+
+        let ci_p =
+            bit_and((a_p, b_p), (a_p_next, b_p_next), ri_p, ri_p_next) ^ carry_p.value.get_bit(i);
+
+        carry_p = carry_p
+            .value
+            .set_bit((i + 1) % T::bits_len(), ci_p.inner())
+            .into();
+    }
+
+    p.view.send_msg(carry_p);
+
+    Ok((lsb(carry_p), lsb(carry_p_next)))
+}
+
+/// Shares of `x > y`, where the inputs are `(x, y)`.
+pub fn mpc_gt<T: Value>(
+    input_p1: (GF2Word<T>, GF2Word<T>),
+    input_p2: (GF2Word<T>, GF2Word<T>),
+    input_p3: (GF2Word<T>, GF2Word<T>),
+    p1: &mut Party<T>,
+    p2: &mut Party<T>,
+    p3: &mut Party<T>,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    mpc_lt(
+        (input_p1.1, input_p1.0),
+        (input_p2.1, input_p2.0),
+        (input_p3.1, input_p3.0),
+        p1,
+        p2,
+        p3,
+    )
+}
+
+pub fn gt_verify<T: Value>(
+    input_p: (GF2Word<T>, GF2Word<T>),
+    input_p_next: (GF2Word<T>, GF2Word<T>),
+    p: &mut Party<T>,
+    p_next: &mut Party<T>,
+) -> Result<(GF2Word<T>, GF2Word<T>), Error> {
+    lt_verify(
+        (input_p.1, input_p.0),
+        (input_p_next.1, input_p_next.0),
+        p,
+        p_next,
+    )
+}
+
+#[cfg(test)]
+mod test_compare {
+    use rand::{rngs::ThreadRng, thread_rng, Rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::{Circuit, Output, TwoThreeDecOutput},
+        gadgets::{mpc_public, prepare::generic_parse, public_verify},
+        prover::Prover,
+        verifier::Verifier,
+    };
+
+    use super::*;
+
+    /// Outputs `[x == y, x < y, x > y]` for a secret `x` and a public `y`.
+    struct ThresholdCircuit<T: Value> {
+        threshold: GF2Word<T>,
+    }
+
+    impl<T: Value> Circuit<T> for ThresholdCircuit<T> {
+        fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+            let x = generic_parse::<T>(input, self.party_input_len()).unwrap()[0].value;
+            let y = self.threshold.value;
+            vec![
+                equal(x, y).into(),
+                less_than(x, y).into(),
+                less_than(y, x).into(),
+            ]
+        }
+
+        fn compute_23_decomposition(
+            &self,
+            p1: &mut Party<T>,
+            p2: &mut Party<T>,
+            p3: &mut Party<T>,
+        ) -> TwoThreeDecOutput<T> {
+            let x1 = generic_parse(&p1.view.input, self.party_input_len()).unwrap()[0];
+            let x2 = generic_parse(&p2.view.input, self.party_input_len()).unwrap()[0];
+            let x3 = generic_parse(&p3.view.input, self.party_input_len()).unwrap()[0];
+            let (y1, y2, y3) = mpc_public(self.threshold);
+
+            let eq = mpc_eq((x1, y1), (x2, y2), (x3, y3), p1, p2, p3);
+            let lt = mpc_lt((x1, y1), (x2, y2), (x3, y3), p1, p2, p3);
+            let gt = mpc_gt((x1, y1), (x2, y2), (x3, y3), p1, p2, p3);
+
+            (
+                vec![eq.0, lt.0, gt.0],
+                vec![eq.1, lt.1, gt.1],
+                vec![eq.2, lt.2, gt.2],
+            )
+        }
+
+        fn simulate_two_parties(
+            &self,
+            p: &mut Party<T>,
+            p_next: &mut Party<T>,
+        ) -> Result<(Output<T>, Output<T>), Error> {
+            let x = generic_parse(&p.view.input, self.party_input_len())?[0];
+            let x_next = generic_parse(&p_next.view.input, self.party_input_len())?[0];
+            let (y, y_next) = public_verify(self.threshold);
+
+            let eq = eq_verify((x, y), (x_next, y_next), p, p_next)?;
+            let lt = lt_verify((x, y), (x_next, y_next), p, p_next)?;
+            let gt = gt_verify((x, y), (x_next, y_next), p, p_next)?;
+
+            Ok((vec![eq.0, lt.0, gt.0], vec![eq.1, lt.1, gt.1]))
+        }
+
+        fn party_input_len(&self) -> usize {
+            1
+        }
+
+        fn party_output_len(&self) -> usize {
+            3
+        }
+
+        fn num_of_mul_gates(&self) -> usize {
+            eq_num_of_mul_gates::<T>() + 2 * lt_num_of_mul_gates::<T>()
+        }
+
+        fn public_input(&self) -> Vec<u8> {
+            self.threshold.value.to_bytes()
+        }
+    }
+
+    #[test]
+    fn test_plain_comparisons() {
+        for x in 0..=u8::MAX {
+            for y in 0..=u8::MAX {
+                assert_eq!(less_than(x, y), u8::from(x < y));
+                assert_eq!(equal(x, y), u8::from(x == y));
+            }
+        }
+
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let (x, y) = (rng.gen::<u64>(), rng.gen::<u64>());
+            assert_eq!(less_than(x, y), u64::from(x < y));
+        }
+    }
+
+    fn prove_threshold<T: Value + PartialEq>(x: T, threshold: T) -> Vec<GF2Word<T>> {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        let circuit = ThresholdCircuit {
+            threshold: threshold.into(),
+        };
+        let input = x.to_bytes().into_iter().rev().collect::<Vec<u8>>();
+        let output = circuit.compute(&input);
+
+        let proof = Prover::<T, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+        Verifier::<T, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output).unwrap();
+
+        output
+    }
+
+    #[test]
+    fn test_threshold_circuit() {
+        let bits = |eq: u32, lt: u32, gt: u32| vec![eq.into(), lt.into(), gt.into()];
+
+        assert_eq!(prove_threshold(100u32, 100), bits(1, 0, 0));
+        assert_eq!(prove_threshold(99u32, 100), bits(0, 1, 0));
+        assert_eq!(prove_threshold(u32::MAX, 100), bits(0, 0, 1));
+        assert_eq!(prove_threshold(0u32, u32::MAX), bits(0, 1, 0));
+
+        assert_eq!(
+            prove_threshold(200u8, 7),
+            vec![0.into(), 0.into(), 1.into()]
+        );
+    }
+
+    #[test]
+    fn test_proof_is_bound_to_threshold() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        let circuit = ThresholdCircuit::<u32> {
+            threshold: 10u32.into(),
+        };
+        let input = 5u32.to_le_bytes();
+        let output = circuit.compute(&input);
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+
+        // another threshold has the same output, but not the same public input
+        let other = ThresholdCircuit::<u32> {
+            threshold: 11u32.into(),
+        };
+        assert_eq!(other.compute(&input), output);
+        assert!(
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &other, &output)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_false_comparison_is_rejected() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        let circuit = ThresholdCircuit::<u32> {
+            threshold: 1000u32.into(),
+        };
+        let input = 999u32.to_le_bytes();
+
+        // claim x > threshold although x < threshold
+        let claimed_output = vec![0u32.into(), 0u32.into(), 1u32.into()];
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng,
+            &input,
+            &circuit,
+            &claimed_output,
+        )
+        .unwrap();
+
+        assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+            &proof,
+            &circuit,
+            &claimed_output
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! HMAC-SHA256 (RFC 2104) with a secret key and a public message.
+//!
+//! HMAC(K, m) = H((K ^ opad) || H((K ^ ipad) || m)), where K is the key padded
+//! with zeros to the block size. The pads and the message are public, so each
+//! party appends them to its own share of the key: an odd number of parties hold
+//! them, hence they are part of the reconstructed input.
+
+use crate::{
+    circuit::{named_id, Circuit, Output, TwoThreeDecOutput},
+    error::Error,
+    gadgets::{
+        prepare::Prepare,
+        sha256::{
+            digest_to_bytes, mpc_sha256_padded, mpc_sha256_padded_verify, sha256,
+            sha256_num_of_mul_gates, PaddingTemplate,
+        },
+    },
+    gf2_word::GF2Word,
+    party::Party,
+};
+
+pub const BLOCK_LEN: usize = 64;
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+/// Brings an HMAC key of any length to the block size, as specified by RFC 2104.
+/// The result is the witness of `HmacSha256Circuit`.
+pub fn hmac_key_block(key: &[u8]) -> Vec<u8> {
+    let mut block = if key.len() > BLOCK_LEN {
+        digest_to_bytes(&sha256(key))
+    } else {
+        key.to_vec()
+    };
+    block.resize(BLOCK_LEN, 0);
+    block
+}
+
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<GF2Word<u32>> {
+    let key = hmac_key_block(key);
+    let inner = sha256(&[xor_pad(&key, IPAD), message.to_vec()].concat());
+    sha256(&[xor_pad(&key, OPAD), digest_to_bytes(&inner)].concat())
+}
+
+fn xor_pad(key: &[u8], pad: u8) -> Vec<u8> {
+    key.iter().map(|&b| b ^ pad).collect()
+}
+
+/// Proves knowledge of a key such that HMAC-SHA256(key, message) is the public output.
+///
+/// The witness is the key block returned by `hmac_key_block`, or equivalently
+/// by `Prepare::prepare_witness`.
+#[derive(Clone, Debug)]
+pub struct HmacSha256Circuit {
+    message: Vec<u8>,
+    inner_template: PaddingTemplate,
+    outer_template: PaddingTemplate,
+}
+
+impl HmacSha256Circuit {
+    pub fn new(message: &[u8]) -> Self {
+        Self {
+            message: message.to_vec(),
+            inner_template: PaddingTemplate::new(BLOCK_LEN, message),
+            outer_template: PaddingTemplate::new(BLOCK_LEN + 32, &[]),
+        }
+    }
+
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    fn inner_input(&self, key_share: &[u8]) -> Vec<GF2Word<u32>> {
+        self.inner_template.apply(&xor_pad(key_share, IPAD))
+    }
+
+    fn outer_input(
+        &self,
+        key_share: &[u8],
+        inner_digest_share: &[GF2Word<u32>],
+    ) -> Vec<GF2Word<u32>> {
+        self.outer_template.apply(
+            &[
+                xor_pad(key_share, OPAD),
+                digest_to_bytes(inner_digest_share),
+            ]
+            .concat(),
+        )
+    }
+}
+
+impl Circuit<u32> for HmacSha256Circuit {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+        assert_eq!(input.len(), BLOCK_LEN);
+        hmac_sha256(input, &self.message)
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<u32>,
+        p2: &mut Party<u32>,
+        p3: &mut Party<u32>,
+    ) -> TwoThreeDecOutput<u32> {
+        let (k1, k2, k3) = (
+            p1.view.input.clone(),
+            p2.view.input.clone(),
+            p3.view.input.clone(),
+        );
+
+        let (inner_1, inner_2, inner_3) = mpc_sha256_padded(
+            &self.inner_input(&k1),
+            &self.inner_input(&k2),
+            &self.inner_input(&k3),
+            p1,
+            p2,
+            p3,
+        );
+
+        mpc_sha256_padded(
+            &self.outer_input(&k1, &inner_1),
+            &self.outer_input(&k2, &inner_2),
+            &self.outer_input(&k3, &inner_3),
+            p1,
+            p2,
+            p3,
+        )
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<u32>,
+        p_next: &mut Party<u32>,
+    ) -> Result<(Output<u32>, Output<u32>), Error> {
+        if p.view.input.len() != BLOCK_LEN || p_next.view.input.len() != BLOCK_LEN {
+            return Err(Error::VerificationError);
+        }
+        let (k, k_next) = (p.view.input.clone(), p_next.view.input.clone());
+
+        let (inner, inner_next) =
+            mpc_sha256_padded_verify(&self.inner_input(&k), &self.inner_input(&k_next), p, p_next)?;
+
+        mpc_sha256_padded_verify(
+            &self.outer_input(&k, &inner),
+            &self.outer_input(&k_next, &inner_next),
+            p,
+            p_next,
+        )
+    }
+
+    fn party_input_len(&self) -> usize {
+        BLOCK_LEN
+    }
+
+    fn party_output_len(&self) -> usize {
+        8
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        sha256_num_of_mul_gates(BLOCK_LEN + self.message.len())
+            + sha256_num_of_mul_gates(BLOCK_LEN + 32)
+    }
+
+    fn public_input(&self) -> Vec<u8> {
+        self.message.clone()
+    }
+
+    fn id(&self) -> Vec<u8> {
+        named_id("hmac-sha256", self, &[])
+    }
+}
+
+impl Prepare<u32> for HmacSha256Circuit {
+    /// Takes the key, of any length.
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(hmac_key_block(bytes))
+    }
+}
+
+#[cfg(test)]
+mod test_hmac_sha256 {
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{circuit::Circuit, prover::Prover, verifier::Verifier};
+
+    use super::*;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_rfc_4231_vectors() {
+        // test case 1
+        let tag = hmac_sha256(&[0x0b; 20], b"Hi There");
+        assert_eq!(
+            digest_to_bytes(&tag),
+            from_hex("b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7")
+        );
+
+        // test case 2
+        let tag = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            digest_to_bytes(&tag),
+            from_hex("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+
+        // test case 6, key larger than the block size
+        let tag = hmac_sha256(
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First",
+        );
+        assert_eq!(
+            digest_to_bytes(&tag),
+            from_hex("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54")
+        );
+    }
+
+    #[test]
+    fn test_hmac_circuit() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        let circuit = HmacSha256Circuit::new(b"what do ya want for nothing?");
+        let key = hmac_key_block(b"Jefe");
+
+        let output = circuit.compute(&key);
+        assert_eq!(
+            digest_to_bytes(&output),
+            from_hex("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &key, &circuit, &output,
+        )
+        .unwrap();
+
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+
+        // the same proof does not verify for another message
+        let other_circuit = HmacSha256Circuit::new(b"what do ya want for something?");
+        assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+            &proof,
+            &other_circuit,
+            &output
+        )
+        .is_err());
+    }
+}
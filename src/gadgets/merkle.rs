@@ -0,0 +1,643 @@
+//! Merkle trees over SHA-256 or BLAKE2s and membership in them.
+//!
+//! A node is `H(left || right)` and leaves are 32 byte digests, with `H` set
+//! by `NodeHash`. A path from a leaf to the root lists, for each level, the
+//! sibling and whether the current node is a right child. In the circuit both
+//! are secret, so a proof does not reveal the position of the leaf: the
+//! direction is a witness byte whose lowest bit is spread over a whole word,
+//! which is linear, and the two children are swapped with one AND per word.
+//!
+//! `MembershipCircuit` proves that a secret leaf is in the tree of a public
+//! root.
+
+use crate::{
+    circuit::{named_id, Circuit, Output, TwoThreeDecOutput},
+    error::Error,
+    gadgets::{
+        blake2s::{blake2s, blake2s_num_of_mul_gates, mpc_blake2s, mpc_blake2s_verify},
+        mpc_and, mpc_and_verify,
+        sha256::{
+            digest_to_bytes, mpc_sha256_padded, mpc_sha256_padded_verify, sha256,
+            sha256_num_of_mul_gates, PaddingTemplate,
+        },
+    },
+    gf2_word::GF2Word,
+    party::Party,
+};
+
+pub const NODE_LEN: usize = 32;
+const NODE_WORDS: usize = NODE_LEN / 4;
+
+/// Witness bytes of one level of a path: the sibling and the direction byte.
+pub const PATH_LEVEL_LEN: usize = NODE_LEN + 1;
+
+pub type Node = [u8; NODE_LEN];
+
+/// The hash of inner nodes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NodeHash {
+    #[default]
+    Sha256,
+    /// Cheaper in the circuit, see `gadgets::blake2s`.
+    Blake2s,
+}
+
+impl NodeHash {
+    pub fn hash_nodes(self, left: &Node, right: &Node) -> Node {
+        let children = [left.as_slice(), right.as_slice()].concat();
+        match self {
+            Self::Sha256 => self.to_node(&sha256(&children)),
+            Self::Blake2s => self.to_node(&blake2s(&children)),
+        }
+    }
+
+    /// A node as the words output by the hash, big-endian for SHA-256 and
+    /// little-endian for BLAKE2s.
+    pub fn to_words(self, node: &[u8]) -> Vec<GF2Word<u32>> {
+        node.chunks(4)
+            .map(|chunk| {
+                let chunk = chunk.try_into().unwrap();
+                match self {
+                    Self::Sha256 => u32::from_be_bytes(chunk),
+                    Self::Blake2s => u32::from_le_bytes(chunk),
+                }
+                .into()
+            })
+            .collect()
+    }
+
+    pub fn to_node(self, words: &[GF2Word<u32>]) -> Node {
+        let bytes: Vec<u8> = match self {
+            Self::Sha256 => digest_to_bytes(words),
+            Self::Blake2s => words
+                .iter()
+                .flat_map(|word| word.value.to_le_bytes())
+                .collect(),
+        };
+        bytes.try_into().unwrap()
+    }
+
+    /// Root of the tree in which `leaf` sits at the end of `path`.
+    pub fn root(self, path: &MerklePath, leaf: &Node) -> Node {
+        path.siblings
+            .iter()
+            .zip(&path.is_right)
+            .fold(*leaf, |node, (sibling, &is_right)| {
+                if is_right {
+                    self.hash_nodes(sibling, &node)
+                } else {
+                    self.hash_nodes(&node, sibling)
+                }
+            })
+    }
+
+    /// Number of multiplication gates used to walk a path of `depth` levels.
+    pub fn merkle_num_of_mul_gates(self, depth: usize) -> usize {
+        let hash = match self {
+            Self::Sha256 => sha256_num_of_mul_gates(2 * NODE_LEN),
+            Self::Blake2s => blake2s_num_of_mul_gates(2 * NODE_LEN),
+        };
+        depth * (NODE_WORDS + hash)
+    }
+
+    /// A share of the sibling of one level and the direction spread over a word.
+    fn parse_level(self, level: &[u8]) -> (Vec<GF2Word<u32>>, GF2Word<u32>) {
+        let mask = if level[NODE_LEN] & 1 == 1 {
+            u32::MAX
+        } else {
+            0
+        };
+        (self.to_words(&level[..NODE_LEN]), mask.into())
+    }
+
+    /// The children `(left, right)` of a level, given the share `t` of
+    /// `mask & (node ^ sibling)`.
+    fn children(
+        self,
+        node: &[GF2Word<u32>],
+        sibling: &[GF2Word<u32>],
+        t: &[GF2Word<u32>],
+    ) -> Vec<u8> {
+        let left: Vec<_> = node.iter().zip(t).map(|(&a, &t)| a ^ t).collect();
+        let right: Vec<_> = sibling.iter().zip(t).map(|(&b, &t)| b ^ t).collect();
+        [self.to_node(&left), self.to_node(&right)].concat()
+    }
+
+    /// Shares of the root reached from the shares of a leaf, given as words of
+    /// the hash, along the shares of a path encoded by
+    /// `MerklePath::to_witness`. Uses `merkle_num_of_mul_gates(depth)`
+    /// multiplication gates.
+    pub fn mpc_merkle_root(
+        self,
+        leaf: (Output<u32>, Output<u32>, Output<u32>),
+        path: (&[u8], &[u8], &[u8]),
+        p1: &mut Party<u32>,
+        p2: &mut Party<u32>,
+        p3: &mut Party<u32>,
+    ) -> TwoThreeDecOutput<u32> {
+        let template = PaddingTemplate::new(2 * NODE_LEN, &[]);
+        let mut node = leaf;
+
+        let levels = path
+            .0
+            .chunks(PATH_LEVEL_LEN)
+            .zip(path.1.chunks(PATH_LEVEL_LEN))
+            .zip(path.2.chunks(PATH_LEVEL_LEN));
+        for ((level_p1, level_p2), level_p3) in levels {
+            let (sibling_p1, mask_p1) = self.parse_level(level_p1);
+            let (sibling_p2, mask_p2) = self.parse_level(level_p2);
+            let (sibling_p3, mask_p3) = self.parse_level(level_p3);
+
+            let (d1, d2, d3) = (
+                diff(&node.0, &sibling_p1),
+                diff(&node.1, &sibling_p2),
+                diff(&node.2, &sibling_p3),
+            );
+
+            let mut t = (vec![], vec![], vec![]);
+            for i in 0..NODE_WORDS {
+                let (t1, t2, t3) = mpc_and(
+                    (mask_p1, d1[i]),
+                    (mask_p2, d2[i]),
+                    (mask_p3, d3[i]),
+                    p1,
+                    p2,
+                    p3,
+                );
+                t.0.push(t1);
+                t.1.push(t2);
+                t.2.push(t3);
+            }
+
+            let children = (
+                self.children(&node.0, &sibling_p1, &t.0),
+                self.children(&node.1, &sibling_p2, &t.1),
+                self.children(&node.2, &sibling_p3, &t.2),
+            );
+            node = match self {
+                Self::Sha256 => mpc_sha256_padded(
+                    &template.apply(&children.0),
+                    &template.apply(&children.1),
+                    &template.apply(&children.2),
+                    p1,
+                    p2,
+                    p3,
+                ),
+                Self::Blake2s => mpc_blake2s(&children.0, &children.1, &children.2, p1, p2, p3),
+            };
+        }
+
+        node
+    }
+
+    pub fn merkle_root_verify(
+        self,
+        leaf: (Output<u32>, Output<u32>),
+        path: (&[u8], &[u8]),
+        p: &mut Party<u32>,
+        p_next: &mut Party<u32>,
+    ) -> Result<(Output<u32>, Output<u32>), Error> {
+        if path.0.len() != path.1.len() || !path.0.len().is_multiple_of(PATH_LEVEL_LEN) {
+            return Err(Error::VerificationError);
+        }
+
+        let template = PaddingTemplate::new(2 * NODE_LEN, &[]);
+        let mut node = leaf;
+
+        for (level_p, level_p_next) in path
+            .0
+            .chunks(PATH_LEVEL_LEN)
+            .zip(path.1.chunks(PATH_LEVEL_LEN))
+        {
+            let (sibling_p, mask_p) = self.parse_level(level_p);
+            let (sibling_p_next, mask_p_next) = self.parse_level(level_p_next);
+
+            let (d, d_next) = (diff(&node.0, &sibling_p), diff(&node.1, &sibling_p_next));
+
+            let mut t = (vec![], vec![]);
+            for i in 0..NODE_WORDS {
+                let (ti, ti_next) =
+                    mpc_and_verify((mask_p, d[i]), (mask_p_next, d_next[i]), p, p_next)?;
+                t.0.push(ti);
+                t.1.push(ti_next);
+            }
+
+            let children = (
+                self.children(&node.0, &sibling_p, &t.0),
+                self.children(&node.1, &sibling_p_next, &t.1),
+            );
+            node = match self {
+                Self::Sha256 => mpc_sha256_padded_verify(
+                    &template.apply(&children.0),
+                    &template.apply(&children.1),
+                    p,
+                    p_next,
+                )?,
+                Self::Blake2s => mpc_blake2s_verify(&children.0, &children.1, p, p_next)?,
+            };
+        }
+
+        Ok(node)
+    }
+}
+
+pub fn hash_nodes(left: &Node, right: &Node) -> Node {
+    NodeHash::Sha256.hash_nodes(left, right)
+}
+
+/// A tree over a list of leaves, padded with zero leaves to a power of two.
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    // levels[0] are the leaves, the last level is the root
+    levels: Vec<Vec<Node>>,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: &[Node]) -> Self {
+        Self::with_hash(leaves, NodeHash::Sha256)
+    }
+
+    pub fn with_hash(leaves: &[Node], hash: NodeHash) -> Self {
+        let mut level = leaves.to_vec();
+        level.resize(leaves.len().max(1).next_power_of_two(), [0; NODE_LEN]);
+
+        let mut levels = vec![level];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| hash.hash_nodes(&pair[0], &pair[1]))
+                .collect();
+            levels.push(next);
+        }
+
+        Self { levels }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    pub fn root(&self) -> Node {
+        self.levels.last().unwrap()[0]
+    }
+
+    /// Path from the leaf at `index` to the root.
+    pub fn path(&self, mut index: usize) -> Option<MerklePath> {
+        if index >= self.levels[0].len() {
+            return None;
+        }
+
+        let mut siblings = Vec::with_capacity(self.depth());
+        let mut is_right = Vec::with_capacity(self.depth());
+        for level in &self.levels[..self.depth()] {
+            siblings.push(level[index ^ 1]);
+            is_right.push(index & 1 == 1);
+            index /= 2;
+        }
+
+        Some(MerklePath { siblings, is_right })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerklePath {
+    pub siblings: Vec<Node>,
+    pub is_right: Vec<bool>,
+}
+
+impl MerklePath {
+    pub fn depth(&self) -> usize {
+        self.siblings.len()
+    }
+
+    /// Root of the tree in which `leaf` sits at the end of this path.
+    pub fn root(&self, leaf: &Node) -> Node {
+        self.siblings
+            .iter()
+            .zip(&self.is_right)
+            .fold(*leaf, |node, (sibling, &is_right)| {
+                if is_right {
+                    hash_nodes(sibling, &node)
+                } else {
+                    hash_nodes(&node, sibling)
+                }
+            })
+    }
+
+    /// Witness bytes of the path, in the layout read by `mpc_merkle_root`.
+    pub fn to_witness(&self) -> Vec<u8> {
+        self.siblings
+            .iter()
+            .zip(&self.is_right)
+            .flat_map(|(sibling, &is_right)| [sibling.as_slice(), &[is_right as u8]].concat())
+            .collect()
+    }
+
+    /// Inverse of `to_witness`, only the lowest bit of a direction byte is read.
+    pub fn from_witness(witness: &[u8]) -> Option<Self> {
+        if !witness.len().is_multiple_of(PATH_LEVEL_LEN) {
+            return None;
+        }
+        let levels = witness.chunks(PATH_LEVEL_LEN);
+        Some(Self {
+            siblings: levels
+                .clone()
+                .map(|level| level[..NODE_LEN].try_into().unwrap())
+                .collect(),
+            is_right: levels.map(|level| level[NODE_LEN] & 1 == 1).collect(),
+        })
+    }
+}
+
+/// Number of multiplication gates used to walk a path of `depth` levels over
+/// SHA-256.
+pub fn merkle_num_of_mul_gates(depth: usize) -> usize {
+    NodeHash::Sha256.merkle_num_of_mul_gates(depth)
+}
+
+fn diff(node: &[GF2Word<u32>], sibling: &[GF2Word<u32>]) -> Vec<GF2Word<u32>> {
+    node.iter().zip(sibling).map(|(&a, &b)| a ^ b).collect()
+}
+
+/// `NodeHash::mpc_merkle_root` over SHA-256, with the leaf as big-endian words.
+pub fn mpc_merkle_root(
+    leaf: (Output<u32>, Output<u32>, Output<u32>),
+    path: (&[u8], &[u8], &[u8]),
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) -> TwoThreeDecOutput<u32> {
+    NodeHash::Sha256.mpc_merkle_root(leaf, path, p1, p2, p3)
+}
+
+pub fn merkle_root_verify(
+    leaf: (Output<u32>, Output<u32>),
+    path: (&[u8], &[u8]),
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<(Output<u32>, Output<u32>), Error> {
+    NodeHash::Sha256.merkle_root_verify(leaf, path, p, p_next)
+}
+
+/// Proves that a secret leaf is in the tree of depth `depth` with the public
+/// root, without revealing the leaf or its position.
+///
+/// ```text
+/// witness = leaf: [u8; 32] || (sibling: [u8; 32] || is_right: u8) * depth
+/// output  = root: 8 words of `hash`
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct MembershipCircuit {
+    pub depth: usize,
+    pub hash: NodeHash,
+}
+
+impl MembershipCircuit {
+    pub fn new(depth: usize, hash: NodeHash) -> Self {
+        Self { depth, hash }
+    }
+
+    pub fn witness(leaf: &Node, path: &MerklePath) -> Vec<u8> {
+        [leaf.as_slice(), &path.to_witness()].concat()
+    }
+
+    /// The public output of the circuit for a tree with `root`.
+    pub fn public_output(&self, root: &Node) -> Vec<GF2Word<u32>> {
+        self.hash.to_words(root)
+    }
+}
+
+impl Circuit<u32> for MembershipCircuit {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+        assert_eq!(input.len(), self.party_input_len());
+        let (leaf, path) = input.split_at(NODE_LEN);
+        let path = MerklePath::from_witness(path).unwrap();
+        self.public_output(&self.hash.root(&path, leaf.try_into().unwrap()))
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<u32>,
+        p2: &mut Party<u32>,
+        p3: &mut Party<u32>,
+    ) -> TwoThreeDecOutput<u32> {
+        let (w1, w2, w3) = (
+            p1.view.input.clone(),
+            p2.view.input.clone(),
+            p3.view.input.clone(),
+        );
+        let (leaf_p1, path_p1) = w1.split_at(NODE_LEN);
+        let (leaf_p2, path_p2) = w2.split_at(NODE_LEN);
+        let (leaf_p3, path_p3) = w3.split_at(NODE_LEN);
+
+        let leaf = (
+            self.hash.to_words(leaf_p1),
+            self.hash.to_words(leaf_p2),
+            self.hash.to_words(leaf_p3),
+        );
+        self.hash
+            .mpc_merkle_root(leaf, (path_p1, path_p2, path_p3), p1, p2, p3)
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<u32>,
+        p_next: &mut Party<u32>,
+    ) -> Result<(Output<u32>, Output<u32>), Error> {
+        if p.view.input.len() != self.party_input_len()
+            || p_next.view.input.len() != self.party_input_len()
+        {
+            return Err(Error::VerificationError);
+        }
+        let (w, w_next) = (p.view.input.clone(), p_next.view.input.clone());
+        let (leaf_p, path_p) = w.split_at(NODE_LEN);
+        let (leaf_p_next, path_p_next) = w_next.split_at(NODE_LEN);
+
+        let leaf = (self.hash.to_words(leaf_p), self.hash.to_words(leaf_p_next));
+        self.hash
+            .merkle_root_verify(leaf, (path_p, path_p_next), p, p_next)
+    }
+
+    fn party_input_len(&self) -> usize {
+        NODE_LEN + self.depth * PATH_LEVEL_LEN
+    }
+
+    fn party_output_len(&self) -> usize {
+        NODE_WORDS
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        self.hash.merkle_num_of_mul_gates(self.depth)
+    }
+
+    fn id(&self) -> Vec<u8> {
+        let name = match self.hash {
+            NodeHash::Sha256 => "merkle-membership-sha256",
+            NodeHash::Blake2s => "merkle-membership-blake2s",
+        };
+        named_id(name, self, &[])
+    }
+}
+
+#[cfg(test)]
+mod merkle_tests {
+    use rand::{rngs::ThreadRng, thread_rng, Rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{circuit::Circuit, prover::Prover, verifier::Verifier};
+
+    use super::*;
+
+    /// Outputs the root reached from a public leaf along a secret path.
+    struct RootCircuit {
+        leaf: Node,
+        depth: usize,
+    }
+
+    impl RootCircuit {
+        fn leaf_words(&self) -> Output<u32> {
+            self.leaf
+                .chunks(4)
+                .map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap()).into())
+                .collect()
+        }
+    }
+
+    impl Circuit<u32> for RootCircuit {
+        fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+            let path = MerklePath::from_witness(input).unwrap();
+            path.root(&self.leaf)
+                .chunks(4)
+                .map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap()).into())
+                .collect()
+        }
+
+        fn compute_23_decomposition(
+            &self,
+            p1: &mut Party<u32>,
+            p2: &mut Party<u32>,
+            p3: &mut Party<u32>,
+        ) -> TwoThreeDecOutput<u32> {
+            // a public leaf is held by every party
+            let leaf = self.leaf_words();
+            let (path_p1, path_p2, path_p3) = (
+                p1.view.input.clone(),
+                p2.view.input.clone(),
+                p3.view.input.clone(),
+            );
+            mpc_merkle_root(
+                (leaf.clone(), leaf.clone(), leaf),
+                (&path_p1, &path_p2, &path_p3),
+                p1,
+                p2,
+                p3,
+            )
+        }
+
+        fn simulate_two_parties(
+            &self,
+            p: &mut Party<u32>,
+            p_next: &mut Party<u32>,
+        ) -> Result<(Output<u32>, Output<u32>), Error> {
+            let leaf = self.leaf_words();
+            let (path_p, path_p_next) = (p.view.input.clone(), p_next.view.input.clone());
+            merkle_root_verify((leaf.clone(), leaf), (&path_p, &path_p_next), p, p_next)
+        }
+
+        fn party_input_len(&self) -> usize {
+            self.depth * PATH_LEVEL_LEN
+        }
+
+        fn party_output_len(&self) -> usize {
+            NODE_WORDS
+        }
+
+        fn num_of_mul_gates(&self) -> usize {
+            merkle_num_of_mul_gates(self.depth)
+        }
+    }
+
+    #[test]
+    fn test_tree() {
+        let leaves: Vec<Node> = (0..5u8).map(|i| [i; NODE_LEN]).collect();
+        let tree = MerkleTree::new(&leaves);
+        assert_eq!(tree.depth(), 3);
+
+        for (index, leaf) in leaves.iter().enumerate() {
+            let path = tree.path(index).unwrap();
+            assert_eq!(path.root(leaf), tree.root());
+        }
+        // padding leaves are part of the tree
+        assert_eq!(tree.path(7).unwrap().root(&[0; NODE_LEN]), tree.root());
+        assert!(tree.path(8).is_none());
+
+        let single = MerkleTree::new(&leaves[..1]);
+        assert_eq!(single.depth(), 0);
+        assert_eq!(single.root(), leaves[0]);
+    }
+
+    #[test]
+    fn test_root_circuit() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        let leaves: Vec<Node> = (0..4).map(|_| rng.gen()).collect();
+        let tree = MerkleTree::new(&leaves);
+        let index = rng.gen_range(0..leaves.len());
+
+        let circuit = RootCircuit {
+            leaf: leaves[index],
+            depth: tree.depth(),
+        };
+        let witness = tree.path(index).unwrap().to_witness();
+        let output = circuit.compute(&witness);
+        assert_eq!(digest_to_bytes(&output), tree.root());
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, &witness, &circuit, &output,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_membership_circuit() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        for hash in [NodeHash::Sha256, NodeHash::Blake2s] {
+            let leaves: Vec<Node> = (0..3).map(|_| rng.gen()).collect();
+            let tree = MerkleTree::with_hash(&leaves, hash);
+            let index = rng.gen_range(0..leaves.len());
+            let path = tree.path(index).unwrap();
+            assert_eq!(hash.root(&path, &leaves[index]), tree.root());
+
+            let circuit = MembershipCircuit::new(tree.depth(), hash);
+            let witness = MembershipCircuit::witness(&leaves[index], &path);
+            let output = circuit.public_output(&tree.root());
+            assert_eq!(circuit.compute(&witness), output);
+
+            let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+                &mut rng, &witness, &circuit, &output,
+            )
+            .unwrap();
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+                .unwrap();
+
+            // a leaf outside the tree reaches another root
+            let other = MembershipCircuit::witness(&rng.gen(), &path);
+            assert_ne!(circuit.compute(&other), output);
+            let other_root = MerkleTree::with_hash(&[rng.gen()], hash).root();
+            assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+                &proof,
+                &circuit,
+                &circuit.public_output(&other_root),
+            )
+            .is_err());
+        }
+    }
+}
@@ -1,6 +1,19 @@
 pub mod add_mod;
+pub mod assertion;
+pub mod bitslice;
+pub mod blake2s;
+pub mod chacha20;
+pub mod compare;
+pub mod hmac_sha256;
+pub mod merkle;
+pub mod nullifier;
 pub mod prepare;
+pub mod range;
+pub mod ripemd160;
 pub mod sha256;
+pub mod sha512;
+pub mod signed;
+pub mod substring;
 pub mod verifier;
 
 use crate::{
@@ -73,5 +86,227 @@ pub fn mpc_and_verify<T: Value>(
     */
     p.view.send_msg(output_p);
 
-    Ok((output_p, p_next.read_view()))
+    Ok((output_p, p_next.read_view()?))
+}
+
+/*
+    Public values, such as `Circuit::public_input`, enter the circuit as replicated
+    shares: every party holds the value and the three copies XOR to it. XOR and AND
+    with a public value are then linear and consume no tape randomness.
+*/
+
+pub fn mpc_public<T: Value>(value: GF2Word<T>) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    (value, value, value)
+}
+
+pub fn public_verify<T: Value>(value: GF2Word<T>) -> (GF2Word<T>, GF2Word<T>) {
+    (value, value)
+}
+
+pub fn mpc_xor_public<T: Value>(
+    input: (GF2Word<T>, GF2Word<T>, GF2Word<T>),
+    value: GF2Word<T>,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    (input.0 ^ value, input.1 ^ value, input.2 ^ value)
+}
+
+pub fn xor_public_verify<T: Value>(
+    input: (GF2Word<T>, GF2Word<T>),
+    value: GF2Word<T>,
+) -> (GF2Word<T>, GF2Word<T>) {
+    (input.0 ^ value, input.1 ^ value)
+}
+
+pub fn mpc_and_public<T: Value>(
+    input: (GF2Word<T>, GF2Word<T>, GF2Word<T>),
+    value: GF2Word<T>,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    (input.0 & value, input.1 & value, input.2 & value)
+}
+
+pub fn and_public_verify<T: Value>(
+    input: (GF2Word<T>, GF2Word<T>),
+    value: GF2Word<T>,
+) -> (GF2Word<T>, GF2Word<T>) {
+    (input.0 & value, input.1 & value)
+}
+
+/*
+    Rotations and shifts are linear, so every party applies them to its own share
+    and no tape randomness is consumed.
+*/
+
+pub fn mpc_rotl<T: Value>(
+    input: (GF2Word<T>, GF2Word<T>, GF2Word<T>),
+    n: usize,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    (input.0.rotl(n), input.1.rotl(n), input.2.rotl(n))
+}
+
+pub fn rotl_verify<T: Value>(
+    input: (GF2Word<T>, GF2Word<T>),
+    n: usize,
+) -> (GF2Word<T>, GF2Word<T>) {
+    (input.0.rotl(n), input.1.rotl(n))
+}
+
+pub fn mpc_rotr<T: Value>(
+    input: (GF2Word<T>, GF2Word<T>, GF2Word<T>),
+    n: usize,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    (input.0.rotr(n), input.1.rotr(n), input.2.rotr(n))
+}
+
+pub fn rotr_verify<T: Value>(
+    input: (GF2Word<T>, GF2Word<T>),
+    n: usize,
+) -> (GF2Word<T>, GF2Word<T>) {
+    (input.0.rotr(n), input.1.rotr(n))
+}
+
+pub fn mpc_shl<T: Value>(
+    input: (GF2Word<T>, GF2Word<T>, GF2Word<T>),
+    n: usize,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    (input.0 << n, input.1 << n, input.2 << n)
+}
+
+pub fn shl_verify<T: Value>(input: (GF2Word<T>, GF2Word<T>), n: usize) -> (GF2Word<T>, GF2Word<T>) {
+    (input.0 << n, input.1 << n)
+}
+
+pub fn mpc_shr<T: Value>(
+    input: (GF2Word<T>, GF2Word<T>, GF2Word<T>),
+    n: usize,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    (input.0 >> n, input.1 >> n, input.2 >> n)
+}
+
+pub fn shr_verify<T: Value>(input: (GF2Word<T>, GF2Word<T>), n: usize) -> (GF2Word<T>, GF2Word<T>) {
+    (input.0 >> n, input.1 >> n)
+}
+
+#[cfg(test)]
+mod gadgets_tests {
+    use rand::{thread_rng, Rng};
+
+    use crate::gf2_word::GF2Word;
+
+    use super::{
+        and_public_verify, mpc_and_public, mpc_public, mpc_rotl, mpc_rotr, mpc_shl, mpc_shr,
+        mpc_xor_public, rotr_verify, shr_verify, xor_public_verify,
+    };
+
+    #[test]
+    fn test_linear_ops_commute_with_sharing() {
+        let mut rng = thread_rng();
+        let x = rng.gen::<u32>();
+        let s1 = rng.gen::<u32>();
+        let s2 = rng.gen::<u32>();
+        let shares: (GF2Word<u32>, GF2Word<u32>, GF2Word<u32>) =
+            (s1.into(), s2.into(), (x ^ s1 ^ s2).into());
+
+        let reconstruct = |(a, b, c): (GF2Word<u32>, GF2Word<u32>, GF2Word<u32>)| (a ^ b ^ c).value;
+
+        for n in [0, 1, 7, 31, 32] {
+            assert_eq!(reconstruct(mpc_rotl(shares, n)), x.rotate_left(n as u32));
+            assert_eq!(reconstruct(mpc_rotr(shares, n)), x.rotate_right(n as u32));
+            assert_eq!(
+                reconstruct(mpc_shl(shares, n)),
+                x.checked_shl(n as u32).unwrap_or(0)
+            );
+            assert_eq!(
+                reconstruct(mpc_shr(shares, n)),
+                x.checked_shr(n as u32).unwrap_or(0)
+            );
+
+            // the two party simulation computes the same shares
+            let (r1, r2, _) = mpc_rotr(shares, n);
+            assert_eq!(rotr_verify((shares.0, shares.1), n), (r1, r2));
+            let (r1, r2, _) = mpc_shr(shares, n);
+            assert_eq!(shr_verify((shares.0, shares.1), n), (r1, r2));
+        }
+    }
+
+    /// Runs the gadgets generic over the word size on each unsigned word size.
+    macro_rules! word_size_tests {
+        ($($t:ident),*) => {$(
+            mod $t {
+                use rand::{thread_rng, Rng};
+                use rand_chacha::ChaCha20Rng;
+
+                use crate::{expander::tape_label, gf2_word::GF2Word, party::Party, tape::Tape};
+
+                use super::super::{mpc_and, mpc_and_verify, mpc_rotl, mpc_shr, mpc_xor};
+
+                type Shares = (GF2Word<$t>, GF2Word<$t>, GF2Word<$t>);
+
+                fn share(x: $t) -> Shares {
+                    let mut rng = thread_rng();
+                    let (s1, s2) = (rng.gen::<$t>(), rng.gen::<$t>());
+                    (s1.into(), s2.into(), (x ^ s1 ^ s2).into())
+                }
+
+                fn reconstruct((a, b, c): Shares) -> $t {
+                    (a ^ b ^ c).value
+                }
+
+                #[test]
+                fn test_gadgets() {
+                    let mut rng = thread_rng();
+                    let (x, y) = (rng.gen::<$t>(), rng.gen::<$t>());
+                    let (xs, ys) = (share(x), share(y));
+
+                    let xor = mpc_xor((xs.0, ys.0), (xs.1, ys.1), (xs.2, ys.2));
+                    assert_eq!(reconstruct(xor), x ^ y);
+                    assert_eq!(reconstruct(mpc_rotl(xs, 3)), x.rotate_left(3));
+                    assert_eq!(reconstruct(mpc_shr(xs, 5)), x >> 5);
+
+                    let mut parties: Vec<Party<$t>> = (0..3)
+                        .map(|i| {
+                            Party::new::<ChaCha20Rng>(vec![], [i as u8; 32], &tape_label(0, i), 1)
+                        })
+                        .collect();
+                    let [p1, p2, p3] = &mut parties[..] else {
+                        unreachable!()
+                    };
+                    let and = mpc_and((xs.0, ys.0), (xs.1, ys.1), (xs.2, ys.2), p1, p2, p3);
+                    assert_eq!(reconstruct(and), x & y);
+
+                    // the first two parties are simulated from the view of the second
+                    let mut p = Party::new::<ChaCha20Rng>(vec![], [0; 32], &tape_label(0, 0), 1);
+                    let mut p_next = Party::from_tape_and_view(
+                        p2.view.clone(),
+                        Tape::from_key::<ChaCha20Rng>([1; 32], &tape_label(0, 1), 1),
+                    );
+                    let simulated =
+                        mpc_and_verify((xs.0, ys.0), (xs.1, ys.1), &mut p, &mut p_next).unwrap();
+                    assert_eq!(simulated, (and.0, and.1));
+                }
+            }
+        )*};
+    }
+
+    word_size_tests!(u8, u16, u32, u64, u128);
+
+    #[test]
+    fn test_public_ops_commute_with_sharing() {
+        let mut rng = thread_rng();
+        let (x, c) = (rng.gen::<u32>(), rng.gen::<u32>());
+        let s1 = rng.gen::<u32>();
+        let s2 = rng.gen::<u32>();
+        let shares: (GF2Word<u32>, GF2Word<u32>, GF2Word<u32>) =
+            (s1.into(), s2.into(), (x ^ s1 ^ s2).into());
+
+        let reconstruct = |(a, b, c): (GF2Word<u32>, GF2Word<u32>, GF2Word<u32>)| (a ^ b ^ c).value;
+
+        assert_eq!(reconstruct(mpc_public(c.into())), c);
+        assert_eq!(reconstruct(mpc_xor_public(shares, c.into())), x ^ c);
+        assert_eq!(reconstruct(mpc_and_public(shares, c.into())), x & c);
+
+        let (r1, r2, _) = mpc_xor_public(shares, c.into());
+        assert_eq!(xor_public_verify((shares.0, shares.1), c.into()), (r1, r2));
+        let (r1, r2, _) = mpc_and_public(shares, c.into());
+        assert_eq!(and_public_verify((shares.0, shares.1), c.into()), (r1, r2));
+    }
 }
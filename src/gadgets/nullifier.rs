@@ -0,0 +1,124 @@
+//! Nullifiers, `SHA-256(secret || scope)` for a secret witness and a public scope.
+//!
+//! A nullifier reveals nothing about the secret, but a secret has a single
+//! nullifier per scope. Revealing it along with a proof about the secret lets a
+//! verifier notice that the same secret is used twice within a scope, while
+//! uses in different scopes stay unlinkable.
+
+use crate::{
+    circuit::{Output, TwoThreeDecOutput},
+    error::Error,
+    gadgets::sha256::{
+        mpc_sha256_padded, mpc_sha256_padded_verify, sha256, sha256_num_of_mul_gates,
+        PaddingTemplate,
+    },
+    gf2_word::GF2Word,
+    party::Party,
+};
+
+pub fn nullifier(secret: &[u8], scope: &[u8]) -> Vec<GF2Word<u32>> {
+    sha256(&[secret, scope].concat())
+}
+
+/// Computes nullifiers of secrets of `secret_len` bytes within a fixed scope.
+#[derive(Clone, Debug)]
+pub struct NullifierGadget {
+    secret_len: usize,
+    scope_len: usize,
+    template: PaddingTemplate,
+}
+
+impl NullifierGadget {
+    pub fn new(secret_len: usize, scope: &[u8]) -> Self {
+        Self {
+            secret_len,
+            scope_len: scope.len(),
+            template: PaddingTemplate::new(secret_len, scope),
+        }
+    }
+
+    pub fn num_of_mul_gates(&self) -> usize {
+        sha256_num_of_mul_gates(self.secret_len + self.scope_len)
+    }
+
+    pub fn mpc_nullifier(
+        &self,
+        secret: (&[u8], &[u8], &[u8]),
+        p1: &mut Party<u32>,
+        p2: &mut Party<u32>,
+        p3: &mut Party<u32>,
+    ) -> TwoThreeDecOutput<u32> {
+        mpc_sha256_padded(
+            &self.template.apply(secret.0),
+            &self.template.apply(secret.1),
+            &self.template.apply(secret.2),
+            p1,
+            p2,
+            p3,
+        )
+    }
+
+    pub fn nullifier_verify(
+        &self,
+        secret: (&[u8], &[u8]),
+        p: &mut Party<u32>,
+        p_next: &mut Party<u32>,
+    ) -> Result<(Output<u32>, Output<u32>), Error> {
+        if secret.0.len() != self.secret_len || secret.1.len() != self.secret_len {
+            return Err(Error::VerificationError);
+        }
+        mpc_sha256_padded_verify(
+            &self.template.apply(secret.0),
+            &self.template.apply(secret.1),
+            p,
+            p_next,
+        )
+    }
+}
+
+#[cfg(test)]
+mod nullifier_tests {
+    use rand::{thread_rng, Rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{expander::tape_label, prover::Prover, tape::Tape};
+
+    use super::*;
+
+    #[test]
+    fn test_nullifier_shares() {
+        let mut rng = thread_rng();
+        let secret: [u8; 32] = rng.gen();
+        let gadget = NullifierGadget::new(secret.len(), b"epoch 7");
+        let tape_len = gadget.num_of_mul_gates();
+
+        let keys = (rng.gen(), rng.gen(), rng.gen());
+        let (mut p1, mut p2, mut p3) = Prover::<u32, ChaCha20Rng, Keccak256>::init_parties(
+            &mut rng, &secret, keys, 0, tape_len,
+        );
+        let (s1, s2, s3) = (
+            p1.view.input.clone(),
+            p2.view.input.clone(),
+            p3.view.input.clone(),
+        );
+
+        let (o1, o2, o3) = gadget.mpc_nullifier((&s1, &s2, &s3), &mut p1, &mut p2, &mut p3);
+        let reconstructed: Vec<_> = (0..8).map(|i| o1[i] ^ o2[i] ^ o3[i]).collect();
+        assert_eq!(reconstructed, nullifier(&secret, b"epoch 7"));
+        assert_ne!(reconstructed, nullifier(&secret, b"epoch 8"));
+
+        // the two party simulation recomputes the shares of the opened parties
+        let mut p = Party::new::<ChaCha20Rng>(s1.clone(), keys.0, &tape_label(0, 0), tape_len);
+        let tape_next = Tape::from_key::<ChaCha20Rng>(keys.1, &tape_label(0, 1), tape_len);
+        let mut p_next = Party::from_tape_and_view(p2.view.clone(), tape_next);
+        let (v1, v2) = gadget
+            .nullifier_verify((&s1, &s2), &mut p, &mut p_next)
+            .unwrap();
+        assert_eq!((v1, v2), (o1, o2));
+
+        assert!(gadget
+            .nullifier_verify((&s1[1..], &s2), &mut p, &mut p_next)
+            .is_err());
+    }
+}
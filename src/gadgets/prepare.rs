@@ -1,9 +1,170 @@
-use crate::gf2_word::{GF2Word, Value};
+use crate::{
+    circuit::{Circuit, Compose, Disclose, Join, PreparedCircuit},
+    error::Error,
+    gf2_word::{GF2Word, Value},
+};
 
-pub fn generic_parse<T: Value>(bytes: &[u8], number_of_words: usize) -> Vec<GF2Word<T>> {
-    assert_eq!(bytes.len(), number_of_words * T::bytes_len());
-    bytes
+/// Parses exactly `number_of_words` little-endian words from `bytes`.
+pub fn generic_parse<T: Value>(
+    bytes: &[u8],
+    number_of_words: usize,
+) -> Result<Vec<GF2Word<T>>, Error> {
+    let expected = number_of_words * T::bytes_len();
+    if bytes.len() != expected {
+        return Err(Error::ParseLengthError(expected, bytes.len()));
+    }
+    try_parse_exact(bytes)
+}
+
+/// Parses `bytes` as little-endian words, of which it must hold a whole number.
+pub fn try_parse_exact<T: Value>(bytes: &[u8]) -> Result<Vec<GF2Word<T>>, Error> {
+    let remainder = bytes.len() % T::bytes_len();
+    if remainder != 0 {
+        let expected = bytes.len() + T::bytes_len() - remainder;
+        return Err(Error::ParseLengthError(expected, bytes.len()));
+    }
+    Ok(bytes
         .chunks(T::bytes_len())
         .map(|chunk| T::from_le_bytes(chunk).into())
-        .collect()
+        .collect())
+}
+
+/// Parses `number_of_words` little-endian words from `bytes`, zero-padding the
+/// last word when `bytes` stops short of the word boundary.
+pub fn parse_padded<T: Value>(
+    bytes: &[u8],
+    number_of_words: usize,
+) -> Result<Vec<GF2Word<T>>, Error> {
+    let expected = number_of_words * T::bytes_len();
+    if bytes.len() > expected || bytes.len() + T::bytes_len() <= expected {
+        return Err(Error::ParseLengthError(expected, bytes.len()));
+    }
+    let mut padded = bytes.to_vec();
+    padded.resize(expected, 0);
+    try_parse_exact(&padded)
+}
+
+/// Packs the input of a circuit into its witness and the public output it is
+/// proven against, so that the prover and the verifier can't be handed a
+/// statement the circuit doesn't compute.
+pub trait Prepare<T: Value>: Circuit<T> {
+    /// The witness of the circuit for `bytes`.
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error>;
+
+    /// The public output of the circuit on the witness prepared from `bytes`.
+    fn expected_public_output(&self, bytes: &[u8]) -> Result<Vec<GF2Word<T>>, Error> {
+        Ok(self.compute(&self.prepare_witness(bytes)?))
+    }
+}
+
+impl<T: Value, A: Prepare<T>, B: Circuit<T>> Prepare<T> for Compose<A, B> {
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        self.first.prepare_witness(bytes)
+    }
+}
+
+impl<T: Value, A: Prepare<T>, B: Circuit<T>> Prepare<T> for Join<A, B> {
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        self.first.prepare_witness(bytes)
+    }
+}
+
+impl<T: Value, C: Prepare<T>> Prepare<T> for Disclose<C> {
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        let witness = self.circuit.prepare_witness(bytes)?;
+        self.mask.disclosed(&witness)?;
+        Ok(witness)
+    }
+}
+
+impl<T: Value, C: Prepare<T>> Prepare<T> for PreparedCircuit<C> {
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        self.circuit().prepare_witness(bytes)
+    }
+}
+
+#[cfg(test)]
+mod prepare_tests {
+    use rand::thread_rng;
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        gadgets::{
+            add_mod::AdderCircuit,
+            hmac_sha256::{hmac_sha256, HmacSha256Circuit, BLOCK_LEN},
+            sha256::{sha256, Sha256Circuit},
+        },
+        prover::Prover,
+        verifier::Verifier,
+    };
+
+    use super::{generic_parse, parse_padded, try_parse_exact, Prepare};
+    use crate::error::Error;
+
+    const SIGMA: usize = 40;
+
+    #[test]
+    fn test_parse() {
+        let bytes = [1, 0, 0, 0, 2, 0, 0, 0, 3];
+
+        let words = generic_parse::<u32>(&bytes[..8], 2).unwrap();
+        assert_eq!((words[0].value, words[1].value), (1, 2));
+        assert!(matches!(
+            generic_parse::<u32>(&bytes, 2),
+            Err(Error::ParseLengthError(8, 9))
+        ));
+
+        assert_eq!(try_parse_exact::<u32>(&bytes[..8]).unwrap().len(), 2);
+        assert!(matches!(
+            try_parse_exact::<u32>(&bytes),
+            Err(Error::ParseLengthError(12, 9))
+        ));
+
+        // padding is only applied to the last word
+        let words = parse_padded::<u32>(&bytes, 3).unwrap();
+        assert_eq!(words[2].value, 3);
+        assert_eq!(parse_padded::<u32>(&bytes[..8], 2).unwrap(), words[..2]);
+        assert!(parse_padded::<u32>(&bytes, 4).is_err());
+        assert!(parse_padded::<u32>(&bytes, 2).is_err());
+    }
+
+    #[test]
+    fn test_prepare() {
+        let adder = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        assert_eq!(adder.prepare_witness(&input).unwrap(), input);
+        assert_eq!(adder.expected_public_output(&input).unwrap()[0].value, 11);
+        assert!(adder.prepare_witness(&input[..7]).is_err());
+
+        let sha = Sha256Circuit::new(3);
+        assert_eq!(sha.expected_public_output(b"abc").unwrap(), sha256(b"abc"));
+        assert!(sha.prepare_witness(b"abcd").is_err());
+
+        // HMAC keys of any length are brought to the block size
+        let hmac = HmacSha256Circuit::new(b"message");
+        for key in [&b"key"[..], &[7u8; 100]] {
+            assert_eq!(hmac.prepare_witness(key).unwrap().len(), BLOCK_LEN);
+            assert_eq!(
+                hmac.expected_public_output(key).unwrap(),
+                hmac_sha256(key, b"message")
+            );
+        }
+    }
+
+    #[test]
+    fn test_prove_prepared() {
+        let mut rng = thread_rng();
+        let circuit = HmacSha256Circuit::new(b"message");
+        let key = b"a short key";
+
+        let witness = circuit.prepare_witness(key).unwrap();
+        let output = circuit.expected_public_output(key).unwrap();
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<_, SIGMA>(
+            &mut rng, &witness, &circuit, &output,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+    }
 }
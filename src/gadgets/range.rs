@@ -0,0 +1,220 @@
+//! Membership of a secret word in a public range `[min, max]`.
+//!
+//! `min <= x <= max` iff neither `x < min` nor `x > max`, so the gadget makes
+//! both unsigned comparisons of `compare` and ANDs their negations. A range of
+//! `k`-bit integers is `[0, 2^k - 1]`. The output bit reveals nothing else
+//! about `x`.
+
+use crate::{
+    circuit::{named_id, Circuit, Output, TwoThreeDecOutput},
+    error::Error,
+    gadgets::{
+        compare::{gt_verify, less_than, lt_num_of_mul_gates, lt_verify, mpc_gt, mpc_lt},
+        mpc_and, mpc_and_verify, mpc_public,
+        prepare::{generic_parse, Prepare},
+        public_verify,
+    },
+    gf2_word::{GF2Word, Value},
+    party::Party,
+};
+
+/// Number of multiplication gates used by `mpc_in_range` over `T`.
+pub fn in_range_num_of_mul_gates<T: Value>() -> usize {
+    2 * lt_num_of_mul_gates::<T>() + 1
+}
+
+/// Returns 1 if `min <= x <= max` and 0 otherwise, comparing as unsigned
+/// integers.
+pub fn in_range<T: Value>(x: T, min: T, max: T) -> T {
+    let outside = less_than(x, min) | less_than(max, x);
+    outside ^ T::from(1)
+}
+
+fn not_bit<T: Value>(x: GF2Word<T>) -> GF2Word<T> {
+    // an odd number of parties flip their shares, which flips the shared bit
+    (x.value ^ T::from(1)).into()
+}
+
+/// Shares of `min <= x <= max`, where the inputs are the shares of `x` and
+/// `(min, max)` is public.
+pub fn mpc_in_range<T: Value>(
+    x: (GF2Word<T>, GF2Word<T>, GF2Word<T>),
+    (min, max): (GF2Word<T>, GF2Word<T>),
+    p1: &mut Party<T>,
+    p2: &mut Party<T>,
+    p3: &mut Party<T>,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    let (min1, min2, min3) = mpc_public(min);
+    let (max1, max2, max3) = mpc_public(max);
+
+    let below = mpc_lt((x.0, min1), (x.1, min2), (x.2, min3), p1, p2, p3);
+    let above = mpc_gt((x.0, max1), (x.1, max2), (x.2, max3), p1, p2, p3);
+
+    mpc_and(
+        (not_bit(below.0), not_bit(above.0)),
+        (not_bit(below.1), not_bit(above.1)),
+        (not_bit(below.2), not_bit(above.2)),
+        p1,
+        p2,
+        p3,
+    )
+}
+
+pub fn in_range_verify<T: Value>(
+    x: (GF2Word<T>, GF2Word<T>),
+    (min, max): (GF2Word<T>, GF2Word<T>),
+    p: &mut Party<T>,
+    p_next: &mut Party<T>,
+) -> Result<(GF2Word<T>, GF2Word<T>), Error> {
+    let (min_p, min_p_next) = public_verify(min);
+    let (max_p, max_p_next) = public_verify(max);
+
+    let below = lt_verify((x.0, min_p), (x.1, min_p_next), p, p_next)?;
+    let above = gt_verify((x.0, max_p), (x.1, max_p_next), p, p_next)?;
+
+    mpc_and_verify(
+        (not_bit(below.0), not_bit(above.0)),
+        (not_bit(below.1), not_bit(above.1)),
+        p,
+        p_next,
+    )
+}
+
+/// Outputs 1 if the secret word is in `[min, max]` and 0 otherwise.
+#[derive(Clone, Debug)]
+pub struct RangeCircuit<T: Value> {
+    min: GF2Word<T>,
+    max: GF2Word<T>,
+}
+
+impl<T: Value> RangeCircuit<T> {
+    pub fn new(min: T, max: T) -> Self {
+        Self {
+            min: min.into(),
+            max: max.into(),
+        }
+    }
+}
+
+impl<T: Value> Circuit<T> for RangeCircuit<T> {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+        let x = generic_parse::<T>(input, self.party_input_len()).unwrap()[0];
+        vec![in_range(x.value, self.min.value, self.max.value).into()]
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<T>,
+        p2: &mut Party<T>,
+        p3: &mut Party<T>,
+    ) -> TwoThreeDecOutput<T> {
+        let x1 = generic_parse(&p1.view.input, self.party_input_len()).unwrap()[0];
+        let x2 = generic_parse(&p2.view.input, self.party_input_len()).unwrap()[0];
+        let x3 = generic_parse(&p3.view.input, self.party_input_len()).unwrap()[0];
+
+        let (o1, o2, o3) = mpc_in_range((x1, x2, x3), (self.min, self.max), p1, p2, p3);
+        (vec![o1], vec![o2], vec![o3])
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<T>,
+        p_next: &mut Party<T>,
+    ) -> Result<(Output<T>, Output<T>), Error> {
+        let x = generic_parse(&p.view.input, self.party_input_len())?[0];
+        let x_next = generic_parse(&p_next.view.input, self.party_input_len())?[0];
+
+        let (o, o_next) = in_range_verify((x, x_next), (self.min, self.max), p, p_next)?;
+        Ok((vec![o], vec![o_next]))
+    }
+
+    fn party_input_len(&self) -> usize {
+        1
+    }
+
+    fn party_output_len(&self) -> usize {
+        1
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        in_range_num_of_mul_gates::<T>()
+    }
+
+    fn public_input(&self) -> Vec<u8> {
+        [self.min.value.to_bytes(), self.max.value.to_bytes()].concat()
+    }
+
+    fn id(&self) -> Vec<u8> {
+        named_id("range", self, &[])
+    }
+}
+
+impl<T: Value> Prepare<T> for RangeCircuit<T> {
+    /// The witness is the word itself, in little-endian bytes.
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        generic_parse::<T>(bytes, self.party_input_len())?;
+        Ok(bytes.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod range_tests {
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::{count_mul_gates, Circuit},
+        gf2_word::Value,
+        prover::Prover,
+        verifier::Verifier,
+    };
+
+    use super::{in_range, RangeCircuit};
+
+    const SIGMA: usize = 40;
+
+    #[test]
+    fn test_plain_in_range() {
+        for x in 0..=u8::MAX {
+            for (min, max) in [(0, 0), (18, 65), (65, 18), (0, u8::MAX), (200, u8::MAX)] {
+                assert_eq!(in_range(x, min, max), u8::from(min <= x && x <= max));
+            }
+        }
+    }
+
+    fn prove_in_range<T: Value>(x: T, min: T, max: T, claimed: u8) -> bool {
+        let circuit = RangeCircuit::new(min, max);
+        let input = x.to_bytes().into_iter().rev().collect::<Vec<u8>>();
+        assert_eq!(
+            count_mul_gates(&circuit, input.len()),
+            circuit.num_of_mul_gates()
+        );
+
+        let output = vec![T::from(claimed).into()];
+        let proof = Prover::<T, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut thread_rng(),
+            &input,
+            &circuit,
+            &output,
+        )
+        .unwrap();
+        Verifier::<T, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output).is_ok()
+    }
+
+    #[test]
+    fn test_range_circuit() {
+        assert!(prove_in_range(42u32, 18, 65, 1));
+        assert!(prove_in_range(18u32, 18, 65, 1));
+        assert!(prove_in_range(65u32, 18, 65, 1));
+        assert!(prove_in_range(17u32, 18, 65, 0));
+        assert!(prove_in_range(u32::MAX, 18, 65, 0));
+        assert!(prove_in_range(1000u64, 0, (1 << 10) - 1, 1));
+        assert!(prove_in_range(7u8, 7, 7, 1));
+
+        // false claims are rejected
+        assert!(!prove_in_range(17u32, 18, 65, 1));
+        assert!(!prove_in_range(66u32, 18, 65, 1));
+        assert!(!prove_in_range(42u32, 18, 65, 0));
+    }
+}
@@ -0,0 +1,606 @@
+//! RIPEMD-160 over 32-bit words, and HASH160 = RIPEMD-160(SHA-256(x)).
+//!
+//! Message words and the digest are little-endian. Each block runs two lines
+//! of 80 steps, each step with one addition per term and, but for `f1`, one
+//! multiplication gate for the boolean function. `Hash160Circuit` proves
+//! knowledge of the preimage of a Bitcoin P2PKH address hash.
+
+use crate::{
+    circuit::{compose, named_id, Circuit, Compose, Output, TwoThreeDecOutput},
+    error::Error,
+    gadgets::{
+        add_mod::{add_mod_verify, add_mod_verify_k, mpc_add_mod, mpc_add_mod_k},
+        assertion::{mpc_or, or_verify},
+        mpc_and, mpc_and_verify,
+        prepare::Prepare,
+        sha256::{sha256, Sha256Circuit},
+    },
+    gf2_word::GF2Word,
+    party::Party,
+};
+
+pub type State = [GF2Word<u32>; 5];
+
+pub const IV: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+const K_LEFT: [u32; 5] = [0x00000000, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xa953fd4e];
+const K_RIGHT: [u32; 5] = [0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x7a6d76e9, 0x00000000];
+
+#[rustfmt::skip]
+const R_LEFT: [usize; 80] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+    7, 4, 13, 1, 10, 6, 15, 3, 12, 0, 9, 5, 2, 14, 11, 8,
+    3, 10, 14, 4, 9, 15, 8, 1, 2, 7, 0, 6, 13, 11, 5, 12,
+    1, 9, 11, 10, 0, 8, 12, 4, 13, 3, 7, 15, 14, 5, 6, 2,
+    4, 0, 5, 9, 7, 12, 2, 10, 14, 1, 3, 8, 11, 6, 15, 13,
+];
+
+#[rustfmt::skip]
+const R_RIGHT: [usize; 80] = [
+    5, 14, 7, 0, 9, 2, 11, 4, 13, 6, 15, 8, 1, 10, 3, 12,
+    6, 11, 3, 7, 0, 13, 5, 10, 14, 15, 8, 12, 4, 9, 1, 2,
+    15, 5, 1, 3, 7, 14, 6, 9, 11, 8, 12, 2, 10, 0, 4, 13,
+    8, 6, 4, 1, 3, 11, 15, 0, 5, 12, 2, 13, 9, 7, 10, 14,
+    12, 15, 10, 4, 1, 5, 8, 7, 6, 2, 13, 14, 0, 3, 9, 11,
+];
+
+#[rustfmt::skip]
+const S_LEFT: [usize; 80] = [
+    11, 14, 15, 12, 5, 8, 7, 9, 11, 13, 14, 15, 6, 7, 9, 8,
+    7, 6, 8, 13, 11, 9, 7, 15, 7, 12, 15, 9, 11, 7, 13, 12,
+    11, 13, 6, 7, 14, 9, 13, 15, 14, 8, 13, 6, 5, 12, 7, 5,
+    11, 12, 14, 15, 14, 15, 9, 8, 9, 14, 5, 6, 8, 6, 5, 12,
+    9, 15, 5, 11, 6, 8, 13, 12, 5, 12, 13, 14, 11, 8, 5, 6,
+];
+
+#[rustfmt::skip]
+const S_RIGHT: [usize; 80] = [
+    8, 9, 9, 11, 13, 15, 15, 5, 7, 7, 8, 11, 14, 14, 12, 6,
+    9, 13, 15, 7, 12, 8, 9, 11, 7, 7, 12, 7, 6, 15, 13, 11,
+    9, 7, 15, 11, 8, 6, 6, 14, 12, 13, 5, 14, 13, 13, 7, 5,
+    15, 5, 8, 11, 14, 14, 6, 14, 6, 9, 12, 9, 12, 5, 15, 8,
+    8, 5, 12, 9, 12, 5, 14, 6, 8, 13, 6, 5, 15, 13, 11, 11,
+];
+
+/// Word index, rotation, boolean function and constant of step `j` of a line.
+fn step_params(left: bool, j: usize) -> (usize, usize, usize, u32) {
+    let round = j / 16;
+    if left {
+        (R_LEFT[j], S_LEFT[j], round, K_LEFT[round])
+    } else {
+        (R_RIGHT[j], S_RIGHT[j], 4 - round, K_RIGHT[round])
+    }
+}
+
+fn negate(x: GF2Word<u32>) -> GF2Word<u32> {
+    // an odd number of parties negate their shares, which negates the shared value
+    (!x.value).into()
+}
+
+fn f(index: usize, x: u32, y: u32, z: u32) -> u32 {
+    match index {
+        0 => x ^ y ^ z,
+        1 => (x & y) | (!x & z),
+        2 => (x | !y) ^ z,
+        3 => (x & z) | (y & !z),
+        _ => x ^ (y | !z),
+    }
+}
+
+pub fn padding(input: &[u8]) -> Vec<GF2Word<u32>> {
+    let mut msg = input.to_vec();
+    let length_u64 = (8 * input.len()) as u64; // msg len in bits
+    msg.push(0x80); // append one 1 bit and seven 0 bits
+
+    while !(msg.len() * 8 + 64).is_multiple_of(512) {
+        msg.push(0x00);
+    }
+    msg.extend_from_slice(&length_u64.to_le_bytes());
+
+    assert!((msg.len() * 8).is_multiple_of(512));
+    msg.chunks(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()).into())
+        .collect()
+}
+
+fn line(left: bool, state: &State, block: &[GF2Word<u32>]) -> [u32; 5] {
+    let mut v = state.map(|word| word.value);
+    for j in 0..80 {
+        let (r, s, index, k) = step_params(left, j);
+        let [a, b, c, d, e] = v;
+        let t = a
+            .wrapping_add(f(index, b, c, d))
+            .wrapping_add(block[r].value)
+            .wrapping_add(k)
+            .rotate_left(s as u32)
+            .wrapping_add(e);
+        v = [e, t, b, c.rotate_left(10), d];
+    }
+    v
+}
+
+/// h[i] := h[i + 1] + left[i + 2] + right[i + 3], indices mod 5
+fn combine(state: &State, left: [u32; 5], right: [u32; 5]) -> State {
+    std::array::from_fn(|i| {
+        state[(i + 1) % 5]
+            .value
+            .wrapping_add(left[(i + 2) % 5])
+            .wrapping_add(right[(i + 3) % 5])
+            .into()
+    })
+}
+
+fn hash_padded(words: &[GF2Word<u32>]) -> State {
+    words
+        .chunks(16)
+        .fold(IV.map(GF2Word::from), |state, block| {
+            combine(
+                &state,
+                line(true, &state, block),
+                line(false, &state, block),
+            )
+        })
+}
+
+/// Computes the RIPEMD-160 digest of `input`, as little-endian words.
+pub fn ripemd160(input: &[u8]) -> Vec<GF2Word<u32>> {
+    hash_padded(&padding(input)).to_vec()
+}
+
+/// Computes RIPEMD-160(SHA-256(`input`)), as little-endian words.
+pub fn hash160(input: &[u8]) -> Vec<GF2Word<u32>> {
+    let digest: Vec<u8> = sha256(input)
+        .iter()
+        .flat_map(|word| word.value.to_be_bytes())
+        .collect();
+    ripemd160(&digest)
+}
+
+#[allow(clippy::type_complexity)]
+fn mpc_f(
+    index: usize,
+    input_p1: (GF2Word<u32>, GF2Word<u32>, GF2Word<u32>),
+    input_p2: (GF2Word<u32>, GF2Word<u32>, GF2Word<u32>),
+    input_p3: (GF2Word<u32>, GF2Word<u32>, GF2Word<u32>),
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) -> (GF2Word<u32>, GF2Word<u32>, GF2Word<u32>) {
+    let ((x1, y1, z1), (x2, y2, z2), (x3, y3, z3)) = (input_p1, input_p2, input_p3);
+    match index {
+        0 => (x1 ^ y1 ^ z1, x2 ^ y2 ^ z2, x3 ^ y3 ^ z3),
+        // (x and (y xor z)) xor z
+        1 => {
+            let (o1, o2, o3) = mpc_and((x1, y1 ^ z1), (x2, y2 ^ z2), (x3, y3 ^ z3), p1, p2, p3);
+            (o1 ^ z1, o2 ^ z2, o3 ^ z3)
+        }
+        2 => {
+            let (o1, o2, o3) = mpc_or(
+                (x1, negate(y1)),
+                (x2, negate(y2)),
+                (x3, negate(y3)),
+                p1,
+                p2,
+                p3,
+            );
+            (o1 ^ z1, o2 ^ z2, o3 ^ z3)
+        }
+        // (z and (x xor y)) xor y
+        3 => {
+            let (o1, o2, o3) = mpc_and((z1, x1 ^ y1), (z2, x2 ^ y2), (z3, x3 ^ y3), p1, p2, p3);
+            (o1 ^ y1, o2 ^ y2, o3 ^ y3)
+        }
+        _ => {
+            let (o1, o2, o3) = mpc_or(
+                (y1, negate(z1)),
+                (y2, negate(z2)),
+                (y3, negate(z3)),
+                p1,
+                p2,
+                p3,
+            );
+            (o1 ^ x1, o2 ^ x2, o3 ^ x3)
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn f_verify(
+    index: usize,
+    input_p: (GF2Word<u32>, GF2Word<u32>, GF2Word<u32>),
+    input_p_next: (GF2Word<u32>, GF2Word<u32>, GF2Word<u32>),
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<(GF2Word<u32>, GF2Word<u32>), Error> {
+    let ((x, y, z), (x_next, y_next, z_next)) = (input_p, input_p_next);
+    Ok(match index {
+        0 => (x ^ y ^ z, x_next ^ y_next ^ z_next),
+        1 => {
+            let (o, o_next) = mpc_and_verify((x, y ^ z), (x_next, y_next ^ z_next), p, p_next)?;
+            (o ^ z, o_next ^ z_next)
+        }
+        2 => {
+            let (o, o_next) = or_verify((x, negate(y)), (x_next, negate(y_next)), p, p_next)?;
+            (o ^ z, o_next ^ z_next)
+        }
+        3 => {
+            let (o, o_next) = mpc_and_verify((z, x ^ y), (z_next, x_next ^ y_next), p, p_next)?;
+            (o ^ y, o_next ^ y_next)
+        }
+        _ => {
+            let (o, o_next) = or_verify((y, negate(z)), (y_next, negate(z_next)), p, p_next)?;
+            (o ^ x, o_next ^ x_next)
+        }
+    })
+}
+
+/// Runs one line of 80 steps on the shares of a block.
+#[allow(clippy::type_complexity)]
+pub fn mpc_line(
+    left: bool,
+    block: (&[GF2Word<u32>], &[GF2Word<u32>], &[GF2Word<u32>]),
+    state: (&State, &State, &State),
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) -> (State, State, State) {
+    let (mut v1, mut v2, mut v3) = (*state.0, *state.1, *state.2);
+
+    for j in 0..80 {
+        let (r, s, index, k) = step_params(left, j);
+
+        // t := rotl(a + f(b, c, d) + x[r] + k, s) + e
+        let t = mpc_f(
+            index,
+            (v1[1], v1[2], v1[3]),
+            (v2[1], v2[2], v2[3]),
+            (v3[1], v3[2], v3[3]),
+            p1,
+            p2,
+            p3,
+        );
+        let t = mpc_add_mod((v1[0], t.0), (v2[0], t.1), (v3[0], t.2), p1, p2, p3);
+        let mut t = mpc_add_mod(
+            (t.0, block.0[r]),
+            (t.1, block.1[r]),
+            (t.2, block.2[r]),
+            p1,
+            p2,
+            p3,
+        );
+        if k != 0 {
+            t = mpc_add_mod_k(t.0, t.1, t.2, k.into(), p1, p2, p3);
+        }
+        let t = mpc_add_mod(
+            (t.0.rotl(s), v1[4]),
+            (t.1.rotl(s), v2[4]),
+            (t.2.rotl(s), v3[4]),
+            p1,
+            p2,
+            p3,
+        );
+
+        v1 = [v1[4], t.0, v1[1], v1[2].rotl(10), v1[3]];
+        v2 = [v2[4], t.1, v2[1], v2[2].rotl(10), v2[3]];
+        v3 = [v3[4], t.2, v3[1], v3[2].rotl(10), v3[3]];
+    }
+
+    (v1, v2, v3)
+}
+
+pub fn line_verify(
+    left: bool,
+    block: (&[GF2Word<u32>], &[GF2Word<u32>]),
+    state: (&State, &State),
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<(State, State), Error> {
+    let (mut v, mut v_next) = (*state.0, *state.1);
+
+    for j in 0..80 {
+        let (r, s, index, k) = step_params(left, j);
+
+        let t = f_verify(
+            index,
+            (v[1], v[2], v[3]),
+            (v_next[1], v_next[2], v_next[3]),
+            p,
+            p_next,
+        )?;
+        let t = add_mod_verify((v[0], t.0), (v_next[0], t.1), p, p_next)?;
+        let mut t = add_mod_verify((t.0, block.0[r]), (t.1, block.1[r]), p, p_next)?;
+        if k != 0 {
+            t = add_mod_verify_k(t.0, t.1, k.into(), p, p_next)?;
+        }
+        let t = add_mod_verify((t.0.rotl(s), v[4]), (t.1.rotl(s), v_next[4]), p, p_next)?;
+
+        v = [v[4], t.0, v[1], v[2].rotl(10), v[3]];
+        v_next = [v_next[4], t.1, v_next[1], v_next[2].rotl(10), v_next[3]];
+    }
+
+    Ok((v, v_next))
+}
+
+pub fn mpc_ripemd160_block(
+    input_p1: &[GF2Word<u32>],
+    input_p2: &[GF2Word<u32>],
+    input_p3: &[GF2Word<u32>],
+    state: (&State, &State, &State),
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) -> (State, State, State) {
+    let block = (input_p1, input_p2, input_p3);
+    let left = mpc_line(true, block, state, p1, p2, p3);
+    let right = mpc_line(false, block, state, p1, p2, p3);
+
+    let (mut s1, mut s2, mut s3) = (*state.0, *state.1, *state.2);
+    for i in 0..5 {
+        let (j, l, r) = ((i + 1) % 5, (i + 2) % 5, (i + 3) % 5);
+        let t = mpc_add_mod(
+            (state.0[j], left.0[l]),
+            (state.1[j], left.1[l]),
+            (state.2[j], left.2[l]),
+            p1,
+            p2,
+            p3,
+        );
+        (s1[i], s2[i], s3[i]) = mpc_add_mod(
+            (t.0, right.0[r]),
+            (t.1, right.1[r]),
+            (t.2, right.2[r]),
+            p1,
+            p2,
+            p3,
+        );
+    }
+    (s1, s2, s3)
+}
+
+pub fn mpc_ripemd160_block_verify(
+    input_p: &[GF2Word<u32>],
+    input_p_next: &[GF2Word<u32>],
+    state: (&State, &State),
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<(State, State), Error> {
+    let block = (input_p, input_p_next);
+    let left = line_verify(true, block, state, p, p_next)?;
+    let right = line_verify(false, block, state, p, p_next)?;
+
+    let (mut s, mut s_next) = (*state.0, *state.1);
+    for i in 0..5 {
+        let (j, l, r) = ((i + 1) % 5, (i + 2) % 5, (i + 3) % 5);
+        let t = add_mod_verify((state.0[j], left.0[l]), (state.1[j], left.1[l]), p, p_next)?;
+        (s[i], s_next[i]) = add_mod_verify((t.0, right.0[r]), (t.1, right.1[r]), p, p_next)?;
+    }
+    Ok((s, s_next))
+}
+
+/// Hashes the shares of an already padded input.
+pub fn mpc_ripemd160_padded(
+    p1_words: &[GF2Word<u32>],
+    p2_words: &[GF2Word<u32>],
+    p3_words: &[GF2Word<u32>],
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) -> TwoThreeDecOutput<u32> {
+    let iv = IV.map(GF2Word::from);
+    let (mut s1, mut s2, mut s3) = (iv, iv, iv);
+    let iter_chunks = p1_words
+        .chunks(16)
+        .zip(p2_words.chunks(16))
+        .zip(p3_words.chunks(16));
+    for ((block_1, block_2), block_3) in iter_chunks {
+        (s1, s2, s3) = mpc_ripemd160_block(block_1, block_2, block_3, (&s1, &s2, &s3), p1, p2, p3);
+    }
+    (s1.to_vec(), s2.to_vec(), s3.to_vec())
+}
+
+pub fn mpc_ripemd160_padded_verify(
+    p_words: &[GF2Word<u32>],
+    p_next_words: &[GF2Word<u32>],
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<(Output<u32>, Output<u32>), Error> {
+    let iv = IV.map(GF2Word::from);
+    let (mut s, mut s_next) = (iv, iv);
+    for (block, block_next) in p_words.chunks(16).zip(p_next_words.chunks(16)) {
+        (s, s_next) = mpc_ripemd160_block_verify(block, block_next, (&s, &s_next), p, p_next)?;
+    }
+    Ok((s.to_vec(), s_next.to_vec()))
+}
+
+/// Number of multiplication gates used to hash an input of `input_len` bytes.
+pub fn ripemd160_num_of_mul_gates(input_len: usize) -> usize {
+    // padding appends at least 9 bytes
+    let num_blocks = (input_len + 9).div_ceil(64);
+
+    // 64 steps of each line use a boolean function with a gate and a constant
+    let line = 64 + 3 * 80 + 64;
+    let digest = 2 * 5;
+
+    (2 * line + digest) * num_blocks
+}
+
+/// The padded message with the input set to zero, to which the shares of the
+/// input are XORed.
+fn padding_template(input_len: usize) -> Vec<GF2Word<u32>> {
+    padding(&vec![0; input_len])
+}
+
+fn apply_template(template: &[GF2Word<u32>], input: &[u8]) -> Vec<GF2Word<u32>> {
+    let mut words = template.to_vec();
+    for (word, chunk) in words.iter_mut().zip(input.chunks(4)) {
+        let mut le_bytes = [0u8; 4];
+        le_bytes[..chunk.len()].copy_from_slice(chunk);
+        *word = *word ^ u32::from_le_bytes(le_bytes).into();
+    }
+    words
+}
+
+/// Proves knowledge of a preimage of `input_len` bytes for a RIPEMD-160 digest.
+#[derive(Clone, Debug)]
+pub struct Ripemd160Circuit {
+    pub input_len: usize,
+    template: Vec<GF2Word<u32>>,
+}
+
+impl Ripemd160Circuit {
+    pub fn new(input_len: usize) -> Self {
+        Self {
+            input_len,
+            template: padding_template(input_len),
+        }
+    }
+}
+
+impl Circuit<u32> for Ripemd160Circuit {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+        assert_eq!(input.len(), self.input_len);
+        ripemd160(input)
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<u32>,
+        p2: &mut Party<u32>,
+        p3: &mut Party<u32>,
+    ) -> TwoThreeDecOutput<u32> {
+        let (words_p1, words_p2, words_p3) = (
+            apply_template(&self.template, &p1.view.input),
+            apply_template(&self.template, &p2.view.input),
+            apply_template(&self.template, &p3.view.input),
+        );
+        mpc_ripemd160_padded(&words_p1, &words_p2, &words_p3, p1, p2, p3)
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<u32>,
+        p_next: &mut Party<u32>,
+    ) -> Result<(Output<u32>, Output<u32>), Error> {
+        if p.view.input.len() != self.input_len || p_next.view.input.len() != self.input_len {
+            return Err(Error::VerificationError);
+        }
+        let (words_p, words_p_next) = (
+            apply_template(&self.template, &p.view.input),
+            apply_template(&self.template, &p_next.view.input),
+        );
+        mpc_ripemd160_padded_verify(&words_p, &words_p_next, p, p_next)
+    }
+
+    fn party_input_len(&self) -> usize {
+        self.input_len
+    }
+
+    fn party_output_len(&self) -> usize {
+        5
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        ripemd160_num_of_mul_gates(self.input_len)
+    }
+
+    fn id(&self) -> Vec<u8> {
+        named_id("ripemd160", self, &[])
+    }
+}
+
+impl Prepare<u32> for Ripemd160Circuit {
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        if bytes.len() != self.input_len {
+            return Err(Error::ParseLengthError(self.input_len, bytes.len()));
+        }
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Proves knowledge of a preimage of `input_len` bytes, e.g. a 33 byte
+/// compressed public key, for a HASH160 digest.
+pub type Hash160Circuit = Compose<Sha256Circuit, Ripemd160Circuit>;
+
+impl Hash160Circuit {
+    pub fn new(input_len: usize) -> Self {
+        compose(Sha256Circuit::new(input_len), Ripemd160Circuit::new(32))
+    }
+}
+
+#[cfg(test)]
+mod test_ripemd160 {
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use ripemd::{Digest, Ripemd160};
+    use sha2::Sha256;
+    use sha3::Keccak256;
+
+    use crate::{circuit::Circuit, prover::Prover, verifier::Verifier};
+
+    use super::*;
+
+    fn to_bytes(digest: &[GF2Word<u32>]) -> Vec<u8> {
+        digest
+            .iter()
+            .flat_map(|word| word.value.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_plain_ripemd160() {
+        for len in [0, 3, 55, 56, 63, 64, 65, 119, 200] {
+            let input: Vec<u8> = (0..len).map(|i| (i * 7 + 1) as u8).collect();
+            assert_eq!(
+                to_bytes(&ripemd160(&input)),
+                Ripemd160::digest(&input).to_vec()
+            );
+            assert_eq!(
+                to_bytes(&hash160(&input)),
+                Ripemd160::digest(Sha256::digest(&input)).to_vec()
+            );
+        }
+    }
+
+    #[test]
+    fn test_num_of_mul_gates() {
+        let per_block = ripemd160_num_of_mul_gates(0);
+        assert_eq!(per_block, 746);
+        assert_eq!(ripemd160_num_of_mul_gates(55), per_block);
+        assert_eq!(ripemd160_num_of_mul_gates(56), 2 * per_block);
+    }
+
+    fn prove_and_verify(circuit: &impl Circuit<u32>, preimage: &[u8]) {
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+        let output = circuit.compute(preimage);
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, preimage, circuit, &output,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, circuit, &output).unwrap();
+
+        let mut wrong_output = output.clone();
+        wrong_output[4] = wrong_output[4] ^ 1u32.into();
+        assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+            &proof,
+            circuit,
+            &wrong_output
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_ripemd160_circuit() {
+        prove_and_verify(&Ripemd160Circuit::new(3), b"abc");
+        prove_and_verify(&Ripemd160Circuit::new(70), &[0x11; 70]);
+    }
+
+    #[test]
+    fn test_hash160_circuit() {
+        // a compressed public key
+        let mut public_key = [0x5a; 33];
+        public_key[0] = 0x02;
+        prove_and_verify(&Hash160Circuit::new(33), &public_key);
+    }
+}
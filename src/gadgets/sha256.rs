@@ -4,18 +4,25 @@ mod iv;
 mod msg_schedule;
 mod padding;
 
+#[cfg(test)]
 mod test_vectors;
 
-use crate::{error::Error, gf2_word::GF2Word, party::Party};
+use crate::{
+    circuit::{compose, named_id, Circuit, Compose, Output, TwoThreeDecOutput},
+    error::Error,
+    gadgets::{add_mod::adder, prepare::Prepare},
+    gf2_word::GF2Word,
+    party::Party,
+};
 use std::ops::Deref;
 
 use self::{
-    compression::{mpc_compression, mpc_compression_verify},
+    compression::{compression, mpc_compression, mpc_compression_verify},
     final_digest::{mpc_update_state, mpc_update_state_verify},
-    msg_schedule::{mpc_msg_schedule, mpc_msg_schedule_verify},
+    msg_schedule::{mpc_msg_schedule, mpc_msg_schedule_verify, msg_schedule},
 };
 pub use iv::init_iv;
-pub use padding::padding;
+pub use padding::{padding, PaddingTemplate};
 
 /// TODO: Doc
 #[derive(Debug, Clone, Copy)]
@@ -216,7 +223,7 @@ pub fn mpc_sha256_block_verify(
     p_next: &mut Party<u32>,
 ) -> Result<(State, State), Error> {
     let (msg_schedule_p, msg_schedule_p_next) =
-        mpc_msg_schedule_verify(input_p, input_p_next, p, p_next);
+        mpc_msg_schedule_verify(input_p, input_p_next, p, p_next)?;
 
     // Initialise working variables to current state
     let working_variables = (state.0.to_vec().into(), state.1.to_vec().into());
@@ -229,144 +236,272 @@ pub fn mpc_sha256_block_verify(
         p_next,
     )?;
 
-    Ok(mpc_update_state_verify(
+    mpc_update_state_verify(
         &compression_output_p.try_into().unwrap(),
         &compression_output_p_next.try_into().unwrap(),
         state,
         p,
         p_next,
-    ))
+    )
 }
 
-#[cfg(test)]
-mod test_sha256 {
+/// Number of multiplication gates used to hash an input of `input_len` bytes.
+pub fn sha256_num_of_mul_gates(input_len: usize) -> usize {
+    // padding appends at least 9 bytes
+    let num_blocks = (input_len + 9).div_ceil(64);
 
-    use rand::{rngs::ThreadRng, thread_rng};
-    use rand_chacha::ChaCha20Rng;
-    use sha2::{Digest, Sha256};
-    use sha3::Keccak256;
+    let msg_schedule = 3 * 48;
+    let compression = 9 * 64;
+    let digest = 8;
 
-    use crate::{
-        circuit::{Circuit, Output},
-        error::Error,
-        gadgets::{sha256::padding::padding, verifier},
-        gf2_word::GF2Word,
-        num_of_repetitions_given_desired_security,
-        party::Party,
-        prover::{InteractiveProver, Prover},
-        verifier::{InteractiveVerifier, Verifier},
-    };
+    (msg_schedule + compression + digest) * num_blocks
+}
+
+/// Computes the SHA-256 digest of `input`, as big-endian words.
+pub fn sha256(input: &[u8]) -> Vec<GF2Word<u32>> {
+    let mut state = init_iv().to_vec();
 
-    use super::{iv::init_iv, *};
+    for block in padding(input).chunks(16) {
+        let w = msg_schedule(block.try_into().unwrap());
+        let compression_output = compression(&w, &state.clone().into());
 
-    pub struct Sha256Circuit {
-        preimage: String,
+        state = state
+            .iter()
+            .zip(compression_output)
+            .map(|(h, o)| adder(h.value, o.value).into())
+            .collect();
     }
 
-    impl Circuit<u32> for Sha256Circuit {
-        fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
-            assert_eq!(input.len(), 0);
-            // create a Sha256 object
-            let mut hasher = Sha256::new();
+    state
+}
 
-            // write input message
-            hasher.update(self.preimage.as_bytes());
+/// The bytes of a digest given as big-endian words, e.g. to hash it again.
+pub fn digest_to_bytes(digest: &[GF2Word<u32>]) -> Vec<u8> {
+    digest
+        .iter()
+        .flat_map(|word| word.value.to_be_bytes())
+        .collect()
+}
 
-            // read hash digest and consume hasher
-            let digest = hasher.finalize().to_vec();
+/// Hashes the shares of an input of arbitrary length.
+///
+/// Padding is applied to each share, which is sound since every party appends
+/// the same public bytes.
+pub fn mpc_sha256(
+    input_p1: &[u8],
+    input_p2: &[u8],
+    input_p3: &[u8],
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) -> TwoThreeDecOutput<u32> {
+    mpc_sha256_padded(
+        &padding(input_p1),
+        &padding(input_p2),
+        &padding(input_p3),
+        p1,
+        p2,
+        p3,
+    )
+}
+
+/// Hashes the shares of an already padded input, e.g. from a `PaddingTemplate`.
+pub fn mpc_sha256_padded(
+    p1_words: &[GF2Word<u32>],
+    p2_words: &[GF2Word<u32>],
+    p3_words: &[GF2Word<u32>],
+    p1: &mut Party<u32>,
+    p2: &mut Party<u32>,
+    p3: &mut Party<u32>,
+) -> TwoThreeDecOutput<u32> {
+    // Initialize state
+    let mut p1_state: State = init_iv().to_vec().into();
+    let mut p2_state: State = init_iv().to_vec().into();
+    let mut p3_state: State = init_iv().to_vec().into();
+
+    // Process padded input chunk by chunk
+    let iter_chunks = p1_words
+        .chunks(16)
+        .zip(p2_words.chunks(16))
+        .zip(p3_words.chunks(16));
+
+    for ((p1_words, p2_words), p3_words) in iter_chunks {
+        (p1_state, p2_state, p3_state) = mpc_sha256_block(
+            &p1_words.try_into().unwrap(),
+            &p2_words.try_into().unwrap(),
+            &p3_words.try_into().unwrap(),
+            &(p1_state, p2_state, p3_state),
+            p1,
+            p2,
+            p3,
+        );
+    }
 
-            let mut res = Vec::with_capacity(8);
-            for i in 0..8 {
-                let word = u32::from_be_bytes(digest[(4 * i)..(4 * i + 4)].try_into().unwrap());
-                res.push(word.into())
-            }
+    (p1_state.to_vec(), p2_state.to_vec(), p3_state.to_vec())
+}
 
-            res
-        }
+pub fn mpc_sha256_verify(
+    input_p: &[u8],
+    input_p_next: &[u8],
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<(Output<u32>, Output<u32>), Error> {
+    mpc_sha256_padded_verify(&padding(input_p), &padding(input_p_next), p, p_next)
+}
 
-        fn compute_23_decomposition(
-            &self,
-            p1: &mut Party<u32>,
-            p2: &mut Party<u32>,
-            p3: &mut Party<u32>,
-        ) -> (Vec<GF2Word<u32>>, Vec<GF2Word<u32>>, Vec<GF2Word<u32>>) {
-            let p1_words = padding(&p1.view.input);
-            let p2_words = padding(&p2.view.input);
-            let p3_words = padding(&p3.view.input);
-
-            // Initialize state
-            let mut p1_state: State = init_iv().to_vec().into();
-            let mut p2_state: State = init_iv().to_vec().into();
-            let mut p3_state: State = init_iv().to_vec().into();
-
-            // Process padded input chunk by chunk
-            let iter_chunks = p1_words
-                .chunks(16)
-                .zip(p2_words.chunks(16))
-                .zip(p3_words.chunks(16));
-
-            for ((p1_words, p2_words), p3_words) in iter_chunks {
-                (p1_state, p2_state, p3_state) = mpc_sha256_block(
-                    &p1_words.try_into().unwrap(),
-                    &p2_words.try_into().unwrap(),
-                    &p3_words.try_into().unwrap(),
-                    &(p1_state, p2_state, p3_state),
-                    p1,
-                    p2,
-                    p3,
-                );
-            }
-
-            (p1_state.to_vec(), p2_state.to_vec(), p3_state.to_vec())
-        }
+pub fn mpc_sha256_padded_verify(
+    p_words: &[GF2Word<u32>],
+    p_next_words: &[GF2Word<u32>],
+    p: &mut Party<u32>,
+    p_next: &mut Party<u32>,
+) -> Result<(Output<u32>, Output<u32>), Error> {
+    // Initialize state
+    let mut p_state: State = init_iv().to_vec().into();
+    let mut p_next_state: State = init_iv().to_vec().into();
+
+    // Process padded input chunk by chunk
+    let iter_chunks = p_words.chunks(16).zip(p_next_words.chunks(16));
+
+    for (p_words, p_next_words) in iter_chunks {
+        (p_state, p_next_state) = mpc_sha256_block_verify(
+            &p_words.try_into().unwrap(),
+            &p_next_words.try_into().unwrap(),
+            &(p_state, p_next_state),
+            p,
+            p_next,
+        )?;
+    }
 
-        fn simulate_two_parties(
-            &self,
-            p: &mut Party<u32>,
-            p_next: &mut Party<u32>,
-        ) -> Result<(Output<u32>, Output<u32>), Error> {
-            let p_words = padding(&p.view.input);
-            let p_next_words = padding(&p_next.view.input);
-
-            // Initialize state
-            let mut p_state: State = init_iv().to_vec().into();
-            let mut p_next_state: State = init_iv().to_vec().into();
-
-            // Process padded input chunk by chunk
-            let iter_chunks = p_words.chunks(16).zip(p_next_words.chunks(16));
-
-            for (p_words, p_next_words) in iter_chunks {
-                (p_state, p_next_state) = mpc_sha256_block_verify(
-                    &p_words.try_into().unwrap(),
-                    &p_next_words.try_into().unwrap(),
-                    &(p_state.to_vec().into(), p_next_state.to_vec().into()),
-                    p,
-                    p_next,
-                )?;
-            }
-
-            Ok((p_state.to_vec(), p_next_state.to_vec()))
+    Ok((p_state.to_vec(), p_next_state.to_vec()))
+}
+
+/// Proves knowledge of a preimage of `input_len` bytes for a SHA-256 digest.
+#[derive(Clone, Debug)]
+pub struct Sha256Circuit {
+    pub input_len: usize,
+    template: PaddingTemplate,
+}
+
+impl Sha256Circuit {
+    pub fn new(input_len: usize) -> Self {
+        Self {
+            input_len,
+            template: PaddingTemplate::new(input_len, &[]),
         }
+    }
+}
+
+impl Circuit<u32> for Sha256Circuit {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+        assert_eq!(input.len(), self.input_len);
+        sha256(input)
+    }
 
-        fn party_input_len(&self) -> usize {
-            16
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<u32>,
+        p2: &mut Party<u32>,
+        p3: &mut Party<u32>,
+    ) -> TwoThreeDecOutput<u32> {
+        let (words_p1, words_p2, words_p3) = (
+            self.template.apply(&p1.view.input),
+            self.template.apply(&p2.view.input),
+            self.template.apply(&p3.view.input),
+        );
+        mpc_sha256_padded(&words_p1, &words_p2, &words_p3, p1, p2, p3)
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<u32>,
+        p_next: &mut Party<u32>,
+    ) -> Result<(Output<u32>, Output<u32>), Error> {
+        if p.view.input.len() != self.input_len || p_next.view.input.len() != self.input_len {
+            return Err(Error::VerificationError);
         }
+        let (words_p, words_p_next) = (
+            self.template.apply(&p.view.input),
+            self.template.apply(&p_next.view.input),
+        );
+        mpc_sha256_padded_verify(&words_p, &words_p_next, p, p_next)
+    }
+
+    fn party_input_len(&self) -> usize {
+        self.input_len
+    }
 
-        fn party_output_len(&self) -> usize {
-            8
+    fn party_output_len(&self) -> usize {
+        8
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        sha256_num_of_mul_gates(self.input_len)
+    }
+
+    fn id(&self) -> Vec<u8> {
+        named_id("sha256", self, &[])
+    }
+}
+
+impl Prepare<u32> for Sha256Circuit {
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        if bytes.len() != self.input_len {
+            return Err(Error::ParseLengthError(self.input_len, bytes.len()));
         }
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Proves knowledge of a preimage of `input_len` bytes for a double SHA-256
+/// digest, SHA-256(SHA-256(x)).
+pub type Sha256dCircuit = Compose<Sha256Circuit, Sha256Circuit>;
+
+impl Sha256dCircuit {
+    pub fn new(input_len: usize) -> Self {
+        compose(Sha256Circuit::new(input_len), Sha256Circuit::new(32))
+    }
+}
+
+#[cfg(test)]
+mod test_sha256 {
 
-        fn num_of_mul_gates(&self) -> usize {
-            let num_chunks = padding(self.preimage.as_bytes()).len();
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha2::{Digest, Sha256};
+    use sha3::Keccak256;
 
-            let msg_schedule = 3 * 48;
-            let compression = 9 * 64;
-            let digest = 8;
+    use crate::{
+        circuit::Circuit,
+        data_structures::{FirstMessageA, SessionState},
+        num_of_repetitions_given_desired_security,
+        prover::{InteractiveProver, Prover},
+        verifier::{InteractiveVerifier, Verifier},
+    };
 
-            (msg_schedule + compression + digest) * num_chunks
+    use super::*;
+
+    #[test]
+    fn test_plain_sha256() {
+        for len in [0, 3, 55, 56, 63, 64, 65, 119, 200] {
+            let input: Vec<u8> = (0..len).map(|i| (i * 7 + 1) as u8).collect();
+            let expected = Sha256::digest(&input);
+            let digest: Vec<u8> = sha256(&input)
+                .iter()
+                .flat_map(|word| word.value.to_be_bytes())
+                .collect();
+            assert_eq!(digest, expected.to_vec());
         }
     }
 
+    #[test]
+    fn test_num_of_mul_gates() {
+        let per_block = sha256_num_of_mul_gates(0);
+        assert_eq!(sha256_num_of_mul_gates(55), per_block);
+        assert_eq!(sha256_num_of_mul_gates(56), 2 * per_block);
+        assert_eq!(sha256_num_of_mul_gates(119), 2 * per_block);
+        assert_eq!(sha256_num_of_mul_gates(120), 3 * per_block);
+    }
+
     #[test]
     fn test_short_input() {
         let mut rng = thread_rng();
@@ -374,11 +509,9 @@ mod test_sha256 {
 
         let preimage = String::from("abc");
 
-        let circuit = Sha256Circuit {
-            preimage: preimage.clone(),
-        };
+        let circuit = Sha256Circuit::new(preimage.len());
 
-        let output = circuit.compute(&[]);
+        let output = circuit.compute(preimage.as_bytes());
         let expected_output = crate::gadgets::sha256::test_vectors::short::DIGEST_OUTPUT;
         for (&word, &expected_word) in output.iter().zip(expected_output.iter()) {
             assert_eq!(word.value, expected_word);
@@ -392,7 +525,43 @@ mod test_sha256 {
         )
         .unwrap();
 
-        Verifier::<u32, ChaCha20Rng, Keccak256>::verify(&proof, &circuit, &output).unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sha256d() {
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+
+        let preimage = b"hello";
+        let circuit = Sha256dCircuit::new(preimage.len());
+        let output = circuit.compute(preimage);
+        let digest: Vec<u8> = output
+            .iter()
+            .flat_map(|word| word.value.to_be_bytes())
+            .collect();
+        assert_eq!(digest, Sha256::digest(Sha256::digest(preimage)).to_vec());
+        assert_eq!(circuit.num_of_mul_gates(), 2 * sha256_num_of_mul_gates(32));
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut rng, preimage, &circuit, &output,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+
+        // the views hold the preimage shares, not the intermediate digest
+        assert!(proof.views.iter().all(|view| view.input.len() == 5));
+        assert!(proof.party_inputs.iter().all(|input| input.len() == 5));
+
+        // a single SHA-256 of the preimage is another statement
+        assert!(Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+            &proof,
+            &circuit,
+            &sha256(preimage)
+        )
+        .is_err());
     }
 
     #[test]
@@ -402,11 +571,9 @@ mod test_sha256 {
 
         let preimage = String::from("abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu");
 
-        let circuit = Sha256Circuit {
-            preimage: preimage.clone(),
-        };
+        let circuit = Sha256Circuit::new(preimage.len());
 
-        let output = circuit.compute(&[]);
+        let output = circuit.compute(preimage.as_bytes());
         let expected_output = crate::gadgets::sha256::test_vectors::long::DIGEST_OUTPUT;
         for (&word, &expected_word) in output.iter().zip(expected_output.iter()) {
             assert_eq!(word.value, expected_word);
@@ -420,7 +587,8 @@ mod test_sha256 {
         )
         .unwrap();
 
-        Verifier::<u32, ChaCha20Rng, Keccak256>::verify(&proof, &circuit, &output).unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
     }
 
     #[test]
@@ -430,11 +598,9 @@ mod test_sha256 {
 
         let preimage = String::from("abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu");
 
-        let circuit = Sha256Circuit {
-            preimage: preimage.clone(),
-        };
+        let circuit = Sha256Circuit::new(preimage.len());
 
-        let output = circuit.compute(&[]);
+        let output = circuit.compute(preimage.as_bytes());
         let expected_output = crate::gadgets::sha256::test_vectors::long::DIGEST_OUTPUT;
         for (&word, &expected_word) in output.iter().zip(expected_output.iter()) {
             assert_eq!(word.value, expected_word);
@@ -447,14 +613,204 @@ mod test_sha256 {
             .round1::<ThreadRng, SIGMA>(&mut rng, preimage.as_bytes(), &circuit, &output)
             .unwrap();
 
-        let sm = verifier.round2(
-            &mut rng,
-            num_of_repetitions_given_desired_security(SIGMA),
-            fm,
-        );
+        let sm = verifier.round2::<ThreadRng, SIGMA>(&mut rng, fm).unwrap();
 
         let proof = prover.round3::<SIGMA>(sm).unwrap();
 
-        verifier.verify(&proof, &circuit, &output).unwrap();
+        verifier.verify::<SIGMA>(&proof, &circuit, &output).unwrap();
+    }
+    #[test]
+    fn test_interactive_tampered_commitment() {
+        const SIGMA: usize = 40;
+
+        let preimage = b"abc";
+        let circuit = Sha256Circuit::new(preimage.len());
+        let output = circuit.compute(preimage);
+
+        let tampered = |mutate: &dyn Fn(&mut FirstMessageA<u32, Keccak256>)| -> Result<(), Error> {
+            let mut rng = thread_rng();
+            let mut prover = InteractiveProver::<u32, ChaCha20Rng, Keccak256>::new();
+            let mut verifier = InteractiveVerifier::<u32, ChaCha20Rng, Keccak256>::new();
+
+            let mut fm = prover
+                .round1::<ThreadRng, SIGMA>(&mut rng, preimage, &circuit, &output)
+                .unwrap();
+            mutate(&mut fm);
+
+            let sm = verifier.round2::<ThreadRng, SIGMA>(&mut rng, fm)?;
+            let proof = prover.round3::<SIGMA>(sm).unwrap();
+            verifier.verify::<SIGMA>(&proof, &circuit, &output)
+        };
+
+        // a flipped bit in one commitment
+        assert!(matches!(
+            tampered(&|fm| fm.all_commitments[5].data[0] ^= 1),
+            Err(Error::VerificationError)
+        ));
+        // commitments missing from the first message
+        assert!(matches!(
+            tampered(&|fm| fm.all_commitments.truncate(3)),
+            Err(Error::VerificationError)
+        ));
+    }
+
+    #[test]
+    fn test_interactive_out_of_order() {
+        const SIGMA: usize = 40;
+        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        let mut rng = thread_rng();
+
+        let preimage = b"abc";
+        let circuit = Sha256Circuit::new(preimage.len());
+        let output = circuit.compute(preimage);
+
+        let mut prover = InteractiveProver::<u32, ChaCha20Rng, Keccak256>::new();
+        let mut verifier = InteractiveVerifier::<u32, ChaCha20Rng, Keccak256>::new();
+
+        // nothing to open or check before the first message
+        assert!(matches!(
+            prover.round3::<SIGMA>(vec![0; num_of_repetitions]),
+            Err(Error::ProtocolStateError)
+        ));
+
+        let fm = prover
+            .round1::<ThreadRng, SIGMA>(&mut rng, preimage, &circuit, &output)
+            .unwrap();
+        assert_eq!(prover.state(), SessionState::Committed);
+        assert!(matches!(
+            prover.round1::<ThreadRng, SIGMA>(&mut rng, preimage, &circuit, &output),
+            Err(Error::ProtocolStateError)
+        ));
+
+        let sm = verifier
+            .round2::<ThreadRng, SIGMA>(&mut rng, fm.clone())
+            .unwrap();
+        assert!(matches!(
+            verifier.round2::<ThreadRng, SIGMA>(&mut rng, fm),
+            Err(Error::ProtocolStateError)
+        ));
+
+        // the challenge must be for the security level of the first message
+        assert!(matches!(
+            prover.round3::<80>(sm.clone()),
+            Err(Error::ProtocolStateError)
+        ));
+        let proof = prover.round3::<SIGMA>(sm.clone()).unwrap();
+        assert_eq!(prover.state(), SessionState::Finished);
+        assert!(prover.all_commitments.is_empty());
+
+        // a second challenge is not answered
+        assert!(matches!(
+            prover.round3::<SIGMA>(sm),
+            Err(Error::ProtocolStateError)
+        ));
+
+        verifier.verify::<SIGMA>(&proof, &circuit, &output).unwrap();
+        assert!(matches!(
+            verifier.verify::<SIGMA>(&proof, &circuit, &output),
+            Err(Error::ProtocolStateError)
+        ));
+
+        let mut verifier = InteractiveVerifier::<u32, ChaCha20Rng, Keccak256>::new();
+        assert!(matches!(
+            verifier.verify::<SIGMA>(&proof, &circuit, &output),
+            Err(Error::ProtocolStateError)
+        ));
+
+        // a reset prover runs a new session
+        prover.reset();
+        assert_eq!(prover.state(), SessionState::Start);
+        let fm = prover
+            .round1::<ThreadRng, SIGMA>(&mut rng, preimage, &circuit, &output)
+            .unwrap();
+        let sm = verifier.round2::<ThreadRng, SIGMA>(&mut rng, fm).unwrap();
+        let proof = prover.round3::<SIGMA>(sm).unwrap();
+        verifier.verify::<SIGMA>(&proof, &circuit, &output).unwrap();
+    }
+
+    #[test]
+    fn test_interactive_security_param() {
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+
+        let preimage = b"abc";
+        let circuit = Sha256Circuit::new(preimage.len());
+        let output = circuit.compute(preimage);
+
+        // a first message for another security level gets no challenge
+        let fm = InteractiveProver::<u32, ChaCha20Rng, Keccak256>::new()
+            .round1::<ThreadRng, SIGMA>(&mut rng, preimage, &circuit, &output)
+            .unwrap();
+        let mut verifier = InteractiveVerifier::<u32, ChaCha20Rng, Keccak256>::new();
+        assert!(matches!(
+            verifier.round2::<ThreadRng, 80>(&mut rng, fm),
+            Err(Error::VerificationError)
+        ));
+        assert_eq!(verifier.state(), SessionState::Finished);
+
+        // the proof is checked at the security level of the challenge
+        let mut prover = InteractiveProver::<u32, ChaCha20Rng, Keccak256>::new();
+        let mut verifier = InteractiveVerifier::<u32, ChaCha20Rng, Keccak256>::new();
+        let fm = prover
+            .round1::<ThreadRng, SIGMA>(&mut rng, preimage, &circuit, &output)
+            .unwrap();
+        let sm = verifier.round2::<ThreadRng, SIGMA>(&mut rng, fm).unwrap();
+        assert_eq!(sm.len(), num_of_repetitions_given_desired_security(SIGMA));
+        let proof = prover.round3::<SIGMA>(sm).unwrap();
+        assert!(matches!(
+            verifier.verify::<80>(&proof, &circuit, &output),
+            Err(Error::ProtocolStateError)
+        ));
+    }
+
+    #[test]
+    fn test_interactive_challenge_binding() {
+        use crate::{encoding::encode_interactive_seed, fs::SigmaFS};
+
+        const SIGMA: usize = 40;
+        type IV = InteractiveVerifier<u32, ChaCha20Rng, Keccak256>;
+        let mut rng = thread_rng();
+
+        let preimage = b"abc";
+        let circuit = Sha256Circuit::new(preimage.len());
+        let output = circuit.compute(preimage);
+
+        let mut prover = InteractiveProver::<u32, ChaCha20Rng, Keccak256>::new();
+        let mut verifier = IV::new();
+        let fm = prover
+            .round1::<ThreadRng, SIGMA>(&mut rng, preimage, &circuit, &output)
+            .unwrap();
+        let sm = verifier
+            .round2::<ThreadRng, SIGMA>(&mut rng, fm.clone())
+            .unwrap();
+
+        // the recorded session gives back the challenge
+        let transcript = || SigmaFS::<Keccak256>::initialize(&encode_interactive_seed());
+        let randomness = verifier.randomness().to_vec();
+        assert_eq!(
+            IV::derive_challenge::<SIGMA>(&randomness, &fm, &mut transcript()).unwrap(),
+            sm
+        );
+
+        // and is bound to the first message and the randomness
+        let mut other = fm.clone();
+        other.all_commitments[0].data[0] ^= 1;
+        assert_ne!(
+            IV::derive_challenge::<SIGMA>(&randomness, &other, &mut transcript()).unwrap(),
+            sm
+        );
+        let mut other = fm.clone();
+        other.outputs[0][0] = other.outputs[0][0] ^ 1u32.into();
+        assert_ne!(
+            IV::derive_challenge::<SIGMA>(&randomness, &other, &mut transcript()).unwrap(),
+            sm
+        );
+        assert_ne!(
+            IV::derive_challenge::<SIGMA>(&[0u8; 32], &fm, &mut transcript()).unwrap(),
+            sm
+        );
+
+        let proof = prover.round3::<SIGMA>(sm).unwrap();
+        verifier.verify::<SIGMA>(&proof, &circuit, &output).unwrap();
     }
 }
@@ -5,38 +5,38 @@ mod temp2;
 
 use self::{
     ch::{ch_verify, mpc_ch},
-    maj::{maj, maj_verify, mpc_maj},
+    maj::{maj_verify, mpc_maj},
     temp1::{mpc_temp1, mpc_temp1_verify},
     temp2::{mpc_temp2, mpc_temp2_verify},
 };
 
-use super::{
-    iv::{init_iv, k},
-    *,
-};
+use super::{iv::K, *};
 use crate::{
+    circuit::{Output, TwoThreeDecOutput},
     error::Error,
     gadgets::{
-        add_mod::{add_mod_verify, adder, mpc_add_mod},
+        add_mod::{add_mod_verify, mpc_add_mod},
         Party,
     },
-    gf2_word::{BitUtils, GF2Word},
+    gf2_word::GF2Word,
 };
 
+#[cfg(test)]
+use super::iv::init_iv;
+use crate::gadgets::add_mod::adder;
+
 /// S0 := (a rightrotate 2) xor (a rightrotate 13) xor (a rightrotate 22)
 pub fn sigma_0(a: A) -> GF2Word<u32> {
-    let s0 = a.value.right_rotate(2) ^ a.value.right_rotate(13) ^ a.value.right_rotate(22);
-    s0.into()
+    a.rotr(2) ^ a.rotr(13) ^ a.rotr(22)
 }
 
 /// S1 := (e rightrotate 6) xor (e rightrotate 11) xor (e rightrotate 25)
 pub fn sigma_1(e: E) -> GF2Word<u32> {
-    let s1 = e.value.right_rotate(6) ^ e.value.right_rotate(11) ^ e.value.right_rotate(25);
-    s1.into()
+    e.rotr(6) ^ e.rotr(11) ^ e.rotr(25)
 }
 
-pub fn compression(w: &[GF2Word<u32>; 64]) -> Vec<GF2Word<u32>> {
-    let mut variables = init_iv();
+pub fn compression(w: &[GF2Word<u32>; 64], variables: &WorkingVariables) -> Vec<GF2Word<u32>> {
+    let mut variables = *variables;
 
     for i in 0..64 {
         // - ch  := (e and f) xor ((not e) and g)
@@ -44,11 +44,11 @@ pub fn compression(w: &[GF2Word<u32>; 64]) -> Vec<GF2Word<u32>> {
         // - S1 := (e rightrotate 6) xor (e rightrotate 11) xor (e rightrotate 25)
         let s1 = sigma_1(variables.e);
         // - temp1 := h + S1 + ch + k[i] + w[i]
-        let temp_1 = temp1::temp1(variables.h.value, s1.value, ch, w[i].value, k[i]);
+        let temp_1 = temp1::temp1(variables.h.value, s1.value, ch, w[i].value, K[i]);
         // - S0 := (a rightrotate 2) xor (a rightrotate 13) xor (a rightrotate 22)
         let s0 = sigma_0(variables.a);
         // - maj := (a and b) xor (a and c) xor (b and c)
-        let maj = maj(variables.a.value, variables.b.value, variables.c.value);
+        let maj = maj::maj(variables.a.value, variables.b.value, variables.c.value);
         // - temp2 := S0 + maj
         let temp_2 = temp2::temp2(s0.value, maj);
 
@@ -87,7 +87,7 @@ pub fn mpc_compression(
     p1: &mut Party<u32>,
     p2: &mut Party<u32>,
     p3: &mut Party<u32>,
-) -> (Vec<GF2Word<u32>>, Vec<GF2Word<u32>>, Vec<GF2Word<u32>>) {
+) -> TwoThreeDecOutput<u32> {
     let mut variables_1 = *vars_1;
     let mut variables_2 = *vars_2;
     let mut variables_3 = *vars_3;
@@ -112,7 +112,7 @@ pub fn mpc_compression(
             let input_p2 = (*variables_2.h, s1_2, ch_2, w_p2[i]);
             let input_p3 = (*variables_3.h, s1_3, ch_3, w_p3[i]);
 
-            mpc_temp1(input_p1, input_p2, input_p3, k[i].into(), p1, p2, p3)
+            mpc_temp1(input_p1, input_p2, input_p3, K[i].into(), p1, p2, p3)
         };
         // - S0 := (a rightrotate 2) xor (a rightrotate 13) xor (a rightrotate 22)
         let (s0_1, s0_2, s0_3) = (
@@ -205,7 +205,7 @@ pub fn mpc_compression_verify(
     (vars_p, vars_p_next): &(WorkingVariables, WorkingVariables),
     p: &mut Party<u32>,
     p_next: &mut Party<u32>,
-) -> Result<(Vec<GF2Word<u32>>, Vec<GF2Word<u32>>), Error> {
+) -> Result<(Output<u32>, Output<u32>), Error> {
     let mut variables_p = *vars_p;
     let mut variables_p_next = *vars_p_next;
 
@@ -227,7 +227,7 @@ pub fn mpc_compression_verify(
             let input_p = (*variables_p.h, s1_p, ch_p, w_p[i]);
             let input_p_next = (*variables_p_next.h, s1_p_next, ch_p_next, w_p_next[i]);
 
-            mpc_temp1_verify(input_p, input_p_next, k[i].into(), p, p_next)?
+            mpc_temp1_verify(input_p, input_p_next, K[i].into(), p, p_next)?
         };
         // Maj := (a and b) xor (a and c) xor (b and c)
         let (maj_p, maj_p_next) = {
@@ -270,7 +270,7 @@ pub fn mpc_compression_verify(
             let input_p = (*variables_p.d, temp1_p);
             let input_p_next = (*variables_p_next.d, temp1_p_next);
 
-            let (o1, o2) = add_mod_verify(input_p, input_p_next, p, p_next);
+            let (o1, o2) = add_mod_verify(input_p, input_p_next, p, p_next)?;
             (E(o1), E(o2))
         };
         // d := c
@@ -294,7 +294,7 @@ pub fn mpc_compression_verify(
             let input_p = (temp1_p, temp2_p);
             let input_p_next = (temp1_p_next, temp2_p_next);
 
-            let (o1, o2) = add_mod_verify(input_p, input_p_next, p, p_next);
+            let (o1, o2) = add_mod_verify(input_p, input_p_next, p, p_next)?;
             (A(o1), A(o2))
         }
     }
@@ -324,8 +324,8 @@ mod test_compression {
 
     impl Circuit<u32> for CompressionCircuit {
         fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
-            let words = generic_parse(input, self.party_input_len());
-            compression(&words.try_into().unwrap())
+            let words = generic_parse(input, self.party_input_len()).unwrap();
+            compression(&words.try_into().unwrap(), &init_iv())
         }
 
         fn compute_23_decomposition(
@@ -334,9 +334,9 @@ mod test_compression {
             p2: &mut Party<u32>,
             p3: &mut Party<u32>,
         ) -> (Vec<GF2Word<u32>>, Vec<GF2Word<u32>>, Vec<GF2Word<u32>>) {
-            let p1_words = generic_parse(&p1.view.input, self.party_input_len());
-            let p2_words = generic_parse(&p2.view.input, self.party_input_len());
-            let p3_words = generic_parse(&p3.view.input, self.party_input_len());
+            let p1_words = generic_parse(&p1.view.input, self.party_input_len()).unwrap();
+            let p2_words = generic_parse(&p2.view.input, self.party_input_len()).unwrap();
+            let p3_words = generic_parse(&p3.view.input, self.party_input_len()).unwrap();
 
             let variables_1 = init_iv();
             let variables_2 = init_iv();
@@ -346,7 +346,7 @@ mod test_compression {
                 &p1_words.try_into().unwrap(),
                 &p2_words.try_into().unwrap(),
                 &p3_words.try_into().unwrap(),
-                &mut (variables_1, variables_2, variables_3),
+                &(variables_1, variables_2, variables_3),
                 p1,
                 p2,
                 p3,
@@ -358,8 +358,8 @@ mod test_compression {
             p: &mut Party<u32>,
             p_next: &mut Party<u32>,
         ) -> Result<(Output<u32>, Output<u32>), Error> {
-            let p_words = generic_parse(&p.view.input, self.party_input_len());
-            let p_next_words = generic_parse(&p_next.view.input, self.party_input_len());
+            let p_words = generic_parse(&p.view.input, self.party_input_len())?;
+            let p_next_words = generic_parse(&p_next.view.input, self.party_input_len())?;
 
             let state_p = init_iv();
             let state_p_next = init_iv();
@@ -395,9 +395,7 @@ mod test_compression {
 
         let input: Vec<u8> = crate::gadgets::sha256::test_vectors::short::MSG_SCHEDULE_TEST_OUTPUT
             .iter()
-            .map(|&wi| wi.to_le_bytes())
-            .into_iter()
-            .flatten()
+            .flat_map(|&wi| wi.to_le_bytes())
             .collect();
 
         let circuit = CompressionCircuit;
@@ -413,6 +411,7 @@ mod test_compression {
         )
         .unwrap();
 
-        Verifier::<u32, ChaCha20Rng, Keccak256>::verify(&proof, &circuit, &output).unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
     }
 }
@@ -94,7 +94,7 @@ mod test_ch {
 
     impl Circuit<u32> for ChCircuit {
         fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
-            let words = generic_parse(input, self.party_input_len());
+            let words = generic_parse(input, self.party_input_len()).unwrap();
             let res = ch(words[0].value, words[1].value, words[2].value);
             vec![res.into()]
         }
@@ -105,9 +105,9 @@ mod test_ch {
             p2: &mut Party<u32>,
             p3: &mut Party<u32>,
         ) -> (Vec<GF2Word<u32>>, Vec<GF2Word<u32>>, Vec<GF2Word<u32>>) {
-            let p1_words = generic_parse(&p1.view.input, self.party_input_len());
-            let p2_words = generic_parse(&p2.view.input, self.party_input_len());
-            let p3_words = generic_parse(&p3.view.input, self.party_input_len());
+            let p1_words = generic_parse(&p1.view.input, self.party_input_len()).unwrap();
+            let p2_words = generic_parse(&p2.view.input, self.party_input_len()).unwrap();
+            let p3_words = generic_parse(&p3.view.input, self.party_input_len()).unwrap();
 
             let input_p1 = (p1_words[0], p1_words[1], p1_words[2]);
             let input_p2 = (p2_words[0], p2_words[1], p2_words[2]);
@@ -122,8 +122,8 @@ mod test_ch {
             p: &mut Party<u32>,
             p_next: &mut Party<u32>,
         ) -> Result<(Output<u32>, Output<u32>), Error> {
-            let p_words = generic_parse(&p.view.input, self.party_input_len());
-            let p_next_words = generic_parse(&p_next.view.input, self.party_input_len());
+            let p_words = generic_parse(&p.view.input, self.party_input_len())?;
+            let p_next_words = generic_parse(&p_next.view.input, self.party_input_len())?;
 
             let input_p = (p_words[0], p_words[1], p_words[2]);
             let input_p_next = (p_next_words[0], p_next_words[1], p_next_words[2]);
@@ -169,6 +169,7 @@ mod test_ch {
         )
         .unwrap();
 
-        Verifier::<u32, ChaCha20Rng, Keccak256>::verify(&proof, &circuit, &output).unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
     }
 }
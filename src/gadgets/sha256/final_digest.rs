@@ -1,19 +1,24 @@
 use crate::{
-    gadgets::add_mod::{add_mod_verify, adder, mpc_add_mod},
+    error::Error,
+    gadgets::add_mod::{add_mod_verify, mpc_add_mod},
     gf2_word::GF2Word,
     party::Party,
 };
 
-use super::{iv::init_iv, State};
+#[cfg(test)]
+use crate::gadgets::add_mod::adder;
+
+#[cfg(test)]
+use super::iv::init_iv;
+use super::State;
 
+#[cfg(test)]
 pub fn digest(compression_output: &[GF2Word<u32>; 8]) -> Vec<GF2Word<u32>> {
     let hs = init_iv().to_vec();
     hs.into_iter()
         .zip(compression_output.iter())
         .map(|(hs, &output)| adder(hs.value, output.value).into())
-        .collect::<Vec<_>>()
-        .try_into()
-        .unwrap()
+        .collect()
 }
 
 pub fn mpc_update_state(
@@ -57,7 +62,7 @@ pub fn mpc_update_state_verify(
     state: &(State, State),
     p: &mut Party<u32>,
     p_next: &mut Party<u32>,
-) -> (State, State) {
+) -> Result<(State, State), Error> {
     let hs_p = state.0.to_vec();
     let hs_p_next = state.1.to_vec();
 
@@ -70,13 +75,13 @@ pub fn mpc_update_state_verify(
             (compression_output_p_next[i], hs_p_next[i]),
             p,
             p_next,
-        );
+        )?;
 
         output_p.push(o1);
         output_p_next.push(o2);
     }
 
-    (output_p.into(), output_p_next.into())
+    Ok((output_p.into(), output_p_next.into()))
 }
 
 #[cfg(test)]
@@ -102,7 +107,7 @@ mod test_digest {
 
     impl Circuit<u32> for DigestCircuit {
         fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
-            let input = generic_parse(input, self.party_input_len());
+            let input = generic_parse(input, self.party_input_len()).unwrap();
             digest(&input.try_into().unwrap())
         }
 
@@ -112,9 +117,9 @@ mod test_digest {
             p2: &mut Party<u32>,
             p3: &mut Party<u32>,
         ) -> (Vec<GF2Word<u32>>, Vec<GF2Word<u32>>, Vec<GF2Word<u32>>) {
-            let p1_words = generic_parse(&p1.view.input, self.party_input_len());
-            let p2_words = generic_parse(&p2.view.input, self.party_input_len());
-            let p3_words = generic_parse(&p3.view.input, self.party_input_len());
+            let p1_words = generic_parse(&p1.view.input, self.party_input_len()).unwrap();
+            let p2_words = generic_parse(&p2.view.input, self.party_input_len()).unwrap();
+            let p3_words = generic_parse(&p3.view.input, self.party_input_len()).unwrap();
 
             let state_1 = init_iv();
             let state_2 = init_iv();
@@ -142,8 +147,8 @@ mod test_digest {
             p: &mut Party<u32>,
             p_next: &mut Party<u32>,
         ) -> Result<(Output<u32>, Output<u32>), Error> {
-            let p_words = generic_parse(&p.view.input, self.party_input_len());
-            let p_next_words = generic_parse(&p_next.view.input, self.party_input_len());
+            let p_words = generic_parse(&p.view.input, self.party_input_len())?;
+            let p_next_words = generic_parse(&p_next.view.input, self.party_input_len())?;
 
             let state_p = init_iv();
             let state_p_next = init_iv();
@@ -154,7 +159,7 @@ mod test_digest {
                 &(state_p.to_vec().into(), state_p_next.to_vec().into()),
                 p,
                 p_next,
-            );
+            )?;
 
             Ok((o1.to_vec(), o2.to_vec()))
         }
@@ -194,6 +199,7 @@ mod test_digest {
         )
         .unwrap();
 
-        Verifier::<u32, ChaCha20Rng, Keccak256>::verify(&proof, &circuit, &output).unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
     }
 }
@@ -1,15 +1,15 @@
 use super::*;
 
-pub(crate) const h0: u32 = 0x6a09e667;
-pub(crate) const h1: u32 = 0xbb67ae85;
-pub(crate) const h2: u32 = 0x3c6ef372;
-pub(crate) const h3: u32 = 0xa54ff53a;
-pub(crate) const h4: u32 = 0x510e527f;
-pub(crate) const h5: u32 = 0x9b05688c;
-pub(crate) const h6: u32 = 0x1f83d9ab;
-pub(crate) const h7: u32 = 0x5be0cd19;
+pub(crate) const H0: u32 = 0x6a09e667;
+pub(crate) const H1: u32 = 0xbb67ae85;
+pub(crate) const H2: u32 = 0x3c6ef372;
+pub(crate) const H3: u32 = 0xa54ff53a;
+pub(crate) const H4: u32 = 0x510e527f;
+pub(crate) const H5: u32 = 0x9b05688c;
+pub(crate) const H6: u32 = 0x1f83d9ab;
+pub(crate) const H7: u32 = 0x5be0cd19;
 
-pub(crate) const k: [u32; 64] = [
+pub(crate) const K: [u32; 64] = [
     0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
     0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
     0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
@@ -22,13 +22,13 @@ pub(crate) const k: [u32; 64] = [
 
 pub fn init_iv() -> WorkingVariables {
     WorkingVariables {
-        a: A(h0.into()),
-        b: B(h1.into()),
-        c: C(h2.into()),
-        d: D(h3.into()),
-        e: E(h4.into()),
-        f: F(h5.into()),
-        g: G(h6.into()),
-        h: H(h7.into()),
+        a: A(H0.into()),
+        b: B(H1.into()),
+        c: C(H2.into()),
+        d: D(H3.into()),
+        e: E(H4.into()),
+        f: F(H5.into()),
+        g: G(H6.into()),
+        h: H(H7.into()),
     }
 }
@@ -1,28 +1,27 @@
 use crate::{
+    error::Error,
     gadgets::{
-        add_mod::{add_mod_verify, adder, mpc_add_mod},
+        add_mod::{add_mod_verify, mpc_add_mod},
         Party,
     },
-    gf2_word::{BitUtils, GF2Word},
+    gf2_word::GF2Word,
 };
 
+use crate::gadgets::add_mod::adder;
+
+pub type MsgSchedule = [GF2Word<u32>; 64];
+
 /// s0 := (w[i-15] rightrotate  7) xor (w[i-15] rightrotate 18) xor (w[i-15] rightshift  3)
 fn s0(i: usize, w: &[GF2Word<u32>]) -> GF2Word<u32> {
-    (w[i - 15].value.right_rotate(7)
-        ^ w[i - 15].value.right_rotate(18)
-        ^ w[i - 15].value.right_shift(3))
-    .into()
+    w[i - 15].rotr(7) ^ w[i - 15].rotr(18) ^ (w[i - 15] >> 3)
 }
 
 /// s1 := (w[i-2] rightrotate 17) xor (w[i-2] rightrotate 19) xor (w[i-2] rightshift 10)
 fn s1(i: usize, w: &[GF2Word<u32>]) -> GF2Word<u32> {
-    (w[i - 2].value.right_rotate(17)
-        ^ w[i - 2].value.right_rotate(19)
-        ^ w[i - 2].value.right_shift(10))
-    .into()
+    w[i - 2].rotr(17) ^ w[i - 2].rotr(19) ^ (w[i - 2] >> 10)
 }
 
-pub fn msg_schedule(input: &[GF2Word<u32>; 16]) -> [GF2Word<u32>; 64] {
+pub fn msg_schedule(input: &[GF2Word<u32>; 16]) -> MsgSchedule {
     let mut w = input[..].to_vec();
 
     // extend words
@@ -51,7 +50,7 @@ pub fn mpc_msg_schedule(
     p1: &mut Party<u32>,
     p2: &mut Party<u32>,
     p3: &mut Party<u32>,
-) -> ([GF2Word<u32>; 64], [GF2Word<u32>; 64], [GF2Word<u32>; 64]) {
+) -> (MsgSchedule, MsgSchedule, MsgSchedule) {
     let mut w_1 = input_p1[..].to_vec();
     let mut w_2 = input_p2[..].to_vec();
     let mut w_3 = input_p3[..].to_vec();
@@ -102,7 +101,7 @@ pub fn mpc_msg_schedule_verify(
     input_p_next: &[GF2Word<u32>; 16],
     p: &mut Party<u32>,
     p_next: &mut Party<u32>,
-) -> ([GF2Word<u32>; 64], [GF2Word<u32>; 64]) {
+) -> Result<(MsgSchedule, MsgSchedule), Error> {
     let mut w = input_p[..].to_vec();
     let mut w_next = input_p_next[..].to_vec();
 
@@ -120,22 +119,22 @@ pub fn mpc_msg_schedule_verify(
             (w_next[i - 16], s0(i, &w_next)),
             p,
             p_next,
-        );
+        )?;
 
         let (rhs, rhs_next) = add_mod_verify(
             (w[i - 7], s1(i, &w)),
             (w_next[i - 7], s1(i, &w_next)),
             p,
             p_next,
-        );
+        )?;
 
-        let (o, o_next) = add_mod_verify((lhs, rhs), (lhs_next, rhs_next), p, p_next);
+        let (o, o_next) = add_mod_verify((lhs, rhs), (lhs_next, rhs_next), p, p_next)?;
 
         w.push(o);
         w_next.push(o_next);
     }
 
-    (w.try_into().unwrap(), w_next.try_into().unwrap())
+    Ok((w.try_into().unwrap(), w_next.try_into().unwrap()))
 }
 
 #[cfg(test)]
@@ -161,7 +160,7 @@ mod test_msg_schedule {
 
     impl Circuit<u32> for MsgScheduleCircuit {
         fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
-            let words = generic_parse(input, self.party_input_len());
+            let words = generic_parse(input, self.party_input_len()).unwrap();
             let res = msg_schedule(&words.try_into().unwrap());
             res.to_vec()
         }
@@ -172,9 +171,9 @@ mod test_msg_schedule {
             p2: &mut Party<u32>,
             p3: &mut Party<u32>,
         ) -> (Vec<GF2Word<u32>>, Vec<GF2Word<u32>>, Vec<GF2Word<u32>>) {
-            let p1_words = generic_parse(&p1.view.input, self.party_input_len());
-            let p2_words = generic_parse(&p2.view.input, self.party_input_len());
-            let p3_words = generic_parse(&p3.view.input, self.party_input_len());
+            let p1_words = generic_parse(&p1.view.input, self.party_input_len()).unwrap();
+            let p2_words = generic_parse(&p2.view.input, self.party_input_len()).unwrap();
+            let p3_words = generic_parse(&p3.view.input, self.party_input_len()).unwrap();
 
             let (o1, o2, o3) = mpc_msg_schedule(
                 &p1_words.try_into().unwrap(),
@@ -192,15 +191,15 @@ mod test_msg_schedule {
             p: &mut Party<u32>,
             p_next: &mut Party<u32>,
         ) -> Result<(Output<u32>, Output<u32>), Error> {
-            let p_words = generic_parse(&p.view.input, self.party_input_len());
-            let p_next_words = generic_parse(&p_next.view.input, self.party_input_len());
+            let p_words = generic_parse(&p.view.input, self.party_input_len())?;
+            let p_next_words = generic_parse(&p_next.view.input, self.party_input_len())?;
 
             let (o1, o2) = mpc_msg_schedule_verify(
                 &p_words.try_into().unwrap(),
                 &p_next_words.try_into().unwrap(),
                 p,
                 p_next,
-            );
+            )?;
 
             Ok((o1.to_vec(), o2.to_vec()))
         }
@@ -240,6 +239,7 @@ mod test_msg_schedule {
         )
         .unwrap();
 
-        Verifier::<u32, ChaCha20Rng, Keccak256>::verify(&proof, &circuit, &output).unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
     }
 }
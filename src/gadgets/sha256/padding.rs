@@ -5,23 +5,58 @@ pub fn padding(input: &[u8]) -> Vec<GF2Word<u32>> {
     let length_u64 = (8 * input.len()) as u64; // msg len in bits
     msg.push(0x80); // append one 1 bit and seven 0 bits
 
-    while (msg.len() * 8 + 64) % 512 != 0 {
+    while !(msg.len() * 8 + 64).is_multiple_of(512) {
         msg.push(0x00);
     }
     msg.extend_from_slice(&length_u64.to_be_bytes());
 
-    assert!(msg.len() * 8 % 512 == 0);
+    assert!((msg.len() * 8).is_multiple_of(512));
     msg.chunks(4)
         .map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap()).into())
         .collect()
 }
 
+/// The padded message of an input made of a secret prefix of `secret_len` bytes
+/// followed by public bytes, with the secret bytes set to zero.
+///
+/// Padding places bytes independently of their values, so the padded share of
+/// a party is the template XOR its share of the prefix. The template only
+/// depends on public data, hence circuits compute it once and reuse it for all
+/// repetitions and proofs.
+#[derive(Clone, Debug)]
+pub struct PaddingTemplate {
+    secret_len: usize,
+    words: Vec<GF2Word<u32>>,
+}
+
+impl PaddingTemplate {
+    pub fn new(secret_len: usize, public_suffix: &[u8]) -> Self {
+        Self {
+            secret_len,
+            words: padding(&[vec![0; secret_len], public_suffix.to_vec()].concat()),
+        }
+    }
+
+    /// The padded message with `secret` as prefix.
+    pub fn apply(&self, secret: &[u8]) -> Vec<GF2Word<u32>> {
+        assert_eq!(secret.len(), self.secret_len);
+
+        let mut words = self.words.clone();
+        for (word, chunk) in words.iter_mut().zip(secret.chunks(4)) {
+            let mut be_bytes = [0u8; 4];
+            be_bytes[..chunk.len()].copy_from_slice(chunk);
+            *word = *word ^ u32::from_be_bytes(be_bytes).into();
+        }
+        words
+    }
+}
+
 #[cfg(test)]
 mod test_padding {
     use crate::gadgets::sha256::test_vectors::long::TEST_INPUT as LONG_TEST;
     use crate::gadgets::sha256::test_vectors::short::TEST_INPUT as SHORT_TEST;
 
-    use super::padding;
+    use super::{padding, PaddingTemplate};
     #[test]
     fn short_padding() {
         let input = "abc".as_bytes();
@@ -39,4 +74,18 @@ mod test_padding {
             assert_eq!(word.value, expected_word);
         }
     }
+
+    #[test]
+    fn template_padding() {
+        let input: Vec<u8> = (0..150u8).collect();
+        for len in [0, 1, 3, 55, 56, 64, 119, 150] {
+            for secret_len in [0, 1, 5, len / 2, len] {
+                if secret_len > len {
+                    continue;
+                }
+                let template = PaddingTemplate::new(secret_len, &input[secret_len..len]);
+                assert_eq!(template.apply(&input[..secret_len]), padding(&input[..len]));
+            }
+        }
+    }
 }
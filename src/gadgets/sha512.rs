@@ -0,0 +1,617 @@
+//! SHA-512 and SHA-384 over 64-bit words.
+//!
+//! The circuit follows the SHA-256 gadget: the message schedule, 80 rounds of
+//! compression and the update of the state, with each addition modulo 2^64
+//! done by `add_mod` in one multiplication gate. SHA-384 is SHA-512 with
+//! another initial state, truncated to its first 6 words.
+
+use crate::{
+    circuit::{named_id, Circuit, Output, TwoThreeDecOutput},
+    error::Error,
+    gadgets::{
+        add_mod::{add_mod_verify, add_mod_verify_k, mpc_add_mod, mpc_add_mod_k},
+        mpc_and, mpc_and_verify,
+        prepare::Prepare,
+    },
+    gf2_word::GF2Word,
+    party::Party,
+};
+
+pub type State = [GF2Word<u64>; 8];
+pub type MsgSchedule = [GF2Word<u64>; 80];
+
+pub const SHA512_IV: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+pub const SHA384_IV: [u64; 8] = [
+    0xcbbb9d5dc1059ed8,
+    0x629a292a367cd507,
+    0x9159015a3070dd17,
+    0x152fecd8f70e5939,
+    0x67332667ffc00b31,
+    0x8eb44a8768581511,
+    0xdb0c2e0d64f98fa7,
+    0x47b5481dbefa4fa4,
+];
+
+#[rustfmt::skip]
+const K: [u64; 80] = [
+    0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+    0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+    0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+    0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 0xc19bf174cf692694,
+    0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+    0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+    0x983e5152ee66dfab, 0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+    0xc6e00bf33da88fc2, 0xd5a79147930aa725, 0x06ca6351e003826f, 0x142929670a0e6e70,
+    0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+    0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b,
+    0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30,
+    0xd192e819d6ef5218, 0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8,
+    0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+    0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+    0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+    0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+    0xca273eceea26619c, 0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+    0x06f067aa72176fba, 0x0a637dc5a2c898a6, 0x113f9804bef90dae, 0x1b710b35131c471b,
+    0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+    0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817,
+];
+
+fn init_state(iv: &[u64; 8]) -> State {
+    iv.map(GF2Word::from)
+}
+
+/// Σ0 := (a rightrotate 28) xor (a rightrotate 34) xor (a rightrotate 39)
+fn big_sigma0(a: GF2Word<u64>) -> GF2Word<u64> {
+    a.rotr(28) ^ a.rotr(34) ^ a.rotr(39)
+}
+
+/// Σ1 := (e rightrotate 14) xor (e rightrotate 18) xor (e rightrotate 41)
+fn big_sigma1(e: GF2Word<u64>) -> GF2Word<u64> {
+    e.rotr(14) ^ e.rotr(18) ^ e.rotr(41)
+}
+
+/// s0 := (w[i-15] rightrotate 1) xor (w[i-15] rightrotate 8) xor (w[i-15] rightshift 7)
+fn s0(i: usize, w: &[GF2Word<u64>]) -> GF2Word<u64> {
+    w[i - 15].rotr(1) ^ w[i - 15].rotr(8) ^ (w[i - 15] >> 7)
+}
+
+/// s1 := (w[i-2] rightrotate 19) xor (w[i-2] rightrotate 61) xor (w[i-2] rightshift 6)
+fn s1(i: usize, w: &[GF2Word<u64>]) -> GF2Word<u64> {
+    w[i - 2].rotr(19) ^ w[i - 2].rotr(61) ^ (w[i - 2] >> 6)
+}
+
+pub fn padding(input: &[u8]) -> Vec<GF2Word<u64>> {
+    let mut msg = input.to_vec();
+    let length_u128 = (8 * input.len()) as u128; // msg len in bits
+    msg.push(0x80); // append one 1 bit and seven 0 bits
+
+    while !(msg.len() * 8 + 128).is_multiple_of(1024) {
+        msg.push(0x00);
+    }
+    msg.extend_from_slice(&length_u128.to_be_bytes());
+
+    assert!((msg.len() * 8).is_multiple_of(1024));
+    msg.chunks(8)
+        .map(|chunk| u64::from_be_bytes(chunk.try_into().unwrap()).into())
+        .collect()
+}
+
+fn compress(state: &State, block: &[GF2Word<u64>]) -> State {
+    let mut w = block.to_vec();
+    for i in 16..80 {
+        let word = w[i - 16]
+            .value
+            .wrapping_add(s0(i, &w).value)
+            .wrapping_add(w[i - 7].value)
+            .wrapping_add(s1(i, &w).value);
+        w.push(word.into());
+    }
+
+    let mut v = state.map(|word| word.value);
+    for i in 0..80 {
+        let [a, b, c, d, e, f, g, h] = v;
+        let ch = (e & f) ^ (!e & g);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let temp1 = h
+            .wrapping_add(big_sigma1(e.into()).value)
+            .wrapping_add(ch)
+            .wrapping_add(w[i].value)
+            .wrapping_add(K[i]);
+        let temp2 = big_sigma0(a.into()).value.wrapping_add(maj);
+        v = [
+            temp1.wrapping_add(temp2),
+            a,
+            b,
+            c,
+            d.wrapping_add(temp1),
+            e,
+            f,
+            g,
+        ];
+    }
+
+    let mut output = *state;
+    for (word, v) in output.iter_mut().zip(v) {
+        *word = word.value.wrapping_add(v).into();
+    }
+    output
+}
+
+fn hash_padded(words: &[GF2Word<u64>], iv: &[u64; 8]) -> State {
+    words
+        .chunks(16)
+        .fold(init_state(iv), |state, block| compress(&state, block))
+}
+
+/// Computes the SHA-512 digest of `input`, as big-endian words.
+pub fn sha512(input: &[u8]) -> Vec<GF2Word<u64>> {
+    hash_padded(&padding(input), &SHA512_IV).to_vec()
+}
+
+/// Computes the SHA-384 digest of `input`, as big-endian words.
+pub fn sha384(input: &[u8]) -> Vec<GF2Word<u64>> {
+    hash_padded(&padding(input), &SHA384_IV)[..6].to_vec()
+}
+
+/// Extend the first 16 words into the remaining 64 words w[16..79] of the message schedule array
+pub fn mpc_msg_schedule(
+    input_p1: &[GF2Word<u64>],
+    input_p2: &[GF2Word<u64>],
+    input_p3: &[GF2Word<u64>],
+    p1: &mut Party<u64>,
+    p2: &mut Party<u64>,
+    p3: &mut Party<u64>,
+) -> (MsgSchedule, MsgSchedule, MsgSchedule) {
+    let mut w_1 = input_p1.to_vec();
+    let mut w_2 = input_p2.to_vec();
+    let mut w_3 = input_p3.to_vec();
+
+    for i in 16..80 {
+        // w[i] = w[i - 16] + s0 + w[i - 7] + s1, in 3 multiplications
+        let (lhs_1, lhs_2, lhs_3) = mpc_add_mod(
+            (w_1[i - 16], s0(i, &w_1)),
+            (w_2[i - 16], s0(i, &w_2)),
+            (w_3[i - 16], s0(i, &w_3)),
+            p1,
+            p2,
+            p3,
+        );
+        let (rhs_1, rhs_2, rhs_3) = mpc_add_mod(
+            (w_1[i - 7], s1(i, &w_1)),
+            (w_2[i - 7], s1(i, &w_2)),
+            (w_3[i - 7], s1(i, &w_3)),
+            p1,
+            p2,
+            p3,
+        );
+        let (o1, o2, o3) = mpc_add_mod((lhs_1, rhs_1), (lhs_2, rhs_2), (lhs_3, rhs_3), p1, p2, p3);
+
+        w_1.push(o1);
+        w_2.push(o2);
+        w_3.push(o3);
+    }
+
+    (
+        w_1.try_into().unwrap(),
+        w_2.try_into().unwrap(),
+        w_3.try_into().unwrap(),
+    )
+}
+
+pub fn mpc_msg_schedule_verify(
+    input_p: &[GF2Word<u64>],
+    input_p_next: &[GF2Word<u64>],
+    p: &mut Party<u64>,
+    p_next: &mut Party<u64>,
+) -> Result<(MsgSchedule, MsgSchedule), Error> {
+    let mut w = input_p.to_vec();
+    let mut w_next = input_p_next.to_vec();
+
+    for i in 16..80 {
+        let (lhs, lhs_next) = add_mod_verify(
+            (w[i - 16], s0(i, &w)),
+            (w_next[i - 16], s0(i, &w_next)),
+            p,
+            p_next,
+        )?;
+        let (rhs, rhs_next) = add_mod_verify(
+            (w[i - 7], s1(i, &w)),
+            (w_next[i - 7], s1(i, &w_next)),
+            p,
+            p_next,
+        )?;
+        let (o, o_next) = add_mod_verify((lhs, rhs), (lhs_next, rhs_next), p, p_next)?;
+
+        w.push(o);
+        w_next.push(o_next);
+    }
+
+    Ok((w.try_into().unwrap(), w_next.try_into().unwrap()))
+}
+
+/// Runs the 80 rounds on the working variables, in 9 multiplications per
+/// round: ch, maj, 4 additions for temp1, 1 for temp2 and 2 for e and a.
+pub fn mpc_compression(
+    w: (&MsgSchedule, &MsgSchedule, &MsgSchedule),
+    state: (&State, &State, &State),
+    p1: &mut Party<u64>,
+    p2: &mut Party<u64>,
+    p3: &mut Party<u64>,
+) -> (State, State, State) {
+    let (mut v1, mut v2, mut v3) = (*state.0, *state.1, *state.2);
+
+    for (i, &k) in K.iter().enumerate() {
+        // ch := e and (f xor g) xor g
+        let (ch_1, ch_2, ch_3) = mpc_and(
+            (v1[4], v1[5] ^ v1[6]),
+            (v2[4], v2[5] ^ v2[6]),
+            (v3[4], v3[5] ^ v3[6]),
+            p1,
+            p2,
+            p3,
+        );
+        let (ch_1, ch_2, ch_3) = (ch_1 ^ v1[6], ch_2 ^ v2[6], ch_3 ^ v3[6]);
+
+        // temp1 := h + Σ1 + ch + k[i] + w[i]
+        let t = mpc_add_mod(
+            (v1[7], big_sigma1(v1[4])),
+            (v2[7], big_sigma1(v2[4])),
+            (v3[7], big_sigma1(v3[4])),
+            p1,
+            p2,
+            p3,
+        );
+        let t = mpc_add_mod((t.0, ch_1), (t.1, ch_2), (t.2, ch_3), p1, p2, p3);
+        let t = mpc_add_mod((t.0, w.0[i]), (t.1, w.1[i]), (t.2, w.2[i]), p1, p2, p3);
+        let temp1 = mpc_add_mod_k(t.0, t.1, t.2, k.into(), p1, p2, p3);
+
+        // maj := (a xor b) and (a xor c) xor a
+        let (maj_1, maj_2, maj_3) = mpc_and(
+            (v1[0] ^ v1[1], v1[0] ^ v1[2]),
+            (v2[0] ^ v2[1], v2[0] ^ v2[2]),
+            (v3[0] ^ v3[1], v3[0] ^ v3[2]),
+            p1,
+            p2,
+            p3,
+        );
+        let (maj_1, maj_2, maj_3) = (maj_1 ^ v1[0], maj_2 ^ v2[0], maj_3 ^ v3[0]);
+
+        // temp2 := Σ0 + maj
+        let temp2 = mpc_add_mod(
+            (big_sigma0(v1[0]), maj_1),
+            (big_sigma0(v2[0]), maj_2),
+            (big_sigma0(v3[0]), maj_3),
+            p1,
+            p2,
+            p3,
+        );
+
+        let e = mpc_add_mod(
+            (v1[3], temp1.0),
+            (v2[3], temp1.1),
+            (v3[3], temp1.2),
+            p1,
+            p2,
+            p3,
+        );
+        let a = mpc_add_mod(
+            (temp1.0, temp2.0),
+            (temp1.1, temp2.1),
+            (temp1.2, temp2.2),
+            p1,
+            p2,
+            p3,
+        );
+
+        v1 = [a.0, v1[0], v1[1], v1[2], e.0, v1[4], v1[5], v1[6]];
+        v2 = [a.1, v2[0], v2[1], v2[2], e.1, v2[4], v2[5], v2[6]];
+        v3 = [a.2, v3[0], v3[1], v3[2], e.2, v3[4], v3[5], v3[6]];
+    }
+
+    (v1, v2, v3)
+}
+
+pub fn mpc_compression_verify(
+    w: (&MsgSchedule, &MsgSchedule),
+    state: (&State, &State),
+    p: &mut Party<u64>,
+    p_next: &mut Party<u64>,
+) -> Result<(State, State), Error> {
+    let (mut v, mut v_next) = (*state.0, *state.1);
+
+    for (i, &k) in K.iter().enumerate() {
+        let (ch, ch_next) = mpc_and_verify(
+            (v[4], v[5] ^ v[6]),
+            (v_next[4], v_next[5] ^ v_next[6]),
+            p,
+            p_next,
+        )?;
+        let (ch, ch_next) = (ch ^ v[6], ch_next ^ v_next[6]);
+
+        let t = add_mod_verify(
+            (v[7], big_sigma1(v[4])),
+            (v_next[7], big_sigma1(v_next[4])),
+            p,
+            p_next,
+        )?;
+        let t = add_mod_verify((t.0, ch), (t.1, ch_next), p, p_next)?;
+        let t = add_mod_verify((t.0, w.0[i]), (t.1, w.1[i]), p, p_next)?;
+        let temp1 = add_mod_verify_k(t.0, t.1, k.into(), p, p_next)?;
+
+        let (maj, maj_next) = mpc_and_verify(
+            (v[0] ^ v[1], v[0] ^ v[2]),
+            (v_next[0] ^ v_next[1], v_next[0] ^ v_next[2]),
+            p,
+            p_next,
+        )?;
+        let (maj, maj_next) = (maj ^ v[0], maj_next ^ v_next[0]);
+
+        let temp2 = add_mod_verify(
+            (big_sigma0(v[0]), maj),
+            (big_sigma0(v_next[0]), maj_next),
+            p,
+            p_next,
+        )?;
+
+        let e = add_mod_verify((v[3], temp1.0), (v_next[3], temp1.1), p, p_next)?;
+        let a = add_mod_verify((temp1.0, temp2.0), (temp1.1, temp2.1), p, p_next)?;
+
+        v = [a.0, v[0], v[1], v[2], e.0, v[4], v[5], v[6]];
+        v_next = [
+            a.1, v_next[0], v_next[1], v_next[2], e.1, v_next[4], v_next[5], v_next[6],
+        ];
+    }
+
+    Ok((v, v_next))
+}
+
+pub fn mpc_sha512_block(
+    input_p1: &[GF2Word<u64>],
+    input_p2: &[GF2Word<u64>],
+    input_p3: &[GF2Word<u64>],
+    state: (&State, &State, &State),
+    p1: &mut Party<u64>,
+    p2: &mut Party<u64>,
+    p3: &mut Party<u64>,
+) -> (State, State, State) {
+    let (w_1, w_2, w_3) = mpc_msg_schedule(input_p1, input_p2, input_p3, p1, p2, p3);
+    let (v1, v2, v3) = mpc_compression((&w_1, &w_2, &w_3), state, p1, p2, p3);
+
+    // add the compressed chunk to the current hash value
+    let (mut s1, mut s2, mut s3) = (*state.0, *state.1, *state.2);
+    for j in 0..8 {
+        (s1[j], s2[j], s3[j]) =
+            mpc_add_mod((s1[j], v1[j]), (s2[j], v2[j]), (s3[j], v3[j]), p1, p2, p3);
+    }
+    (s1, s2, s3)
+}
+
+pub fn mpc_sha512_block_verify(
+    input_p: &[GF2Word<u64>],
+    input_p_next: &[GF2Word<u64>],
+    state: (&State, &State),
+    p: &mut Party<u64>,
+    p_next: &mut Party<u64>,
+) -> Result<(State, State), Error> {
+    let (w, w_next) = mpc_msg_schedule_verify(input_p, input_p_next, p, p_next)?;
+    let (v, v_next) = mpc_compression_verify((&w, &w_next), state, p, p_next)?;
+
+    let (mut s, mut s_next) = (*state.0, *state.1);
+    for j in 0..8 {
+        (s[j], s_next[j]) = add_mod_verify((s[j], v[j]), (s_next[j], v_next[j]), p, p_next)?;
+    }
+    Ok((s, s_next))
+}
+
+/// Number of multiplication gates used to hash an input of `input_len` bytes.
+pub fn sha512_num_of_mul_gates(input_len: usize) -> usize {
+    // padding appends at least 17 bytes
+    let num_blocks = (input_len + 17).div_ceil(128);
+
+    let msg_schedule = 3 * 64;
+    let compression = 9 * 80;
+    let digest = 8;
+
+    (msg_schedule + compression + digest) * num_blocks
+}
+
+/// Proves knowledge of a preimage of `input_len` bytes for a SHA-512 or
+/// SHA-384 digest.
+#[derive(Clone, Debug)]
+pub struct Sha512Circuit {
+    pub input_len: usize,
+    iv: [u64; 8],
+    output_len: usize,
+    /// The padded message with the preimage set to zero, see
+    /// `sha256::padding::PaddingTemplate`.
+    template: Vec<GF2Word<u64>>,
+}
+
+impl Sha512Circuit {
+    pub fn new(input_len: usize) -> Self {
+        Self::with_iv(input_len, SHA512_IV, 8)
+    }
+
+    pub fn sha384(input_len: usize) -> Self {
+        Self::with_iv(input_len, SHA384_IV, 6)
+    }
+
+    fn with_iv(input_len: usize, iv: [u64; 8], output_len: usize) -> Self {
+        Self {
+            input_len,
+            iv,
+            output_len,
+            template: padding(&vec![0; input_len]),
+        }
+    }
+
+    fn padded(&self, input: &[u8]) -> Vec<GF2Word<u64>> {
+        let mut words = self.template.clone();
+        for (word, chunk) in words.iter_mut().zip(input.chunks(8)) {
+            let mut be_bytes = [0u8; 8];
+            be_bytes[..chunk.len()].copy_from_slice(chunk);
+            *word = *word ^ u64::from_be_bytes(be_bytes).into();
+        }
+        words
+    }
+}
+
+impl Circuit<u64> for Sha512Circuit {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<u64>> {
+        assert_eq!(input.len(), self.input_len);
+        hash_padded(&padding(input), &self.iv)[..self.output_len].to_vec()
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<u64>,
+        p2: &mut Party<u64>,
+        p3: &mut Party<u64>,
+    ) -> TwoThreeDecOutput<u64> {
+        let (words_p1, words_p2, words_p3) = (
+            self.padded(&p1.view.input),
+            self.padded(&p2.view.input),
+            self.padded(&p3.view.input),
+        );
+
+        let iv = init_state(&self.iv);
+        let (mut s1, mut s2, mut s3) = (iv, iv, iv);
+        let iter_chunks = words_p1
+            .chunks(16)
+            .zip(words_p2.chunks(16))
+            .zip(words_p3.chunks(16));
+        for ((block_1, block_2), block_3) in iter_chunks {
+            (s1, s2, s3) = mpc_sha512_block(block_1, block_2, block_3, (&s1, &s2, &s3), p1, p2, p3);
+        }
+
+        (
+            s1[..self.output_len].to_vec(),
+            s2[..self.output_len].to_vec(),
+            s3[..self.output_len].to_vec(),
+        )
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<u64>,
+        p_next: &mut Party<u64>,
+    ) -> Result<(Output<u64>, Output<u64>), Error> {
+        if p.view.input.len() != self.input_len || p_next.view.input.len() != self.input_len {
+            return Err(Error::VerificationError);
+        }
+        let (words_p, words_p_next) = (self.padded(&p.view.input), self.padded(&p_next.view.input));
+
+        let iv = init_state(&self.iv);
+        let (mut s, mut s_next) = (iv, iv);
+        for (block, block_next) in words_p.chunks(16).zip(words_p_next.chunks(16)) {
+            (s, s_next) = mpc_sha512_block_verify(block, block_next, (&s, &s_next), p, p_next)?;
+        }
+
+        Ok((
+            s[..self.output_len].to_vec(),
+            s_next[..self.output_len].to_vec(),
+        ))
+    }
+
+    fn party_input_len(&self) -> usize {
+        self.input_len
+    }
+
+    fn party_output_len(&self) -> usize {
+        self.output_len
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        sha512_num_of_mul_gates(self.input_len)
+    }
+
+    fn id(&self) -> Vec<u8> {
+        named_id("sha512", self, &[])
+    }
+}
+
+impl Prepare<u64> for Sha512Circuit {
+    fn prepare_witness(&self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        if bytes.len() != self.input_len {
+            return Err(Error::ParseLengthError(self.input_len, bytes.len()));
+        }
+        Ok(bytes.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod test_sha512 {
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha2::{Digest, Sha384, Sha512};
+    use sha3::Keccak256;
+
+    use crate::{circuit::Circuit, prover::Prover, verifier::Verifier};
+
+    use super::*;
+
+    fn to_bytes(digest: &[GF2Word<u64>]) -> Vec<u8> {
+        digest
+            .iter()
+            .flat_map(|word| word.value.to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_plain_sha512() {
+        for len in [0, 3, 111, 112, 127, 128, 129, 239, 300] {
+            let input: Vec<u8> = (0..len).map(|i| (i * 7 + 1) as u8).collect();
+            assert_eq!(to_bytes(&sha512(&input)), Sha512::digest(&input).to_vec());
+            assert_eq!(to_bytes(&sha384(&input)), Sha384::digest(&input).to_vec());
+        }
+    }
+
+    #[test]
+    fn test_num_of_mul_gates() {
+        let per_block = sha512_num_of_mul_gates(0);
+        assert_eq!(per_block, 920);
+        assert_eq!(sha512_num_of_mul_gates(111), per_block);
+        assert_eq!(sha512_num_of_mul_gates(112), 2 * per_block);
+    }
+
+    #[test]
+    fn test_sha512_circuit() {
+        const SIGMA: usize = 40;
+        let mut rng = thread_rng();
+
+        for (circuit, preimage) in [
+            (Sha512Circuit::new(3), &b"abc"[..]),
+            (Sha512Circuit::sha384(3), &b"abc"[..]),
+            (Sha512Circuit::new(120), &[0x5a; 120][..]),
+        ] {
+            let output = circuit.compute(preimage);
+            assert_eq!(circuit.expected_public_output(preimage).unwrap(), output);
+
+            let proof = Prover::<u64, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+                &mut rng, preimage, &circuit, &output,
+            )
+            .unwrap();
+            Verifier::<u64, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+                .unwrap();
+
+            let mut wrong_output = output.clone();
+            wrong_output[0] = wrong_output[0] ^ 1u64.into();
+            assert!(Verifier::<u64, ChaCha20Rng, Keccak256>::verify::<SIGMA>(
+                &proof,
+                &circuit,
+                &wrong_output
+            )
+            .is_err());
+        }
+    }
+}
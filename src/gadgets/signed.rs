@@ -0,0 +1,290 @@
+//! Two's complement signed integers and fixed-point numbers.
+//!
+//! A signed value is stored in a word as its two's complement, so addition
+//! (`mpc_add_mod`) and equality (`mpc_eq`) work on it unchanged. Only ordering
+//! differs: flipping the sign bit maps two's complement order onto unsigned
+//! order, and since the sign bit is a public constant each party flips it in
+//! its own share (three flips reconstruct to one).
+//!
+//! A fixed-point number with `frac_bits` fractional bits is the signed integer
+//! `round(x * 2^frac_bits)`. Numbers with the same scale are added and compared
+//! as signed integers.
+
+use crate::{
+    error::Error,
+    gadgets::compare::{less_than, lt_verify, mpc_lt},
+    gf2_word::{GF2Word, Value},
+    party::Party,
+};
+
+fn sign_bit<T: Value>() -> T {
+    T::from(1) << (T::bits_len() - 1)
+}
+
+/// Encodes `x` as the two's complement of a `T`, failing if it does not fit.
+pub fn encode_signed<T: Value>(x: i128) -> Result<T, Error> {
+    let bits = T::bits_len() as u32;
+    if bits < 128 && (x < -(1i128 << (bits - 1)) || x >= 1i128 << (bits - 1)) {
+        return Err(Error::OutOfRangeError);
+    }
+    Ok(T::from_le_bytes(&x.to_le_bytes()[..T::bytes_len()]))
+}
+
+/// Reads `x` as the two's complement of a signed integer.
+pub fn decode_signed<T: Value>(x: T) -> i128 {
+    let mut le_bytes = x.to_bytes();
+    le_bytes.reverse();
+
+    let fill = if x.get_bit(T::bits_len() - 1).inner() {
+        0xff
+    } else {
+        0
+    };
+    le_bytes.resize(16, fill);
+    i128::from_le_bytes(le_bytes.try_into().unwrap())
+}
+
+/// Little-endian witness bytes of signed integers, in the layout read by
+/// `generic_parse`.
+pub fn signed_witness<T: Value>(values: &[i128]) -> Result<Vec<u8>, Error> {
+    let mut witness = Vec::with_capacity(values.len() * T::bytes_len());
+    for &x in values {
+        let mut le_bytes = encode_signed::<T>(x)?.to_bytes();
+        le_bytes.reverse();
+        witness.extend(le_bytes);
+    }
+    Ok(witness)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedPoint {
+    pub frac_bits: u32,
+}
+
+impl FixedPoint {
+    pub fn new(frac_bits: u32) -> Self {
+        Self { frac_bits }
+    }
+
+    /// Encodes `x` rounded to the nearest multiple of `2^-frac_bits`.
+    pub fn encode<T: Value>(&self, x: f64) -> Result<T, Error> {
+        let scaled = (x * (self.frac_bits as f64).exp2()).round();
+        if !scaled.is_finite() || scaled.abs() >= 2f64.powi(127) {
+            return Err(Error::OutOfRangeError);
+        }
+        encode_signed(scaled as i128)
+    }
+
+    pub fn decode<T: Value>(&self, x: T) -> f64 {
+        decode_signed(x) as f64 / (self.frac_bits as f64).exp2()
+    }
+}
+
+/// Returns 1 if `x < y` and 0 otherwise, comparing as signed integers.
+pub fn less_than_signed<T: Value>(x: T, y: T) -> T {
+    less_than(x ^ sign_bit(), y ^ sign_bit())
+}
+
+fn flip_signs<T: Value>(input: (GF2Word<T>, GF2Word<T>)) -> (GF2Word<T>, GF2Word<T>) {
+    let sign: GF2Word<T> = sign_bit::<T>().into();
+    (input.0 ^ sign, input.1 ^ sign)
+}
+
+/// Shares of `x < y` for signed `x` and `y`, where the inputs are `(x, y)`.
+/// Uses `lt_num_of_mul_gates` multiplication gates.
+pub fn mpc_slt<T: Value>(
+    input_p1: (GF2Word<T>, GF2Word<T>),
+    input_p2: (GF2Word<T>, GF2Word<T>),
+    input_p3: (GF2Word<T>, GF2Word<T>),
+    p1: &mut Party<T>,
+    p2: &mut Party<T>,
+    p3: &mut Party<T>,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    mpc_lt(
+        flip_signs(input_p1),
+        flip_signs(input_p2),
+        flip_signs(input_p3),
+        p1,
+        p2,
+        p3,
+    )
+}
+
+pub fn slt_verify<T: Value>(
+    input_p: (GF2Word<T>, GF2Word<T>),
+    input_p_next: (GF2Word<T>, GF2Word<T>),
+    p: &mut Party<T>,
+    p_next: &mut Party<T>,
+) -> Result<(GF2Word<T>, GF2Word<T>), Error> {
+    lt_verify(flip_signs(input_p), flip_signs(input_p_next), p, p_next)
+}
+
+/// Shares of `x > y` for signed `x` and `y`, where the inputs are `(x, y)`.
+pub fn mpc_sgt<T: Value>(
+    input_p1: (GF2Word<T>, GF2Word<T>),
+    input_p2: (GF2Word<T>, GF2Word<T>),
+    input_p3: (GF2Word<T>, GF2Word<T>),
+    p1: &mut Party<T>,
+    p2: &mut Party<T>,
+    p3: &mut Party<T>,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    mpc_slt(
+        (input_p1.1, input_p1.0),
+        (input_p2.1, input_p2.0),
+        (input_p3.1, input_p3.0),
+        p1,
+        p2,
+        p3,
+    )
+}
+
+pub fn sgt_verify<T: Value>(
+    input_p: (GF2Word<T>, GF2Word<T>),
+    input_p_next: (GF2Word<T>, GF2Word<T>),
+    p: &mut Party<T>,
+    p_next: &mut Party<T>,
+) -> Result<(GF2Word<T>, GF2Word<T>), Error> {
+    slt_verify(
+        (input_p.1, input_p.0),
+        (input_p_next.1, input_p_next.0),
+        p,
+        p_next,
+    )
+}
+
+#[cfg(test)]
+mod signed_tests {
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::{Circuit, Output, TwoThreeDecOutput},
+        gadgets::{compare::lt_num_of_mul_gates, prepare::generic_parse},
+        prover::Prover,
+        verifier::Verifier,
+    };
+
+    use super::*;
+
+    /// Outputs `[lo < x, x < hi]` for a secret signed `x`.
+    struct RangeCircuit {
+        lo: GF2Word<u32>,
+        hi: GF2Word<u32>,
+    }
+
+    impl Circuit<u32> for RangeCircuit {
+        fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+            let x = generic_parse::<u32>(input, 1).unwrap()[0].value;
+            vec![
+                less_than_signed(self.lo.value, x).into(),
+                less_than_signed(x, self.hi.value).into(),
+            ]
+        }
+
+        fn compute_23_decomposition(
+            &self,
+            p1: &mut Party<u32>,
+            p2: &mut Party<u32>,
+            p3: &mut Party<u32>,
+        ) -> TwoThreeDecOutput<u32> {
+            let x1 = generic_parse(&p1.view.input, 1).unwrap()[0];
+            let x2 = generic_parse(&p2.view.input, 1).unwrap()[0];
+            let x3 = generic_parse(&p3.view.input, 1).unwrap()[0];
+            let (lo, hi) = (self.lo, self.hi);
+
+            let above = mpc_sgt((x1, lo), (x2, lo), (x3, lo), p1, p2, p3);
+            let below = mpc_slt((x1, hi), (x2, hi), (x3, hi), p1, p2, p3);
+
+            (
+                vec![above.0, below.0],
+                vec![above.1, below.1],
+                vec![above.2, below.2],
+            )
+        }
+
+        fn simulate_two_parties(
+            &self,
+            p: &mut Party<u32>,
+            p_next: &mut Party<u32>,
+        ) -> Result<(Output<u32>, Output<u32>), Error> {
+            let x = generic_parse(&p.view.input, 1)?[0];
+            let x_next = generic_parse(&p_next.view.input, 1)?[0];
+            let (lo, hi) = (self.lo, self.hi);
+
+            let above = sgt_verify((x, lo), (x_next, lo), p, p_next)?;
+            let below = slt_verify((x, hi), (x_next, hi), p, p_next)?;
+
+            Ok((vec![above.0, below.0], vec![above.1, below.1]))
+        }
+
+        fn party_input_len(&self) -> usize {
+            1
+        }
+
+        fn party_output_len(&self) -> usize {
+            2
+        }
+
+        fn num_of_mul_gates(&self) -> usize {
+            2 * lt_num_of_mul_gates::<u32>()
+        }
+    }
+
+    #[test]
+    fn test_encoding() {
+        for x in i8::MIN..=i8::MAX {
+            let encoded = encode_signed::<u8>(x as i128).unwrap();
+            assert_eq!(encoded, x as u8);
+            assert_eq!(decode_signed(encoded), x as i128);
+        }
+        assert!(encode_signed::<u8>(128).is_err());
+        assert!(encode_signed::<u8>(-129).is_err());
+
+        assert_eq!(decode_signed(encode_signed::<u64>(-5).unwrap()), -5);
+        assert_eq!(
+            signed_witness::<u32>(&[-2, 3]).unwrap(),
+            [(-2i32).to_le_bytes(), 3i32.to_le_bytes()].concat()
+        );
+
+        let fixed = FixedPoint::new(8);
+        assert_eq!(fixed.encode::<u32>(-1.5).unwrap(), (-384i32) as u32);
+        assert_eq!(fixed.decode(fixed.encode::<u32>(21.25).unwrap()), 21.25);
+        assert!(fixed.encode::<u8>(1.0).is_err());
+    }
+
+    #[test]
+    fn test_plain_signed_comparison() {
+        for x in i8::MIN..=i8::MAX {
+            for y in i8::MIN..=i8::MAX {
+                assert_eq!(less_than_signed(x as u8, y as u8), u8::from(x < y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_signed_range_circuit() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        // a temperature reading between -20.0 and 45.5 degrees
+        let fixed = FixedPoint::new(4);
+        let circuit = RangeCircuit {
+            lo: fixed.encode::<u32>(-20.0).unwrap().into(),
+            hi: fixed.encode::<u32>(45.5).unwrap().into(),
+        };
+
+        for (reading, expected) in [(-3.25, [1, 1]), (-20.5, [0, 1]), (50.0, [1, 0])] {
+            let input = fixed.encode::<u32>(reading).unwrap().to_le_bytes();
+            let output = circuit.compute(&input);
+            assert_eq!(output, vec![expected[0].into(), expected[1].into()]);
+
+            let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+                &mut rng, &input, &circuit, &output,
+            )
+            .unwrap();
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+                .unwrap();
+        }
+    }
+}
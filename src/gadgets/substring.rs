@@ -0,0 +1,302 @@
+//! Containment of a public pattern in a secret byte string.
+//!
+//! The circuit compares the pattern with the string at every offset and ORs
+//! the results, so the output bit reveals whether the pattern occurs but not
+//! where. The bytes of every window are xored with the pattern and packed into
+//! words, see `circuit::pack_bytes`, which costs no AND gate: a window matches
+//! iff all its packed words are zero.
+//!
+//! Joined with a hash circuit on the same input, e.g. with `circuit::join`, it
+//! proves that a committed document such as an email or a JWT contains a given
+//! string, without revealing the rest of the document.
+
+use std::marker::PhantomData;
+
+use crate::{
+    circuit::{named_id, pack_bytes, Circuit, Output, TwoThreeDecOutput},
+    error::Error,
+    gadgets::{
+        assertion::{mpc_or, or_verify},
+        compare::{eq_num_of_mul_gates, eq_verify, mpc_eq},
+        mpc_public, public_verify,
+    },
+    gf2_word::{GF2Word, Value},
+    party::Party,
+};
+
+/// Returns 1 if `pattern` occurs in `input` and 0 otherwise. The empty pattern
+/// occurs in every input.
+pub fn contains<T: Value>(input: &[u8], pattern: &[u8]) -> GF2Word<T> {
+    let found = pattern.is_empty() || input.windows(pattern.len()).any(|w| w == pattern);
+    T::from(u8::from(found)).into()
+}
+
+/// Number of offsets at which a pattern of `pattern_len` bytes fits in an
+/// input of `input_len` bytes.
+fn num_of_offsets(input_len: usize, pattern_len: usize) -> usize {
+    (input_len + 1).saturating_sub(pattern_len)
+}
+
+/// Number of multiplication gates used by `mpc_contains` over `T`.
+pub fn contains_num_of_mul_gates<T: Value>(input_len: usize, pattern_len: usize) -> usize {
+    let offsets = num_of_offsets(input_len, pattern_len);
+    if pattern_len == 0 || offsets == 0 {
+        return 0;
+    }
+    let words = (8 * pattern_len).div_ceil(T::bits_len());
+    // per offset: OR the words together and compare with zero; then OR the
+    // results of all offsets
+    offsets * (words - 1 + eq_num_of_mul_gates::<T>()) + offsets - 1
+}
+
+/// A party's share of the xor of `pattern` and the window at `offset`.
+fn window_diff<T: Value>(input: &[u8], pattern: &[u8], offset: usize) -> Vec<GF2Word<T>> {
+    let diff: Vec<u8> = input[offset..offset + pattern.len()]
+        .iter()
+        .zip(pattern)
+        .map(|(x, p)| x ^ p)
+        .collect();
+    pack_bytes(&diff)
+}
+
+/// Shares of 1 if `pattern` occurs in the string shared by the inputs of the
+/// parties, and of 0 otherwise.
+pub fn mpc_contains<T: Value>(
+    pattern: &[u8],
+    p1: &mut Party<T>,
+    p2: &mut Party<T>,
+    p3: &mut Party<T>,
+) -> (GF2Word<T>, GF2Word<T>, GF2Word<T>) {
+    let input_len = p1.view.input.len();
+    if pattern.is_empty() {
+        return mpc_public(T::from(1).into());
+    }
+
+    let zero = T::zero().into();
+    let mut found: Option<(GF2Word<T>, GF2Word<T>, GF2Word<T>)> = None;
+    for offset in 0..num_of_offsets(input_len, pattern.len()) {
+        let d1 = window_diff(&p1.view.input, pattern, offset);
+        let d2 = window_diff(&p2.view.input, pattern, offset);
+        let d3 = window_diff(&p3.view.input, pattern, offset);
+
+        let mut diff = (d1[0], d2[0], d3[0]);
+        for i in 1..d1.len() {
+            diff = mpc_or(
+                (diff.0, d1[i]),
+                (diff.1, d2[i]),
+                (diff.2, d3[i]),
+                p1,
+                p2,
+                p3,
+            );
+        }
+        let matches = mpc_eq((diff.0, zero), (diff.1, zero), (diff.2, zero), p1, p2, p3);
+
+        found = Some(match found {
+            None => matches,
+            Some(found) => mpc_or(
+                (found.0, matches.0),
+                (found.1, matches.1),
+                (found.2, matches.2),
+                p1,
+                p2,
+                p3,
+            ),
+        });
+    }
+
+    found.unwrap_or_else(|| mpc_public(zero))
+}
+
+pub fn contains_verify<T: Value>(
+    pattern: &[u8],
+    p: &mut Party<T>,
+    p_next: &mut Party<T>,
+) -> Result<(GF2Word<T>, GF2Word<T>), Error> {
+    let input_len = p.view.input.len();
+    if p_next.view.input.len() != input_len {
+        return Err(Error::VerificationError);
+    }
+    if pattern.is_empty() {
+        return Ok(public_verify(T::from(1).into()));
+    }
+
+    let zero = T::zero().into();
+    let mut found: Option<(GF2Word<T>, GF2Word<T>)> = None;
+    for offset in 0..num_of_offsets(input_len, pattern.len()) {
+        let d = window_diff(&p.view.input, pattern, offset);
+        let d_next = window_diff(&p_next.view.input, pattern, offset);
+
+        let mut diff = (d[0], d_next[0]);
+        for i in 1..d.len() {
+            diff = or_verify((diff.0, d[i]), (diff.1, d_next[i]), p, p_next)?;
+        }
+        let matches = eq_verify((diff.0, zero), (diff.1, zero), p, p_next)?;
+
+        found = Some(match found {
+            None => matches,
+            Some(found) => or_verify((found.0, matches.0), (found.1, matches.1), p, p_next)?,
+        });
+    }
+
+    Ok(found.unwrap_or_else(|| public_verify(zero)))
+}
+
+/// Proves that a secret string of `input_len` bytes contains `pattern`,
+/// outputting 1 if it does and 0 otherwise.
+#[derive(Clone, Debug)]
+pub struct SubstringCircuit<T: Value> {
+    input_len: usize,
+    pattern: Vec<u8>,
+    _word: PhantomData<T>,
+}
+
+impl<T: Value> SubstringCircuit<T> {
+    pub fn new(input_len: usize, pattern: &[u8]) -> Self {
+        Self {
+            input_len,
+            pattern: pattern.to_vec(),
+            _word: PhantomData,
+        }
+    }
+}
+
+impl<T: Value> Circuit<T> for SubstringCircuit<T> {
+    fn compute(&self, input: &[u8]) -> Vec<GF2Word<T>> {
+        vec![contains(input, &self.pattern)]
+    }
+
+    fn compute_23_decomposition(
+        &self,
+        p1: &mut Party<T>,
+        p2: &mut Party<T>,
+        p3: &mut Party<T>,
+    ) -> TwoThreeDecOutput<T> {
+        let (o1, o2, o3) = mpc_contains(&self.pattern, p1, p2, p3);
+        (vec![o1], vec![o2], vec![o3])
+    }
+
+    fn simulate_two_parties(
+        &self,
+        p: &mut Party<T>,
+        p_next: &mut Party<T>,
+    ) -> Result<(Output<T>, Output<T>), Error> {
+        if p.view.input.len() != self.input_len {
+            return Err(Error::ParseLengthError(self.input_len, p.view.input.len()));
+        }
+        let (o, o_next) = contains_verify(&self.pattern, p, p_next)?;
+        Ok((vec![o], vec![o_next]))
+    }
+
+    fn party_input_len(&self) -> usize {
+        self.input_len
+    }
+
+    fn party_output_len(&self) -> usize {
+        1
+    }
+
+    fn num_of_mul_gates(&self) -> usize {
+        contains_num_of_mul_gates::<T>(self.input_len, self.pattern.len())
+    }
+
+    fn public_input(&self) -> Vec<u8> {
+        self.pattern.clone()
+    }
+
+    fn id(&self) -> Vec<u8> {
+        named_id("substring", self, &[])
+    }
+}
+
+#[cfg(test)]
+mod substring_tests {
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::{count_mul_gates, join, Circuit},
+        gadgets::sha256::{sha256, Sha256Circuit},
+        gf2_word::{GF2Word, Value},
+        prover::Prover,
+        verifier::Verifier,
+    };
+
+    use super::SubstringCircuit;
+
+    const SIGMA: usize = 40;
+
+    fn prove_and_verify<T: Value>(input: &[u8], pattern: &[u8], expected: u8) {
+        let circuit = SubstringCircuit::<T>::new(input.len(), pattern);
+        assert_eq!(
+            count_mul_gates(&circuit, input.len()),
+            circuit.num_of_mul_gates()
+        );
+
+        let output = circuit.compute(input);
+        assert_eq!(output, vec![T::from(expected).into()]);
+        let proof = Prover::<T, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut thread_rng(),
+            input,
+            &circuit,
+            &output,
+        )
+        .unwrap();
+        Verifier::<T, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output).unwrap();
+
+        // the other outcome is rejected
+        let other = vec![T::from(1 - expected).into()];
+        assert!(
+            Verifier::<T, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &other)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_contains() {
+        let input = b"From: alice@example.com";
+        prove_and_verify::<u32>(input, b"@example.com", 1);
+        prove_and_verify::<u32>(input, b"From", 1);
+        prove_and_verify::<u32>(input, b"@example.org", 0);
+        prove_and_verify::<u8>(input, b"lice", 1);
+        prove_and_verify::<u64>(input, b"bob", 0);
+
+        // edge cases
+        prove_and_verify::<u32>(input, b"", 1);
+        prove_and_verify::<u32>(input, input, 1);
+        prove_and_verify::<u32>(b"short", b"longer pattern", 0);
+    }
+
+    #[test]
+    fn test_hashed_document_contains() {
+        let document = b"{\"sub\":\"1234\",\"admin\":true}";
+        let circuit = join(
+            Sha256Circuit::new(document.len()),
+            SubstringCircuit::<u32>::new(document.len(), b"\"admin\":true"),
+        );
+
+        let output = circuit.compute(document);
+        assert_eq!(output[..8], sha256(document));
+        assert_eq!(output[8], GF2Word::from(1u32));
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut thread_rng(),
+            document,
+            &circuit,
+            &output,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+
+        // the proof is bound to the pattern
+        let other = join(
+            Sha256Circuit::new(document.len()),
+            SubstringCircuit::<u32>::new(document.len(), b"\"admin\":fals"),
+        );
+        assert!(
+            Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &other, &output)
+                .is_err()
+        );
+    }
+}
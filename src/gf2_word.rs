@@ -4,7 +4,10 @@ use std::{
 };
 
 use rand_core::{CryptoRng, RngCore};
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroize;
+
+use crate::error::Error;
 
 pub trait BytesUtils {
     fn to_bytes(&self) -> Vec<u8>;
@@ -12,8 +15,15 @@ pub trait BytesUtils {
     fn from_le_bytes(le_bytes: &[u8]) -> Self;
 }
 
-pub trait GenRand {
+pub trait GenRand: Sized {
     fn gen_rand<R: RngCore + CryptoRng>(rng: &mut R) -> Self;
+
+    /// Generate `n` values at once. Values narrower than a 32-bit output of
+    /// the generator are packed several to an output instead of taking one
+    /// each.
+    fn gen_rand_packed<R: RngCore + CryptoRng>(rng: &mut R, n: usize) -> Vec<Self> {
+        (0..n).map(|_| Self::gen_rand(rng)).collect()
+    }
 }
 
 pub trait BitTrait:
@@ -30,8 +40,16 @@ pub trait BitTrait:
 {
 }
 
+/// A word the circuits compute on.
+///
+/// Gadgets written against `Value` work for every word size: `BitAnd` and
+/// `BitXor` are the gates, `BitUtils` gives the bit length, single bits,
+/// rotations and shifts, `BytesUtils` the byte encodings and `GenRand` the
+/// tapes. It is implemented for `u8` to `u128` and for `Bit`.
 pub trait Value:
     Copy
+    + Send
+    + Sync
     + Debug
     + Default
     + Display
@@ -41,10 +59,36 @@ pub trait Value:
     + BytesUtils
     + GenRand
     + Serialize
+    + Zeroize
 {
+    /// Serialize the messages of a view. Only their values are written, since
+    /// the size of a word follows from `Self`; values narrower than a byte
+    /// override it to pack them densely.
+    fn serialize_messages<S: Serializer>(
+        messages: &[GF2Word<Self>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(messages.iter().map(|msg| msg.value))
+    }
+
+    fn deserialize_messages<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<GF2Word<Self>>, D::Error>
+    where
+        Self: Deserialize<'de>,
+    {
+        let values: Vec<Self> = Vec::deserialize(deserializer)?;
+        Ok(values.into_iter().map(GF2Word::from).collect())
+    }
 }
 
-#[derive(Clone, Copy)]
+/// A single bit, for circuits specified at bit granularity.
+///
+/// Tapes pack 32 bits in each output of the generator and views serialize
+/// their messages 8 to a byte, so a bit costs a bit rather than a machine
+/// word. Witness shares still hold one bit per byte, in the least significant
+/// position.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
 pub struct Bit(u8);
 
 impl Bit {
@@ -53,6 +97,33 @@ impl Bit {
     }
 }
 
+impl From<u8> for Bit {
+    fn from(value: u8) -> Self {
+        Self(value & 1)
+    }
+}
+
+impl<'de> Deserialize<'de> for Bit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match u8::deserialize(deserializer)? {
+            bit @ (0 | 1) => Ok(Self(bit)),
+            _ => Err(de::Error::custom("bit out of range")),
+        }
+    }
+}
+
+impl Display for Bit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Zeroize for Bit {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 impl BitAnd for Bit {
     type Output = Self;
 
@@ -69,6 +140,46 @@ impl BitXor for Bit {
     }
 }
 
+impl BitOr for Bit {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl Not for Bit {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(self.0 ^ 1)
+    }
+}
+
+impl Shl<usize> for Bit {
+    type Output = Self;
+
+    fn shl(self, n: usize) -> Self {
+        if n == 0 {
+            self
+        } else {
+            Self(0)
+        }
+    }
+}
+
+impl Shr<usize> for Bit {
+    type Output = Self;
+
+    fn shr(self, n: usize) -> Self {
+        if n == 0 {
+            self
+        } else {
+            Self(0)
+        }
+    }
+}
+
 pub trait BitUtils: BitTrait {
     fn zero() -> Self {
         Self::from(0)
@@ -92,78 +203,98 @@ pub trait BitUtils: BitTrait {
             *self & !mask
         }
     }
-    /// Rotate left by `n` bits.
+    /// Rotate left by `n` bits, where `n` is taken modulo the bit length.
     fn left_rotate(&self, n: usize) -> Self {
-        assert!(n <= Self::bits_len());
+        let n = n % Self::bits_len();
+        if n == 0 {
+            return *self;
+        }
         (*self << n) | (*self >> (Self::bits_len() - n))
     }
-    /// Rotate right by `n` bits.
+    /// Rotate right by `n` bits, where `n` is taken modulo the bit length.
     fn right_rotate(&self, n: usize) -> Self {
-        assert!(n <= Self::bits_len());
+        let n = n % Self::bits_len();
+        if n == 0 {
+            return *self;
+        }
         (*self >> n) | (*self << (Self::bits_len() - n))
     }
-
+    /// Shift left by `n` bits, shifting everything out if `n` >= bit length.
     fn left_shift(&self, n: usize) -> Self {
-        assert!(n <= Self::bits_len());
+        if n >= Self::bits_len() {
+            return Self::zero();
+        }
         *self << n
     }
-
+    /// Shift right by `n` bits, shifting everything out if `n` >= bit length.
     fn right_shift(&self, n: usize) -> Self {
-        assert!(n <= Self::bits_len());
+        if n >= Self::bits_len() {
+            return Self::zero();
+        }
         *self >> n
     }
 }
 
-impl BitTrait for u8 {}
-impl Value for u8 {}
-impl BitUtils for u8 {
-    fn bits_len() -> usize {
-        Self::BITS as usize
-    }
-}
+/// Implements `Value` for unsigned integers, which provide every operation it
+/// needs natively. Only `GenRand` differs between them, in how many values are
+/// drawn from an output of the generator.
+macro_rules! impl_value {
+    ($($t:ty),*) => {$(
+        impl BitTrait for $t {}
+        impl Value for $t {}
+
+        impl BitUtils for $t {
+            fn bits_len() -> usize {
+                Self::BITS as usize
+            }
+        }
 
-impl BytesUtils for u8 {
-    fn to_bytes(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
-    }
+        impl BytesUtils for $t {
+            fn to_bytes(&self) -> Vec<u8> {
+                self.to_be_bytes().to_vec()
+            }
 
-    fn bytes_len() -> usize {
-        1
-    }
-    fn from_le_bytes(le_bytes: &[u8]) -> Self {
-        assert_eq!(le_bytes.len(), Self::bytes_len());
-        Self::from_le_bytes(le_bytes.try_into().unwrap())
-    }
+            fn bytes_len() -> usize {
+                std::mem::size_of::<Self>()
+            }
+
+            fn from_le_bytes(le_bytes: &[u8]) -> Self {
+                assert_eq!(le_bytes.len(), Self::bytes_len());
+                Self::from_le_bytes(le_bytes.try_into().unwrap())
+            }
+        }
+    )*};
 }
 
+impl_value!(u8, u16, u32, u64, u128);
+
 impl GenRand for u8 {
     fn gen_rand<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
         let mut buff = vec![0u8; 1];
         rng.fill_bytes(&mut buff);
         buff[0]
     }
-}
 
-impl BitTrait for u32 {}
-impl Value for u32 {}
-impl BitUtils for u32 {
-    fn bits_len() -> usize {
-        Self::BITS as usize
+    fn gen_rand_packed<R: RngCore + CryptoRng>(rng: &mut R, n: usize) -> Vec<Self> {
+        let mut buff = vec![0u8; n];
+        rng.fill_bytes(&mut buff);
+        buff
     }
 }
 
-impl BytesUtils for u32 {
-    fn to_bytes(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
-    }
-
-    fn bytes_len() -> usize {
-        4
+impl GenRand for u16 {
+    fn gen_rand<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        let mut buff = [0u8; 2];
+        rng.fill_bytes(&mut buff);
+        u16::from_le_bytes(buff)
     }
 
-    fn from_le_bytes(le_bytes: &[u8]) -> Self {
-        assert_eq!(le_bytes.len(), Self::bytes_len());
-        Self::from_le_bytes(le_bytes.try_into().unwrap())
+    fn gen_rand_packed<R: RngCore + CryptoRng>(rng: &mut R, n: usize) -> Vec<Self> {
+        let mut buff = vec![0u8; 2 * n];
+        rng.fill_bytes(&mut buff);
+        buff.chunks(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect()
     }
 }
 
@@ -173,62 +304,92 @@ impl GenRand for u32 {
     }
 }
 
-impl BitTrait for u64 {}
-impl Value for u64 {}
-impl BitUtils for u64 {
-    fn bits_len() -> usize {
-        Self::BITS as usize
-    }
-}
-
-impl BytesUtils for u64 {
-    fn to_bytes(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
-    }
-    fn bytes_len() -> usize {
-        8
-    }
-    fn from_le_bytes(le_bytes: &[u8]) -> Self {
-        assert_eq!(le_bytes.len(), Self::bytes_len());
-        Self::from_le_bytes(le_bytes.try_into().unwrap())
+impl GenRand for u64 {
+    fn gen_rand<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        rng.next_u64()
     }
 }
 
-impl GenRand for u64 {
+impl GenRand for u128 {
     fn gen_rand<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
-        rng.next_u64()
+        let hi: u128 = rng.next_u64().into();
+        let low: u128 = rng.next_u64().into();
+        (hi << 64) | low
     }
 }
 
-impl BitTrait for u128 {}
-impl Value for u128 {}
-impl BitUtils for u128 {
+impl BitTrait for Bit {}
+impl BitUtils for Bit {
     fn bits_len() -> usize {
-        Self::BITS as usize
+        1
     }
 }
 
-impl BytesUtils for u128 {
+impl BytesUtils for Bit {
     fn to_bytes(&self) -> Vec<u8> {
-        self.to_be_bytes().to_vec()
+        vec![self.0]
     }
+
     fn bytes_len() -> usize {
-        16
+        1
     }
+
     fn from_le_bytes(le_bytes: &[u8]) -> Self {
         assert_eq!(le_bytes.len(), Self::bytes_len());
-        Self::from_le_bytes(le_bytes.try_into().unwrap())
+        le_bytes[0].into()
     }
 }
 
-impl GenRand for u128 {
+impl GenRand for Bit {
     fn gen_rand<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
-        let hi: u128 = rng.next_u64().try_into().unwrap();
-        let low: u128 = rng.next_u64().try_into().unwrap();
-        (hi << 64) | low
+        u8::gen_rand(rng).into()
+    }
+
+    fn gen_rand_packed<R: RngCore + CryptoRng>(rng: &mut R, n: usize) -> Vec<Self> {
+        unpack_bits(&u8::gen_rand_packed(rng, n.div_ceil(8)), n)
+    }
+}
+
+impl Value for Bit {
+    fn serialize_messages<S: Serializer>(
+        messages: &[GF2Word<Self>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let bits: Vec<Bit> = messages.iter().map(|msg| msg.value).collect();
+        (messages.len() as u64, pack_bits(&bits)).serialize(serializer)
+    }
+
+    fn deserialize_messages<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<GF2Word<Self>>, D::Error> {
+        let (len, packed) = <(u64, Vec<u8>)>::deserialize(deserializer)?;
+        let len = len as usize;
+        if packed.len() != len.div_ceil(8) || pack_bits(&unpack_bits(&packed, len)) != packed {
+            return Err(de::Error::custom("malformed packed bits"));
+        }
+        Ok(unpack_bits(&packed, len)
+            .into_iter()
+            .map(GF2Word::from)
+            .collect())
     }
 }
 
+/// Packs bits 8 to a byte, from the least significant bit of the first byte.
+pub fn pack_bits(bits: &[Bit]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|byte| {
+            byte.iter()
+                .enumerate()
+                .fold(0u8, |acc, (i, bit)| acc | bit.0 << i)
+        })
+        .collect()
+}
+
+/// The first `len` bits packed in `bytes`.
+pub fn unpack_bits(bytes: &[u8], len: usize) -> Vec<Bit> {
+    (0..len).map(|i| (bytes[i / 8] >> (i % 8)).into()).collect()
+}
+
 /// A wrapper type for which we implement `BitAnd`, `BitXor`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GF2Word<T: Value> {
@@ -238,14 +399,81 @@ pub struct GF2Word<T: Value> {
     pub size: usize,
 }
 
+impl<T: Value> Zeroize for GF2Word<T> {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+    }
+}
+
 impl<T: Value> From<T> for GF2Word<T> {
     fn from(value: T) -> Self {
         GF2Word::<T> {
             value,
-            size: T::bytes_len() * 8,
+            size: T::bits_len(),
         }
     }
 }
+impl<T: Value> GF2Word<T> {
+    /// Rotate left by `n` bits.
+    pub fn rotl(self, n: usize) -> Self {
+        self.value.left_rotate(n).into()
+    }
+
+    /// Rotate right by `n` bits.
+    pub fn rotr(self, n: usize) -> Self {
+        self.value.right_rotate(n).into()
+    }
+
+    /// The value in `T::bytes_len()` big-endian bytes, as hashed into the
+    /// commitments of views.
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        self.value.to_bytes()
+    }
+
+    /// The value in `T::bytes_len()` little-endian bytes.
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        let mut bytes = self.value.to_bytes();
+        bytes.reverse();
+        bytes
+    }
+
+    /// Inverse of `to_bytes_be`, rejecting bytes it never outputs.
+    pub fn from_bytes_be(bytes: &[u8]) -> Result<Self, Error> {
+        let mut le_bytes = bytes.to_vec();
+        le_bytes.reverse();
+        Self::from_bytes_le(&le_bytes)
+    }
+
+    /// Inverse of `to_bytes_le`, rejecting bytes it never outputs.
+    pub fn from_bytes_le(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != T::bytes_len() {
+            return Err(Error::SerializationError);
+        }
+        let word = Self::from(T::from_le_bytes(bytes));
+        // bits only take the values 0 and 1
+        if word.to_bytes_le() != bytes {
+            return Err(Error::SerializationError);
+        }
+        Ok(word)
+    }
+}
+
+impl<T: Value> Shl<usize> for GF2Word<T> {
+    type Output = Self;
+
+    fn shl(self, n: usize) -> Self {
+        self.value.left_shift(n).into()
+    }
+}
+
+impl<T: Value> Shr<usize> for GF2Word<T> {
+    type Output = Self;
+
+    fn shr(self, n: usize) -> Self {
+        self.value.right_shift(n).into()
+    }
+}
+
 impl<T: Value> BitAnd for GF2Word<T> {
     type Output = Self;
 
@@ -261,6 +489,8 @@ impl<T: Value> BitXor for GF2Word<T> {
     type Output = Self;
 
     fn bitxor(self, rhs: Self) -> Self {
+        #[cfg(feature = "bench")]
+        crate::bench::count_xor();
         Self {
             value: self.value ^ rhs.value,
             size: self.size,
@@ -270,7 +500,8 @@ impl<T: Value> BitXor for GF2Word<T> {
 
 #[cfg(test)]
 mod gf2_word_tests {
-    use super::GF2Word;
+    use super::{pack_bits, unpack_bits, Bit, BitUtils, GF2Word};
+    use crate::view::View;
 
     #[test]
     fn simple_and() {
@@ -305,4 +536,147 @@ mod gf2_word_tests {
 
         assert_eq!((x ^ y).value, v1 ^ v2);
     }
+
+    #[test]
+    fn rotations_and_shifts() {
+        let x: GF2Word<u32> = 0x80000001u32.into();
+
+        assert_eq!(x.rotl(1).value, 0x00000003);
+        assert_eq!(x.rotr(1).value, 0xc0000000);
+        assert_eq!((x << 1).value, 0x00000002);
+        assert_eq!((x >> 1).value, 0x40000000);
+
+        // rotations are taken modulo the bit length
+        assert_eq!(x.rotl(0), x);
+        assert_eq!(x.rotr(32), x);
+        assert_eq!(x.rotl(33), x.rotl(1));
+
+        // shifting by the full bit length clears the word
+        assert_eq!(x << 0, x);
+        assert_eq!((x << 32).value, 0);
+        assert_eq!((x >> 40).value, 0);
+
+        let y: GF2Word<u8> = 0b1001_0110u8.into();
+        assert_eq!(y.rotl(3).value, 0b1001_0110u8.rotate_left(3));
+        assert_eq!(y.rotr(5).value, 0b1001_0110u8.rotate_right(5));
+    }
+
+    #[test]
+    fn test_byte_order() {
+        let word: GF2Word<u32> = 0x01020304u32.into();
+        assert_eq!(word.to_bytes_be(), [1, 2, 3, 4]);
+        assert_eq!(word.to_bytes_le(), [4, 3, 2, 1]);
+        assert_eq!(GF2Word::<u32>::from_bytes_be(&[1, 2, 3, 4]).unwrap(), word);
+        assert_eq!(GF2Word::<u32>::from_bytes_le(&[4, 3, 2, 1]).unwrap(), word);
+        assert!(GF2Word::<u32>::from_bytes_le(&[4, 3, 2]).is_err());
+
+        let word: GF2Word<u16> = 0xabcdu16.into();
+        assert_eq!(
+            GF2Word::<u16>::from_bytes_be(&word.to_bytes_be()).unwrap(),
+            word
+        );
+        assert_eq!(
+            GF2Word::<u16>::from_bytes_le(&word.to_bytes_le()).unwrap(),
+            word
+        );
+
+        let bit = GF2Word::from(Bit::from(1));
+        assert_eq!(bit.to_bytes_be(), [1]);
+        assert_eq!(GF2Word::<Bit>::from_bytes_le(&[1]).unwrap(), bit);
+        assert!(GF2Word::<Bit>::from_bytes_le(&[3]).is_err());
+    }
+
+    /// The operations of every unsigned word size match those of the
+    /// primitive type.
+    macro_rules! word_size_tests {
+        ($($t:ident),*) => {$(
+            mod $t {
+                use rand::{thread_rng, Rng};
+
+                use crate::gf2_word::{BytesUtils, GF2Word};
+
+                #[test]
+                fn test_word_ops() {
+                    let mut rng = thread_rng();
+                    let (x, y) = (rng.gen::<$t>(), rng.gen::<$t>());
+                    let word = GF2Word::from(x);
+                    let bits = <$t>::BITS as usize;
+
+                    assert_eq!(word.size, bits);
+                    assert_eq!(<$t as BytesUtils>::bytes_len(), bits / 8);
+                    assert_eq!((word & y.into()).value, x & y);
+                    assert_eq!((word ^ y.into()).value, x ^ y);
+
+                    for n in [0, 1, 7, bits - 1, bits, bits + 1] {
+                        assert_eq!(word.rotl(n).value, x.rotate_left(n as u32));
+                        assert_eq!(word.rotr(n).value, x.rotate_right(n as u32));
+                        assert_eq!((word << n).value, x.checked_shl(n as u32).unwrap_or(0));
+                        assert_eq!((word >> n).value, x.checked_shr(n as u32).unwrap_or(0));
+                    }
+
+                    assert_eq!(word.to_bytes_be(), x.to_be_bytes());
+                    assert_eq!(word.to_bytes_le(), x.to_le_bytes());
+                    assert_eq!(GF2Word::<$t>::from_bytes_be(&x.to_be_bytes()).unwrap(), word);
+                    assert_eq!(GF2Word::<$t>::from_bytes_le(&x.to_le_bytes()).unwrap(), word);
+                }
+            }
+        )*};
+    }
+
+    word_size_tests!(u8, u16, u32, u64, u128);
+
+    #[test]
+    fn test_bit() {
+        let (zero, one) = (Bit::from(0), Bit::from(1));
+        assert_eq!(Bit::from(3), one);
+        assert_eq!(!zero, one);
+        assert_eq!(one ^ one, zero);
+        assert_eq!(one & zero, zero);
+        assert_eq!(one << 1, zero);
+        assert_eq!(GF2Word::from(one).rotl(5).value, one);
+        assert_eq!(GF2Word::from(one).size, 1);
+        assert_eq!(zero.set_bit(0, true), one);
+
+        let bits: Vec<Bit> = [1, 0, 1, 1, 0, 0, 0, 1, 1].map(Bit::from).to_vec();
+        let packed = pack_bits(&bits);
+        assert_eq!(packed, vec![0b1000_1101, 0b1]);
+        assert_eq!(unpack_bits(&packed, bits.len()), bits);
+
+        assert!(bincode::deserialize::<Bit>(&[2]).is_err());
+    }
+
+    #[test]
+    fn test_messages() {
+        let mut view = View::<u32>::new(vec![1, 2, 3]);
+        for i in 0..100u32 {
+            view.send_msg(i.into());
+        }
+
+        // messages take the bytes of their value only
+        let bytes = bincode::serialize(&view).unwrap();
+        assert_eq!(bytes.len(), (8 + 3) + (8 + 4 * 100));
+
+        let decoded: View<u32> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.messages, view.messages);
+    }
+
+    #[test]
+    fn test_bit_messages() {
+        let mut view = View::<Bit>::new(vec![1, 2, 3]);
+        for i in 0..100u8 {
+            view.send_msg(Bit::from(i % 3).into());
+        }
+
+        // messages take a bit each, after their count and byte length
+        let bytes = bincode::serialize(&view).unwrap();
+        assert_eq!(bytes.len(), (8 + 3) + 8 + (8 + 13));
+
+        let decoded: View<Bit> = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.messages, view.messages);
+
+        // padding bits must be zero
+        let mut malformed = bytes.clone();
+        *malformed.last_mut().unwrap() |= 0x80;
+        assert!(bincode::deserialize::<View<Bit>>(&malformed).is_err());
+    }
 }
@@ -0,0 +1,348 @@
+//! Self-describing header for serialized proofs.
+//!
+//! `Proof::to_bytes` leaves out everything the verifier is expected to know:
+//! the hash, the tape expander, the word type and the circuit. A proof written
+//! with `Proof::write_with_header_to` starts with a `ProofHeader` recording
+//! them, so that a verifier handed a proof made for other parameters reports
+//! which one differs, with `Error::ParameterMismatchError`, instead of failing
+//! somewhere during verification.
+//!
+//! Integers are little-endian:
+//!
+//! ```text
+//! header      = magic: "ZKBH" || variant: u8 || hash_id: u64
+//!               || expander_id: u64 || word_bits: u64 || security_param: u64
+//!               || num_of_repetitions: u64 || circuit_id: [u8; HASH_LEN]
+//! hash_id     = first 8 bytes of D(hash_domain)
+//! expander_id = first 8 bytes of D(expander_domain || TapeExpander::ID)
+//! circuit_id  = D(circuit_domain || word_bits: u64 || len: u64 || id
+//!               || len: u64 || public_input)
+//! ```
+//!
+//! where `id` is the `Circuit::id` of the circuit. Hash functions have no
+//! registry of identifiers, so a hash is identified by its digest of a fixed
+//! string.
+
+use std::io::{Read, Write};
+
+use sha3::Digest;
+
+use crate::{
+    circuit::Circuit, config::HASH_LEN, error::Error, expander::TapeExpander, gf2_word::Value,
+    num_of_repetitions_given_desired_security,
+};
+
+const MAGIC: &[u8; 4] = b"ZKBH";
+const HASH_DOMAIN: &[u8] = b"zkboo-hash-id-v1";
+const EXPANDER_DOMAIN: &[u8] = b"zkboo-expander-id-v1";
+const CIRCUIT_DOMAIN: &[u8] = b"zkboo-circuit-id-v1";
+
+/// The protocol a proof was made with. This crate only makes and verifies
+/// `ZkBoo` proofs; the others are named so that their proofs are recognised
+/// and rejected as such.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProtocolVariant {
+    ZkBoo,
+    ZkbPlusPlus,
+    Kkw,
+}
+
+impl ProtocolVariant {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::ZkBoo => 0,
+            Self::ZkbPlusPlus => 1,
+            Self::Kkw => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self, Error> {
+        match byte {
+            0 => Ok(Self::ZkBoo),
+            1 => Ok(Self::ZkbPlusPlus),
+            2 => Ok(Self::Kkw),
+            _ => Err(Error::SerializationError),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofHeader {
+    pub variant: ProtocolVariant,
+    pub hash_id: u64,
+    pub expander_id: u64,
+    pub word_bits: usize,
+    pub security_param: usize,
+    pub num_of_repetitions: usize,
+    pub circuit_id: [u8; HASH_LEN],
+}
+
+impl ProofHeader {
+    /// Length of a serialized header in bytes.
+    pub const LEN: usize = MAGIC.len() + 1 + 5 * 8 + HASH_LEN;
+
+    /// The header of a ZKBoo proof of `circuit` for `security_param`, with
+    /// words `T`, tapes expanded by `TapeR` and hash `D`.
+    pub fn new<T: Value, TapeR: TapeExpander, D: Digest>(
+        circuit: &(impl Circuit<T> + ?Sized),
+        security_param: usize,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            variant: ProtocolVariant::ZkBoo,
+            hash_id: hash_id::<D>()?,
+            expander_id: expander_id::<TapeR, D>()?,
+            word_bits: T::bits_len(),
+            security_param,
+            num_of_repetitions: num_of_repetitions_given_desired_security(security_param),
+            circuit_id: circuit_id::<T, D>(circuit)?,
+        })
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writer
+            .write_all(&self.to_bytes())
+            .map_err(|_| Error::SerializationError)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::LEN);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(self.variant.to_byte());
+        for id in [self.hash_id, self.expander_id] {
+            bytes.extend_from_slice(&id.to_le_bytes());
+        }
+        for x in [self.word_bits, self.security_param, self.num_of_repetitions] {
+            bytes.extend_from_slice(&(x as u64).to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.circuit_id);
+        bytes
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let mut bytes = [0u8; Self::LEN];
+        reader
+            .read_exact(&mut bytes)
+            .map_err(|_| Error::SerializationError)?;
+        if &bytes[..MAGIC.len()] != MAGIC {
+            return Err(Error::SerializationError);
+        }
+
+        let mut rest = &bytes[MAGIC.len()..];
+        let mut take = |n: usize| {
+            let (taken, tail) = rest.split_at(n);
+            rest = tail;
+            taken
+        };
+        let variant = ProtocolVariant::from_byte(take(1)[0])?;
+        let mut read_u64 = || u64::from_le_bytes(take(8).try_into().unwrap());
+        let hash_id = read_u64();
+        let expander_id = read_u64();
+        let mut read_usize = || usize::try_from(read_u64()).map_err(|_| Error::SerializationError);
+
+        Ok(Self {
+            variant,
+            hash_id,
+            expander_id,
+            word_bits: read_usize()?,
+            security_param: read_usize()?,
+            num_of_repetitions: read_usize()?,
+            circuit_id: take(HASH_LEN).try_into().unwrap(),
+        })
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = bytes;
+        Self::read_from(&mut reader)
+    }
+
+    /// Check that this header is `expected`, failing on the first field which
+    /// differs with the expected and the found value.
+    pub fn check(&self, expected: &Self) -> Result<(), Error> {
+        let mismatch = |field: &'static str, expected: String, found: String| {
+            Err(Error::ParameterMismatchError(field, expected, found))
+        };
+
+        if self.variant != expected.variant {
+            return mismatch(
+                "protocol variant",
+                format!("{:?}", expected.variant),
+                format!("{:?}", self.variant),
+            );
+        }
+        let ids = [
+            ("hash function", expected.hash_id, self.hash_id),
+            ("tape expander", expected.expander_id, self.expander_id),
+        ];
+        for (field, expected, found) in ids {
+            if expected != found {
+                return mismatch(field, format!("{expected:016x}"), format!("{found:016x}"));
+            }
+        }
+        let sizes = [
+            ("word bits", expected.word_bits, self.word_bits),
+            (
+                "security parameter",
+                expected.security_param,
+                self.security_param,
+            ),
+            (
+                "number of repetitions",
+                expected.num_of_repetitions,
+                self.num_of_repetitions,
+            ),
+        ];
+        for (field, expected, found) in sizes {
+            if expected != found {
+                return mismatch(field, expected.to_string(), found.to_string());
+            }
+        }
+        if self.circuit_id != expected.circuit_id {
+            return mismatch("circuit", hex(&expected.circuit_id), hex(&self.circuit_id));
+        }
+
+        Ok(())
+    }
+}
+
+/// Identifier of hash `D`, see the module documentation.
+pub fn hash_id<D: Digest>() -> Result<u64, Error> {
+    short_digest::<D>(&[HASH_DOMAIN])
+}
+
+/// Identifier of tape expander `TapeR` under hash `D`, see the module
+/// documentation.
+pub fn expander_id<TapeR: TapeExpander, D: Digest>() -> Result<u64, Error> {
+    short_digest::<D>(&[EXPANDER_DOMAIN, TapeR::ID.as_bytes()])
+}
+
+/// The first 8 bytes of the digest of `parts`.
+fn short_digest<D: Digest>(parts: &[&[u8]]) -> Result<u64, Error> {
+    let mut hasher = D::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    let digest = hasher.finalize();
+    let bytes = digest
+        .get(..8)
+        .ok_or(Error::HashLenError(8, digest.len()))?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Identifier of `circuit` with words `T` under hash `D`, see the module
+/// documentation. Circuits of the same id and public input share it.
+pub fn circuit_id<T: Value, D: Digest>(
+    circuit: &(impl Circuit<T> + ?Sized),
+) -> Result<[u8; HASH_LEN], Error> {
+    let digest_len = <D as Digest>::output_size();
+    if HASH_LEN != digest_len {
+        return Err(Error::HashLenError(HASH_LEN, digest_len));
+    }
+
+    let mut hasher = D::new_with_prefix(CIRCUIT_DOMAIN);
+    hasher.update((T::bits_len() as u64).to_le_bytes());
+    for bytes in [circuit.id(), circuit.public_input()] {
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(&bytes);
+    }
+    // safe to unwrap since we check digest output is of right side
+    Ok(hasher.finalize().to_vec().try_into().unwrap())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod header_tests {
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha2::Sha256;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::Circuit,
+        error::Error,
+        expander::{LegacyChaCha20Rng, Shake128Expander},
+        gadgets::add_mod::AdderCircuit,
+        prover::Prover,
+        verifier::Verifier,
+    };
+
+    use super::{ProofHeader, ProtocolVariant};
+
+    const SIGMA: usize = 40;
+    type V = Verifier<u32, ChaCha20Rng, Keccak256>;
+
+    #[test]
+    fn test_proof_with_header() {
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<ThreadRng, SIGMA>(
+            &mut thread_rng(),
+            &input,
+            &circuit,
+            &output,
+        )
+        .unwrap();
+
+        let bytes = proof.to_bytes_with_header::<ChaCha20Rng>(&circuit).unwrap();
+        let header = ProofHeader::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            header,
+            ProofHeader::new::<u32, ChaCha20Rng, Keccak256>(&circuit, SIGMA).unwrap()
+        );
+        assert_eq!(header.num_of_repetitions, proof.num_of_repetitions());
+        V::verify_with_header::<SIGMA, _>(&mut &bytes[..], &circuit, &output).unwrap();
+
+        let mismatch = |bytes: &[u8]| match V::verify_with_header::<SIGMA, _>(
+            &mut &bytes[..],
+            &circuit,
+            &output,
+        ) {
+            Err(Error::ParameterMismatchError(field, _, _)) => field,
+            other => panic!("expected a parameter mismatch, got {:?}", other.err()),
+        };
+        let with_header =
+            |header: ProofHeader| [header.to_bytes(), bytes[ProofHeader::LEN..].to_vec()].concat();
+
+        let mut other = header.clone();
+        other.variant = ProtocolVariant::Kkw;
+        assert_eq!(mismatch(&with_header(other)), "protocol variant");
+
+        let other = ProofHeader::new::<u32, ChaCha20Rng, Sha256>(&circuit, SIGMA).unwrap();
+        assert_eq!(mismatch(&with_header(other)), "hash function");
+
+        let other = ProofHeader::new::<u32, LegacyChaCha20Rng, Keccak256>(&circuit, SIGMA);
+        assert_eq!(mismatch(&with_header(other.unwrap())), "tape expander");
+        assert!(matches!(
+            Verifier::<u32, Shake128Expander, Keccak256>::verify_with_header::<SIGMA, _>(
+                &mut &bytes[..],
+                &circuit,
+                &output
+            ),
+            Err(Error::ParameterMismatchError("tape expander", _, _))
+        ));
+
+        let other =
+            ProofHeader::new::<u64, ChaCha20Rng, Keccak256>(&AdderCircuit::<u64>::new(), SIGMA);
+        assert_eq!(mismatch(&with_header(other.unwrap())), "word bits");
+
+        let other = ProofHeader::new::<u32, ChaCha20Rng, Keccak256>(&circuit, 80).unwrap();
+        assert_eq!(mismatch(&with_header(other)), "security parameter");
+
+        let mut other = header.clone();
+        other.num_of_repetitions += 1;
+        assert_eq!(mismatch(&with_header(other)), "number of repetitions");
+
+        let mut other = header;
+        other.circuit_id[0] ^= 1;
+        assert_eq!(mismatch(&with_header(other)), "circuit");
+
+        // not a header
+        assert!(matches!(
+            V::verify_with_header::<SIGMA, _>(&mut &bytes[1..], &circuit, &output),
+            Err(Error::SerializationError)
+        ));
+    }
+}
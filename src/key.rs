@@ -1,26 +1,75 @@
 use rand::{CryptoRng, RngCore};
+use sha3::{Digest, Keccak256};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
-use crate::config::KEY_LEN;
+use crate::{
+    config::{HASH_LEN, KEY_LEN},
+    error::Error,
+};
 
 pub type Key = [u8; KEY_LEN];
+
+/// Separates the blinding of a party's commitment from its tape, whether the
+/// tape is expanded from `labeled_key(key, tape_label(..))` or, for legacy
+/// proofs, from the key itself.
+const BLINDING_DOMAIN: &[u8] = b"zkboo-blinding-v1";
+
+/// Blinding of the commitment to the view of the party holding `key`,
+/// `H(domain || key)`.
+pub fn derive_blinding<D: Digest>(key: &Key) -> Result<[u8; HASH_LEN], Error> {
+    let digest_len = <D as Digest>::output_size();
+    if HASH_LEN != digest_len {
+        return Err(Error::HashLenError(HASH_LEN, digest_len));
+    }
+    let mut hasher = D::new_with_prefix(BLINDING_DOMAIN);
+    hasher.update(key);
+    // safe to unwrap since we check digest output is of right side
+    Ok(hasher.finalize().to_vec().try_into().unwrap())
+}
+
+/// Separates the keys derived by `KeyManager` from other uses of its seed.
+const KEY_DOMAIN: &[u8] = b"zkboo-key-v1";
+
+/// Hands out the `3 * num_repetitions` party keys of a proof, the key of party
+/// `j` in repetition `r` at index `3 * r + j`.
+///
+/// Only a master seed is stored: key `i` is `Keccak256(domain || seed || i)`
+/// with `i` as a little-endian `u64`, derived when it is requested. Keys are
+/// revealed to the verifier through `reveal`, which never exposes the seed.
 #[derive(Clone, Default)]
 pub struct KeyManager {
-    pub keys_bytes: Vec<u8>,
+    seed: Key,
     num_of_accessible_keys: usize,
     offset: usize,
 }
 
 impl KeyManager {
     pub fn new<R: RngCore + CryptoRng>(num_repetitions: usize, rng: &mut R) -> Self {
-        let mut keys_bytes = vec![0u8; 3 * num_repetitions * KEY_LEN];
-        rng.fill_bytes(&mut keys_bytes);
+        let mut seed = [0u8; KEY_LEN];
+        rng.fill_bytes(&mut seed);
+        Self::from_seed(seed, num_repetitions)
+    }
+
+    pub fn from_seed(seed: Key, num_repetitions: usize) -> Self {
         Self {
-            keys_bytes,
+            seed,
             num_of_accessible_keys: 3 * num_repetitions,
             offset: 0,
         }
     }
 
+    pub fn num_of_keys(&self) -> usize {
+        self.num_of_accessible_keys
+    }
+
+    fn derive_key(&self, pos: usize) -> Key {
+        let mut hasher = Keccak256::new_with_prefix(KEY_DOMAIN);
+        hasher.update(self.seed);
+        hasher.update((pos as u64).to_le_bytes());
+        hasher.finalize().into()
+    }
+
     pub fn request_key(&mut self) -> Key {
         let offset = self.offset;
         if offset == self.num_of_accessible_keys {
@@ -28,14 +77,180 @@ impl KeyManager {
         }
 
         self.offset += 1;
-        self.keys_bytes[offset * KEY_LEN..(offset + 1) * KEY_LEN]
-            .try_into()
-            .unwrap()
+        self.derive_key(offset)
     }
 
     pub fn request_key_i(&self, pos: usize) -> Key {
-        self.keys_bytes[pos * KEY_LEN..(pos + 1) * KEY_LEN]
-            .try_into()
+        assert!(pos < self.num_of_accessible_keys, "No key at {pos}!");
+        self.derive_key(pos)
+    }
+
+    /// The keys at `positions`, in order, e.g. those of the two opened parties
+    /// of every repetition.
+    pub fn reveal(&self, positions: impl IntoIterator<Item = usize>) -> Result<Vec<Key>, Error> {
+        positions
+            .into_iter()
+            .map(|pos| {
+                if pos < self.num_of_accessible_keys {
+                    Ok(self.derive_key(pos))
+                } else {
+                    Err(Error::OutOfRangeError)
+                }
+            })
+            .collect()
+    }
+
+    /// Returns `true` if every byte of the seed is the same, as drawn from a
+    /// broken generator.
+    ///
+    /// Keys are distinct within a proof whatever the seed, but such a
+    /// generator gives the same keys to every proof, which correlates the
+    /// tapes of their unopened parties.
+    pub fn has_constant_seed(&self) -> bool {
+        self.seed.iter().all(|&byte| byte == self.seed[0])
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for KeyManager {
+    fn drop(&mut self) {
+        self.seed.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod key_tests {
+    use std::collections::HashSet;
+
+    use rand::{CryptoRng, RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::{Keccak224, Keccak256};
+
+    use super::{derive_blinding, KeyManager};
+    use crate::{
+        circuit::Circuit, commitment::Blinding, config::KEY_LEN, data_structures::PartyExecution,
+        gadgets::add_mod::AdderCircuit, prover::Prover, view::View,
+    };
+
+    /// A broken rng which always outputs the same bytes.
+    pub struct ConstantRng;
+
+    impl RngCore for ConstantRng {
+        fn next_u32(&mut self) -> u32 {
+            7
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            7
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.fill(7)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for ConstantRng {}
+
+    #[test]
+    fn test_keys_are_distinct() {
+        let mut rng = ChaCha20Rng::from_seed([42u8; KEY_LEN]);
+        let key_manager = KeyManager::new(137, &mut rng);
+        assert!(!key_manager.has_constant_seed());
+        let keys: HashSet<_> = key_manager
+            .reveal(0..3 * 137)
             .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(keys.len(), 3 * 137);
+    }
+
+    #[test]
+    fn test_deterministic_keys() {
+        let num_of_repetitions = 69;
+        let mut rng = ChaCha20Rng::from_seed([1u8; KEY_LEN]);
+        let mut key_manager = KeyManager::new(num_of_repetitions, &mut rng);
+        let mut rng = ChaCha20Rng::from_seed([1u8; KEY_LEN]);
+        let same_key_manager = KeyManager::new(num_of_repetitions, &mut rng);
+
+        assert_eq!(
+            key_manager.reveal(0..3 * num_of_repetitions).unwrap(),
+            same_key_manager.reveal(0..3 * num_of_repetitions).unwrap()
+        );
+
+        // keys of consecutive repetitions differ
+        let first_repetition: Vec<_> = (0..3).map(|_| key_manager.request_key()).collect();
+        let second_repetition: Vec<_> = (0..3).map(|_| key_manager.request_key()).collect();
+        for key in first_repetition.iter() {
+            assert!(!second_repetition.contains(key));
+        }
+    }
+
+    #[test]
+    fn test_keys_are_derived_from_seed() {
+        let key_manager = KeyManager::new(2, &mut ConstantRng);
+        let same_key_manager = KeyManager::from_seed([7u8; KEY_LEN], 2);
+        assert_eq!(key_manager.num_of_keys(), 6);
+
+        // a broken rng still gives distinct keys within a proof, but the same
+        // keys to every proof
+        assert!(key_manager.has_constant_seed());
+        assert_eq!(
+            key_manager.reveal([4, 0]).unwrap(),
+            vec![
+                same_key_manager.request_key_i(4),
+                same_key_manager.request_key_i(0)
+            ]
+        );
+        assert!(!key_manager.reveal(0..6).unwrap().contains(&[7u8; KEY_LEN]));
+        assert!(matches!(
+            key_manager.reveal([6]),
+            Err(crate::error::Error::OutOfRangeError)
+        ));
+
+        // and the prover refuses to use it
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+        assert!(matches!(
+            Prover::<u32, ChaCha20Rng, Keccak256>::prove::<_, 40>(
+                &mut ConstantRng,
+                &input,
+                &circuit,
+                &output
+            ),
+            Err(crate::error::Error::KeySeedError)
+        ));
+    }
+
+    #[test]
+    fn test_blinding() {
+        let key = [3u8; KEY_LEN];
+        let blinding = derive_blinding::<Keccak256>(&key).unwrap();
+        assert_ne!(blinding, key);
+        assert_eq!(blinding, derive_blinding::<Keccak256>(&key).unwrap());
+        assert_ne!(
+            blinding,
+            derive_blinding::<Keccak256>(&[4u8; KEY_LEN]).unwrap()
+        );
+        assert!(derive_blinding::<Keccak224>(&key).is_err());
+
+        // views are not committed to under the tape key
+        let view = View::<u32>::new(vec![1, 2, 3, 4]);
+        let commitment = PartyExecution {
+            key: &key,
+            view: &view,
+        }
+        .commit::<Keccak256>()
+        .unwrap();
+        let message = view.to_bytes_be();
+        assert!(!commitment.verify_opening(&Blinding(key), &message).unwrap());
+        assert!(commitment
+            .verify_opening(&Blinding(blinding), &message)
+            .unwrap());
     }
 }
@@ -1,19 +1,52 @@
+pub mod batch;
+#[cfg(feature = "bench")]
+pub mod bench;
 pub mod circuit;
 pub mod commitment;
 pub mod config;
+pub mod conformance;
+pub mod credentials;
 pub mod data_structures;
+pub mod encoding;
 pub mod error;
+pub mod expander;
+#[cfg(feature = "fault-injection")]
+pub mod fault;
+#[cfg(not(feature = "fault-injection"))]
+mod fault;
 pub mod fs;
 pub mod gf2_word;
+pub mod header;
 pub mod key;
+pub mod merkle;
+pub mod params;
 pub mod party;
+pub mod progress;
 pub mod prover;
+pub mod registry;
+pub mod serialized;
+pub mod sharing;
+pub mod signature;
+pub mod statement;
+pub mod statements;
 pub mod tape;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "testvectors")]
+pub mod testvectors;
+#[cfg(feature = "transport")]
+pub mod transport;
 pub mod verifier;
 pub mod view;
+pub mod view_store;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod witness;
 
 pub mod gadgets;
 
+pub use serialized::verify_bytes;
+
 pub fn num_of_repetitions_given_desired_security(sigma: usize) -> usize {
     // log_2(3) - 1
     let log_2_3_minus_1: f64 = 0.58496;
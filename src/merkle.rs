@@ -0,0 +1,186 @@
+//! Merkle trees over `HASH_LEN` byte leaves, with any hash `D`.
+//!
+//! Unlike `gadgets::merkle`, these are not meant to be recomputed in a circuit.
+//! Leaves and inner nodes are hashed under different prefixes,
+//! `H(0x00 || leaf data)` and `H(0x01 || left || right)`, so a node can't be
+//! passed off as a leaf. A level of odd length moves its last node up
+//! unchanged, rather than pairing it with itself.
+//!
+//! `Prover::prove_merkle` commits to the parties of a proof with a tree,
+//! see `MerkleProof`.
+
+use sha3::Digest;
+
+use crate::{config::HASH_LEN, error::Error};
+
+pub type Node = [u8; HASH_LEN];
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+fn hash<D: Digest>(prefix: u8, parts: &[&[u8]]) -> Result<Node, Error> {
+    let digest_len = <D as Digest>::output_size();
+    if HASH_LEN != digest_len {
+        return Err(Error::HashLenError(HASH_LEN, digest_len));
+    }
+    let mut hasher = D::new_with_prefix([prefix]);
+    for part in parts {
+        hasher.update(part);
+    }
+    // safe to unwrap since we check digest output is of right side
+    Ok(hasher.finalize().to_vec().try_into().unwrap())
+}
+
+/// The leaf holding `data`.
+pub fn hash_leaf<D: Digest>(data: &[u8]) -> Result<Node, Error> {
+    hash::<D>(LEAF_PREFIX, &[data])
+}
+
+fn hash_node<D: Digest>(left: &Node, right: &Node) -> Result<Node, Error> {
+    hash::<D>(NODE_PREFIX, &[left, right])
+}
+
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    /// From the leaves up to the root.
+    levels: Vec<Vec<Node>>,
+}
+
+impl MerkleTree {
+    pub fn new<D: Digest>(leaves: Vec<Node>) -> Result<Self, Error> {
+        if leaves.is_empty() {
+            return Err(Error::OutOfRangeError);
+        }
+
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let level = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_node::<D>(left, right),
+                    [last] => Ok(*last),
+                    _ => unreachable!(),
+                })
+                .collect::<Result<_, Error>>()?;
+            levels.push(level);
+        }
+
+        Ok(Self { levels })
+    }
+
+    pub fn root(&self) -> Node {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    pub fn num_of_leaves(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Siblings of the leaf at `index` from the bottom up, skipping levels on
+    /// which its ancestor is moved up unchanged.
+    pub fn path(&self, index: usize) -> Vec<Node> {
+        let mut path = vec![];
+        let mut index = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                path.push(*sibling);
+            }
+            index /= 2;
+        }
+        path
+    }
+}
+
+/// Root of a tree of `num_of_leaves` leaves, with `leaf` at `index` and
+/// authentication path `path`.
+pub fn root_from_path<D: Digest>(
+    leaf: Node,
+    index: usize,
+    num_of_leaves: usize,
+    path: &[Node],
+) -> Result<Node, Error> {
+    if index >= num_of_leaves {
+        return Err(Error::OutOfRangeError);
+    }
+
+    let mut siblings = path.iter();
+    let (mut node, mut index, mut level_len) = (leaf, index, num_of_leaves);
+    while level_len > 1 {
+        if index ^ 1 < level_len {
+            let sibling = siblings.next().ok_or(Error::VerificationError)?;
+            node = if index % 2 == 0 {
+                hash_node::<D>(&node, sibling)?
+            } else {
+                hash_node::<D>(sibling, &node)?
+            };
+        }
+        index /= 2;
+        level_len = level_len.div_ceil(2);
+    }
+
+    if siblings.next().is_some() {
+        return Err(Error::VerificationError);
+    }
+    Ok(node)
+}
+
+#[cfg(test)]
+mod merkle_tests {
+    use sha3::{Keccak224, Keccak256};
+
+    use super::{hash_leaf, root_from_path, MerkleTree, Node};
+
+    fn leaves(n: u8) -> Vec<Node> {
+        (0..n)
+            .map(|i| hash_leaf::<Keccak256>(&[i]).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_paths() {
+        for n in [1, 2, 3, 5, 8, 69] {
+            let leaves = leaves(n);
+            let tree = MerkleTree::new::<Keccak256>(leaves.clone()).unwrap();
+            assert_eq!(tree.num_of_leaves(), n as usize);
+
+            for (i, &leaf) in leaves.iter().enumerate() {
+                let path = tree.path(i);
+                assert_eq!(
+                    root_from_path::<Keccak256>(leaf, i, leaves.len(), &path).unwrap(),
+                    tree.root()
+                );
+
+                // another position, a truncated or an extended path
+                if n > 1 {
+                    let j = (i + 1) % leaves.len();
+                    assert_ne!(
+                        root_from_path::<Keccak256>(leaf, j, leaves.len(), &tree.path(j)).unwrap(),
+                        tree.root()
+                    );
+                    assert!(
+                        root_from_path::<Keccak256>(leaf, i, leaves.len(), &path[1..]).is_err()
+                    );
+                }
+                let extended = [path.clone(), vec![leaf]].concat();
+                assert!(root_from_path::<Keccak256>(leaf, i, leaves.len(), &extended).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_structure() {
+        let leaves = leaves(3);
+        let tree = MerkleTree::new::<Keccak256>(leaves.clone()).unwrap();
+
+        // the odd leaf is moved up unchanged and the single leaf tree is the leaf
+        assert_eq!(tree.path(2), vec![tree.levels[1][0]]);
+        assert_eq!(tree.levels[1][1], leaves[2]);
+        let single = MerkleTree::new::<Keccak256>(vec![leaves[0]]).unwrap();
+        assert_eq!(single.root(), leaves[0]);
+        assert!(single.path(0).is_empty());
+
+        assert!(MerkleTree::new::<Keccak256>(vec![]).is_err());
+        assert!(hash_leaf::<Keccak224>(&[0]).is_err());
+        assert!(root_from_path::<Keccak256>(leaves[0], 3, 3, &[]).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+//! Proof parameters chosen at runtime.
+//!
+//! The `SIGMA` const generic derives the number of repetitions from a target
+//! security level. `ProofParams` instead fixes the number of repetitions
+//! directly, along with the commitment hash `D` and tape generator `TapeR`, so
+//! soundness can be traded for speed explicitly. Proofs made with `ProofParams`
+//! only verify under the same parameters.
+
+use std::marker::PhantomData;
+
+use crate::{expander::TapeExpander, num_of_repetitions_given_desired_security};
+
+pub struct ProofParams<TapeR, D> {
+    repetitions: usize,
+    security_param: usize,
+    pd: PhantomData<(TapeR, D)>,
+}
+
+impl<TapeR, D> ProofParams<TapeR, D> {
+    /// Parameters with soundness error `2^-sigma`, as used by the `SIGMA` path.
+    pub fn from_security(sigma: usize) -> Self {
+        Self {
+            repetitions: num_of_repetitions_given_desired_security(sigma),
+            security_param: sigma,
+            pd: PhantomData,
+        }
+    }
+
+    /// Parameters with exactly `repetitions` repetitions, i.e. with soundness
+    /// error `(2/3)^repetitions`.
+    pub fn with_repetitions(repetitions: usize) -> Self {
+        Self {
+            repetitions,
+            security_param: (repetitions as f64 * (3f64 / 2.).log2()).floor() as usize,
+            pd: PhantomData,
+        }
+    }
+
+    /// The parameters a proof of `repetitions` repetitions for the security
+    /// level `security_param` was made under, by either constructor.
+    pub fn of_proof(security_param: usize, repetitions: usize) -> Option<Self> {
+        [
+            Self::from_security(security_param),
+            Self::with_repetitions(repetitions),
+        ]
+        .into_iter()
+        .find(|params| params.repetitions == repetitions && params.security_param == security_param)
+    }
+
+    pub fn repetitions(&self) -> usize {
+        self.repetitions
+    }
+
+    /// The soundness of the parameters in bits, which the Fiat-Shamir
+    /// transcript is bound to.
+    pub fn security_param(&self) -> usize {
+        self.security_param
+    }
+}
+
+impl<TapeR: TapeExpander, D> ProofParams<TapeR, D> {
+    /// `TapeExpander::ID` of the generator the tapes are expanded with.
+    pub fn tape_expander(&self) -> &'static str {
+        TapeR::ID
+    }
+}
+
+impl<TapeR, D> Clone for ProofParams<TapeR, D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<TapeR, D> Copy for ProofParams<TapeR, D> {}
+
+impl<TapeR: TapeExpander, D> std::fmt::Debug for ProofParams<TapeR, D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProofParams")
+            .field("repetitions", &self.repetitions)
+            .field("security_param", &self.security_param)
+            .field("tape_expander", &self.tape_expander())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod params_tests {
+    use rand::thread_rng;
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::Circuit,
+        data_structures::{Proof, TypedProof},
+        error::Error,
+        expander::{LegacyChaCha20Rng, Shake128Expander},
+        gadgets::add_mod::AdderCircuit,
+        prover::Prover,
+        verifier::Verifier,
+    };
+
+    use super::ProofParams;
+
+    type Params = ProofParams<ChaCha20Rng, Keccak256>;
+    type P = Prover<u32, ChaCha20Rng, Keccak256>;
+    type V = Verifier<u32, ChaCha20Rng, Keccak256>;
+
+    #[test]
+    fn test_params() {
+        let params = Params::from_security(40);
+        assert_eq!(params.repetitions(), 69);
+        assert_eq!(params.security_param(), 40);
+
+        // 69 repetitions give 40 bits of soundness
+        assert_eq!(Params::with_repetitions(69).security_param(), 40);
+        assert_eq!(Params::with_repetitions(20).security_param(), 11);
+    }
+
+    #[test]
+    fn test_prove_with_params() {
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        let params = Params::with_repetitions(20);
+        let proof = P::prove_with_params(&mut rng, &input, &circuit, &output, &params).unwrap();
+        assert_eq!(proof.num_of_repetitions(), 20);
+
+        V::verify_with_params(&proof, &circuit, &output, &params).unwrap();
+        assert!(
+            V::verify_with_params(&proof, &circuit, &output, &Params::with_repetitions(21))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_verify_dyn() {
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        let proof = P::prove::<_, 80>(&mut rng, &input, &circuit, &output).unwrap();
+        assert_eq!(proof.security_level(), 80);
+        let bytes = proof.to_bytes().unwrap();
+        V::verify_dyn(&bytes, &circuit, &output, 40).unwrap();
+        V::verify_dyn(&bytes, &circuit, &output, 80).unwrap();
+        assert!(matches!(
+            V::verify_dyn(&bytes, &circuit, &output, 128),
+            Err(Error::SecurityLevelError(80, 128))
+        ));
+
+        // proofs made with explicit parameters are accepted at their level
+        let params = Params::with_repetitions(20);
+        let proof = P::prove_with_params(&mut rng, &input, &circuit, &output, &params).unwrap();
+        let bytes = proof.to_bytes().unwrap();
+        V::verify_dyn(&bytes, &circuit, &output, 11).unwrap();
+        assert!(V::verify_dyn(&bytes, &circuit, &output, 40).is_err());
+
+        // the level cannot be raised without the repetitions
+        let mut proof = Proof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        proof.security_param = 40;
+        let bytes = proof.to_bytes().unwrap();
+        assert!(V::verify_dyn(&bytes, &circuit, &output, 40).is_err());
+    }
+
+    #[test]
+    fn test_tape_expander() {
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        let params = ProofParams::<Shake128Expander, Keccak256>::from_security(40);
+        assert_eq!(params.tape_expander(), "shake128");
+        assert_eq!(Params::from_security(40).tape_expander(), "chacha20");
+
+        let proof = Prover::<u32, Shake128Expander, Keccak256>::prove_with_params(
+            &mut rng, &input, &circuit, &output, &params,
+        )
+        .unwrap();
+        Verifier::<u32, Shake128Expander, Keccak256>::verify_with_params(
+            &proof, &circuit, &output, &params,
+        )
+        .unwrap();
+
+        // tapes expanded by another generator do not match the views
+        assert!(
+            V::verify_with_params(&proof, &circuit, &output, &Params::from_security(40)).is_err()
+        );
+    }
+
+    #[test]
+    fn test_legacy_tapes() {
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        let params = ProofParams::<LegacyChaCha20Rng, Keccak256>::from_security(40);
+        assert_eq!(params.tape_expander(), "chacha20-legacy");
+        let proof = Prover::<u32, LegacyChaCha20Rng, Keccak256>::prove_with_params(
+            &mut rng, &input, &circuit, &output, &params,
+        )
+        .unwrap();
+        Verifier::<u32, LegacyChaCha20Rng, Keccak256>::verify_with_params(
+            &proof, &circuit, &output, &params,
+        )
+        .unwrap();
+
+        // labeled tapes are the default, and differ from unlabeled ones
+        assert!(
+            V::verify_with_params(&proof, &circuit, &output, &Params::from_security(40)).is_err()
+        );
+    }
+
+    #[test]
+    fn test_security_param() {
+        const SIGMA: usize = 40;
+
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        let proof = P::prove::<_, SIGMA>(&mut rng, &input, &circuit, &output).unwrap();
+        assert_eq!(proof.security_param, SIGMA);
+        let bytes = proof.to_bytes().unwrap();
+        let proof = Proof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        assert_eq!(proof.security_param, SIGMA);
+        V::verify::<SIGMA>(&proof, &circuit, &output).unwrap();
+
+        // a proof only verifies at the security level it claims
+        assert!(V::verify::<41>(&proof, &circuit, &output).is_err());
+        let mut relabeled = Proof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        relabeled.security_param = 41;
+        assert!(V::verify::<41>(&relabeled, &circuit, &output).is_err());
+        assert!(V::verify::<SIGMA>(&relabeled, &circuit, &output).is_err());
+
+        assert!(TypedProof::<u32, Keccak256, 41>::from_bytes(&bytes).is_err());
+        let typed = TypedProof::<u32, Keccak256, SIGMA>::try_from(proof).unwrap();
+        V::verify::<SIGMA>(&typed, &circuit, &output).unwrap();
+    }
+}
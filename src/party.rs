@@ -1,6 +1,6 @@
-use rand::{CryptoRng, RngCore, SeedableRng};
-
 use crate::{
+    error::Error,
+    expander::TapeExpander,
     gf2_word::{GF2Word, Value},
     key::Key,
     tape::Tape,
@@ -14,13 +14,14 @@ pub struct Party<T: Value> {
 }
 
 impl<T: Value> Party<T> {
-    pub fn new<TapeR: SeedableRng<Seed = Key> + RngCore + CryptoRng>(
-        share: Vec<u8>,
-        k: Key,
-        tape_len: usize,
-    ) -> Self {
-        let tape = Tape::<T>::from_key::<TapeR>(k, tape_len);
-        let view = View::new(share);
+    pub fn new<TapeR: TapeExpander>(share: Vec<u8>, k: Key, label: &[u8], tape_len: usize) -> Self {
+        Self::from_share_and_tape(share, Tape::<T>::from_key::<TapeR>(k, label, tape_len))
+    }
+
+    pub fn from_share_and_tape(share: Vec<u8>, tape: Tape<T>) -> Self {
+        // every AND gate reads one value and sends one message
+        let mut view = View::new(share);
+        view.messages.reserve_exact(tape.len());
 
         Self { view, tape }
     }
@@ -33,7 +34,21 @@ impl<T: Value> Party<T> {
         self.tape.read_next()
     }
 
-    pub fn read_view(&mut self) -> GF2Word<T> {
+    pub fn read_view(&mut self) -> Result<GF2Word<T>, Error> {
         self.view.read_next()
     }
 }
+
+/// Emits the tape bytes read by `parties` and the number of AND gates they
+/// ran, one per message in the view of the first of them.
+#[cfg(feature = "tracing")]
+pub(crate) fn trace_counters<T: Value>(parties: &[&Party<T>]) {
+    tracing::debug!(
+        tape_bytes = parties
+            .iter()
+            .map(|p| p.tape.offset() * T::bytes_len())
+            .sum::<usize>(),
+        and_gates = parties[0].view.messages.len(),
+        "executed repetition"
+    );
+}
@@ -0,0 +1,322 @@
+//! Progress reports of long-running proofs and verifications.
+//!
+//! A `ProgressObserver` is called by `Prover::prove_with_progress`,
+//! `Verifier::verify_with_progress` and
+//! `Verifier::verify_from_reader_with_progress` when a phase starts and after
+//! every repetition of it, so CLIs and UIs can render progress bars. Returning
+//! `ControlFlow::Break` cancels the job, which then fails with
+//! `Error::CancelledError` with the time spent in each phase so far.
+//!
+//! A server aborting jobs from another thread hands a clone of a
+//! `CancellationToken` to the job as its observer.
+
+use std::{
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::error::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    /// The prover runs the circuit and commits to the views of each repetition.
+    Commit,
+    /// The challenge is drawn from the transcript.
+    Challenge,
+    /// The prover opens two views of each repetition.
+    Open,
+    /// The verifier simulates the opened views of each repetition.
+    Simulate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub phase: Phase,
+    /// Steps of `phase` done so far, 0 when it starts.
+    pub completed: usize,
+    pub total: usize,
+}
+
+pub trait ProgressObserver {
+    fn on_progress(&mut self, _progress: Progress) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Observes nothing and never cancels.
+pub struct NoProgress;
+
+impl ProgressObserver for NoProgress {}
+
+impl<F: FnMut(Progress) -> ControlFlow<()>> ProgressObserver for F {
+    fn on_progress(&mut self, progress: Progress) -> ControlFlow<()> {
+        self(progress)
+    }
+}
+
+/// Cancels every job observing one of its clones once `cancel` is called.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl ProgressObserver for CancellationToken {
+    fn on_progress(&mut self, _progress: Progress) -> ControlFlow<()> {
+        if self.is_cancelled() {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+/// How far a cancelled job got.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CancelStats {
+    /// The last progress reported before the job stopped.
+    pub at: Progress,
+    /// The time spent in each phase started, in order. Zero on wasm32, which
+    /// has no clock.
+    pub phases: Vec<(Phase, Duration)>,
+}
+
+impl CancelStats {
+    pub fn elapsed(&self) -> Duration {
+        self.phases.iter().map(|(_, duration)| *duration).sum()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type Instant = std::time::Instant;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> Instant {
+    Instant::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn since(started: Instant) -> Duration {
+    started.elapsed()
+}
+
+#[cfg(target_arch = "wasm32")]
+type Instant = ();
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> Instant {}
+
+#[cfg(target_arch = "wasm32")]
+fn since(_started: Instant) -> Duration {
+    Duration::ZERO
+}
+
+/// Reports the progress of a job to its observer, timing each phase.
+pub(crate) struct Tracker<'a, O: ProgressObserver> {
+    observer: &'a mut O,
+    phases: Vec<(Phase, Duration)>,
+    phase_started: Instant,
+}
+
+impl<'a, O: ProgressObserver> Tracker<'a, O> {
+    pub(crate) fn new(observer: &'a mut O) -> Self {
+        Self {
+            observer,
+            phases: Vec::new(),
+            phase_started: now(),
+        }
+    }
+
+    /// Reports `completed` of `total` steps of `phase`.
+    pub(crate) fn report(
+        &mut self,
+        phase: Phase,
+        completed: usize,
+        total: usize,
+    ) -> Result<(), Error> {
+        if self.phases.last().map(|(last, _)| *last) != Some(phase) {
+            self.phases.push((phase, Duration::ZERO));
+            self.phase_started = now();
+        }
+        if let Some((_, duration)) = self.phases.last_mut() {
+            *duration = since(self.phase_started);
+        }
+
+        let at = Progress {
+            phase,
+            completed,
+            total,
+        };
+        match self.observer.on_progress(at) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(()) => Err(Error::CancelledError(Box::new(CancelStats {
+                at,
+                phases: self.phases.clone(),
+            }))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod progress_tests {
+    use std::ops::ControlFlow;
+
+    use rand::thread_rng;
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::Circuit, error::Error, gadgets::add_mod::AdderCircuit,
+        num_of_repetitions_given_desired_security, prover::Prover, verifier::Verifier,
+    };
+
+    use super::{CancellationToken, Phase, Progress};
+
+    type P = Prover<u32, ChaCha20Rng, Keccak256>;
+    type V = Verifier<u32, ChaCha20Rng, Keccak256>;
+
+    #[test]
+    fn test_progress() {
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+        let reps = num_of_repetitions_given_desired_security(40);
+
+        let mut reports = Vec::new();
+        let proof = P::prove_with_progress::<_, 40>(
+            &mut rng,
+            &input,
+            &circuit,
+            &output,
+            b"ctx",
+            &mut |progress: Progress| {
+                reports.push(progress);
+                ControlFlow::Continue(())
+            },
+        )
+        .unwrap();
+        let count =
+            |reports: &[Progress], phase| reports.iter().filter(|p| p.phase == phase).count();
+        assert_eq!(count(&reports, Phase::Commit), reps + 1);
+        assert_eq!(count(&reports, Phase::Challenge), 2);
+        assert_eq!(count(&reports, Phase::Open), reps + 1);
+        assert_eq!(reports.last().unwrap().completed, reps);
+
+        let mut reports = Vec::new();
+        V::verify_with_progress::<40>(&proof, &circuit, &output, b"ctx", &mut |progress| {
+            reports.push(progress);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        assert_eq!(count(&reports, Phase::Simulate), reps + 1);
+        assert_eq!(count(&reports, Phase::Challenge), 2);
+
+        // the streaming verifier reports the same phases
+        let bytes = proof.to_bytes().unwrap();
+        let mut streamed = Vec::new();
+        V::verify_from_reader_with_progress::<40, _>(
+            &mut &bytes[..],
+            &circuit,
+            &output,
+            b"ctx",
+            &mut |progress| {
+                streamed.push(progress);
+                ControlFlow::Continue(())
+            },
+        )
+        .unwrap();
+        assert_eq!(streamed, reports);
+
+        // breaking cancels the job
+        let mut seen = 0;
+        let result = P::prove_with_progress::<_, 40>(
+            &mut rng,
+            &input,
+            &circuit,
+            &output,
+            &[],
+            &mut |progress: Progress| {
+                seen += 1;
+                if progress.completed == 3 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+        );
+        match result {
+            Err(Error::CancelledError(stats)) => {
+                assert_eq!(stats.at.phase, Phase::Commit);
+                assert_eq!(stats.at.completed, 3);
+                assert_eq!(stats.phases.len(), 1);
+                assert_eq!(stats.elapsed(), stats.phases[0].1);
+            }
+            _ => panic!("expected the proof to be cancelled"),
+        }
+        assert_eq!(seen, 4);
+        assert!(matches!(
+            V::verify_with_progress::<40>(&proof, &circuit, &output, b"ctx", &mut |_| {
+                ControlFlow::Break(())
+            }),
+            Err(Error::CancelledError(_))
+        ));
+        assert!(matches!(
+            V::verify_from_reader_with_progress::<40, _>(
+                &mut &bytes[..],
+                &circuit,
+                &output,
+                b"ctx",
+                &mut |progress: Progress| {
+                    if progress.completed == 2 {
+                        ControlFlow::Break(())
+                    } else {
+                        ControlFlow::Continue(())
+                    }
+                }
+            ),
+            Err(Error::CancelledError(stats)) if stats.at.phase == Phase::Simulate
+        ));
+    }
+
+    #[test]
+    fn test_cancellation_token() {
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        let mut token = CancellationToken::new();
+        let proof =
+            P::prove_with_progress::<_, 40>(&mut rng, &input, &circuit, &output, &[], &mut token)
+                .unwrap();
+
+        // cancelled from elsewhere, e.g. another thread of a server
+        let handle = token.clone();
+        std::thread::spawn(move || handle.cancel()).join().unwrap();
+        assert!(token.is_cancelled());
+        assert!(matches!(
+            P::prove_with_progress::<_, 40>(&mut rng, &input, &circuit, &output, &[], &mut token),
+            Err(Error::CancelledError(stats)) if stats.at.completed == 0
+        ));
+        assert!(matches!(
+            V::verify_with_progress::<40>(&proof, &circuit, &output, &[], &mut token),
+            Err(Error::CancelledError(_))
+        ));
+    }
+}
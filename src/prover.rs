@@ -1,64 +1,116 @@
 use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
 use rand_core::{CryptoRng, RngCore};
 use sha3::{digest::FixedOutputReset, Digest};
 use std::{fmt::Debug, marker::PhantomData};
 
+#[cfg(feature = "debug-trace")]
+use crate::data_structures::ProofTrace;
 use crate::{
-    circuit::{Circuit, TwoThreeDecOutput},
-    commitment::Commitment,
-    config::HASH_LEN,
-    data_structures::{FirstMessageA, PartyExecution, Proof, PublicInput},
+    circuit::{any_of, Circuit, TwoThreeDecOutput},
+    commitment::{Commitment, CommitmentScheme, HashCommitment},
+    data_structures::{
+        AggregateProof, FirstMessageA, Instance, MerkleProof, Proof, PublicInput, RingProof,
+        SessionState,
+    },
+    encoding::{encode_aggregate_seed, encode_derivation, encode_merkle_seed, encode_party_leaf},
     error::Error,
-    fs::SigmaFS,
+    expander::{tape_label, TapeExpander},
+    fault::{FaultInjector, NoFaults},
+    fs::{Oracle, SigmaFS, Transcript},
+    gadgets::assertion::success_public_output,
     gf2_word::{GF2Word, GenRand, Value},
     key::{Key, KeyManager},
+    merkle::{hash_leaf, MerkleTree},
     num_of_repetitions_given_desired_security,
+    params::ProofParams,
     party::Party,
-    view::View,
+    progress::{NoProgress, Phase, ProgressObserver, Tracker},
+    sharing::{self, Shares},
+    tape::expand_tapes,
+    view::{View, ViewArena},
+    view_store::{MemoryViewStore, ProverMemoryBudget, ViewStore},
+    witness::Witness,
 };
 
 pub type Share<T> = Vec<GF2Word<T>>;
 
+/// Separates the key derivation of deterministic proving from other uses of
+/// the hash.
+const DERIVATION_DOMAIN: &[u8] = b"zkboo-deterministic-v2";
+
 pub struct RepetitionOutput<T: Value> {
     pub party_outputs: TwoThreeDecOutput<T>,
     pub party_views: (View<T>, View<T>, View<T>),
 }
 
-pub struct Prover<T: Value, TapeR, D>(PhantomData<(T, TapeR, D)>)
+/// Non-interactive prover, committing to views with `C` and drawing its
+/// challenges from the oracle `F`.
+pub struct Prover<T: Value, TapeR, D, C = HashCommitment, F = SigmaFS<D>>(
+    PhantomData<(T, TapeR, D, C, F)>,
+)
 where
-    TapeR: SeedableRng<Seed = Key> + RngCore + CryptoRng,
+    TapeR: TapeExpander,
     D: Debug + Default + Digest + FixedOutputReset + Clone;
 
-impl<T: Value, TapeR, D> Prover<T, TapeR, D>
+impl<T: Value, TapeR, D, C, F> Prover<T, TapeR, D, C, F>
 where
-    TapeR: SeedableRng<Seed = Key> + RngCore + CryptoRng,
+    TapeR: TapeExpander,
     D: Debug + Default + Digest + FixedOutputReset + Clone,
+    C: CommitmentScheme<D>,
+    F: Oracle<D>,
 {
-    pub fn share<R: RngCore + CryptoRng>(rng: &mut R, input: &[u8]) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
-        let share_1: Vec<u8> = (0..input.len()).map(|_| u8::gen_rand(rng)).collect();
-        let share_2: Vec<u8> = (0..input.len()).map(|_| u8::gen_rand(rng)).collect();
-
-        let share_3: Vec<u8> = input
-            .iter()
-            .zip(share_1.iter())
-            .zip(share_2.iter())
-            .map(|((&i1, &i2), &i3)| i1 ^ i2 ^ i3)
-            .collect();
+    pub fn share<R: RngCore + CryptoRng>(rng: &mut R, input: &[u8]) -> Shares {
+        sharing::share(rng, input)
+    }
 
-        (share_1, share_2, share_3)
+    /// Same as `share`, with randomness expanded from `seed`.
+    pub fn share_with_seed(seed: [u8; 32], input: &[u8]) -> Shares {
+        sharing::share_with_seed(seed, input)
     }
 
     pub fn init_parties<R: RngCore + CryptoRng>(
         rng: &mut R,
         input: &[u8],
         keys: (Key, Key, Key),
+        repetition: usize,
+        tape_len: usize,
+    ) -> (Party<T>, Party<T>, Party<T>) {
+        Self::init_parties_in(
+            rng,
+            input,
+            keys,
+            repetition,
+            tape_len,
+            &mut ViewArena::new(),
+        )
+    }
+
+    /// Same as `init_parties`, taking the message buffers of the views from
+    /// `arena`.
+    pub fn init_parties_in<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        input: &[u8],
+        keys: (Key, Key, Key),
+        repetition: usize,
         tape_len: usize,
+        arena: &mut ViewArena<T>,
     ) -> (Party<T>, Party<T>, Party<T>) {
         let (share_1, share_2, share_3) = Self::share(rng, input);
 
-        let p1 = Party::new::<TapeR>(share_1, keys.0, tape_len);
-        let p2 = Party::new::<TapeR>(share_2, keys.1, tape_len);
-        let p3 = Party::new::<TapeR>(share_3, keys.2, tape_len);
+        let [t1, t2, t3] = expand_tapes::<T, TapeR, 3>(
+            [
+                (keys.0, tape_label(repetition, 0)),
+                (keys.1, tape_label(repetition, 1)),
+                (keys.2, tape_label(repetition, 2)),
+            ],
+            tape_len,
+        );
+
+        // every AND gate reads one value and sends one message
+        let p1 = Party::from_tape_and_view(arena.view(share_1, t1.len()), t1);
+        let p2 = Party::from_tape_and_view(arena.view(share_2, t2.len()), t2);
+        let p3 = Party::from_tape_and_view(arena.view(share_3, t3.len()), t3);
 
         (p1, p2, p3)
     }
@@ -67,15 +119,78 @@ where
         rng: &mut R,
         input: &[u8],
         keys: (Key, Key, Key),
+        repetition: usize,
         circuit: &impl Circuit<T>,
-    ) -> RepetitionOutput<T> {
-        let (mut p1, mut p2, mut p3) =
-            Self::init_parties(rng, input, keys, circuit.num_of_mul_gates());
+    ) -> Result<RepetitionOutput<T>, Error> {
+        let parties = Self::init_parties(rng, input, keys, repetition, circuit.num_of_mul_gates());
+        Self::execute(parties, circuit)
+    }
+
+    fn execute(
+        (mut p1, mut p2, mut p3): (Party<T>, Party<T>, Party<T>),
+        circuit: &(impl Circuit<T> + ?Sized),
+    ) -> Result<RepetitionOutput<T>, Error> {
         let party_outputs = circuit.compute_23_decomposition(&mut p1, &mut p2, &mut p3);
-        RepetitionOutput {
+        #[cfg(feature = "tracing")]
+        crate::party::trace_counters(&[&p1, &p2, &p3]);
+        [&p1, &p2, &p3]
+            .iter()
+            .try_for_each(|p| p.tape.check_consumed())?;
+
+        Ok(RepetitionOutput {
             party_outputs,
             party_views: (p1.view, p2.view, p3.view),
+        })
+    }
+
+    /// Checks `witness` against the ranges declared by `circuit`, then
+    /// evaluates `circuit` on it in the clear and checks that it outputs
+    /// `public_output`, which is much cheaper than finding out from a proof
+    /// that does not verify.
+    pub fn check_witness(
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &[GF2Word<T>],
+    ) -> Result<(), Error> {
+        circuit
+            .witness_ranges()
+            .iter()
+            .try_for_each(|range| range.check(witness))?;
+
+        if public_output.len() != circuit.party_output_len() {
+            return Err(Error::PublicOutputLenError(
+                circuit.party_output_len(),
+                public_output.len(),
+            ));
         }
+        let output = circuit.compute(witness);
+        match output.iter().zip(public_output).position(|(o, p)| o != p) {
+            Some(word) => Err(Error::WitnessOutputError(word)),
+            None => Ok(()),
+        }
+    }
+
+    /// Same as `prove`, rejecting witnesses which fail `check_witness` before
+    /// any work is done.
+    pub fn prove_checked<R: RngCore + CryptoRng, const SIGMA: usize>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+    ) -> Result<Proof<T, D>, Error> {
+        Self::check_witness(witness, circuit, public_output)?;
+        Self::prove::<R, SIGMA>(rng, witness, circuit, public_output)
+    }
+
+    /// Same as `prove`, but takes the witness in a wrapper which wipes it
+    /// once the caller drops it.
+    pub fn prove_witness<R: RngCore + CryptoRng, const SIGMA: usize>(
+        rng: &mut R,
+        witness: &Witness,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+    ) -> Result<Proof<T, D>, Error> {
+        Self::prove::<R, SIGMA>(rng, witness.expose_secret(), circuit, public_output)
     }
 
     pub fn prove<R: RngCore + CryptoRng, const SIGMA: usize>(
@@ -83,73 +198,699 @@ where
         witness: &[u8],
         circuit: &impl Circuit<T>,
         public_output: &Vec<GF2Word<T>>,
-    ) -> Result<Proof<T, D, SIGMA>, Error> {
+    ) -> Result<Proof<T, D>, Error> {
+        Self::prove_with_context::<R, SIGMA>(rng, witness, circuit, public_output, &[])
+    }
+
+    /// Prove with `context` folded into the Fiat-Shamir transcript. The proof
+    /// only verifies for the same context.
+    pub fn prove_with_context<R: RngCore + CryptoRng, const SIGMA: usize>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+    ) -> Result<Proof<T, D>, Error> {
+        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        let mut store = MemoryViewStore::with_capacity(3 * num_of_repetitions);
+
+        Self::prove_with_store::<R, _, SIGMA>(
+            rng,
+            witness,
+            circuit,
+            public_output,
+            context,
+            &mut store,
+        )
+    }
+
+    /// Same as `prove_with_context`, drawing the input shares, keys and
+    /// blindings from a generator seeded with a hash of `seed`, the witness
+    /// and the whole statement: the public output, the context, the security
+    /// parameter, the number of repetitions and the id and public input of the
+    /// circuit. Proving the same statement twice gives the same proof, and as
+    /// in RFC 6979 the witness keeps the randomness secret even if `seed` is
+    /// empty or known.
+    pub fn prove_deterministic<const SIGMA: usize>(
+        seed: &[u8],
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+    ) -> Result<Proof<T, D>, Error> {
+        let params = ProofParams::from_security(SIGMA);
+        let mut rng =
+            Self::deterministic_rng(seed, witness, circuit, public_output, context, &params)?;
+        Self::prove_with_context::<_, SIGMA>(&mut rng, witness, circuit, public_output, context)
+    }
+
+    /// Same as `prove_deterministic`, also returning the `ProofTrace` of
+    /// `prove_with_trace`.
+    #[cfg(feature = "debug-trace")]
+    #[allow(clippy::type_complexity)]
+    pub fn prove_deterministic_with_trace<const SIGMA: usize>(
+        seed: &[u8],
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+    ) -> Result<(Proof<T, D>, ProofTrace<T, D>), Error> {
+        let params = ProofParams::from_security(SIGMA);
+        let mut rng =
+            Self::deterministic_rng(seed, witness, circuit, public_output, context, &params)?;
+        Self::prove_with_trace::<_, SIGMA>(&mut rng, witness, circuit, public_output, context)
+    }
+
+    fn deterministic_rng(
+        seed: &[u8],
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &[GF2Word<T>],
+        context: &[u8],
+        params: &ProofParams<TapeR, D>,
+    ) -> Result<ChaCha20Rng, Error> {
+        let derivation = encode_derivation(
+            DERIVATION_DOMAIN,
+            seed,
+            witness,
+            public_output,
+            context,
+            (params.security_param(), params.repetitions()),
+            &circuit.public_input(),
+            &circuit.id(),
+        );
+        let digest = D::digest(&derivation);
+        let rng_seed: [u8; 32] = digest
+            .get(..32)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(Error::HashLenError(32, digest.len()))?;
+        Ok(ChaCha20Rng::from_seed(rng_seed))
+    }
+
+    /// Same as `prove_with_context`, keeping the views of all parties in
+    /// `store` between committing to them and opening them.
+    pub fn prove_with_store<R: RngCore + CryptoRng, S: ViewStore<T>, const SIGMA: usize>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+        store: &mut S,
+    ) -> Result<Proof<T, D>, Error> {
+        Self::prove_inner::<R, S>(
+            rng,
+            witness,
+            circuit,
+            public_output,
+            &ProofParams::from_security(SIGMA),
+            &mut F::from_context(context),
+            store,
+            &mut NoFaults,
+            &mut NoProgress,
+        )
+    }
+
+    /// Same as `prove_with_context`, reporting to `progress` as each phase
+    /// starts and after each repetition of it. The proof is cancelled with
+    /// `Error::CancelledError` as soon as `progress` breaks.
+    pub fn prove_with_progress<R: RngCore + CryptoRng, const SIGMA: usize>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+        progress: &mut impl ProgressObserver,
+    ) -> Result<Proof<T, D>, Error> {
+        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        let mut store = MemoryViewStore::with_capacity(3 * num_of_repetitions);
+
+        Self::prove_inner::<R, _>(
+            rng,
+            witness,
+            circuit,
+            public_output,
+            &ProofParams::from_security(SIGMA),
+            &mut F::from_context(context),
+            &mut store,
+            &mut NoFaults,
+            progress,
+        )
+    }
+
+    /// Same as `prove_with_store`, failing with `Error::MemoryBudgetError`
+    /// before running any repetition if the views and tapes of the proof would
+    /// not fit in `budget`.
+    pub fn prove_with_budget<R: RngCore + CryptoRng, S: ViewStore<T>, const SIGMA: usize>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+        store: &mut S,
+        budget: &ProverMemoryBudget,
+    ) -> Result<Proof<T, D>, Error> {
+        budget.check(ProverMemoryBudget::needed(
+            circuit,
+            witness.len(),
+            num_of_repetitions_given_desired_security(SIGMA),
+            store.in_memory(),
+        ))?;
+        Self::prove_with_store::<R, S, SIGMA>(rng, witness, circuit, public_output, context, store)
+    }
+
+    /// Prove with the challenge drawn from `transcript`, which may already hold
+    /// messages of an enclosing protocol. The verifier must use a transcript in
+    /// the same state.
+    pub fn prove_with_transcript<R: RngCore + CryptoRng, const SIGMA: usize>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        transcript: &mut impl Transcript<D>,
+    ) -> Result<Proof<T, D>, Error> {
+        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        let mut store = MemoryViewStore::with_capacity(3 * num_of_repetitions);
+
+        Self::prove_inner::<R, _>(
+            rng,
+            witness,
+            circuit,
+            public_output,
+            &ProofParams::from_security(SIGMA),
+            transcript,
+            &mut store,
+            &mut NoFaults,
+            &mut NoProgress,
+        )
+    }
+
+    /// Same as `prove`, calling `faults` at each point of the pipeline where a
+    /// fault can be injected.
+    #[cfg(feature = "fault-injection")]
+    pub fn prove_with_faults<R: RngCore + CryptoRng, const SIGMA: usize>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        faults: &mut impl FaultInjector<T, D>,
+    ) -> Result<Proof<T, D>, Error> {
+        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        let mut store = MemoryViewStore::with_capacity(3 * num_of_repetitions);
+
+        Self::prove_inner::<R, _>(
+            rng,
+            witness,
+            circuit,
+            public_output,
+            &ProofParams::from_security(SIGMA),
+            &mut F::from_context(&[]),
+            &mut store,
+            faults,
+            &mut NoProgress,
+        )
+    }
+
+    /// Prove with the number of repetitions given by `params` instead of a
+    /// security level. The proof only verifies under the same `params`.
+    pub fn prove_with_params<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        params: &ProofParams<TapeR, D>,
+    ) -> Result<Proof<T, D>, Error> {
+        let mut store = MemoryViewStore::with_capacity(3 * params.repetitions());
+
+        Self::prove_inner::<R, _>(
+            rng,
+            witness,
+            circuit,
+            public_output,
+            params,
+            &mut F::from_context(&[]),
+            &mut store,
+            &mut NoFaults,
+            &mut NoProgress,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn prove_inner<R: RngCore + CryptoRng, S: ViewStore<T>>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        params: &ProofParams<TapeR, D>,
+        transcript: &mut impl Transcript<D>,
+        store: &mut S,
+        faults: &mut impl FaultInjector<T, D>,
+        progress: &mut impl ProgressObserver,
+    ) -> Result<Proof<T, D>, Error> {
+        let num_of_repetitions = params.repetitions();
+        let progress = &mut Tracker::new(progress);
+        let committed = Self::commit(
+            rng,
+            witness,
+            circuit,
+            num_of_repetitions,
+            store,
+            faults,
+            progress,
+        )?;
+        progress.report(Phase::Challenge, 0, 1)?;
+        let opening_indices = Self::challenge(
+            &committed,
+            public_output,
+            circuit,
+            params.security_param(),
+            transcript,
+        )?;
+        progress.report(Phase::Challenge, 1, 1)?;
+
+        let mut proof = Self::open(
+            committed,
+            params.security_param(),
+            &opening_indices,
+            store,
+            progress,
+        )?;
+        faults.proof(&mut proof);
+
+        Ok(proof)
+    }
+
+    /// Same as `prove_with_context`, also returning a `ProofTrace` of
+    /// everything the prover computed, to diff a failing verification against.
+    ///
+    /// The trace holds the keys of all parties and so reveals the witness; it
+    /// is for debugging circuits and must never leave the prover.
+    #[cfg(feature = "debug-trace")]
+    #[allow(clippy::type_complexity)]
+    pub fn prove_with_trace<R: RngCore + CryptoRng, const SIGMA: usize>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+    ) -> Result<(Proof<T, D>, ProofTrace<T, D>), Error> {
+        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        let mut store = TracingViewStore {
+            store: MemoryViewStore::with_capacity(3 * num_of_repetitions),
+            views: Vec::with_capacity(3 * num_of_repetitions),
+        };
+
+        let committed = Self::commit(
+            rng,
+            witness,
+            circuit,
+            num_of_repetitions,
+            &mut store,
+            &mut NoFaults,
+            &mut Tracker::new(&mut NoProgress),
+        )?;
+        let opening_indices = Self::challenge(
+            &committed,
+            public_output,
+            circuit,
+            SIGMA,
+            &mut F::from_context(context),
+        )?;
+
+        let trace = ProofTrace {
+            views: std::mem::take(&mut store.views),
+            keys: committed.key_manager.reveal(0..3 * num_of_repetitions)?,
+            commitments: committed.all_commitments.clone(),
+            outputs: committed.outputs.clone(),
+            challenge: opening_indices.clone(),
+        };
+        let proof = Self::open(
+            committed,
+            SIGMA,
+            &opening_indices,
+            &mut store,
+            &mut Tracker::new(&mut NoProgress),
+        )?;
+
+        Ok((proof, trace))
+    }
+
+    /// Prove `witnesses[i]` for `instances[i]` for every `i`, with the
+    /// challenges of all proofs drawn from one transcript bound to `context`.
+    /// The proofs only verify together, with `Verifier::verify_aggregate`.
+    pub fn prove_many<R: RngCore + CryptoRng, const SIGMA: usize>(
+        rng: &mut R,
+        witnesses: &[&[u8]],
+        instances: &[Instance<T>],
+        context: &[u8],
+    ) -> Result<AggregateProof<T, D>, Error> {
+        if witnesses.len() != instances.len() {
+            return Err(Error::StatementMismatchError);
+        }
+        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+
+        let mut stores = Vec::with_capacity(instances.len());
+        let mut all_committed = Vec::with_capacity(instances.len());
+        for (witness, instance) in witnesses.iter().zip(instances) {
+            let mut store = MemoryViewStore::with_capacity(3 * num_of_repetitions);
+            all_committed.push(Self::commit(
+                rng,
+                witness,
+                instance.circuit,
+                num_of_repetitions,
+                &mut store,
+                &mut NoFaults,
+                &mut Tracker::new(&mut NoProgress),
+            )?);
+            stores.push(store);
+        }
+
+        let mut transcript = F::from_seed(&encode_aggregate_seed(instances.len(), context));
+        for (committed, instance) in all_committed.iter().zip(instances) {
+            let pi = PublicInput {
+                outputs: &committed.outputs,
+                public_output: instance.public_output,
+                public_input: &instance.circuit.public_input(),
+                security_param: SIGMA,
+                circuit_id: &instance.circuit.id(),
+            };
+            transcript.absorb_public_input(&pi)?;
+            transcript.absorb_commitments(&committed.all_commitments)?;
+        }
+
+        let opening_indices = transcript.challenge_trits(instances.len() * num_of_repetitions);
+
+        let proofs = all_committed
+            .into_iter()
+            .zip(stores.iter_mut())
+            .zip(opening_indices.chunks(num_of_repetitions))
+            .map(|((committed, store), opening_indices)| {
+                Self::open(
+                    committed,
+                    SIGMA,
+                    opening_indices,
+                    store,
+                    &mut Tracker::new(&mut NoProgress),
+                )
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(AggregateProof { proofs })
+    }
+
+    /// Prove that `witness` is a witness for one of `instances`, without
+    /// revealing which, see `RingProof`. All members must read inputs of the
+    /// same length.
+    pub fn prove_ring<R: RngCore + CryptoRng, const SIGMA: usize>(
+        rng: &mut R,
+        witness: &[u8],
+        instances: &[Instance<T>],
+        context: &[u8],
+    ) -> Result<RingProof<T, D>, Error> {
+        let circuit = any_of(instances);
+        if instances.is_empty()
+            || instances
+                .iter()
+                .any(|member| member.circuit.party_input_len() != circuit.party_input_len())
+        {
+            return Err(Error::StatementMismatchError);
+        }
+
+        let proof = Self::prove_with_context::<R, SIGMA>(
+            rng,
+            witness,
+            &circuit,
+            &success_public_output(),
+            context,
+        )?;
+        Ok(RingProof { proof })
+    }
+
+    /// A proof for `circuit` and `public_output` which opens the parties
+    /// picked by `claimed_trits`, made without a witness.
+    ///
+    /// Each repetition is simulated from the views of its opened parties: the
+    /// messages of the second one are drawn at random, as its tape masks them
+    /// in an honest run, and the first one is run against them. The third
+    /// party is committed to with a random view. Every repetition passes the
+    /// checks of the verifier, but the proof fails the Fiat-Shamir check
+    /// unless `claimed_trits` happens to be the challenge. The input shares
+    /// have `input_len` bytes.
+    pub fn simulate<R: RngCore + CryptoRng, const SIGMA: usize>(
+        rng: &mut R,
+        circuit: &impl Circuit<T>,
+        public_output: &[GF2Word<T>],
+        input_len: usize,
+        claimed_trits: Vec<u8>,
+    ) -> Result<Proof<T, D>, Error> {
+        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        if claimed_trits.len() != num_of_repetitions {
+            return Err(Error::ChallengeLenError(
+                num_of_repetitions,
+                claimed_trits.len(),
+            ));
+        }
+        if claimed_trits.iter().any(|&party_index| party_index > 2) {
+            return Err(Error::VerificationError);
+        }
+        if public_output.len() != circuit.party_output_len() {
+            return Err(Error::PublicOutputLenError(
+                circuit.party_output_len(),
+                public_output.len(),
+            ));
+        }
+        let tape_len = circuit.num_of_mul_gates();
+        let mut key_manager = KeyManager::new(num_of_repetitions, rng);
+
+        let random_view = |rng: &mut R| {
+            let mut view = View::new(u8::gen_rand_packed(rng, input_len));
+            view.messages = T::gen_rand_packed(rng, tape_len)
+                .into_iter()
+                .map(GF2Word::from)
+                .collect();
+            view
+        };
+
+        let mut party_inputs = Vec::with_capacity(num_of_repetitions);
+        let mut views = Vec::with_capacity(num_of_repetitions);
+        let mut keys = Vec::with_capacity(2 * num_of_repetitions);
+        let mut commitments = Vec::with_capacity(num_of_repetitions);
+
+        for (repetition, &party_index) in claimed_trits.iter().enumerate() {
+            let i0 = party_index as usize;
+            let i1 = (i0 + 1) % 3;
+            let k_i0 = key_manager.request_key();
+            let k_i1 = key_manager.request_key();
+            let k_i2 = key_manager.request_key();
+
+            let [tape_i0, tape_i1] = expand_tapes::<T, TapeR, 2>(
+                [
+                    (k_i0, tape_label(repetition, i0)),
+                    (k_i1, tape_label(repetition, i1)),
+                ],
+                tape_len,
+            );
+            let view_i1 = random_view(rng);
+            let mut p = Party::from_share_and_tape(u8::gen_rand_packed(rng, input_len), tape_i0);
+            let mut p_next = Party::from_tape_and_view(view_i1.clone(), tape_i1);
+
+            let (o0, o1) = circuit.simulate_two_parties(&mut p, &mut p_next)?;
+            if o0.len() != public_output.len() || o1.len() != public_output.len() {
+                return Err(Error::OutputReconstructionError);
+            }
+
+            party_inputs.push(std::mem::take(&mut p.view.input));
+            views.push(view_i1);
+            keys.extend([k_i0, k_i1]);
+            commitments.push(C::commit_view(&k_i2, &random_view(rng))?);
+        }
+
+        Ok(Proof {
+            security_param: SIGMA,
+            party_inputs,
+            commitments,
+            views,
+            keys,
+            claimed_trits,
+        })
+    }
+
+    /// Prove `witness` with the repetitions committed to by a Merkle tree,
+    /// see `MerkleProof`.
+    pub fn prove_merkle<R: RngCore + CryptoRng, const SIGMA: usize>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+    ) -> Result<MerkleProof<T, D>, Error> {
         let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        let mut store = MemoryViewStore::with_capacity(3 * num_of_repetitions);
+        let committed = Self::commit(
+            rng,
+            witness,
+            circuit,
+            num_of_repetitions,
+            &mut store,
+            &mut NoFaults,
+            &mut Tracker::new(&mut NoProgress),
+        )?;
+
+        let leaves = committed
+            .all_commitments
+            .iter()
+            .zip(&committed.outputs)
+            .map(|(commitment, output)| hash_leaf::<D>(&encode_party_leaf(commitment, output)))
+            .collect::<Result<_, Error>>()?;
+        let tree = MerkleTree::new::<D>(leaves)?;
 
+        let pi = PublicInput {
+            outputs: &vec![],
+            public_output,
+            public_input: &circuit.public_input(),
+            security_param: SIGMA,
+            circuit_id: &circuit.id(),
+        };
+        let mut transcript = F::from_seed(&encode_merkle_seed(context));
+        transcript.absorb_public_input(&pi)?;
+        transcript.absorb_commitments(&[Commitment::from_data(tree.root())])?;
+
+        let opening_indices = transcript.challenge_trits(num_of_repetitions);
+        let proof = Self::open(
+            committed,
+            SIGMA,
+            &opening_indices,
+            &mut store,
+            &mut Tracker::new(&mut NoProgress),
+        )?;
+        let paths = opening_indices
+            .iter()
+            .enumerate()
+            .flat_map(|(repetition, &party_index)| {
+                let i0 = party_index as usize;
+                [i0, (i0 + 1) % 3, (i0 + 2) % 3].map(|party| tree.path(3 * repetition + party))
+            })
+            .collect();
+
+        Ok(MerkleProof {
+            root: tree.root(),
+            proof,
+            paths,
+        })
+    }
+
+    /// Runs all repetitions and commits to the views of their parties, which
+    /// are kept in `store` until they are opened.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    fn commit<R: RngCore + CryptoRng, S: ViewStore<T>>(
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &(impl Circuit<T> + ?Sized),
+        num_of_repetitions: usize,
+        store: &mut S,
+        faults: &mut impl FaultInjector<T, D>,
+        progress: &mut Tracker<impl ProgressObserver>,
+    ) -> Result<Committed<T, D>, Error> {
         let mut key_manager = KeyManager::new(num_of_repetitions, rng);
+        if key_manager.has_constant_seed() {
+            return Err(Error::KeySeedError);
+        }
 
         let mut outputs = Vec::<Vec<GF2Word<T>>>::with_capacity(3 * num_of_repetitions);
         let mut all_commitments = Vec::<Commitment<D>>::with_capacity(3 * num_of_repetitions);
-        let mut all_views = Vec::with_capacity(3 * num_of_repetitions);
+        let mut arena = ViewArena::new();
+        progress.report(Phase::Commit, 0, num_of_repetitions)?;
+
+        for repetition in 0..num_of_repetitions {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("prove_repetition", repetition).entered();
 
-        for _ in 0..num_of_repetitions {
             let k1 = key_manager.request_key();
             let k2 = key_manager.request_key();
             let k3 = key_manager.request_key();
 
-            let repetition_output = Self::prove_repetition(rng, witness, (k1, k2, k3), circuit);
+            let (mut p1, mut p2, mut p3) = Self::init_parties_in(
+                rng,
+                witness,
+                (k1, k2, k3),
+                repetition,
+                circuit.num_of_mul_gates(),
+                &mut arena,
+            );
+            for (party, p) in [&mut p1, &mut p2, &mut p3].into_iter().enumerate() {
+                faults.tape(repetition, party, &mut p.tape);
+            }
+            let repetition_output = Self::execute((p1, p2, p3), circuit)?;
 
             // record all outputs
             outputs.push(repetition_output.party_outputs.0);
             outputs.push(repetition_output.party_outputs.1);
             outputs.push(repetition_output.party_outputs.2);
 
-            // record all views
-            all_views.push(repetition_output.party_views.0);
-            all_views.push(repetition_output.party_views.1);
-            all_views.push(repetition_output.party_views.2);
+            let (v1, v2, v3) = repetition_output.party_views;
 
-            let views_len = all_views.len();
+            for (party, (key, mut view)) in [(k1, v1), (k2, v2), (k3, v3)].into_iter().enumerate() {
+                faults.view(repetition, party, &mut view);
 
-            let p1_execution = PartyExecution {
-                key: &k1,
-                view: &all_views[views_len - 3],
-            };
-            let p2_execution = PartyExecution {
-                key: &k2,
-                view: &all_views[views_len - 2],
-            };
-            let p3_execution = PartyExecution {
-                key: &k3,
-                view: &all_views[views_len - 1],
-            };
+                all_commitments.push(C::commit_view(&key, &view)?);
 
-            for pi_execution in [p1_execution, p2_execution, p3_execution] {
-                let cmi = pi_execution.commit()?;
-                all_commitments.push(cmi);
+                // record all views
+                store.push_reclaiming(view, &mut arena)?;
             }
+            progress.report(Phase::Commit, repetition + 1, num_of_repetitions)?;
         }
 
+        Ok(Committed {
+            outputs,
+            all_commitments,
+            key_manager,
+        })
+    }
+
+    /// Draws the challenge for `committed` from `transcript`.
+    fn challenge(
+        committed: &Committed<T, D>,
+        public_output: &Vec<GF2Word<T>>,
+        circuit: &(impl Circuit<T> + ?Sized),
+        security_param: usize,
+        transcript: &mut impl Transcript<D>,
+    ) -> Result<Vec<u8>, Error> {
         let pi = PublicInput {
-            outputs: &outputs,
+            outputs: &committed.outputs,
             public_output,
-            hash_len: HASH_LEN,
-            security_param: SIGMA,
+            public_input: &circuit.public_input(),
+            security_param,
+            circuit_id: &circuit.id(),
         };
 
-        // TODO: remove hardcoded seed
-        let mut fs_oracle = SigmaFS::<D>::initialize(&[0u8]);
-        fs_oracle.digest_public_data(&pi)?;
-        fs_oracle.digest_prover_message(&all_commitments)?;
+        transcript.absorb_public_input(&pi)?;
+        transcript.absorb_commitments(&committed.all_commitments)?;
+
+        Ok(transcript.challenge_trits(committed.all_commitments.len() / 3))
+    }
 
-        let opening_indices = fs_oracle.sample_trits(num_of_repetitions);
+    /// Opens the views of the two parties picked by each challenge trit.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    fn open<S: ViewStore<T>>(
+        committed: Committed<T, D>,
+        security_param: usize,
+        opening_indices: &[u8],
+        store: &mut S,
+        progress: &mut Tracker<impl ProgressObserver>,
+    ) -> Result<Proof<T, D>, Error> {
+        let Committed {
+            mut all_commitments,
+            key_manager,
+            ..
+        } = committed;
+        let num_of_repetitions = opening_indices.len();
 
         let mut claimed_trits = Vec::with_capacity(num_of_repetitions);
         let mut party_inputs = Vec::with_capacity(num_of_repetitions);
 
-        let mut keys = Vec::<Key>::with_capacity(2 * num_of_repetitions);
+        let mut opened = Vec::with_capacity(2 * num_of_repetitions);
         let mut views = Vec::with_capacity(num_of_repetitions);
         let mut commitments = Vec::with_capacity(2 * num_of_repetitions);
+        progress.report(Phase::Open, 0, num_of_repetitions)?;
 
         for (repetition, &party_index) in opening_indices.iter().enumerate() {
             let party_index = party_index as usize;
@@ -157,19 +898,31 @@ where
             let i1 = repetition * 3 + ((party_index + 1) % 3);
             let i2 = repetition * 3 + ((party_index + 2) % 3);
 
-            party_inputs.push(std::mem::take(&mut all_views[i0].input));
+            // views are taken in increasing order, which keeps access to the store sequential
+            let (mut view_i0, view_i1) = if i0 < i1 {
+                let view_i0 = store.take(i0)?;
+                (view_i0, store.take(i1)?)
+            } else {
+                let view_i1 = store.take(i1)?;
+                (store.take(i0)?, view_i1)
+            };
+
+            party_inputs.push(std::mem::take(&mut view_i0.input));
 
             claimed_trits.push(party_index as u8);
 
-            views.push(std::mem::take(&mut all_views[i1]));
+            views.push(view_i1);
 
-            keys.push(key_manager.request_key_i(i0));
-            keys.push(key_manager.request_key_i(i1));
+            opened.extend([i0, i1]);
 
             commitments.push(std::mem::take(&mut all_commitments[i2]));
+            progress.report(Phase::Open, repetition + 1, num_of_repetitions)?;
         }
 
+        let keys = key_manager.reveal(opened)?;
+
         Ok(Proof {
+            security_param,
             party_inputs,
             commitments,
             views,
@@ -179,60 +932,107 @@ where
     }
 }
 
+/// What `Prover::commit` leaves for `Prover::open`.
+struct Committed<T: Value, D: Default + Digest + Clone> {
+    outputs: Vec<Vec<GF2Word<T>>>,
+    all_commitments: Vec<Commitment<D>>,
+    key_manager: KeyManager,
+}
+
+/// Keeps a copy of every view pushed to `store`.
+#[cfg(feature = "debug-trace")]
+struct TracingViewStore<T: Value> {
+    store: MemoryViewStore<T>,
+    views: Vec<View<T>>,
+}
+
+#[cfg(feature = "debug-trace")]
+impl<T: Value> ViewStore<T> for TracingViewStore<T> {
+    fn push(&mut self, view: View<T>) -> Result<(), Error> {
+        self.views.push(view.clone());
+        self.store.push(view)
+    }
+
+    fn take(&mut self, index: usize) -> Result<View<T>, Error> {
+        self.store.take(index)
+    }
+}
+
 #[derive(Default)]
-pub struct InteractiveProver<T: Value, TapeR, D>
+pub struct InteractiveProver<T: Value, TapeR, D, C = HashCommitment>
 where
-    TapeR: SeedableRng<Seed = Key> + RngCore + CryptoRng,
+    TapeR: TapeExpander,
     D: Debug + Default + Digest + FixedOutputReset + Clone,
 {
-    pd: PhantomData<(T, TapeR, D)>,
+    pd: PhantomData<(T, TapeR, D, C)>,
     pub public_output: Vec<GF2Word<T>>,
     pub outputs: Vec<Vec<GF2Word<T>>>,
     pub all_commitments: Vec<Commitment<D>>,
     all_views: Vec<View<T>>,
     key_manager: KeyManager,
+    state: SessionState,
+    security_param: usize,
 }
 
-impl<T: Value, TapeR, D> InteractiveProver<T, TapeR, D>
+impl<T: Value, TapeR, D, C> InteractiveProver<T, TapeR, D, C>
 where
-    TapeR: SeedableRng<Seed = Key> + RngCore + CryptoRng,
+    TapeR: TapeExpander,
     D: Debug + Default + Digest + FixedOutputReset + Clone,
+    C: CommitmentScheme<D>,
 {
     pub fn new() -> Self {
         InteractiveProver {
-            pd: PhantomData::default(),
+            pd: PhantomData,
             public_output: Vec::new(),
             outputs: Vec::new(),
             all_commitments: Vec::new(),
             all_views: Vec::new(),
             key_manager: KeyManager::default(),
+            state: SessionState::Start,
+            security_param: 0,
         }
     }
-    pub fn share<R: RngCore + CryptoRng>(rng: &mut R, input: &[u8]) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
-        let share_1: Vec<u8> = (0..input.len()).map(|_| u8::gen_rand(rng)).collect();
-        let share_2: Vec<u8> = (0..input.len()).map(|_| u8::gen_rand(rng)).collect();
 
-        let share_3: Vec<u8> = input
-            .iter()
-            .zip(share_1.iter())
-            .zip(share_2.iter())
-            .map(|((&i1, &i2), &i3)| i1 ^ i2 ^ i3)
-            .collect();
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
 
-        (share_1, share_2, share_3)
+    /// Drop the state of the current session, wiping its views, so that the
+    /// prover can run a new one.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn share<R: RngCore + CryptoRng>(rng: &mut R, input: &[u8]) -> Shares {
+        sharing::share(rng, input)
+    }
+
+    /// Same as `share`, with randomness expanded from `seed`.
+    pub fn share_with_seed(seed: [u8; 32], input: &[u8]) -> Shares {
+        sharing::share_with_seed(seed, input)
     }
 
     pub fn init_parties<R: RngCore + CryptoRng>(
         rng: &mut R,
         input: &[u8],
         keys: (Key, Key, Key),
+        repetition: usize,
         tape_len: usize,
     ) -> (Party<T>, Party<T>, Party<T>) {
         let (share_1, share_2, share_3) = Self::share(rng, input);
 
-        let p1 = Party::new::<TapeR>(share_1, keys.0, tape_len);
-        let p2 = Party::new::<TapeR>(share_2, keys.1, tape_len);
-        let p3 = Party::new::<TapeR>(share_3, keys.2, tape_len);
+        let [t1, t2, t3] = expand_tapes::<T, TapeR, 3>(
+            [
+                (keys.0, tape_label(repetition, 0)),
+                (keys.1, tape_label(repetition, 1)),
+                (keys.2, tape_label(repetition, 2)),
+            ],
+            tape_len,
+        );
+
+        let p1 = Party::from_share_and_tape(share_1, t1);
+        let p2 = Party::from_share_and_tape(share_2, t2);
+        let p3 = Party::from_share_and_tape(share_3, t3);
 
         (p1, p2, p3)
     }
@@ -241,15 +1041,28 @@ where
         rng: &mut R,
         input: &[u8],
         keys: (Key, Key, Key),
+        repetition: usize,
         circuit: &impl Circuit<T>,
-    ) -> RepetitionOutput<T> {
-        let (mut p1, mut p2, mut p3) =
-            Self::init_parties(rng, input, keys, circuit.num_of_mul_gates());
+    ) -> Result<RepetitionOutput<T>, Error> {
+        let parties = Self::init_parties(rng, input, keys, repetition, circuit.num_of_mul_gates());
+        Self::execute(parties, circuit)
+    }
+
+    fn execute(
+        (mut p1, mut p2, mut p3): (Party<T>, Party<T>, Party<T>),
+        circuit: &(impl Circuit<T> + ?Sized),
+    ) -> Result<RepetitionOutput<T>, Error> {
         let party_outputs = circuit.compute_23_decomposition(&mut p1, &mut p2, &mut p3);
-        RepetitionOutput {
+        #[cfg(feature = "tracing")]
+        crate::party::trace_counters(&[&p1, &p2, &p3]);
+        [&p1, &p2, &p3]
+            .iter()
+            .try_for_each(|p| p.tape.check_consumed())?;
+
+        Ok(RepetitionOutput {
             party_outputs,
             party_views: (p1.view, p2.view, p3.view),
-        }
+        })
     }
 
     pub fn round1<R: RngCore + CryptoRng, const SIGMA: usize>(
@@ -257,22 +1070,29 @@ where
         rng: &mut R,
         witness: &[u8],
         circuit: &impl Circuit<T>,
-        public_output: &Vec<GF2Word<T>>,
+        public_output: &[GF2Word<T>],
     ) -> Result<FirstMessageA<T, D>, Error> {
+        if self.state != SessionState::Start {
+            return Err(Error::ProtocolStateError);
+        }
         let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
 
         let mut key_manager = KeyManager::new(num_of_repetitions, rng);
+        if key_manager.has_constant_seed() {
+            return Err(Error::KeySeedError);
+        }
 
         let mut outputs = Vec::<Vec<GF2Word<T>>>::with_capacity(3 * num_of_repetitions);
         let mut all_commitments = Vec::<Commitment<D>>::with_capacity(3 * num_of_repetitions);
         let mut all_views = Vec::with_capacity(3 * num_of_repetitions);
 
-        for _ in 0..num_of_repetitions {
+        for repetition in 0..num_of_repetitions {
             let k1 = key_manager.request_key();
             let k2 = key_manager.request_key();
             let k3 = key_manager.request_key();
 
-            let repetition_output = Self::prove_repetition(rng, witness, (k1, k2, k3), circuit);
+            let repetition_output =
+                Self::prove_repetition(rng, witness, (k1, k2, k3), repetition, circuit)?;
 
             // record all outputs
             outputs.push(repetition_output.party_outputs.0);
@@ -286,21 +1106,8 @@ where
 
             let views_len = all_views.len();
 
-            let p1_execution = PartyExecution {
-                key: &k1,
-                view: &all_views[views_len - 3],
-            };
-            let p2_execution = PartyExecution {
-                key: &k2,
-                view: &all_views[views_len - 2],
-            };
-            let p3_execution = PartyExecution {
-                key: &k3,
-                view: &all_views[views_len - 1],
-            };
-
-            for pi_execution in [p1_execution, p2_execution, p3_execution] {
-                let cmi = pi_execution.commit()?;
+            for (key, view) in [k1, k2, k3].iter().zip(&all_views[views_len - 3..]) {
+                let cmi = C::commit_view(key, view)?;
                 all_commitments.push(cmi);
             }
         }
@@ -310,6 +1117,8 @@ where
         self.public_output = public_output.to_vec();
         self.outputs = outputs.clone();
         self.all_commitments = all_commitments.clone();
+        self.security_param = SIGMA;
+        self.state = SessionState::Committed;
 
         Ok(FirstMessageA {
             outputs,
@@ -317,22 +1126,41 @@ where
         })
     }
 
+    /// Open the views picked by `opening_indices`. This ends the session
+    /// whatever the outcome, since answering two challenges for the same first
+    /// message would reveal the witness.
+    ///
+    /// The opened views and commitments are moved out of the prover rather
+    /// than copied, so `all_commitments` is empty afterwards.
     pub fn round3<const SIGMA: usize>(
-        &self,
+        &mut self,
         opening_indices: Vec<u8>,
-    ) -> Result<Proof<T, D, SIGMA>, Error> {
-        let mut all_views = self.all_views.clone();
-        let mut all_commitments = self.all_commitments.clone();
+    ) -> Result<Proof<T, D>, Error> {
+        if self.state != SessionState::Committed || self.security_param != SIGMA {
+            return Err(Error::ProtocolStateError);
+        }
+        self.state = SessionState::Finished;
+
+        let mut all_views = std::mem::take(&mut self.all_views);
+        let mut all_commitments = std::mem::take(&mut self.all_commitments);
         let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        if opening_indices.len() != num_of_repetitions {
+            return Err(Error::ChallengeLenError(
+                num_of_repetitions,
+                opening_indices.len(),
+            ));
+        }
+        if opening_indices.iter().any(|&party_index| party_index > 2) {
+            return Err(Error::VerificationError);
+        }
+
         let mut claimed_trits = Vec::with_capacity(num_of_repetitions);
         let mut party_inputs = Vec::with_capacity(num_of_repetitions);
 
-        let mut keys = Vec::<Key>::with_capacity(2 * num_of_repetitions);
+        let mut opened = Vec::with_capacity(2 * num_of_repetitions);
         let mut views = Vec::with_capacity(num_of_repetitions);
         let mut commitments = Vec::with_capacity(2 * num_of_repetitions);
 
-        let key_manager = self.key_manager.clone();
-
         for (repetition, &party_index) in opening_indices.iter().enumerate() {
             let party_index = party_index as usize;
             let i0 = repetition * 3 + party_index;
@@ -345,13 +1173,15 @@ where
 
             views.push(std::mem::take(&mut all_views[i1]));
 
-            keys.push(key_manager.request_key_i(i0));
-            keys.push(key_manager.request_key_i(i1));
+            opened.extend([i0, i1]);
 
             commitments.push(std::mem::take(&mut all_commitments[i2]));
         }
 
+        let keys = std::mem::take(&mut self.key_manager).reveal(opened)?;
+
         Ok(Proof {
+            security_param: SIGMA,
             party_inputs,
             commitments,
             views,
@@ -360,3 +1190,81 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod prover_tests {
+    use std::collections::HashSet;
+
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::Circuit,
+        gadgets::{blake2s::Blake2sCircuit, sha256::Sha256Circuit},
+        key::KeyManager,
+        params::ProofParams,
+        sharing::{self, Shares},
+    };
+
+    use super::Prover;
+
+    type P = Prover<u32, ChaCha20Rng, Keccak256>;
+
+    /// The keys and input shares of repetition 0 of a deterministic proof,
+    /// drawn in the order `Prover::commit` draws them.
+    fn first_repetition(circuit: &impl Circuit<u32>, sigma: usize) -> (Vec<[u8; 32]>, Shares) {
+        let witness = b"deterministic";
+        let public_output = Sha256Circuit::new(witness.len()).compute(witness);
+        let params = ProofParams::from_security(sigma);
+        let mut rng =
+            P::deterministic_rng(b"seed", witness, circuit, &public_output, b"", &params).unwrap();
+        let key_manager = KeyManager::new(params.repetitions(), &mut rng);
+        let keys = key_manager.reveal(0..3).unwrap();
+        (keys, sharing::share(&mut rng, witness))
+    }
+
+    /// All keys of a deterministic proof of `witness` for SHA-256.
+    fn deterministic_keys(witness: &[u8], sigma: usize) -> Vec<[u8; 32]> {
+        let circuit = Sha256Circuit::new(witness.len());
+        let public_output = circuit.compute(witness);
+        let params = ProofParams::from_security(sigma);
+        let mut rng =
+            P::deterministic_rng(b"", witness, &circuit, &public_output, b"", &params).unwrap();
+        KeyManager::new(params.repetitions(), &mut rng)
+            .reveal(0..3 * params.repetitions())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_deterministic_keys_are_distinct() {
+        let keys_40 = deterministic_keys(b"witness", 40);
+        let keys_80 = deterministic_keys(b"witness", 80);
+        assert_eq!(keys_40, deterministic_keys(b"witness", 40));
+
+        // no key is reused across the parties and repetitions of a proof, nor
+        // across proofs at two security levels
+        let distinct: HashSet<_> = keys_40.iter().chain(&keys_80).collect();
+        assert_eq!(distinct.len(), keys_40.len() + keys_80.len());
+    }
+
+    #[test]
+    fn test_derivation_binds_statement() {
+        let sha256 = Sha256Circuit::new(13);
+        let (keys, shares) = first_repetition(&sha256, 40);
+        assert_eq!(
+            (keys.clone(), shares.clone()),
+            first_repetition(&sha256, 40)
+        );
+
+        // a proof at another security level or of another circuit shares
+        // neither the keys nor the input shares of any repetition
+        for (other_keys, other_shares) in [
+            first_repetition(&sha256, 80),
+            first_repetition(&Blake2sCircuit::new(13), 40),
+        ] {
+            assert!(keys.iter().all(|key| !other_keys.contains(key)));
+            assert_ne!(shares.0, other_shares.0);
+            assert_ne!(shares.1, other_shares.1);
+        }
+    }
+}
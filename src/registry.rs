@@ -0,0 +1,297 @@
+//! Circuits looked up by name at run time.
+//!
+//! Applications register a constructor for each circuit they accept under a
+//! stable name, e.g. `"sha256-preimage"`, and tag the proofs they serialize
+//! with that name and the length of the witness. A generic verification
+//! service then routes a `NamedProof` to the right circuit without knowing the
+//! circuits at compile time. Names are versioned by the application: a circuit
+//! whose gates change gets a new name, since proofs of the old one no longer
+//! verify against it.
+//!
+//! Integers are little-endian:
+//!
+//! ```text
+//! named_proof = len: u64 || name || input_len: u64 || proof
+//! ```
+//!
+//! where `proof` is written by `Proof::to_bytes`.
+
+use std::collections::BTreeMap;
+
+use rand_chacha::ChaCha20Rng;
+use serde::de::DeserializeOwned;
+use sha3::{digest::FixedOutputReset, Digest, Keccak256};
+
+use crate::{
+    circuit::Circuit,
+    commitment::HashCommitment,
+    data_structures::Proof,
+    error::Error,
+    expander::TapeExpander,
+    fs::SigmaFS,
+    gadgets::{blake2s::Blake2sCircuit, ripemd160::Ripemd160Circuit, sha256::Sha256Circuit},
+    gf2_word::{GF2Word, Value},
+    serialized::{MAX_STATEMENT_LEN, SHA256_PREIMAGE},
+    verifier::Verifier,
+};
+
+/// Longest name of a registered circuit, in bytes.
+pub const MAX_NAME_LEN: usize = 255;
+
+/// Name under which `with_hashes` registers `Blake2sCircuit`.
+pub const BLAKE2S_PREIMAGE: &str = "blake2s-preimage";
+
+/// Name under which `with_hashes` registers `Ripemd160Circuit`.
+pub const RIPEMD160_PREIMAGE: &str = "ripemd160-preimage";
+
+/// Builds a circuit for a witness of the given length.
+pub type CircuitConstructor<T> =
+    Box<dyn Fn(usize) -> Result<Box<dyn Circuit<T>>, Error> + Send + Sync>;
+
+type HashCircuit = fn(usize) -> Box<dyn Circuit<u32>>;
+
+pub struct CircuitRegistry<T: Value> {
+    constructors: BTreeMap<String, CircuitConstructor<T>>,
+}
+
+impl<T: Value> Default for CircuitRegistry<T> {
+    fn default() -> Self {
+        Self {
+            constructors: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T: Value> CircuitRegistry<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `constructor` under `name`, which must not be taken and is at
+    /// most `MAX_NAME_LEN` bytes long.
+    pub fn register<F>(&mut self, name: &str, constructor: F) -> Result<(), Error>
+    where
+        F: Fn(usize) -> Result<Box<dyn Circuit<T>>, Error> + Send + Sync + 'static,
+    {
+        if name.len() > MAX_NAME_LEN {
+            return Err(Error::ParseLengthError(MAX_NAME_LEN, name.len()));
+        }
+        if self.constructors.contains_key(name) {
+            return Err(Error::DuplicateCircuitError(name.to_string()));
+        }
+        self.constructors
+            .insert(name.to_string(), Box::new(constructor));
+        Ok(())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.constructors.contains_key(name)
+    }
+
+    /// The registered names, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.constructors.keys().map(String::as_str)
+    }
+
+    /// The circuit registered under `name`, for a witness of `input_len` bytes.
+    pub fn circuit(&self, name: &str, input_len: usize) -> Result<Box<dyn Circuit<T>>, Error> {
+        let constructor = self
+            .constructors
+            .get(name)
+            .ok_or_else(|| Error::UnknownCircuitError(name.to_string()))?;
+        constructor(input_len)
+    }
+
+    /// Verify `bytes`, a `NamedProof` made at a security level of at least
+    /// `min_sigma`, against `public_output` with the circuit it names.
+    pub fn verify<TapeR, D>(
+        &self,
+        bytes: &[u8],
+        public_output: &Vec<GF2Word<T>>,
+        min_sigma: usize,
+    ) -> Result<(), Error>
+    where
+        T: DeserializeOwned,
+        TapeR: TapeExpander,
+        D: Clone + Default + Digest + FixedOutputReset,
+    {
+        let (name, input_len, proof_bytes) = NamedProof::<T, D>::split(bytes)?;
+        let circuit = self.circuit(&name, input_len)?;
+        Verifier::<T, TapeR, D, HashCommitment, SigmaFS<D>>::verify_dyn(
+            proof_bytes,
+            &circuit,
+            public_output,
+            min_sigma,
+        )
+    }
+}
+
+impl CircuitRegistry<u32> {
+    /// A registry of the preimage circuits of the hashes of this crate, for
+    /// witnesses of at most `serialized::MAX_STATEMENT_LEN` bytes.
+    pub fn with_hashes() -> Self {
+        let mut registry = Self::new();
+        let hashes: [(&str, HashCircuit); 3] = [
+            (SHA256_PREIMAGE, |len| Box::new(Sha256Circuit::new(len))),
+            (BLAKE2S_PREIMAGE, |len| Box::new(Blake2sCircuit::new(len))),
+            (RIPEMD160_PREIMAGE, |len| {
+                Box::new(Ripemd160Circuit::new(len))
+            }),
+        ];
+        for (name, circuit) in hashes {
+            registry
+                .register(name, move |len| {
+                    if len > MAX_STATEMENT_LEN {
+                        return Err(Error::ParseLengthError(MAX_STATEMENT_LEN, len));
+                    }
+                    Ok(circuit(len))
+                })
+                .expect("names are distinct");
+        }
+        registry
+    }
+
+    /// `verify` with `ChaCha20Rng` tapes and `Keccak256`, as `serialized`.
+    pub fn verify_default(
+        &self,
+        bytes: &[u8],
+        public_output: &Vec<GF2Word<u32>>,
+        min_sigma: usize,
+    ) -> Result<(), Error> {
+        self.verify::<ChaCha20Rng, Keccak256>(bytes, public_output, min_sigma)
+    }
+}
+
+/// A proof tagged with the name its circuit is registered under and the length
+/// of its witness.
+pub struct NamedProof<T: Value, D: Default + Digest + Clone> {
+    pub circuit: String,
+    pub input_len: usize,
+    pub proof: Proof<T, D>,
+}
+
+impl<T: Value, D: Default + Digest + Clone> NamedProof<T, D> {
+    pub fn new(circuit: &str, input_len: usize, proof: Proof<T, D>) -> Self {
+        Self {
+            circuit: circuit.to_string(),
+            input_len,
+            proof,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        if self.circuit.len() > MAX_NAME_LEN {
+            return Err(Error::SerializationError);
+        }
+        let mut bytes = (self.circuit.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(self.circuit.as_bytes());
+        bytes.extend_from_slice(&(self.input_len as u64).to_le_bytes());
+        bytes.extend(self.proof.to_bytes()?);
+        Ok(bytes)
+    }
+
+    /// The name, the input length and the bytes of the proof.
+    fn split(bytes: &[u8]) -> Result<(String, usize, &[u8]), Error> {
+        let read_u64 = |bytes: &[u8]| -> Result<usize, Error> {
+            let word = bytes.get(..8).ok_or(Error::SerializationError)?;
+            usize::try_from(u64::from_le_bytes(word.try_into().unwrap()))
+                .map_err(|_| Error::SerializationError)
+        };
+
+        let name_len = read_u64(bytes)?;
+        if name_len > MAX_NAME_LEN {
+            return Err(Error::SerializationError);
+        }
+        let rest = &bytes[8..];
+        let name = rest.get(..name_len).ok_or(Error::SerializationError)?;
+        let name = String::from_utf8(name.to_vec()).map_err(|_| Error::SerializationError)?;
+        let rest = &rest[name_len..];
+        let input_len = read_u64(rest)?;
+
+        Ok((name, input_len, &rest[8..]))
+    }
+}
+
+impl<T: Value + DeserializeOwned, D: Default + Digest + Clone> NamedProof<T, D> {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let (circuit, input_len, proof_bytes) = Self::split(bytes)?;
+        Ok(Self {
+            circuit,
+            input_len,
+            proof: Proof::from_bytes(proof_bytes)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod registry_tests {
+    use rand::thread_rng;
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::Circuit,
+        error::Error,
+        gadgets::{add_mod::AdderCircuit, sha256::Sha256Circuit},
+        prover::Prover,
+        serialized::SHA256_PREIMAGE,
+    };
+
+    use super::{CircuitRegistry, NamedProof, BLAKE2S_PREIMAGE};
+
+    type P = Prover<u32, ChaCha20Rng, Keccak256>;
+
+    #[test]
+    fn test_registry() {
+        let mut rng = thread_rng();
+        let mut registry = CircuitRegistry::with_hashes();
+        registry
+            .register("adder-v1", |_| Ok(Box::new(AdderCircuit::<u32>::new())))
+            .unwrap();
+        assert!(matches!(
+            registry.register("adder-v1", |_| Ok(Box::new(AdderCircuit::<u32>::new()))),
+            Err(Error::DuplicateCircuitError(_))
+        ));
+        assert!(registry.contains(BLAKE2S_PREIMAGE));
+        assert_eq!(registry.names().count(), 4);
+
+        let preimage = b"routed by name";
+        let circuit = Sha256Circuit::new(preimage.len());
+        let output = circuit.compute(preimage);
+        let proof = P::prove::<_, 40>(&mut rng, preimage, &circuit, &output).unwrap();
+        let bytes = NamedProof::new(SHA256_PREIMAGE, preimage.len(), proof)
+            .to_bytes()
+            .unwrap();
+
+        registry.verify_default(&bytes, &output, 40).unwrap();
+        let named = NamedProof::<u32, Keccak256>::from_bytes(&bytes).unwrap();
+        assert_eq!(named.circuit, SHA256_PREIMAGE);
+        assert_eq!(named.input_len, preimage.len());
+
+        // routed to another circuit or length, the proof does not verify
+        let other = NamedProof::new(BLAKE2S_PREIMAGE, preimage.len(), named.proof.clone());
+        assert!(registry
+            .verify_default(&other.to_bytes().unwrap(), &output, 40)
+            .is_err());
+        let other = NamedProof::new(SHA256_PREIMAGE, preimage.len() + 1, named.proof.clone());
+        assert!(registry
+            .verify_default(&other.to_bytes().unwrap(), &output, 40)
+            .is_err());
+        let other = NamedProof::new("sha256-preimage-v2", preimage.len(), named.proof);
+        assert!(matches!(
+            registry.verify_default(&other.to_bytes().unwrap(), &output, 40),
+            Err(Error::UnknownCircuitError(name)) if name == "sha256-preimage-v2"
+        ));
+
+        // the adder is routed as well
+        let adder = registry.circuit("adder-v1", 8).unwrap();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = adder.compute(&input);
+        let proof = P::prove::<_, 40>(&mut rng, &input, &adder, &output).unwrap();
+        let bytes = NamedProof::new("adder-v1", 8, proof).to_bytes().unwrap();
+        registry.verify_default(&bytes, &output, 40).unwrap();
+
+        // truncated input
+        assert!(registry.verify_default(&bytes[..12], &output, 40).is_err());
+    }
+}
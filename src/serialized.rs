@@ -0,0 +1,205 @@
+//! Proving and verification over serialized statements and proofs.
+//!
+//! `verify_bytes` is the one entry point shared by language bindings and
+//! services, and `prove_bytes` its counterpart. They only take bytes, support
+//! a fixed set of circuits and parameters, and reject inputs above fixed sizes
+//! before parsing them, so what they allocate is bounded by those sizes.
+
+use rand::{CryptoRng, RngCore};
+use rand_chacha::ChaCha20Rng;
+use sha3::Keccak256;
+
+use crate::{
+    circuit::Circuit,
+    error::Error,
+    gadgets::sha256::Sha256Circuit,
+    prover::Prover,
+    statement::{CircuitFingerprint, Statement},
+    verifier::Verifier,
+};
+
+pub const MAX_STATEMENT_LEN: usize = 1 << 16;
+pub const MAX_PROOF_LEN: usize = 1 << 26;
+
+/// Name under which statements refer to `Sha256Circuit`.
+pub const SHA256_PREIMAGE: &str = "sha256-preimage";
+
+/// Security parameters accepted by `prove_bytes` and `verify_bytes`.
+pub const SECURITY_PARAMS: [u64; 3] = [40, 80, 128];
+
+/// Fingerprint of the statement "I know a preimage of `input_len` bytes".
+pub fn sha256_preimage_fingerprint(input_len: usize) -> CircuitFingerprint {
+    CircuitFingerprint::of(SHA256_PREIMAGE, &Sha256Circuit::new(input_len))
+}
+
+/// Verify `proof_bytes` against the statement in `statement_blob`, both as
+/// produced by `Statement::to_blob` and `Proof::to_bytes` with `u32` words,
+/// `ChaCha20Rng` tapes and `Keccak256`.
+pub fn verify_bytes(statement_blob: &[u8], proof_bytes: &[u8]) -> Result<(), Error> {
+    if statement_blob.len() > MAX_STATEMENT_LEN || proof_bytes.len() > MAX_PROOF_LEN {
+        return Err(Error::SerializationError);
+    }
+
+    let statement = Statement::<u32>::from_blob::<Keccak256>(statement_blob)?;
+
+    match statement.security_param {
+        40 => verify_statement::<40>(&statement, proof_bytes),
+        80 => verify_statement::<80>(&statement, proof_bytes),
+        128 => verify_statement::<128>(&statement, proof_bytes),
+        _ => Err(Error::StatementMismatchError),
+    }
+}
+
+/// Prove the statement in `statement_blob` with `witness`, returning the proof
+/// in the format read by `verify_bytes`.
+pub fn prove_bytes<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    statement_blob: &[u8],
+    witness: &[u8],
+) -> Result<Vec<u8>, Error> {
+    if statement_blob.len() > MAX_STATEMENT_LEN {
+        return Err(Error::SerializationError);
+    }
+
+    let statement = Statement::<u32>::from_blob::<Keccak256>(statement_blob)?;
+
+    match statement.security_param {
+        40 => prove_statement::<R, 40>(rng, &statement, witness),
+        80 => prove_statement::<R, 80>(rng, &statement, witness),
+        128 => prove_statement::<R, 128>(rng, &statement, witness),
+        _ => Err(Error::StatementMismatchError),
+    }
+}
+
+fn circuit_of<const SIGMA: usize>(statement: &Statement<u32>) -> Result<impl Circuit<u32>, Error> {
+    let circuit = match statement.circuit.name.as_str() {
+        SHA256_PREIMAGE if statement.circuit.party_input_len <= MAX_STATEMENT_LEN as u64 => {
+            Sha256Circuit::new(statement.circuit.party_input_len as usize)
+        }
+        _ => return Err(Error::StatementMismatchError),
+    };
+    statement.check::<SIGMA>(&circuit)?;
+
+    Ok(circuit)
+}
+
+fn prove_statement<R: RngCore + CryptoRng, const SIGMA: usize>(
+    rng: &mut R,
+    statement: &Statement<u32>,
+    witness: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let circuit = circuit_of::<SIGMA>(statement)?;
+    if witness.len() != circuit.party_input_len() {
+        return Err(Error::StatementMismatchError);
+    }
+
+    Prover::<u32, ChaCha20Rng, Keccak256>::prove_with_context::<R, SIGMA>(
+        rng,
+        witness,
+        &circuit,
+        &statement.public_output,
+        &statement.context,
+    )?
+    .to_bytes()
+}
+
+fn verify_statement<const SIGMA: usize>(
+    statement: &Statement<u32>,
+    proof_bytes: &[u8],
+) -> Result<(), Error> {
+    let circuit = circuit_of::<SIGMA>(statement)?;
+
+    let mut reader = proof_bytes;
+    Verifier::<u32, ChaCha20Rng, Keccak256>::verify_from_reader_with_context::<SIGMA, _>(
+        &mut reader,
+        &circuit,
+        &statement.public_output,
+        &statement.context,
+    )?;
+
+    // a proof has a single encoding
+    if !reader.is_empty() {
+        return Err(Error::SerializationError);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod serialized_tests {
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::Circuit, error::Error, gadgets::sha256::Sha256Circuit, prover::Prover,
+        statement::Statement,
+    };
+
+    use super::{prove_bytes, sha256_preimage_fingerprint, verify_bytes};
+
+    const SIGMA: usize = 40;
+
+    #[test]
+    fn test_verify_bytes() {
+        let mut rng = thread_rng();
+        let preimage = b"preimage";
+        let circuit = Sha256Circuit::new(preimage.len());
+
+        let statement = Statement::new::<SIGMA>(
+            sha256_preimage_fingerprint(preimage.len()),
+            circuit.compute(preimage),
+            b"verify_bytes",
+        );
+        let blob = statement.to_blob::<Keccak256>().unwrap();
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove_with_context::<ThreadRng, SIGMA>(
+            &mut rng,
+            preimage,
+            &circuit,
+            &statement.public_output,
+            &statement.context,
+        )
+        .unwrap();
+        let proof_bytes = proof.to_bytes().unwrap();
+
+        verify_bytes(&blob, &proof_bytes).unwrap();
+
+        // trailing bytes
+        let mut padded = proof_bytes.clone();
+        padded.push(0);
+        assert!(verify_bytes(&blob, &padded).is_err());
+
+        // statement for another circuit
+        let mut other = statement.clone();
+        other.circuit.name = "unknown".to_string();
+        assert!(matches!(
+            verify_bytes(&other.to_blob::<Keccak256>().unwrap(), &proof_bytes),
+            Err(Error::StatementMismatchError)
+        ));
+
+        // statement with another context
+        let mut other = statement;
+        other.context.clear();
+        assert!(verify_bytes(&other.to_blob::<Keccak256>().unwrap(), &proof_bytes).is_err());
+    }
+
+    #[test]
+    fn test_prove_bytes() {
+        let mut rng = thread_rng();
+        let preimage = b"preimage";
+        let circuit = Sha256Circuit::new(preimage.len());
+
+        let blob = Statement::new::<SIGMA>(
+            sha256_preimage_fingerprint(preimage.len()),
+            circuit.compute(preimage),
+            &[],
+        )
+        .to_blob::<Keccak256>()
+        .unwrap();
+
+        let proof_bytes = prove_bytes(&mut rng, &blob, preimage).unwrap();
+        verify_bytes(&blob, &proof_bytes).unwrap();
+
+        assert!(prove_bytes(&mut rng, &blob, b"too short").is_err());
+    }
+}
@@ -0,0 +1,136 @@
+//! Byte-wise XOR sharing of the witness among the three parties.
+//!
+//! The first two shares are random and the third is the xor of the witness
+//! with both, so any two shares reveal nothing about the witness and all three
+//! reconstruct it. These are the shares the prover gives to the parties of
+//! every repetition, see `Prover::share`. Sharing from a seed reproduces the
+//! same shares, for test vectors and for tooling outside of this crate.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::{
+    batch::xor3_bytes,
+    error::Error,
+    gf2_word::{GenRand, Value},
+};
+
+pub type Shares = (Vec<u8>, Vec<u8>, Vec<u8>);
+
+/// Split `input` into three shares with randomness drawn from `rng`.
+pub fn share<R: RngCore + CryptoRng>(rng: &mut R, input: &[u8]) -> Shares {
+    let share_1 = u8::gen_rand_packed(rng, input.len());
+    let share_2 = u8::gen_rand_packed(rng, input.len());
+
+    let share_3 = xor3_bytes(input, &share_1, &share_2);
+
+    (share_1, share_2, share_3)
+}
+
+/// Split `input` into three shares with randomness expanded from `seed`. The
+/// same seed and input give the same shares.
+pub fn share_with_seed(seed: [u8; 32], input: &[u8]) -> Shares {
+    share(&mut ChaCha20Rng::from_seed(seed), input)
+}
+
+/// The input shared by the three shares, or `Error::ShareConsistencyError`
+/// if they have different lengths.
+pub fn reconstruct(share_1: &[u8], share_2: &[u8], share_3: &[u8]) -> Result<Vec<u8>, Error> {
+    if share_2.len() != share_1.len() || share_3.len() != share_1.len() {
+        return Err(Error::ShareConsistencyError);
+    }
+    Ok(xor3_bytes(share_1, share_2, share_3))
+}
+
+/// Checks that the shares have the same length and reconstruct `input`.
+pub fn check_shares(
+    (share_1, share_2, share_3): (&[u8], &[u8], &[u8]),
+    input: &[u8],
+) -> Result<(), Error> {
+    if reconstruct(share_1, share_2, share_3)? != input {
+        return Err(Error::ShareConsistencyError);
+    }
+    Ok(())
+}
+
+/// Checks that the output shares of the three parties, as computed by
+/// `Circuit::compute_23_decomposition`, reconstruct `output`.
+pub fn check_output_shares<T: Value>(
+    (o1, o2, o3): (&[T], &[T], &[T]),
+    output: &[T],
+) -> Result<(), Error> {
+    if [o1.len(), o2.len(), o3.len()]
+        .iter()
+        .any(|&len| len != output.len())
+    {
+        return Err(Error::ShareConsistencyError);
+    }
+    let consistent = output
+        .iter()
+        .enumerate()
+        .all(|(i, &o)| o1[i] ^ o2[i] ^ o3[i] == o);
+    if !consistent {
+        return Err(Error::ShareConsistencyError);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod sharing_tests {
+    use rand::thread_rng;
+
+    use crate::error::Error;
+
+    use super::{check_output_shares, check_shares, reconstruct, share, share_with_seed};
+
+    #[test]
+    fn test_share_and_reconstruct() {
+        let input = b"the witness";
+        let (s1, s2, s3) = share(&mut thread_rng(), input);
+        assert_eq!(reconstruct(&s1, &s2, &s3).unwrap(), input);
+        check_shares((&s1, &s2, &s3), input).unwrap();
+
+        let mut s3 = s3;
+        s3[0] ^= 1;
+        assert!(matches!(
+            check_shares((&s1, &s2, &s3), input),
+            Err(Error::ShareConsistencyError)
+        ));
+        assert!(matches!(
+            check_shares((&s1, &s2, &s3[1..]), input),
+            Err(Error::ShareConsistencyError)
+        ));
+        assert!(matches!(
+            reconstruct(&s1[1..], &s2, &s3),
+            Err(Error::ShareConsistencyError)
+        ));
+        assert!(matches!(
+            check_shares((&s1[1..], &s2[1..], &s3[1..]), input),
+            Err(Error::ShareConsistencyError)
+        ));
+
+        // the empty witness has empty shares
+        let (s1, s2, s3) = share(&mut thread_rng(), &[]);
+        assert!(s1.is_empty() && s2.is_empty() && s3.is_empty());
+    }
+
+    #[test]
+    fn test_share_with_seed() {
+        let input = b"the witness";
+        let shares = share_with_seed([7; 32], input);
+        assert_eq!(shares, share_with_seed([7; 32], input));
+        assert_ne!(shares, share_with_seed([8; 32], input));
+        assert_eq!(reconstruct(&shares.0, &shares.1, &shares.2).unwrap(), input);
+    }
+
+    #[test]
+    fn test_output_shares() {
+        let output = [0xdeadbeefu32, 7];
+        let (o1, o2) = ([1u32, 2], [3u32, 4]);
+        let o3 = [output[0] ^ 1 ^ 3, output[1] ^ 2 ^ 4];
+        check_output_shares((&o1, &o2, &o3), &output).unwrap();
+        assert!(check_output_shares((&o1, &o2, &o3), &[0, 7]).is_err());
+        assert!(check_output_shares((&o1, &o2, &o3[..1]), &output).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! Picnic-style signatures.
+//!
+//! The secret key is a random preimage and the public key is its SHA-256
+//! digest. A signature is a non-interactive proof of knowledge of the secret
+//! key, with the message folded into the Fiat-Shamir transcript so that the
+//! proof only verifies for that message.
+//!
+//! A ring signature proves knowledge of the secret key of one of a ring of
+//! public keys, without revealing which, with a `RingProof` over the members.
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::{CryptoRng, RngCore};
+use sha3::Keccak256;
+
+use crate::{
+    circuit::Circuit,
+    data_structures::{Instance, RingProof, TypedProof},
+    error::Error,
+    gadgets::sha256::{sha256, Sha256Circuit},
+    gf2_word::GF2Word,
+    prover::Prover,
+    verifier::Verifier,
+    witness::Witness,
+};
+
+pub const SECRET_KEY_LEN: usize = 32;
+pub const PUBLIC_KEY_LEN: usize = 32;
+
+/// Separates signatures from other proofs about the same public key.
+const DOMAIN: &[u8] = b"zkboo signature";
+
+pub type Signature<const SIGMA: usize> = TypedProof<u32, Keccak256, SIGMA>;
+
+pub type RingSignature = RingProof<u32, Keccak256>;
+
+#[derive(Debug)]
+pub struct SecretKey(Witness);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey(pub [u8; PUBLIC_KEY_LEN]);
+
+impl SecretKey {
+    pub fn public_key(&self) -> PublicKey {
+        let digest: Vec<u8> = sha256(self.0.expose_secret())
+            .iter()
+            .flat_map(|word| word.value.to_be_bytes())
+            .collect();
+        PublicKey(digest.try_into().unwrap())
+    }
+}
+
+impl PublicKey {
+    fn to_words(self) -> Vec<GF2Word<u32>> {
+        self.0
+            .chunks(4)
+            .map(|chunk| u32::from_be_bytes(chunk.try_into().unwrap()).into())
+            .collect()
+    }
+}
+
+fn context(message: &[u8]) -> Vec<u8> {
+    [DOMAIN, message].concat()
+}
+
+pub fn keygen<R: RngCore + CryptoRng>(rng: &mut R) -> (SecretKey, PublicKey) {
+    let mut sk = vec![0u8; SECRET_KEY_LEN];
+    rng.fill_bytes(&mut sk);
+
+    let sk = SecretKey(Witness::new(sk));
+    let pk = sk.public_key();
+    (sk, pk)
+}
+
+pub fn sign<R: RngCore + CryptoRng, const SIGMA: usize>(
+    rng: &mut R,
+    message: &[u8],
+    sk: &SecretKey,
+) -> Result<Signature<SIGMA>, Error> {
+    let circuit = Sha256Circuit::new(SECRET_KEY_LEN);
+    let public_output = circuit.compute(sk.0.expose_secret());
+
+    Prover::<u32, ChaCha20Rng, Keccak256>::prove_with_context::<R, SIGMA>(
+        rng,
+        sk.0.expose_secret(),
+        &circuit,
+        &public_output,
+        &context(message),
+    )?
+    .try_into()
+}
+
+/// Same as `sign`, deriving the randomness of the signature from the secret
+/// key and the message, so that signing a message twice gives the same
+/// signature.
+pub fn sign_deterministic<const SIGMA: usize>(
+    message: &[u8],
+    sk: &SecretKey,
+) -> Result<Signature<SIGMA>, Error> {
+    let circuit = Sha256Circuit::new(SECRET_KEY_LEN);
+    let public_output = circuit.compute(sk.0.expose_secret());
+
+    Prover::<u32, ChaCha20Rng, Keccak256>::prove_deterministic::<SIGMA>(
+        &[],
+        sk.0.expose_secret(),
+        &circuit,
+        &public_output,
+        &context(message),
+    )?
+    .try_into()
+}
+
+pub fn verify<const SIGMA: usize>(
+    message: &[u8],
+    signature: &Signature<SIGMA>,
+    pk: &PublicKey,
+) -> Result<(), Error> {
+    let circuit = Sha256Circuit::new(SECRET_KEY_LEN);
+
+    Verifier::<u32, ChaCha20Rng, Keccak256>::verify_with_context::<SIGMA>(
+        signature,
+        &circuit,
+        &pk.to_words(),
+        &context(message),
+    )
+}
+
+/// Sign `message` on behalf of `ring`, which must hold the public key of `sk`.
+pub fn ring_sign<R: RngCore + CryptoRng, const SIGMA: usize>(
+    rng: &mut R,
+    message: &[u8],
+    sk: &SecretKey,
+    ring: &[PublicKey],
+) -> Result<RingSignature, Error> {
+    let pk = sk.public_key();
+    if !ring.contains(&pk) {
+        return Err(Error::StatementMismatchError);
+    }
+
+    let circuit = Sha256Circuit::new(SECRET_KEY_LEN);
+    let public_outputs: Vec<_> = ring.iter().map(|member| member.to_words()).collect();
+    Prover::<u32, ChaCha20Rng, Keccak256>::prove_ring::<R, SIGMA>(
+        rng,
+        sk.0.expose_secret(),
+        &instances(&circuit, &public_outputs),
+        &context(message),
+    )
+}
+
+pub fn ring_verify<const SIGMA: usize>(
+    message: &[u8],
+    signature: &RingSignature,
+    ring: &[PublicKey],
+) -> Result<(), Error> {
+    let circuit = Sha256Circuit::new(SECRET_KEY_LEN);
+    let public_outputs: Vec<_> = ring.iter().map(|member| member.to_words()).collect();
+
+    Verifier::<u32, ChaCha20Rng, Keccak256>::verify_ring::<SIGMA>(
+        signature,
+        &instances(&circuit, &public_outputs),
+        &context(message),
+    )
+}
+
+fn instances<'a>(
+    circuit: &'a Sha256Circuit,
+    public_outputs: &'a [Vec<GF2Word<u32>>],
+) -> Vec<Instance<'a, u32>> {
+    public_outputs
+        .iter()
+        .map(|public_output| Instance {
+            circuit,
+            public_output,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod signature_tests {
+    use rand::thread_rng;
+
+    use super::{keygen, ring_sign, ring_verify, sign, sign_deterministic, verify};
+
+    const SIGMA: usize = 40;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let mut rng = thread_rng();
+        let (sk, pk) = keygen(&mut rng);
+        assert_eq!(sk.public_key(), pk);
+
+        let signature = sign::<_, SIGMA>(&mut rng, b"hello", &sk).unwrap();
+        verify(b"hello", &signature, &pk).unwrap();
+
+        // the signature is bound to the message and the public key
+        assert!(verify(b"hello!", &signature, &pk).is_err());
+        let (_, other_pk) = keygen(&mut rng);
+        assert!(verify(b"hello", &signature, &other_pk).is_err());
+    }
+
+    #[test]
+    fn test_serialized_signature() {
+        let mut rng = thread_rng();
+        let (sk, pk) = keygen(&mut rng);
+
+        let signature = sign::<_, SIGMA>(&mut rng, b"message", &sk).unwrap();
+        let bytes = signature.to_bytes().unwrap();
+        let signature = super::Signature::<SIGMA>::from_bytes(&bytes).unwrap();
+
+        verify(b"message", &signature, &pk).unwrap();
+    }
+
+    #[test]
+    fn test_deterministic_signature() {
+        let mut rng = thread_rng();
+        let (sk, pk) = keygen(&mut rng);
+
+        let signature = sign_deterministic::<SIGMA>(b"message", &sk).unwrap();
+        verify(b"message", &signature, &pk).unwrap();
+        let again = sign_deterministic::<SIGMA>(b"message", &sk).unwrap();
+        assert_eq!(signature.to_bytes().unwrap(), again.to_bytes().unwrap());
+
+        let other = sign_deterministic::<SIGMA>(b"other message", &sk).unwrap();
+        assert_ne!(signature.keys, other.keys);
+        verify(b"other message", &other, &pk).unwrap();
+    }
+
+    #[test]
+    fn test_ring_signature() {
+        let mut rng = thread_rng();
+        let (sk, pk) = keygen(&mut rng);
+        let (_, pk_0) = keygen(&mut rng);
+        let (_, pk_2) = keygen(&mut rng);
+        let ring = [pk_0, pk, pk_2];
+
+        let signature = ring_sign::<_, SIGMA>(&mut rng, b"hello", &sk, &ring).unwrap();
+        let bytes = signature.to_bytes().unwrap();
+        let signature = super::RingSignature::from_bytes(&bytes).unwrap();
+        ring_verify::<SIGMA>(b"hello", &signature, &ring).unwrap();
+
+        // the signature is bound to the message and the ring, in order
+        assert!(ring_verify::<SIGMA>(b"hello!", &signature, &ring).is_err());
+        assert!(ring_verify::<SIGMA>(b"hello", &signature, &[pk, pk_0, pk_2]).is_err());
+        assert!(ring_verify::<SIGMA>(b"hello", &signature, &ring[..2]).is_err());
+
+        // only members sign
+        assert!(ring_sign::<_, SIGMA>(&mut rng, b"hello", &sk, &[pk_0, pk_2]).is_err());
+    }
+}
@@ -0,0 +1,331 @@
+//! Statements for provisioning verifiers.
+//!
+//! A `Statement` bundles everything a verifier needs besides the proof: which
+//! circuit is checked, the claimed public output, the security parameters and
+//! a context label. It is distributed as a single blob which ends with a hash
+//! of its contents, and the same hash identifies the statement a proof is for.
+//!
+//! Services which keep an append-only log of the proofs they accepted record
+//! the `audit_digest` of each one. It binds the statement and the whole proof,
+//! challenge included, and does not change when the proof is re-serialized.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha3::{digest::FixedOutputReset, Digest};
+
+use crate::{
+    circuit::Circuit,
+    config::HASH_LEN,
+    data_structures::Proof,
+    error::Error,
+    expander::TapeExpander,
+    gf2_word::{GF2Word, Value},
+    verifier::Verifier,
+};
+
+/// Identifies a circuit by a user chosen name together with its shape.
+///
+/// Circuits are code, so the name is what ties a statement to an
+/// implementation; the shape catches most mismatches between the two.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitFingerprint {
+    pub name: String,
+    pub word_bits: u64,
+    pub party_input_len: u64,
+    pub party_output_len: u64,
+    pub num_of_mul_gates: u64,
+}
+
+impl CircuitFingerprint {
+    pub fn of<T: Value>(name: &str, circuit: &impl Circuit<T>) -> Self {
+        Self {
+            name: name.to_string(),
+            word_bits: T::bits_len() as u64,
+            party_input_len: circuit.party_input_len() as u64,
+            party_output_len: circuit.party_output_len() as u64,
+            num_of_mul_gates: circuit.num_of_mul_gates() as u64,
+        }
+    }
+
+    /// Whether `circuit` has the shape recorded in this fingerprint.
+    pub fn matches<T: Value>(&self, circuit: &impl Circuit<T>) -> bool {
+        *self == Self::of(&self.name, circuit)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound(serialize = "", deserialize = "T: Deserialize<'de>"))]
+pub struct Statement<T: Value> {
+    pub circuit: CircuitFingerprint,
+    pub public_output: Vec<GF2Word<T>>,
+    pub security_param: u64,
+    pub hash_len: u64,
+    pub context: Vec<u8>,
+}
+
+impl<T: Value> Statement<T> {
+    pub fn new<const SIGMA: usize>(
+        circuit: CircuitFingerprint,
+        public_output: Vec<GF2Word<T>>,
+        context: &[u8],
+    ) -> Self {
+        Self {
+            circuit,
+            public_output,
+            security_param: SIGMA as u64,
+            hash_len: HASH_LEN as u64,
+            context: context.to_vec(),
+        }
+    }
+
+    /// Hash of the statement, which doubles as its identifier.
+    pub fn id<D: Digest>(&self) -> Result<[u8; HASH_LEN], Error> {
+        let body = bincode::serialize(self).map_err(|_| Error::SerializationError)?;
+        hash::<D>(&body)
+    }
+
+    /// Serializes the statement followed by its integrity hash.
+    pub fn to_blob<D: Digest>(&self) -> Result<Vec<u8>, Error> {
+        let mut blob = bincode::serialize(self).map_err(|_| Error::SerializationError)?;
+        let id = hash::<D>(&blob)?;
+        blob.extend_from_slice(&id);
+        Ok(blob)
+    }
+
+    /// Checks that `circuit` and `SIGMA` are the ones this statement was made for.
+    pub fn check<const SIGMA: usize>(&self, circuit: &impl Circuit<T>) -> Result<(), Error> {
+        if !self.circuit.matches(circuit)
+            || self.security_param != SIGMA as u64
+            || self.hash_len != HASH_LEN as u64
+            || self.public_output.len() != circuit.party_output_len()
+        {
+            return Err(Error::StatementMismatchError);
+        }
+        Ok(())
+    }
+}
+
+impl<T: Value + DeserializeOwned + PartialEq> Statement<T> {
+    /// Parses a blob produced by `to_blob`, rejecting it if the hash does not match.
+    pub fn from_blob<D: Digest>(blob: &[u8]) -> Result<Self, Error> {
+        if blob.len() < HASH_LEN {
+            return Err(Error::SerializationError);
+        }
+        let (body, id) = blob.split_at(blob.len() - HASH_LEN);
+        if hash::<D>(body)? != id {
+            return Err(Error::IntegrityError);
+        }
+
+        let statement: Self = bincode::deserialize(body).map_err(|_| Error::SerializationError)?;
+        // reject trailing bytes, so that every statement has exactly one blob
+        if bincode::serialized_size(&statement).map_err(|_| Error::SerializationError)?
+            != body.len() as u64
+        {
+            return Err(Error::SerializationError);
+        }
+
+        Ok(statement)
+    }
+
+    /// Verifies `proof` against this statement.
+    pub fn verify<TapeR, D, const SIGMA: usize>(
+        &self,
+        proof: &Proof<T, D>,
+        circuit: &impl Circuit<T>,
+    ) -> Result<(), Error>
+    where
+        TapeR: TapeExpander,
+        D: Clone + Default + Digest + FixedOutputReset,
+    {
+        self.check::<SIGMA>(circuit)?;
+        Verifier::<T, TapeR, D>::verify_with_context::<SIGMA>(
+            proof,
+            circuit,
+            &self.public_output,
+            &self.context,
+        )
+    }
+
+    /// Digest of `proof` for this statement, as recorded in audit logs.
+    pub fn audit_digest<D>(&self, proof: &Proof<T, D>) -> Result<[u8; HASH_LEN], Error>
+    where
+        D: Clone + Default + Digest,
+    {
+        let statement_id = self.id::<D>()?;
+        let proof_hash = hash::<D>(&proof.to_bytes()?)?;
+        hash::<D>(&[AUDIT_DOMAIN, &statement_id, &proof_hash].concat())
+    }
+
+    /// Verifies `proof` and returns the digest to log for it.
+    pub fn verify_for_audit<TapeR, D, const SIGMA: usize>(
+        &self,
+        proof: &Proof<T, D>,
+        circuit: &impl Circuit<T>,
+    ) -> Result<[u8; HASH_LEN], Error>
+    where
+        TapeR: TapeExpander,
+        D: Clone + Default + Digest + FixedOutputReset,
+    {
+        self.verify::<TapeR, D, SIGMA>(proof, circuit)?;
+        self.audit_digest(proof)
+    }
+
+    /// Verifies `proof` and checks that it is the one a log entry was made for.
+    pub fn verify_against_log<TapeR, D, const SIGMA: usize>(
+        &self,
+        proof: &Proof<T, D>,
+        circuit: &impl Circuit<T>,
+        logged_digest: &[u8; HASH_LEN],
+    ) -> Result<(), Error>
+    where
+        TapeR: TapeExpander,
+        D: Clone + Default + Digest + FixedOutputReset,
+    {
+        if self.verify_for_audit::<TapeR, D, SIGMA>(proof, circuit)? != *logged_digest {
+            return Err(Error::IntegrityError);
+        }
+        Ok(())
+    }
+}
+
+const AUDIT_DOMAIN: &[u8] = b"zkboo-audit-v1";
+
+fn hash<D: Digest>(data: &[u8]) -> Result<[u8; HASH_LEN], Error> {
+    let digest_len = <D as Digest>::output_size();
+    if HASH_LEN != digest_len {
+        return Err(Error::HashLenError(HASH_LEN, digest_len));
+    }
+    // safe to unwrap since we check digest output is of right side
+    Ok(D::digest(data).to_vec().try_into().unwrap())
+}
+
+#[cfg(test)]
+mod statement_tests {
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::Circuit, data_structures::Proof, error::Error, gadgets::add_mod::AdderCircuit,
+        prover::Prover,
+    };
+
+    use super::{CircuitFingerprint, Statement};
+
+    const SIGMA: usize = 40;
+
+    fn adder_statement(input: &[u8]) -> Statement<u32> {
+        let circuit = AdderCircuit::<u32>::new();
+        Statement::new::<SIGMA>(
+            CircuitFingerprint::of("adder-u32", &circuit),
+            circuit.compute(input),
+            b"provisioning test",
+        )
+    }
+
+    #[test]
+    fn test_blob_roundtrip_and_verify() {
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [3u32.to_le_bytes(), 4u32.to_le_bytes()].concat();
+
+        let statement = adder_statement(&input);
+        let blob = statement.to_blob::<Keccak256>().unwrap();
+        let provisioned = Statement::<u32>::from_blob::<Keccak256>(&blob).unwrap();
+        assert_eq!(provisioned, statement);
+        assert_eq!(
+            provisioned.id::<Keccak256>().unwrap()[..],
+            blob[blob.len() - 32..]
+        );
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove_with_context::<ThreadRng, SIGMA>(
+            &mut rng,
+            &input,
+            &circuit,
+            &statement.public_output,
+            &statement.context,
+        )
+        .unwrap();
+        provisioned
+            .verify::<ChaCha20Rng, Keccak256, SIGMA>(&proof, &circuit)
+            .unwrap();
+
+        // the proof is bound to the context of the statement
+        let mut other = provisioned.clone();
+        other.context = b"another context".to_vec();
+        assert!(other
+            .verify::<ChaCha20Rng, Keccak256, SIGMA>(&proof, &circuit)
+            .is_err());
+    }
+
+    #[test]
+    fn test_tampered_blob_is_rejected() {
+        let input = [3u32.to_le_bytes(), 4u32.to_le_bytes()].concat();
+        let mut blob = adder_statement(&input).to_blob::<Keccak256>().unwrap();
+        blob[0] ^= 1;
+
+        assert!(matches!(
+            Statement::<u32>::from_blob::<Keccak256>(&blob),
+            Err(Error::IntegrityError)
+        ));
+    }
+
+    #[test]
+    fn test_mismatching_parameters_are_rejected() {
+        let input = [3u32.to_le_bytes(), 4u32.to_le_bytes()].concat();
+        let statement = adder_statement(&input);
+        let circuit = AdderCircuit::<u32>::new();
+
+        assert!(statement.check::<SIGMA>(&circuit).is_ok());
+        assert!(matches!(
+            statement.check::<80>(&circuit),
+            Err(Error::StatementMismatchError)
+        ));
+
+        let mut altered = statement.clone();
+        altered.circuit.num_of_mul_gates += 1;
+        assert!(altered.check::<SIGMA>(&circuit).is_err());
+    }
+
+    #[test]
+    fn test_audit_digest() {
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [3u32.to_le_bytes(), 4u32.to_le_bytes()].concat();
+        let statement = adder_statement(&input);
+
+        let prove = |rng: &mut ThreadRng| {
+            Prover::<u32, ChaCha20Rng, Keccak256>::prove_with_context::<ThreadRng, SIGMA>(
+                rng,
+                &input,
+                &circuit,
+                &statement.public_output,
+                &statement.context,
+            )
+            .unwrap()
+        };
+        let proof = prove(&mut rng);
+
+        let logged = statement
+            .verify_for_audit::<ChaCha20Rng, Keccak256, SIGMA>(&proof, &circuit)
+            .unwrap();
+
+        // stable across serialization
+        let presented = Proof::<u32, Keccak256>::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+        statement
+            .verify_against_log::<ChaCha20Rng, Keccak256, SIGMA>(&presented, &circuit, &logged)
+            .unwrap();
+
+        // another valid proof of the same statement was not logged
+        let other = prove(&mut rng);
+        assert!(matches!(
+            statement
+                .verify_against_log::<ChaCha20Rng, Keccak256, SIGMA>(&other, &circuit, &logged),
+            Err(Error::IntegrityError)
+        ));
+
+        // the digest depends on the statement
+        let mut relabeled = statement.clone();
+        relabeled.circuit.name = "adder".to_string();
+        assert_ne!(relabeled.audit_digest(&proof).unwrap(), logged);
+    }
+}
@@ -0,0 +1,305 @@
+//! One-call proofs of common statements.
+//!
+//! These helpers fix every parameter of the protocol, `u32` words,
+//! `ChaCha20Rng` tapes, `Keccak256` commitments and `SECURITY_PARAM` bits of
+//! security, and convert between digests as bytes and the words circuits
+//! output. Applications which need another setting, or a statement not listed
+//! here, use `Prover` and `Verifier` with a `Circuit` directly.
+//!
+//! Preimage proofs do not hide the length of the preimage, which the verifier
+//! reads from the proof. A preimage proof may also disclose some bytes of the
+//! preimage selected by a `WitnessMask`, e.g. a public tag it starts with.
+
+use rand::{CryptoRng, RngCore};
+use rand_chacha::ChaCha20Rng;
+use sha3::Keccak256;
+
+use crate::{
+    circuit::{disclose, pack_bytes},
+    data_structures::Proof,
+    error::Error,
+    gadgets::{
+        blake2s::{Blake2sCircuit, Sha256Blake2sCircuit},
+        prepare::Prepare,
+        range::RangeCircuit,
+        sha256::Sha256Circuit,
+    },
+    gf2_word::GF2Word,
+    prover::Prover,
+    verifier::Verifier,
+    witness::WitnessMask,
+};
+
+pub const SECURITY_PARAM: usize = 80;
+
+pub type Digest = [u8; 32];
+pub type StatementProof = Proof<u32, Keccak256>;
+
+fn prove<R, C>(
+    rng: &mut R,
+    circuit: &C,
+    preimage: &[u8],
+) -> Result<(Vec<GF2Word<u32>>, StatementProof), Error>
+where
+    R: RngCore + CryptoRng,
+    C: Prepare<u32>,
+{
+    let witness = circuit.prepare_witness(preimage)?;
+    let output = circuit.expected_public_output(preimage)?;
+    let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<R, SECURITY_PARAM>(
+        rng, &witness, circuit, &output,
+    )?;
+    Ok((output, proof))
+}
+
+fn verify(
+    circuit: &impl Prepare<u32>,
+    output: Vec<GF2Word<u32>>,
+    proof: &StatementProof,
+) -> Result<(), Error> {
+    Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SECURITY_PARAM>(proof, circuit, &output)
+}
+
+/// Length of the preimage `proof` is about, as revealed by its opened views.
+fn preimage_len(proof: &StatementProof) -> Result<usize, Error> {
+    proof
+        .party_inputs
+        .first()
+        .map(|input| input.len())
+        .ok_or(Error::VerificationError)
+}
+
+fn to_digest(words: &[GF2Word<u32>], to_bytes: fn(u32) -> [u8; 4]) -> Digest {
+    let bytes: Vec<u8> = words.iter().flat_map(|word| to_bytes(word.value)).collect();
+    // safe to unwrap since both circuits output 8 words
+    bytes.try_into().unwrap()
+}
+
+fn to_words(digest: &Digest, from_bytes: fn([u8; 4]) -> u32) -> Vec<GF2Word<u32>> {
+    digest
+        .chunks(4)
+        .map(|chunk| from_bytes(chunk.try_into().unwrap()).into())
+        .collect()
+}
+
+/// Prove knowledge of `preimage`, returning its SHA-256 digest and the proof.
+pub fn prove_sha256_preimage<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    preimage: &[u8],
+) -> Result<(Digest, StatementProof), Error> {
+    let (output, proof) = prove(rng, &Sha256Circuit::new(preimage.len()), preimage)?;
+    Ok((to_digest(&output, u32::to_be_bytes), proof))
+}
+
+/// Verify that `proof` shows knowledge of a preimage of the SHA-256 `digest`.
+pub fn verify_sha256_preimage(digest: &Digest, proof: &StatementProof) -> Result<(), Error> {
+    let circuit = Sha256Circuit::new(preimage_len(proof)?);
+    verify(&circuit, to_words(digest, u32::from_be_bytes), proof)
+}
+
+/// Prove knowledge of `preimage`, disclosing the bytes of it selected by
+/// `mask`. Returns its SHA-256 digest, the disclosed bytes and the proof.
+pub fn prove_sha256_preimage_disclosing<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    preimage: &[u8],
+    mask: &WitnessMask,
+) -> Result<(Digest, Vec<u8>, StatementProof), Error> {
+    let circuit = disclose(Sha256Circuit::new(preimage.len()), mask.clone());
+    let (output, proof) = prove(rng, &circuit, preimage)?;
+    Ok((
+        to_digest(&output[..8], u32::to_be_bytes),
+        mask.disclosed(preimage)?,
+        proof,
+    ))
+}
+
+/// Verify that `proof` shows knowledge of a preimage of the SHA-256 `digest`
+/// whose bytes selected by `mask` are `disclosed`.
+pub fn verify_sha256_preimage_disclosing(
+    digest: &Digest,
+    mask: &WitnessMask,
+    disclosed: &[u8],
+    proof: &StatementProof,
+) -> Result<(), Error> {
+    if disclosed.len() != mask.len() {
+        return Err(Error::ParseLengthError(mask.len(), disclosed.len()));
+    }
+    let circuit = disclose(Sha256Circuit::new(preimage_len(proof)?), mask.clone());
+    let output = [to_words(digest, u32::from_be_bytes), pack_bytes(disclosed)].concat();
+    verify(&circuit, output, proof)
+}
+
+/// Prove knowledge of `preimage`, returning its BLAKE2s-256 digest and the
+/// proof.
+pub fn prove_blake2s_preimage<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    preimage: &[u8],
+) -> Result<(Digest, StatementProof), Error> {
+    let (output, proof) = prove(rng, &Blake2sCircuit::new(preimage.len()), preimage)?;
+    Ok((to_digest(&output, u32::to_le_bytes), proof))
+}
+
+/// Verify that `proof` shows knowledge of a preimage of the BLAKE2s-256
+/// `digest`.
+pub fn verify_blake2s_preimage(digest: &Digest, proof: &StatementProof) -> Result<(), Error> {
+    let circuit = Blake2sCircuit::new(preimage_len(proof)?);
+    verify(&circuit, to_words(digest, u32::from_le_bytes), proof)
+}
+
+/// Prove knowledge of `preimage`, returning its SHA-256 and BLAKE2s-256 digests
+/// and a proof that both have the same preimage.
+pub fn prove_sha256_blake2s_preimage<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    preimage: &[u8],
+) -> Result<(Digest, Digest, StatementProof), Error> {
+    let (output, proof) = prove(rng, &Sha256Blake2sCircuit::new(preimage.len()), preimage)?;
+    let (sha256_digest, blake2s_digest) = output.split_at(8);
+    Ok((
+        to_digest(sha256_digest, u32::to_be_bytes),
+        to_digest(blake2s_digest, u32::to_le_bytes),
+        proof,
+    ))
+}
+
+/// Verify that `proof` shows knowledge of a single preimage of both the
+/// SHA-256 `sha256_digest` and the BLAKE2s-256 `blake2s_digest`.
+pub fn verify_sha256_blake2s_preimage(
+    sha256_digest: &Digest,
+    blake2s_digest: &Digest,
+    proof: &StatementProof,
+) -> Result<(), Error> {
+    let circuit = Sha256Blake2sCircuit::new(preimage_len(proof)?);
+    let output = [
+        to_words(sha256_digest, u32::from_be_bytes),
+        to_words(blake2s_digest, u32::from_le_bytes),
+    ]
+    .concat();
+    verify(&circuit, output, proof)
+}
+
+/// Prove that the secret `value` lies in `[min, max]`, e.g. that an age is at
+/// least 18 or that an amount is within a limit. Fails with
+/// `Error::OutOfRangeError` if it does not.
+pub fn prove_in_range<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    value: u32,
+    min: u32,
+    max: u32,
+) -> Result<StatementProof, Error> {
+    if !(min..=max).contains(&value) {
+        return Err(Error::OutOfRangeError);
+    }
+    let (_, proof) = prove(rng, &RangeCircuit::new(min, max), &value.to_le_bytes())?;
+    Ok(proof)
+}
+
+/// Verify that `proof` shows that a secret value lies in `[min, max]`.
+pub fn verify_in_range(min: u32, max: u32, proof: &StatementProof) -> Result<(), Error> {
+    verify(&RangeCircuit::new(min, max), vec![1u32.into()], proof)
+}
+
+#[cfg(test)]
+mod statements_tests {
+    use blake2::Blake2s256;
+    use rand::thread_rng;
+    use sha2::{Digest, Sha256};
+
+    use crate::witness::WitnessMask;
+
+    use super::{
+        prove_blake2s_preimage, prove_in_range, prove_sha256_blake2s_preimage,
+        prove_sha256_preimage, prove_sha256_preimage_disclosing, verify_blake2s_preimage,
+        verify_in_range, verify_sha256_blake2s_preimage, verify_sha256_preimage,
+        verify_sha256_preimage_disclosing,
+    };
+
+    #[test]
+    fn test_sha256_preimage() {
+        let mut rng = thread_rng();
+        let (digest, proof) = prove_sha256_preimage(&mut rng, b"a secret").unwrap();
+        assert_eq!(digest.to_vec(), Sha256::digest(b"a secret").to_vec());
+        verify_sha256_preimage(&digest, &proof).unwrap();
+
+        // another digest, or the same digest under another hash
+        let (other_digest, other_proof) = prove_sha256_preimage(&mut rng, b"another").unwrap();
+        assert!(verify_sha256_preimage(&other_digest, &proof).is_err());
+        assert!(verify_sha256_preimage(&digest, &other_proof).is_err());
+        assert!(verify_blake2s_preimage(&digest, &proof).is_err());
+    }
+
+    #[test]
+    fn test_sha256_preimage_disclosing() {
+        let mut rng = thread_rng();
+        let preimage = b"TAG1 and a secret";
+        let prefix = WitnessMask::prefix(4);
+
+        let (digest, disclosed, proof) =
+            prove_sha256_preimage_disclosing(&mut rng, preimage, &prefix).unwrap();
+        assert_eq!(digest.to_vec(), Sha256::digest(preimage).to_vec());
+        assert_eq!(disclosed, b"TAG1");
+        verify_sha256_preimage_disclosing(&digest, &prefix, b"TAG1", &proof).unwrap();
+
+        // another tag, another mask, or a proof disclosing nothing
+        assert!(verify_sha256_preimage_disclosing(&digest, &prefix, b"TAG2", &proof).is_err());
+        let suffix = WitnessMask::suffix(preimage.len(), 4);
+        assert!(verify_sha256_preimage_disclosing(&digest, &suffix, b"TAG1", &proof).is_err());
+        assert!(verify_sha256_preimage(&digest, &proof).is_err());
+        let (_, plain) = prove_sha256_preimage(&mut rng, preimage).unwrap();
+        assert!(verify_sha256_preimage_disclosing(&digest, &prefix, b"TAG1", &plain).is_err());
+
+        // several ranges, disclosed in order
+        let mask = WitnessMask::new([13..17, 0..3]);
+        let (digest, disclosed, proof) =
+            prove_sha256_preimage_disclosing(&mut rng, preimage, &mask).unwrap();
+        assert_eq!(disclosed, b"cretTAG");
+        verify_sha256_preimage_disclosing(&digest, &mask, &disclosed, &proof).unwrap();
+
+        // the mask must fit in the preimage
+        let too_long = WitnessMask::prefix(preimage.len() + 1);
+        assert!(prove_sha256_preimage_disclosing(&mut rng, preimage, &too_long).is_err());
+    }
+
+    #[test]
+    fn test_blake2s_preimage() {
+        let mut rng = thread_rng();
+        let (digest, proof) = prove_blake2s_preimage(&mut rng, b"").unwrap();
+        assert_eq!(digest.to_vec(), Blake2s256::digest(b"").to_vec());
+        verify_blake2s_preimage(&digest, &proof).unwrap();
+        assert!(verify_sha256_preimage(&digest, &proof).is_err());
+    }
+
+    #[test]
+    fn test_sha256_blake2s_preimage() {
+        let mut rng = thread_rng();
+        let (sha256_digest, blake2s_digest, proof) =
+            prove_sha256_blake2s_preimage(&mut rng, b"a secret").unwrap();
+        assert_eq!(sha256_digest.to_vec(), Sha256::digest(b"a secret").to_vec());
+        assert_eq!(
+            blake2s_digest.to_vec(),
+            Blake2s256::digest(b"a secret").to_vec()
+        );
+        verify_sha256_blake2s_preimage(&sha256_digest, &blake2s_digest, &proof).unwrap();
+
+        // digests swapped, or of different preimages
+        assert!(verify_sha256_blake2s_preimage(&blake2s_digest, &sha256_digest, &proof).is_err());
+        let (other_digest, _) = prove_blake2s_preimage(&mut rng, b"another").unwrap();
+        assert!(verify_sha256_blake2s_preimage(&sha256_digest, &other_digest, &proof).is_err());
+    }
+
+    #[test]
+    fn test_in_range() {
+        let mut rng = thread_rng();
+        let proof = prove_in_range(&mut rng, 42, 18, 65).unwrap();
+        verify_in_range(18, 65, &proof).unwrap();
+
+        // another range
+        assert!(verify_in_range(43, 65, &proof).is_err());
+        assert!(verify_in_range(18, 64, &proof).is_err());
+
+        // values outside the range are not proven
+        assert!(matches!(
+            prove_in_range(&mut rng, 17, 18, 65),
+            Err(crate::error::Error::OutOfRangeError)
+        ));
+    }
+}
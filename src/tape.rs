@@ -1,38 +1,300 @@
-use rand::{CryptoRng, RngCore, SeedableRng};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rand_core::CryptoRngCore;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
 use crate::{
+    error::Error,
+    expander::TapeExpander,
     gf2_word::{GF2Word, Value},
     key::Key,
 };
 
+/// Default size of a tape chunk, small enough for the chunks of the parties
+/// of a repetition to stay in L1 cache together with the values they mask.
+pub const DEFAULT_CHUNK_BYTES: usize = 4096;
+
+static CHUNK_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_CHUNK_BYTES);
+
+/// Set how many bytes of randomness a tape generates at once. Affects tapes
+/// created afterwards; values are clamped to at least one word.
+pub fn set_chunk_bytes(bytes: usize) {
+    CHUNK_BYTES.store(bytes, Ordering::Relaxed);
+}
+
+pub fn chunk_bytes() -> usize {
+    CHUNK_BYTES.load(Ordering::Relaxed)
+}
+
 /// A tape of values that can be read at its current `offset`.
+///
+/// Values are generated in chunks as they are read, so that the randomness of a
+/// large circuit is never held in memory all at once. The values do not depend
+/// on the chunk size. Values narrower than 32 bits are packed into the outputs
+/// of the generator, so a chunk of them always fills whole outputs.
 pub struct Tape<T: Value> {
     offset: usize,
-    tape: Vec<GF2Word<T>>,
+    len: usize,
+    chunk: Vec<GF2Word<T>>,
+    chunk_offset: usize,
+    chunk_len: usize,
+    exhausted: bool,
+    /// `None` for a tape expanded in full, see `expand_tapes`.
+    rng: Option<Box<dyn CryptoRngCore>>,
 }
 
 impl<T: Value> Tape<T> {
-    /// Initialise a tape with `len` entries using `key` as random seed.
-    pub fn from_key<R: SeedableRng<Seed = Key> + RngCore + CryptoRng>(
+    /// Initialise a tape with `len` entries expanded from `key` under `label`,
+    /// see `expander::tape_label`.
+    pub fn from_key<R: TapeExpander>(key: Key, label: &[u8], len: usize) -> Self {
+        let chunk_len = (chunk_bytes() / T::bytes_len()).max(1);
+        Self::with_chunk_len::<R>(key, label, len, chunk_len)
+    }
+
+    /// Like `from_key`, generating `chunk_len` values at a time.
+    pub fn with_chunk_len<R: TapeExpander>(
         key: Key,
+        label: &[u8],
         len: usize,
+        chunk_len: usize,
     ) -> Self {
-        let mut rng = R::from_seed(key);
-        let mut tape = Vec::with_capacity(len);
+        assert!(chunk_len > 0);
+        let chunk_len = chunk_len
+            .next_multiple_of(values_per_output::<T>())
+            .min(len.max(1));
 
-        for _ in 0..len {
-            tape.push(T::gen_rand(&mut rng).into());
+        Self {
+            offset: 0,
+            len,
+            chunk: Vec::with_capacity(chunk_len),
+            chunk_offset: 0,
+            chunk_len,
+            exhausted: false,
+            rng: Some(R::stream(&key, label)),
         }
+    }
 
-        Self { offset: 0, tape }
+    /// A tape holding `values`, expanded beforehand.
+    #[cfg(feature = "parallel")]
+    fn from_values(values: Vec<GF2Word<T>>) -> Self {
+        Self {
+            offset: 0,
+            len: values.len(),
+            chunk_len: values.len().max(1),
+            chunk: values,
+            chunk_offset: 0,
+            exhausted: false,
+            rng: None,
+        }
     }
 
     /// Read the next value on the tape.
-    /// TODO: Return error if tape runs out of values.
+    ///
+    /// Reading past the end yields zero and marks the tape as exhausted, so that
+    /// gadgets stay infallible and the caller checks `is_exhausted` once the
+    /// circuit has run.
     pub fn read_next(&mut self) -> GF2Word<T> {
-        let ri = self.tape[self.offset];
+        if self.offset == self.len {
+            self.exhausted = true;
+            return T::default().into();
+        }
+        if self.chunk_offset == self.chunk.len() {
+            self.refill();
+        }
+
+        let ri = self.chunk[self.chunk_offset];
+        self.chunk_offset += 1;
         self.offset += 1;
-        assert!(self.offset <= self.tape.len());
         ri
     }
+
+    /// Number of values on the tape.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of values read so far, not counting reads past the end.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Whether more values were read than the tape holds.
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    /// Check that exactly `len` values were read, as when a circuit declares
+    /// its number of AND gates correctly: fails with `TapeExhaustedError` if
+    /// more were read and `TapeLengthError` if fewer were.
+    pub fn check_consumed(&self) -> Result<(), Error> {
+        if self.exhausted {
+            return Err(Error::TapeExhaustedError);
+        }
+        if self.offset != self.len {
+            return Err(Error::TapeLengthError(self.len, self.offset));
+        }
+        Ok(())
+    }
+
+    /// Shorten the tape to `len` values, of which `offset` may already be read.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len.max(self.offset));
+        let available = self.chunk_offset + (self.len - self.offset);
+        self.chunk.truncate(available);
+    }
+
+    fn refill(&mut self) {
+        let n = self.chunk_len.min(self.len - self.offset);
+        // a tape expanded in full reaches its end before its chunk does
+        let rng = self.rng.as_mut().expect("tape was expanded in full");
+
+        self.chunk.clear();
+        self.chunk
+            .extend(T::gen_rand_packed(rng, n).into_iter().map(GF2Word::from));
+        self.chunk_offset = 0;
+    }
+}
+
+/// The tapes of `len` values for each `(key, label)`, holding the values of
+/// `Tape::from_key`.
+///
+/// With the `parallel` feature the tapes are expanded in full, each on its own
+/// thread, rather than a chunk at a time as they are read. This trades the
+/// memory of the whole tapes for the time of expanding them one after the
+/// other.
+pub fn expand_tapes<T: Value, R: TapeExpander, const N: usize>(
+    keys: [(Key, Vec<u8>); N],
+    len: usize,
+) -> [Tape<T>; N] {
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+
+        let mut values: [Vec<GF2Word<T>>; N] = std::array::from_fn(|_| vec![]);
+        values
+            .par_iter_mut()
+            .zip(keys.par_iter())
+            .for_each(|(values, (key, label))| *values = R::expand(key, label, len));
+        values.map(Tape::from_values)
+    }
+    #[cfg(not(feature = "parallel"))]
+    keys.map(|(key, label)| Tape::from_key::<R>(key, &label, len))
+}
+
+/// How many values fit in a 32-bit output of the generator.
+fn values_per_output<T: Value>() -> usize {
+    (32 / T::bits_len()).max(1)
+}
+
+/// Wipes the values generated but not yet read. The state of the generator is
+/// opaque and is not wiped.
+#[cfg(feature = "zeroize")]
+impl<T: Value> Drop for Tape<T> {
+    fn drop(&mut self) {
+        self.chunk.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tape_tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use crate::gf2_word::{Bit, GenRand};
+
+    use super::{expand_tapes, Tape};
+
+    #[test]
+    fn test_chunking_does_not_change_values() {
+        let key = [7u8; 32];
+        let len = 100;
+
+        let mut rng = ChaCha20Rng::from_seed(key);
+        let expected: Vec<u32> = (0..len).map(|_| u32::gen_rand(&mut rng)).collect();
+
+        for chunk_len in [1, 3, 64, 100, 1000] {
+            let mut tape = Tape::<u32>::with_chunk_len::<ChaCha20Rng>(key, &[], len, chunk_len);
+            let values: Vec<u32> = (0..len).map(|_| tape.read_next().value).collect();
+            assert_eq!(values, expected);
+        }
+    }
+
+    #[test]
+    fn test_packed_values() {
+        let key = [7u8; 32];
+        let len = 99;
+
+        let mut rng = ChaCha20Rng::from_seed(key);
+        let expected = u8::gen_rand_packed(&mut rng, len);
+        // four values per output of the generator
+        assert_eq!(rng.get_word_pos(), 25);
+
+        for chunk_len in [1, 3, 64, 99, 1000] {
+            let mut tape = Tape::<u8>::with_chunk_len::<ChaCha20Rng>(key, &[], len, chunk_len);
+            let values: Vec<u8> = (0..len).map(|_| tape.read_next().value).collect();
+            assert_eq!(values, expected);
+        }
+
+        let mut rng = ChaCha20Rng::from_seed(key);
+        let expected = u16::gen_rand_packed(&mut rng, len);
+        for chunk_len in [1, 3, 64, 99, 1000] {
+            let mut tape = Tape::<u16>::with_chunk_len::<ChaCha20Rng>(key, &[], len, chunk_len);
+            let values: Vec<u16> = (0..len).map(|_| tape.read_next().value).collect();
+            assert_eq!(values, expected);
+        }
+
+        let mut rng = ChaCha20Rng::from_seed(key);
+        let expected = Bit::gen_rand_packed(&mut rng, len);
+        assert_eq!(rng.get_word_pos(), 4);
+        for chunk_len in [1, 3, 64, 99, 1000] {
+            let mut tape = Tape::<Bit>::with_chunk_len::<ChaCha20Rng>(key, &[], len, chunk_len);
+            let values: Vec<Bit> = (0..len).map(|_| tape.read_next().value).collect();
+            assert_eq!(values, expected);
+        }
+    }
+
+    #[test]
+    fn test_expand_tapes() {
+        let keys = [([1u8; 32], b"a".to_vec()), ([2u8; 32], vec![])];
+        let len = 50;
+
+        let [mut t1, mut t2] = expand_tapes::<u16, ChaCha20Rng, 2>(keys.clone(), len);
+        for (tape, (key, label)) in [&mut t1, &mut t2].into_iter().zip(keys) {
+            let mut expected = Tape::<u16>::with_chunk_len::<ChaCha20Rng>(key, &label, len, 7);
+            for _ in 0..len {
+                assert_eq!(tape.read_next(), expected.read_next());
+            }
+            assert!(!tape.is_exhausted());
+            tape.read_next();
+            assert!(tape.is_exhausted());
+        }
+    }
+
+    #[test]
+    fn test_reading_past_the_end() {
+        let mut tape = Tape::<u32>::with_chunk_len::<ChaCha20Rng>([0u8; 32], &[], 2, 8);
+        tape.read_next();
+        tape.read_next();
+        assert!(!tape.is_exhausted());
+
+        assert_eq!(tape.read_next().value, 0);
+        assert!(tape.is_exhausted());
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut tape = Tape::<u32>::with_chunk_len::<ChaCha20Rng>([0u8; 32], &[], 10, 4);
+        tape.read_next();
+        tape.truncate(3);
+        tape.read_next();
+        tape.read_next();
+        assert!(!tape.is_exhausted());
+        tape.read_next();
+        assert!(tape.is_exhausted());
+    }
 }
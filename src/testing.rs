@@ -0,0 +1,207 @@
+//! Tampered proofs, for testing that a circuit rejects what it should.
+//!
+//! A `Tamper` is a structured mutation of a valid proof: a flipped message,
+//! swapped challenge trits, a replaced key, a truncated view. The verifier
+//! must reject every tampered proof of a circuit, whatever its gadgets read
+//! from the views. `assert_tampering_rejected` runs the mutations of a few
+//! repetitions of a proof against the verifier.
+
+use std::fmt::Debug;
+
+use sha3::{digest::FixedOutputReset, Digest};
+
+use crate::{
+    circuit::Circuit,
+    data_structures::Proof,
+    expander::TapeExpander,
+    gf2_word::{GF2Word, Value},
+    verifier::Verifier,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tamper {
+    /// Flip the lowest bit of message `message` of the opened view.
+    FlipMessage { repetition: usize, message: usize },
+    /// Drop the messages of the opened view past the first `len`.
+    TruncateView { repetition: usize, len: usize },
+    /// Flip the lowest bit of byte `byte` of the opened input share.
+    FlipInput { repetition: usize, byte: usize },
+    /// Replace the first (`0`) or second (`1`) opened key.
+    ReplaceKey { repetition: usize, key: usize },
+    /// Flip the lowest bit of the commitment to the unopened party.
+    FlipCommitment { repetition: usize },
+    /// Open the next party instead of the claimed one.
+    ShiftTrit { repetition: usize },
+    /// Swap the claimed trits of two repetitions.
+    SwapTrits { first: usize, second: usize },
+}
+
+impl Tamper {
+    /// The mutations of `repetition` of `proof`, one of each kind.
+    pub fn of_repetition<T: Value, D: Default + Digest + Clone>(
+        proof: &Proof<T, D>,
+        repetition: usize,
+    ) -> Vec<Self> {
+        let mut tampers = vec![
+            Self::ReplaceKey { repetition, key: 0 },
+            Self::ReplaceKey { repetition, key: 1 },
+            Self::FlipCommitment { repetition },
+            Self::ShiftTrit { repetition },
+        ];
+
+        let messages = proof.views[repetition].messages.len();
+        if messages > 0 {
+            tampers.push(Self::FlipMessage {
+                repetition,
+                message: messages - 1,
+            });
+            tampers.push(Self::TruncateView {
+                repetition,
+                len: messages - 1,
+            });
+        }
+        if !proof.party_inputs[repetition].is_empty() {
+            tampers.push(Self::FlipInput {
+                repetition,
+                byte: 0,
+            });
+        }
+
+        // with a repetition which opens another party
+        let trit = proof.claimed_trits[repetition];
+        if let Some(other) = proof.claimed_trits.iter().position(|&t| t != trit) {
+            tampers.push(Self::SwapTrits {
+                first: repetition,
+                second: other,
+            });
+        }
+
+        tampers
+    }
+
+    /// `proof` with this mutation applied, or `None` if it does not apply to
+    /// `proof`.
+    pub fn apply<T: Value, D: Default + Digest + Clone>(
+        &self,
+        proof: &Proof<T, D>,
+    ) -> Option<Proof<T, D>> {
+        let mut proof = proof.clone();
+        match *self {
+            Self::FlipMessage {
+                repetition,
+                message,
+            } => {
+                let msg = proof.views.get_mut(repetition)?.messages.get_mut(message)?;
+                *msg = *msg ^ GF2Word::from(T::from_le_bytes(&lowest_bit::<T>()));
+            }
+            Self::TruncateView { repetition, len } => {
+                let messages = &mut proof.views.get_mut(repetition)?.messages;
+                if len >= messages.len() {
+                    return None;
+                }
+                messages.truncate(len);
+            }
+            Self::FlipInput { repetition, byte } => {
+                *proof.party_inputs.get_mut(repetition)?.get_mut(byte)? ^= 1;
+            }
+            Self::ReplaceKey { repetition, key } => {
+                if key > 1 {
+                    return None;
+                }
+                proof.keys.get_mut(2 * repetition + key)?[0] ^= 1;
+            }
+            Self::FlipCommitment { repetition } => {
+                proof.commitments.get_mut(repetition)?.data[0] ^= 1;
+            }
+            Self::ShiftTrit { repetition } => {
+                let trit = proof.claimed_trits.get_mut(repetition)?;
+                *trit = (*trit + 1) % 3;
+            }
+            Self::SwapTrits { first, second } => {
+                let trits = &mut proof.claimed_trits;
+                if trits.get(first)? == trits.get(second)? {
+                    return None;
+                }
+                trits.swap(first, second);
+            }
+        }
+        Some(proof)
+    }
+}
+
+/// The little-endian bytes of the value with only its lowest bit set.
+fn lowest_bit<T: Value>() -> Vec<u8> {
+    let mut bytes = vec![0u8; T::bytes_len()];
+    bytes[0] = 1;
+    bytes
+}
+
+/// Check that `proof` verifies, and that the verifier rejects every mutation
+/// of its first and last repetitions.
+///
+/// # Panics
+///
+/// If `proof` does not verify, or names the first tampered proof which does.
+pub fn assert_tampering_rejected<T, TapeR, D, const SIGMA: usize>(
+    proof: &Proof<T, D>,
+    circuit: &impl Circuit<T>,
+    public_output: &Vec<GF2Word<T>>,
+) where
+    T: Value + PartialEq,
+    TapeR: TapeExpander,
+    D: Debug + Default + Digest + FixedOutputReset + Clone,
+{
+    let verify = |proof: &Proof<T, D>| {
+        Verifier::<T, TapeR, D>::verify::<SIGMA>(proof, circuit, public_output)
+    };
+    if let Err(err) = verify(proof) {
+        panic!("the untampered proof is rejected: {err}");
+    }
+
+    let mut repetitions = vec![0, proof.num_of_repetitions().saturating_sub(1)];
+    repetitions.dedup();
+    let tampers = repetitions
+        .into_iter()
+        .flat_map(|repetition| Tamper::of_repetition(proof, repetition));
+    for tamper in tampers {
+        if let Some(tampered) = tamper.apply(proof) {
+            assert!(verify(&tampered).is_err(), "accepted {tamper:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod testing_tests {
+    use rand::thread_rng;
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{circuit::Circuit, gadgets::add_mod::AdderCircuit, prover::Prover};
+
+    use super::{assert_tampering_rejected, Tamper};
+
+    const SIGMA: usize = 40;
+
+    #[test]
+    fn test_adder_rejects_tampering() {
+        let mut rng = thread_rng();
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove::<_, SIGMA>(
+            &mut rng, &input, &circuit, &output,
+        )
+        .unwrap();
+
+        assert_tampering_rejected::<u32, ChaCha20Rng, Keccak256, SIGMA>(&proof, &circuit, &output);
+
+        let tampers = Tamper::of_repetition(&proof, 0);
+        assert!(tampers.len() >= 7);
+        assert!(Tamper::TruncateView {
+            repetition: 0,
+            len: usize::MAX
+        }
+        .apply(&proof)
+        .is_none());
+    }
+}
@@ -0,0 +1,235 @@
+//! JSON test vectors, for other implementations of the protocol to check
+//! themselves against this crate.
+//!
+//! A `TestVector` records a deterministic proof, see
+//! `Prover::prove_deterministic`, together with the values computed on the
+//! way: the keys of all parties, the commitments to their views, the challenge
+//! and the serialized proof. An implementation proves the witness under the
+//! seed and context of the vector and compares each value in turn, which
+//! points at the first step where the two differ. `TestVector::check` does so
+//! for this crate.
+//!
+//! Bytes are lowercase hex, and the words of the public output are big-endian
+//! as in `GF2Word::to_bytes_be`.
+
+use std::fmt::Debug;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha3::{digest::FixedOutputReset, Digest};
+
+use crate::{
+    circuit::Circuit,
+    data_structures::Proof,
+    error::Error,
+    expander::TapeExpander,
+    gf2_word::{GF2Word, Value},
+    prover::Prover,
+    verifier::Verifier,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestVector {
+    /// Name of the circuit, for the reader.
+    pub circuit: String,
+    pub security_param: usize,
+    /// `TapeExpander::ID` of the tapes.
+    pub tape_expander: String,
+    pub seed: String,
+    pub context: String,
+    pub witness: String,
+    pub public_output: Vec<String>,
+    /// Keys of all parties, three per repetition.
+    pub keys: Vec<String>,
+    /// Commitments to the views of all parties, three per repetition.
+    pub commitments: Vec<String>,
+    /// The party opened first in each repetition.
+    pub challenge: Vec<u8>,
+    /// `Proof::to_bytes` of the proof.
+    pub proof: String,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, Error> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(Error::SerializationError);
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or(Error::SerializationError)
+        })
+        .collect()
+}
+
+fn check_field<F: PartialEq>(name: &'static str, expected: &F, actual: &F) -> Result<(), Error> {
+    if expected != actual {
+        return Err(Error::TestVectorMismatchError(name));
+    }
+    Ok(())
+}
+
+impl TestVector {
+    /// The vector of the deterministic proof of `witness` for `circuit` under
+    /// `seed` and `context`.
+    pub fn generate<T, TapeR, D, const SIGMA: usize>(
+        name: &str,
+        seed: &[u8],
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        context: &[u8],
+    ) -> Result<Self, Error>
+    where
+        T: Value,
+        TapeR: TapeExpander,
+        D: Debug + Default + Digest + FixedOutputReset + Clone,
+    {
+        let public_output = circuit.compute(witness);
+        let (proof, trace) = Prover::<T, TapeR, D>::prove_deterministic_with_trace::<SIGMA>(
+            seed,
+            witness,
+            circuit,
+            &public_output,
+            context,
+        )?;
+
+        Ok(Self {
+            circuit: name.to_string(),
+            security_param: SIGMA,
+            tape_expander: TapeR::ID.to_string(),
+            seed: to_hex(seed),
+            context: to_hex(context),
+            witness: to_hex(witness),
+            public_output: public_output
+                .iter()
+                .map(|word| to_hex(&word.to_bytes_be()))
+                .collect(),
+            keys: trace.keys.iter().map(|key| to_hex(key)).collect(),
+            commitments: trace
+                .commitments
+                .iter()
+                .map(|commitment| to_hex(&commitment.data))
+                .collect(),
+            challenge: trace.challenge,
+            proof: to_hex(&proof.to_bytes()?),
+        })
+    }
+
+    /// Verify the proof of the vector for `circuit`, then prove its witness
+    /// again and compare every value, failing with
+    /// `Error::TestVectorMismatchError` on the first which differs.
+    pub fn check<T, TapeR, D, const SIGMA: usize>(
+        &self,
+        circuit: &impl Circuit<T>,
+    ) -> Result<(), Error>
+    where
+        T: Value + PartialEq + DeserializeOwned,
+        TapeR: TapeExpander,
+        D: Debug + Default + Digest + FixedOutputReset + Clone,
+    {
+        check_field("security_param", &self.security_param, &SIGMA)?;
+        check_field("tape_expander", &self.tape_expander.as_str(), &TapeR::ID)?;
+
+        let public_output = self
+            .public_output
+            .iter()
+            .map(|word| GF2Word::from_bytes_be(&from_hex(word)?))
+            .collect::<Result<Vec<_>, _>>()?;
+        let context = from_hex(&self.context)?;
+        let proof = Proof::<T, D>::from_bytes(&from_hex(&self.proof)?)?;
+        Verifier::<T, TapeR, D>::verify_with_context::<SIGMA>(
+            &proof,
+            circuit,
+            &public_output,
+            &context,
+        )?;
+
+        let expected = Self::generate::<T, TapeR, D, SIGMA>(
+            &self.circuit,
+            &from_hex(&self.seed)?,
+            &from_hex(&self.witness)?,
+            circuit,
+            &context,
+        )?;
+        check_field(
+            "public_output",
+            &expected.public_output,
+            &self.public_output,
+        )?;
+        check_field("keys", &expected.keys, &self.keys)?;
+        check_field("commitments", &expected.commitments, &self.commitments)?;
+        check_field("challenge", &expected.challenge, &self.challenge)?;
+        check_field("proof", &expected.proof, &self.proof)
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|_| Error::SerializationError)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|_| Error::SerializationError)
+    }
+}
+
+#[cfg(test)]
+mod testvectors_tests {
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::Circuit, error::Error, expander::Shake128Expander, gadgets::add_mod::AdderCircuit,
+        prover::Prover,
+    };
+
+    use super::{from_hex, TestVector};
+
+    const SIGMA: usize = 40;
+
+    #[test]
+    fn test_vector_round_trip() {
+        let circuit = AdderCircuit::<u32>::new();
+        let witness = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+
+        let vector = TestVector::generate::<u32, ChaCha20Rng, Keccak256, SIGMA>(
+            "adder", b"seed", &witness, &circuit, b"context",
+        )
+        .unwrap();
+        assert_eq!(vector.public_output, vec!["0000000b"]);
+        assert_eq!(vector.keys.len(), 3 * vector.challenge.len());
+
+        // the proof of the vector is the deterministic proof
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove_deterministic::<SIGMA>(
+            b"seed",
+            &witness,
+            &circuit,
+            &circuit.compute(&witness),
+            b"context",
+        )
+        .unwrap();
+        assert_eq!(from_hex(&vector.proof).unwrap(), proof.to_bytes().unwrap());
+
+        let json = vector.to_json().unwrap();
+        let decoded = TestVector::from_json(&json).unwrap();
+        assert_eq!(decoded, vector);
+        decoded
+            .check::<u32, ChaCha20Rng, Keccak256, SIGMA>(&circuit)
+            .unwrap();
+
+        // another implementation which draws other keys
+        let mut wrong = vector.clone();
+        wrong.keys[0] = "00".repeat(32);
+        assert!(matches!(
+            wrong.check::<u32, ChaCha20Rng, Keccak256, SIGMA>(&circuit),
+            Err(Error::TestVectorMismatchError("keys"))
+        ));
+        assert!(matches!(
+            vector.check::<u32, Shake128Expander, Keccak256, SIGMA>(&circuit),
+            Err(Error::TestVectorMismatchError("tape_expander"))
+        ));
+        assert!(TestVector::from_json("{}").is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! Running the interactive protocol over a connection.
+//!
+//! `SigmaChannel` carries the three messages of a session: the prover's
+//! `FirstMessageA`, the verifier's challenge and the prover's `Proof`.
+//! `TcpChannel` implements it over a tokio `TcpStream`, and
+//! `run_interactive_session` drives a whole session on either side.
+//!
+//! `TcpChannel` sends every message as a frame:
+//!
+//! ```text
+//! frame = tag: u8 || len: u64 (little-endian) || payload: [u8; len]
+//! ```
+//!
+//! where the tag is 1 for the first message, 2 for the challenge and 3 for
+//! the proof. First messages are bincode encoded, challenges are one byte per
+//! repetition and proofs are in the format of `Proof::write_to`.
+
+use std::future::Future;
+
+use rand::{CryptoRng, RngCore};
+use serde::de::DeserializeOwned;
+use sha3::{digest::FixedOutputReset, Digest};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpStream, ToSocketAddrs},
+};
+
+use crate::{
+    circuit::Circuit,
+    commitment::CommitmentScheme,
+    data_structures::{FirstMessageA, Proof},
+    error::Error,
+    expander::TapeExpander,
+    gf2_word::{GF2Word, Value},
+    prover::InteractiveProver,
+    verifier::InteractiveVerifier,
+};
+
+/// A connection between the prover and the verifier of an interactive session.
+pub trait SigmaChannel<T: Value, D: Default + Digest + Clone> {
+    fn send_first_message(
+        &mut self,
+        first_message: &FirstMessageA<T, D>,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    fn recv_first_message(
+        &mut self,
+    ) -> impl Future<Output = Result<FirstMessageA<T, D>, Error>> + Send;
+
+    fn send_challenge(
+        &mut self,
+        challenge: &[u8],
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    fn recv_challenge(&mut self) -> impl Future<Output = Result<Vec<u8>, Error>> + Send;
+
+    fn send_proof(&mut self, proof: &Proof<T, D>)
+        -> impl Future<Output = Result<(), Error>> + Send;
+
+    fn recv_proof(&mut self) -> impl Future<Output = Result<Proof<T, D>, Error>> + Send;
+}
+
+const FIRST_MESSAGE_TAG: u8 = 1;
+const CHALLENGE_TAG: u8 = 2;
+const PROOF_TAG: u8 = 3;
+
+/// Frames longer than this are rejected unless set otherwise with
+/// `TcpChannel::with_max_frame_len`.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 1 << 28;
+
+pub struct TcpChannel {
+    stream: TcpStream,
+    max_frame_len: usize,
+}
+
+impl TcpChannel {
+    pub fn new(stream: TcpStream) -> Self {
+        Self {
+            stream,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+
+    pub async fn connect(addr: impl ToSocketAddrs) -> Result<Self, Error> {
+        let stream = TcpStream::connect(addr)
+            .await
+            .map_err(|_| Error::TransportError)?;
+        Ok(Self::new(stream))
+    }
+
+    /// Reject received frames longer than `max_frame_len` bytes, before
+    /// reading them.
+    pub fn with_max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+
+    pub fn into_inner(self) -> TcpStream {
+        self.stream
+    }
+
+    async fn write_frame(&mut self, tag: u8, payload: &[u8]) -> Result<(), Error> {
+        let mut frame = Vec::with_capacity(9 + payload.len());
+        frame.push(tag);
+        frame.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        frame.extend_from_slice(payload);
+
+        self.stream
+            .write_all(&frame)
+            .await
+            .map_err(|_| Error::TransportError)?;
+        self.stream.flush().await.map_err(|_| Error::TransportError)
+    }
+
+    async fn read_frame(&mut self, expected_tag: u8) -> Result<Vec<u8>, Error> {
+        let mut header = [0u8; 9];
+        self.stream
+            .read_exact(&mut header)
+            .await
+            .map_err(|_| Error::TransportError)?;
+
+        // safe to unwrap since the header holds 8 bytes after the tag
+        let len = u64::from_le_bytes(header[1..].try_into().unwrap());
+        if header[0] != expected_tag || len > self.max_frame_len as u64 {
+            return Err(Error::TransportError);
+        }
+
+        let mut payload = vec![0u8; len as usize];
+        self.stream
+            .read_exact(&mut payload)
+            .await
+            .map_err(|_| Error::TransportError)?;
+        Ok(payload)
+    }
+}
+
+impl<T, D> SigmaChannel<T, D> for TcpChannel
+where
+    T: Value + DeserializeOwned,
+    D: Default + Digest + Clone,
+{
+    fn send_first_message(
+        &mut self,
+        first_message: &FirstMessageA<T, D>,
+    ) -> impl Future<Output = Result<(), Error>> + Send {
+        let payload = bincode::serialize(first_message).map_err(|_| Error::SerializationError);
+        async move { self.write_frame(FIRST_MESSAGE_TAG, &payload?).await }
+    }
+
+    async fn recv_first_message(&mut self) -> Result<FirstMessageA<T, D>, Error> {
+        let payload = self.read_frame(FIRST_MESSAGE_TAG).await?;
+        bincode::deserialize(&payload).map_err(|_| Error::SerializationError)
+    }
+
+    fn send_challenge(
+        &mut self,
+        challenge: &[u8],
+    ) -> impl Future<Output = Result<(), Error>> + Send {
+        let payload = challenge.to_vec();
+        async move { self.write_frame(CHALLENGE_TAG, &payload).await }
+    }
+
+    async fn recv_challenge(&mut self) -> Result<Vec<u8>, Error> {
+        self.read_frame(CHALLENGE_TAG).await
+    }
+
+    fn send_proof(
+        &mut self,
+        proof: &Proof<T, D>,
+    ) -> impl Future<Output = Result<(), Error>> + Send {
+        let payload = proof.to_bytes();
+        async move { self.write_frame(PROOF_TAG, &payload?).await }
+    }
+
+    async fn recv_proof(&mut self) -> Result<Proof<T, D>, Error> {
+        let payload = self.read_frame(PROOF_TAG).await?;
+        Proof::from_bytes(&payload)
+    }
+}
+
+impl<T, TapeR, D, CS> InteractiveProver<T, TapeR, D, CS>
+where
+    T: Value,
+    TapeR: TapeExpander,
+    D: std::fmt::Debug + Default + Digest + FixedOutputReset + Clone,
+    CS: CommitmentScheme<D>,
+{
+    /// Prove `witness` over `channel`: send the first message, answer the
+    /// verifier's challenge and send the proof.
+    pub async fn run_interactive_session<R, C, const SIGMA: usize>(
+        channel: &mut C,
+        rng: &mut R,
+        witness: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &[GF2Word<T>],
+    ) -> Result<(), Error>
+    where
+        R: RngCore + CryptoRng,
+        C: SigmaChannel<T, D>,
+    {
+        let mut prover = Self::new();
+        let first_message = prover.round1::<R, SIGMA>(rng, witness, circuit, public_output)?;
+        channel.send_first_message(&first_message).await?;
+
+        let challenge = channel.recv_challenge().await?;
+        let proof = prover.round3::<SIGMA>(challenge)?;
+        channel.send_proof(&proof).await
+    }
+}
+
+impl<T, TapeR, D, CS> InteractiveVerifier<T, TapeR, D, CS>
+where
+    T: Value + PartialEq,
+    TapeR: TapeExpander,
+    D: Clone + Default + Digest + FixedOutputReset,
+    CS: CommitmentScheme<D>,
+{
+    /// Verify over `channel` that the prover knows a witness for which
+    /// `circuit` outputs `public_output`.
+    pub async fn run_interactive_session<R, C, const SIGMA: usize>(
+        channel: &mut C,
+        rng: &mut R,
+        circuit: &impl Circuit<T>,
+        public_output: &[GF2Word<T>],
+    ) -> Result<(), Error>
+    where
+        R: RngCore + CryptoRng,
+        C: SigmaChannel<T, D>,
+    {
+        let mut verifier = Self::new();
+        let first_message = channel.recv_first_message().await?;
+        let challenge = verifier.round2::<R, SIGMA>(rng, first_message)?;
+        channel.send_challenge(&challenge).await?;
+
+        let proof = channel.recv_proof().await?;
+        verifier.verify::<SIGMA>(&proof, circuit, public_output)
+    }
+}
+
+#[cfg(test)]
+mod transport_tests {
+    use rand::{rngs::StdRng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+    use tokio::{io::AsyncWriteExt, net::TcpListener};
+
+    use crate::{
+        circuit::Circuit, error::Error, gadgets::add_mod::AdderCircuit, prover::InteractiveProver,
+        verifier::InteractiveVerifier,
+    };
+
+    use super::{SigmaChannel, TcpChannel};
+
+    const SIGMA: usize = 40;
+    type IP = InteractiveProver<u32, ChaCha20Rng, Keccak256>;
+    type IV = InteractiveVerifier<u32, ChaCha20Rng, Keccak256>;
+
+    async fn connected_pair() -> (TcpChannel, TcpChannel) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (client, server) = tokio::join!(TcpChannel::connect(addr), listener.accept());
+        (client.unwrap(), TcpChannel::new(server.unwrap().0))
+    }
+
+    #[tokio::test]
+    async fn test_interactive_session() {
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        let (mut prover_channel, mut verifier_channel) = connected_pair().await;
+        let (mut prover_rng, mut verifier_rng) = (StdRng::from_entropy(), StdRng::from_entropy());
+
+        let (proved, verified) = tokio::join!(
+            IP::run_interactive_session::<_, _, SIGMA>(
+                &mut prover_channel,
+                &mut prover_rng,
+                &input,
+                &circuit,
+                &output,
+            ),
+            IV::run_interactive_session::<_, _, SIGMA>(
+                &mut verifier_channel,
+                &mut verifier_rng,
+                &circuit,
+                &output,
+            ),
+        );
+        proved.unwrap();
+        verified.unwrap();
+
+        // a prover claiming another output is caught
+        let (mut prover_channel, mut verifier_channel) = connected_pair().await;
+        let wrong_output =
+            circuit.compute(&[input[..4].to_vec(), 7u32.to_le_bytes().to_vec()].concat());
+        let (proved, verified) = tokio::join!(
+            IP::run_interactive_session::<_, _, SIGMA>(
+                &mut prover_channel,
+                &mut prover_rng,
+                &input,
+                &circuit,
+                &wrong_output,
+            ),
+            IV::run_interactive_session::<_, _, SIGMA>(
+                &mut verifier_channel,
+                &mut verifier_rng,
+                &circuit,
+                &wrong_output,
+            ),
+        );
+        proved.unwrap();
+        assert!(verified.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_malformed_frames() {
+        // a message of the wrong kind
+        let (mut sender, mut receiver) = connected_pair().await;
+        SigmaChannel::<u32, Keccak256>::send_challenge(&mut sender, &[0, 1, 2])
+            .await
+            .unwrap();
+        assert!(matches!(
+            SigmaChannel::<u32, Keccak256>::recv_proof(&mut receiver).await,
+            Err(Error::TransportError)
+        ));
+
+        // a frame over the limit is rejected before being read
+        let (mut sender, receiver) = connected_pair().await;
+        let mut receiver = receiver.with_max_frame_len(2);
+        SigmaChannel::<u32, Keccak256>::send_challenge(&mut sender, &[0, 1, 2])
+            .await
+            .unwrap();
+        assert!(
+            SigmaChannel::<u32, Keccak256>::recv_challenge(&mut receiver)
+                .await
+                .is_err()
+        );
+
+        // a connection closed mid-frame
+        let (sender, mut receiver) = connected_pair().await;
+        let mut stream = sender.into_inner();
+        stream
+            .write_all(&[2, 10, 0, 0, 0, 0, 0, 0, 0, 1])
+            .await
+            .unwrap();
+        drop(stream);
+        assert!(
+            SigmaChannel::<u32, Keccak256>::recv_challenge(&mut receiver)
+                .await
+                .is_err()
+        );
+    }
+}
@@ -1,139 +1,959 @@
-use std::{fmt::Debug, marker::PhantomData};
+use std::{collections::BTreeSet, io::Read, marker::PhantomData};
 
-use rand::{CryptoRng, Rng, RngCore, SeedableRng};
+use rand::{CryptoRng, RngCore};
+use serde::de::DeserializeOwned;
 
 use sha3::{digest::FixedOutputReset, Digest};
+#[cfg(feature = "constant-time")]
+use subtle::{Choice, ConstantTimeEq};
 
 use crate::{
-    circuit::Circuit,
-    commitment::Commitment,
+    batch::xor3_words,
+    circuit::{any_of, Circuit},
+    commitment::{Commitment, CommitmentScheme, HashCommitment},
     config::HASH_LEN,
-    data_structures::{FirstMessageA, PartyExecution, Proof, PublicInput},
-    error::Error,
-    fs::SigmaFS,
+    data_structures::{
+        AggregateProof, FirstMessageA, Instance, MerkleProof, Proof, PublicInput, RepetitionProof,
+        RepetitionTranscript, RingProof, SessionState,
+    },
+    encoding::{
+        encode_aggregate_seed, encode_interactive_seed, encode_merkle_seed, encode_party_leaf,
+    },
+    error::{Error, VerificationCheck},
+    expander::{tape_label, TapeExpander},
+    fs::{Oracle, SigmaFS, Transcript},
+    gadgets::assertion::success_public_output,
     gf2_word::{GF2Word, Value},
+    header::ProofHeader,
     key::Key,
+    merkle::{hash_leaf, root_from_path},
     num_of_repetitions_given_desired_security,
+    params::ProofParams,
     party::Party,
-    tape::Tape,
+    progress::{NoProgress, Phase, ProgressObserver, Tracker},
+    tape::expand_tapes,
+    view::View,
 };
 
-pub struct Verifier<T: Value, TapeR, D>(PhantomData<(T, TapeR, D)>)
+/// Verifier of non-interactive proofs made with commitment scheme `C` and
+/// oracle `F`.
+pub struct Verifier<T: Value, TapeR, D, C = HashCommitment, F = SigmaFS<D>>(
+    PhantomData<(T, TapeR, D, C, F)>,
+)
 where
     D: Digest + FixedOutputReset,
-    TapeR: SeedableRng<Seed = Key> + RngCore + CryptoRng;
+    TapeR: TapeExpander;
 
-impl<T, TapeR, D> Verifier<T, TapeR, D>
+impl<T, TapeR, D, C, F> Verifier<T, TapeR, D, C, F>
 where
     T: Value + PartialEq,
-    TapeR: SeedableRng<Seed = Key> + RngCore + CryptoRng,
+    TapeR: TapeExpander,
     D: Clone + Default + Digest + FixedOutputReset,
+    C: CommitmentScheme<D>,
+    F: Oracle<D>,
 {
     pub fn verify<const SIGMA: usize>(
-        proof: &Proof<T, D, SIGMA>,
+        proof: &Proof<T, D>,
         circuit: &impl Circuit<T>,
         public_output: &Vec<GF2Word<T>>,
     ) -> Result<(), Error> {
-        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        Self::verify_with_context::<SIGMA>(proof, circuit, public_output, &[])
+    }
 
-        // Based on O3 and O5 of (https://eprint.iacr.org/2017/279.pdf)
-        assert_eq!(proof.party_inputs.len(), num_of_repetitions);
-        assert_eq!(proof.commitments.len(), num_of_repetitions);
-        assert_eq!(proof.views.len(), num_of_repetitions);
-        assert_eq!(proof.claimed_trits.len(), num_of_repetitions);
-        assert_eq!(proof.keys.len(), 2 * num_of_repetitions);
+    /// Verify a proof made by `Prover::prove_with_context` for `context`.
+    pub fn verify_with_context<const SIGMA: usize>(
+        proof: &Proof<T, D>,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+    ) -> Result<(), Error> {
+        Self::verify_with_transcript::<SIGMA>(
+            proof,
+            circuit,
+            public_output,
+            &mut F::from_context(context),
+        )
+    }
+
+    /// Verify a proof made by `Prover::prove_with_transcript`, with `transcript`
+    /// in the state the prover's was in.
+    pub fn verify_with_transcript<const SIGMA: usize>(
+        proof: &Proof<T, D>,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        transcript: &mut impl Transcript<D>,
+    ) -> Result<(), Error> {
+        Self::verify_inner(
+            proof,
+            circuit,
+            public_output,
+            &ProofParams::from_security(SIGMA),
+            transcript,
+            &mut NoProgress,
+        )
+    }
+
+    /// Verify a serialized proof made at any security level of at least
+    /// `min_sigma` bits, by `Prover::prove` for any `SIGMA` or by
+    /// `Prover::prove_with_params`, so the level is a run-time policy.
+    pub fn verify_dyn(
+        proof_bytes: &[u8],
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        min_sigma: usize,
+    ) -> Result<(), Error>
+    where
+        T: DeserializeOwned,
+    {
+        let proof = Proof::<T, D>::from_bytes(proof_bytes)?;
+        if proof.security_level() < min_sigma {
+            return Err(Error::SecurityLevelError(proof.security_level(), min_sigma));
+        }
+        let params = ProofParams::of_proof(proof.security_level(), proof.num_of_repetitions())
+            .ok_or(Error::VerificationError)?;
 
+        Self::verify_inner(
+            &proof,
+            circuit,
+            public_output,
+            &params,
+            &mut F::from_context(&[]),
+            &mut NoProgress,
+        )
+    }
+
+    /// Verify a proof made by `Prover::prove_with_progress` for `context`,
+    /// reporting to `progress` as each phase starts and after each repetition
+    /// of it. The verification is cancelled with `Error::CancelledError` as
+    /// soon as `progress` breaks.
+    pub fn verify_with_progress<const SIGMA: usize>(
+        proof: &Proof<T, D>,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+        progress: &mut impl ProgressObserver,
+    ) -> Result<(), Error> {
+        Self::verify_inner(
+            proof,
+            circuit,
+            public_output,
+            &ProofParams::from_security(SIGMA),
+            &mut F::from_context(context),
+            progress,
+        )
+    }
+
+    /// Verify a proof made by `Prover::prove_with_params` under `params`.
+    pub fn verify_with_params(
+        proof: &Proof<T, D>,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        params: &ProofParams<TapeR, D>,
+    ) -> Result<(), Error> {
+        Self::verify_inner(
+            proof,
+            circuit,
+            public_output,
+            params,
+            &mut F::from_context(&[]),
+            &mut NoProgress,
+        )
+    }
+
+    /// Verify like `verify_with_context`, but without returning before every
+    /// repetition is simulated and the challenge is recomputed.
+    ///
+    /// Failed repetitions are collected and the challenge is compared in
+    /// constant time, so the time taken doesn't reveal which repetition or
+    /// check failed, and every failure is a `VerificationError`. A circuit
+    /// whose `simulate_two_parties` returns early still simulates its failing
+    /// repetition in less time, and the shape of `proof` is checked up front.
+    #[cfg(feature = "constant-time")]
+    pub fn verify_constant_time<const SIGMA: usize>(
+        proof: &Proof<T, D>,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+    ) -> Result<(), Error> {
+        let params = ProofParams::<TapeR, D>::from_security(SIGMA);
+        let num_of_repetitions = params.repetitions();
+        Self::check_params(proof, &params)?;
+        Self::check_public_output(circuit, public_output)?;
+
+        let mut valid = Choice::from(1);
         let mut all_commitments = Vec::<Commitment<D>>::with_capacity(3 * num_of_repetitions);
         let mut outputs = Vec::<Vec<GF2Word<T>>>::with_capacity(3 * num_of_repetitions);
 
         for (repetition, &party_index) in proof.claimed_trits.iter().enumerate() {
-            let k_i0 = proof.keys[2 * repetition];
-            let mut p = Party::new::<TapeR>(
+            let recorded = Self::verify_repetition(
+                circuit,
+                public_output,
+                repetition,
+                party_index,
                 proof.party_inputs[repetition].clone(),
-                k_i0,
-                circuit.num_of_mul_gates(),
+                (proof.keys[2 * repetition], proof.keys[2 * repetition + 1]),
+                &proof.views[repetition],
+                &proof.commitments[repetition],
+                (&mut all_commitments, &mut outputs),
             );
+            if recorded.is_err() {
+                valid &= Choice::from(0);
+                // keep the transcript aligned with the repetitions that follow
+                all_commitments.resize(3 * (repetition + 1), Commitment::from_data([0; HASH_LEN]));
+                outputs.resize(
+                    3 * (repetition + 1),
+                    vec![GF2Word::from(T::zero()); circuit.party_output_len()],
+                );
+            }
+        }
 
-            let k_i1 = proof.keys[2 * repetition + 1];
-            let view_i1 = &proof.views[repetition];
+        let pi = PublicInput {
+            outputs: &outputs,
+            public_output,
+            public_input: &circuit.public_input(),
+            security_param: params.security_param(),
+            circuit_id: &circuit.id(),
+        };
+        let mut transcript = F::from_context(context);
+        transcript.absorb_public_input(&pi)?;
+        transcript.absorb_commitments(&all_commitments)?;
+        let opening_indices = transcript.challenge_trits(num_of_repetitions);
+        valid &= opening_indices.as_slice().ct_eq(&proof.claimed_trits);
 
-            let tape_i1 = Tape::from_key::<TapeR>(k_i1, circuit.num_of_mul_gates());
-            let mut p_next = Party::from_tape_and_view(view_i1.clone(), tape_i1);
+        if bool::from(valid) {
+            Ok(())
+        } else {
+            Err(Error::VerificationError)
+        }
+    }
 
-            let (o0, o1) = circuit.simulate_two_parties(&mut p, &mut p_next)?;
-            let o2 = Self::derive_third_output(public_output, circuit, (&o0, &o1));
+    /// Verify like `verify_with_context`, running the checks of every
+    /// repetition instead of stopping at the first failure, and report what
+    /// failed.
+    ///
+    /// Views are bound to their commitments through the challenge, so a view
+    /// other than the one committed to shows as a challenge mismatch rather
+    /// than as a failure of its repetition.
+    pub fn verify_with_report<const SIGMA: usize>(
+        proof: &Proof<T, D>,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+    ) -> VerificationReport {
+        let params = ProofParams::<TapeR, D>::from_security(SIGMA);
+        let mut report = VerificationReport {
+            num_of_repetitions: params.repetitions(),
+            malformed: None,
+            failures: vec![],
+            challenge_matches: None,
+        };
+        let checked = Self::check_params(proof, &params)
+            .and_then(|_| Self::check_public_output(circuit, public_output));
+        if let Err(error) = checked {
+            report.malformed = Some(error);
+            return report;
+        }
 
-            /*
-                Based on O6 of (https://eprint.iacr.org/2017/279.pdf)
-                Instead of checking view consistency, full view is computed through simulation
-                then security comes from binding property of H used when committing
-            */
-            let view_i0 = &p.view;
+        let mut all_commitments = Vec::<Commitment<D>>::with_capacity(3 * params.repetitions());
+        let mut outputs = Vec::<Vec<GF2Word<T>>>::with_capacity(3 * params.repetitions());
+        for (repetition, &party_index) in proof.claimed_trits.iter().enumerate() {
+            if let Err(error) = Self::verify_repetition(
+                circuit,
+                public_output,
+                repetition,
+                party_index,
+                proof.party_inputs[repetition].clone(),
+                (proof.keys[2 * repetition], proof.keys[2 * repetition + 1]),
+                &proof.views[repetition],
+                &proof.commitments[repetition],
+                (&mut all_commitments, &mut outputs),
+            ) {
+                report.failures.push(error);
+            }
+        }
 
-            let pi0_execution = PartyExecution {
-                key: &k_i0,
-                view: view_i0,
-            };
+        // the transcript is only complete if every repetition passed
+        if report.failures.is_empty() {
+            let challenge = Self::check_fiat_shamir(
+                params.security_param(),
+                public_output,
+                circuit,
+                &outputs,
+                &all_commitments,
+                &proof.claimed_trits,
+                &mut F::from_context(context),
+            );
+            report.challenge_matches = Some(challenge.is_ok());
+        }
+        report
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    fn verify_inner(
+        proof: &Proof<T, D>,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        params: &ProofParams<TapeR, D>,
+        transcript: &mut impl Transcript<D>,
+        progress: &mut impl ProgressObserver,
+    ) -> Result<(), Error> {
+        let num_of_repetitions = params.repetitions();
+        let progress = &mut Tracker::new(progress);
 
-            // Based on O4 of (https://eprint.iacr.org/2017/279.pdf)
-            let cm_i0 = pi0_execution.commit::<D>()?;
+        // Based on O3 and O5 of (https://eprint.iacr.org/2017/279.pdf)
+        Self::check_params(proof, params)?;
+        Self::check_public_output(circuit, public_output)?;
 
-            let pi1_execution = PartyExecution {
-                key: &k_i1,
-                view: view_i1,
-            };
+        let mut all_commitments = Vec::<Commitment<D>>::with_capacity(3 * num_of_repetitions);
+        let mut outputs = Vec::<Vec<GF2Word<T>>>::with_capacity(3 * num_of_repetitions);
 
-            // Based on O4 of (https://eprint.iacr.org/2017/279.pdf)
-            let cm_i1 = pi1_execution.commit::<D>()?;
+        progress.report(Phase::Simulate, 0, num_of_repetitions)?;
+        for (repetition, &party_index) in proof.claimed_trits.iter().enumerate() {
+            Self::verify_repetition(
+                circuit,
+                public_output,
+                repetition,
+                party_index,
+                proof.party_inputs[repetition].clone(),
+                (proof.keys[2 * repetition], proof.keys[2 * repetition + 1]),
+                &proof.views[repetition],
+                &proof.commitments[repetition],
+                (&mut all_commitments, &mut outputs),
+            )?;
+            progress.report(Phase::Simulate, repetition + 1, num_of_repetitions)?;
+        }
 
-            let cm_i2 = &proof.commitments[repetition];
+        progress.report(Phase::Challenge, 0, 1)?;
+        Self::check_fiat_shamir(
+            params.security_param(),
+            public_output,
+            circuit,
+            &outputs,
+            &all_commitments,
+            &proof.claimed_trits,
+            transcript,
+        )?;
+        progress.report(Phase::Challenge, 1, 1)
+    }
 
-            match party_index {
-                0 => {
-                    all_commitments.push(cm_i0);
-                    all_commitments.push(cm_i1);
-                    all_commitments.push(cm_i2.clone());
+    /// Verify a proof against a set of acceptable public outputs and return the
+    /// index of the one it was made for.
+    ///
+    /// The parties are simulated once; only the Fiat-Shamir check is repeated
+    /// for each candidate.
+    pub fn verify_any<const SIGMA: usize>(
+        proof: &Proof<T, D>,
+        circuit: &impl Circuit<T>,
+        public_outputs: &[Vec<GF2Word<T>>],
+    ) -> Result<usize, Error> {
+        Self::verify_any_with_context::<SIGMA>(proof, circuit, public_outputs, &[])
+    }
 
-                    outputs.push(o0);
-                    outputs.push(o1);
-                    outputs.push(o2);
-                }
-                1 => {
-                    all_commitments.push(cm_i2.clone());
-                    all_commitments.push(cm_i0);
-                    all_commitments.push(cm_i1);
-
-                    outputs.push(o2);
-                    outputs.push(o0);
-                    outputs.push(o1);
-                }
-                2 => {
-                    all_commitments.push(cm_i1);
-                    all_commitments.push(cm_i2.clone());
-                    all_commitments.push(cm_i0);
-
-                    outputs.push(o1);
-                    outputs.push(o2);
-                    outputs.push(o0);
-                }
-                _ => panic!("Not trit"),
+    pub fn verify_any_with_context<const SIGMA: usize>(
+        proof: &Proof<T, D>,
+        circuit: &impl Circuit<T>,
+        public_outputs: &[Vec<GF2Word<T>>],
+        context: &[u8],
+    ) -> Result<usize, Error> {
+        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+
+        Self::check_params(proof, &ProofParams::from_security(SIGMA))?;
+
+        let simulated = proof
+            .claimed_trits
+            .iter()
+            .enumerate()
+            .map(|(repetition, &party_index)| {
+                Self::simulate_repetition(
+                    circuit,
+                    repetition,
+                    party_index,
+                    proof.party_inputs[repetition].clone(),
+                    (proof.keys[2 * repetition], proof.keys[2 * repetition + 1]),
+                    &proof.views[repetition],
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        for (index, public_output) in public_outputs.iter().enumerate() {
+            if public_output.len() != circuit.party_output_len() {
+                continue;
+            }
+
+            let mut all_commitments = Vec::<Commitment<D>>::with_capacity(3 * num_of_repetitions);
+            let mut outputs = Vec::<Vec<GF2Word<T>>>::with_capacity(3 * num_of_repetitions);
+
+            for (repetition, simulated) in simulated.iter().enumerate() {
+                let o2 = Self::derive_third_output(
+                    public_output,
+                    circuit,
+                    (&simulated.outputs.0, &simulated.outputs.1),
+                )?;
+                simulated.record(
+                    o2,
+                    &proof.commitments[repetition],
+                    (&mut all_commitments, &mut outputs),
+                )?;
+            }
+
+            if Self::check_fiat_shamir(
+                SIGMA,
+                public_output,
+                circuit,
+                &outputs,
+                &all_commitments,
+                &proof.claimed_trits,
+                &mut F::from_context(context),
+            )
+            .is_ok()
+            {
+                return Ok(index);
+            }
+        }
+
+        Err(Error::FiatShamirOutputsMatchingError)
+    }
+
+    /// Verify a proof made by `Prover::prove_many` for `instances`, in the
+    /// order they were proven in, and `context`.
+    pub fn verify_aggregate<const SIGMA: usize>(
+        proof: &AggregateProof<T, D>,
+        instances: &[Instance<T>],
+        context: &[u8],
+    ) -> Result<(), Error> {
+        if proof.proofs.len() != instances.len() {
+            return Err(Error::StatementMismatchError);
+        }
+        let params = ProofParams::from_security(SIGMA);
+        let num_of_repetitions = params.repetitions();
+
+        let mut transcript = F::from_seed(&encode_aggregate_seed(instances.len(), context));
+        let mut claimed_trits = Vec::with_capacity(instances.len() * num_of_repetitions);
+
+        for (proof, instance) in proof.proofs.iter().zip(instances) {
+            Self::check_params(proof, &params)?;
+            Self::check_public_output(instance.circuit, instance.public_output)?;
+
+            let mut all_commitments = Vec::<Commitment<D>>::with_capacity(3 * num_of_repetitions);
+            let mut outputs = Vec::<Vec<GF2Word<T>>>::with_capacity(3 * num_of_repetitions);
+
+            for (repetition, &party_index) in proof.claimed_trits.iter().enumerate() {
+                Self::verify_repetition(
+                    instance.circuit,
+                    instance.public_output,
+                    repetition,
+                    party_index,
+                    proof.party_inputs[repetition].clone(),
+                    (proof.keys[2 * repetition], proof.keys[2 * repetition + 1]),
+                    &proof.views[repetition],
+                    &proof.commitments[repetition],
+                    (&mut all_commitments, &mut outputs),
+                )?;
+            }
+
+            let pi = PublicInput {
+                outputs: &outputs,
+                public_output: instance.public_output,
+                public_input: &instance.circuit.public_input(),
+                security_param: SIGMA,
+                circuit_id: &instance.circuit.id(),
             };
+            transcript.absorb_public_input(&pi)?;
+            transcript.absorb_commitments(&all_commitments)?;
+            claimed_trits.extend_from_slice(&proof.claimed_trits);
+        }
+
+        if transcript.challenge_trits(claimed_trits.len()) != claimed_trits {
+            return Err(Error::FiatShamirOutputsMatchingError);
+        }
+
+        Ok(())
+    }
+
+    /// Verify a proof made by `Prover::prove_ring` for the members
+    /// `instances`, in the order they were given, and `context`.
+    pub fn verify_ring<const SIGMA: usize>(
+        proof: &RingProof<T, D>,
+        instances: &[Instance<T>],
+        context: &[u8],
+    ) -> Result<(), Error> {
+        if instances.is_empty() {
+            return Err(Error::StatementMismatchError);
+        }
+        for member in instances {
+            Self::check_public_output(member.circuit, member.public_output)?;
+        }
+
+        Self::verify_with_context::<SIGMA>(
+            &proof.proof,
+            &any_of(instances),
+            &success_public_output(),
+            context,
+        )
+    }
+
+    /// Verify a proof made by `Prover::prove_merkle` for `context`.
+    pub fn verify_merkle<const SIGMA: usize>(
+        proof: &MerkleProof<T, D>,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+    ) -> Result<(), Error> {
+        Self::verify_merkle_repetitions::<SIGMA>(
+            proof,
+            circuit,
+            public_output,
+            context,
+            0..proof.proof.num_of_repetitions(),
+        )
+    }
+
+    /// Verify only `repetitions` of a proof made by `Prover::prove_merkle`,
+    /// which must be distinct and at least one.
+    ///
+    /// A cheating prover passes each repetition with probability at most 2/3,
+    /// so checking `k` distinct repetitions leaves a soundness error of
+    /// `(2/3)^k` rather than `2^-SIGMA`.
+    pub fn verify_merkle_repetitions<const SIGMA: usize>(
+        proof: &MerkleProof<T, D>,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+        repetitions: impl IntoIterator<Item = usize>,
+    ) -> Result<(), Error> {
+        let repetitions: Vec<usize> = repetitions.into_iter().collect();
+        let distinct: BTreeSet<usize> = repetitions.iter().copied().collect();
+        if distinct.is_empty() || distinct.len() != repetitions.len() {
+            return Err(Error::VerificationError);
+        }
+
+        let params = ProofParams::from_security(SIGMA);
+        let num_of_repetitions = params.repetitions();
+        Self::check_params(&proof.proof, &params)?;
+        Self::check_public_output(circuit, public_output)?;
+        if proof.paths.len() != 3 * num_of_repetitions {
+            return Err(Error::VerificationError);
         }
 
+        // the root binds the commitments and outputs of all parties, so the
+        // challenge is known before any repetition is checked
         let pi = PublicInput {
-            outputs: &outputs,
+            outputs: &vec![],
             public_output,
-            hash_len: HASH_LEN,
+            public_input: &circuit.public_input(),
             security_param: SIGMA,
+            circuit_id: &circuit.id(),
         };
+        let mut transcript = F::from_seed(&encode_merkle_seed(context));
+        transcript.absorb_public_input(&pi)?;
+        transcript.absorb_commitments(&[Commitment::from_data(proof.root)])?;
+        if transcript.challenge_trits(num_of_repetitions) != proof.proof.claimed_trits {
+            return Err(Error::FiatShamirOutputsMatchingError);
+        }
 
-        // TODO: remove hardcoded seed
-        let mut fs_oracle = SigmaFS::<D>::initialize(&[0u8]);
-        fs_oracle.digest_public_data(&pi)?;
-        fs_oracle.digest_prover_message(&all_commitments)?;
+        let inner = &proof.proof;
+        for repetition in repetitions {
+            if repetition >= num_of_repetitions {
+                return Err(Error::OutOfRangeError);
+            }
 
-        let opening_indices = fs_oracle.sample_trits(num_of_repetitions);
-        if opening_indices != proof.claimed_trits {
+            let party_index = inner.claimed_trits[repetition];
+            let simulated = Self::simulate_repetition(
+                circuit,
+                repetition,
+                party_index,
+                inner.party_inputs[repetition].clone(),
+                (inner.keys[2 * repetition], inner.keys[2 * repetition + 1]),
+                &inner.views[repetition],
+            )?;
+            let i0 = party_index as usize;
+            // the leaf of the unopened party ties the root to `public_output`
+            let third_output = Self::derive_third_output(
+                public_output,
+                circuit,
+                (&simulated.outputs.0, &simulated.outputs.1),
+            )
+            .map_err(|source| {
+                repetition_error(repetition, (i0 + 2) % 3, VerificationCheck::Output, source)
+            })?;
+
+            let parties = [
+                (i0, &simulated.commitments.0, &simulated.outputs.0),
+                ((i0 + 1) % 3, &simulated.commitments.1, &simulated.outputs.1),
+                ((i0 + 2) % 3, &inner.commitments[repetition], &third_output),
+            ];
+            for (k, (party, commitment, output)) in parties.into_iter().enumerate() {
+                let leaf = hash_leaf::<D>(&encode_party_leaf(commitment, output))?;
+                let root = root_from_path::<D>(
+                    leaf,
+                    3 * repetition + party,
+                    3 * num_of_repetitions,
+                    &proof.paths[3 * repetition + k],
+                )?;
+                if root != proof.root {
+                    return Err(repetition_error(
+                        repetition,
+                        party,
+                        VerificationCheck::Commitment,
+                        Error::VerificationError,
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that `proof` was made for `params` and has the matching shape.
+    fn check_params(proof: &Proof<T, D>, params: &ProofParams<TapeR, D>) -> Result<(), Error> {
+        Self::check_shape(proof, params, params.repetitions())
+    }
+
+    /// Same as `check_params`, for a proof carrying `num_of_commitments`
+    /// commitments of unopened parties.
+    fn check_shape(
+        proof: &Proof<T, D>,
+        params: &ProofParams<TapeR, D>,
+        num_of_commitments: usize,
+    ) -> Result<(), Error> {
+        let digest_len = <D as Digest>::output_size();
+        if digest_len != HASH_LEN {
+            return Err(Error::HashLenError(HASH_LEN, digest_len));
+        }
+        let num_of_repetitions = params.repetitions();
+        if proof.security_param != params.security_param()
+            || proof.party_inputs.len() != num_of_repetitions
+            || proof.commitments.len() != num_of_commitments
+            || proof.views.len() != num_of_repetitions
+            || proof.claimed_trits.len() != num_of_repetitions
+            || proof.keys.len() != 2 * num_of_repetitions
+        {
+            return Err(Error::VerificationError);
+        }
+        Ok(())
+    }
+
+    /// Checks that `public_output` has as many words as each party outputs.
+    fn check_public_output(
+        circuit: &(impl Circuit<T> + ?Sized),
+        public_output: &[GF2Word<T>],
+    ) -> Result<(), Error> {
+        if public_output.len() != circuit.party_output_len() {
+            return Err(Error::PublicOutputLenError(
+                circuit.party_output_len(),
+                public_output.len(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Verify a serialized proof while reading it, without materializing the
+    /// whole `Proof` in memory.
+    pub fn verify_from_reader<const SIGMA: usize, R: Read>(
+        reader: &mut R,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+    ) -> Result<(), Error>
+    where
+        T: DeserializeOwned,
+    {
+        Self::verify_from_reader_with_context::<SIGMA, R>(reader, circuit, public_output, &[])
+    }
+
+    /// Verify a proof written by `Proof::write_with_header_to`, checking its
+    /// header against `circuit`, `SIGMA` and the types of this verifier first.
+    pub fn verify_with_header<const SIGMA: usize, R: Read>(
+        reader: &mut R,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+    ) -> Result<(), Error>
+    where
+        T: DeserializeOwned,
+    {
+        let expected = ProofHeader::new::<T, TapeR, D>(circuit, SIGMA)?;
+        ProofHeader::read_from(reader)?.check(&expected)?;
+        Self::verify_from_reader::<SIGMA, R>(reader, circuit, public_output)
+    }
+
+    /// Same as `verify_from_reader`, for a proof made for `context`.
+    pub fn verify_from_reader_with_context<const SIGMA: usize, R: Read>(
+        reader: &mut R,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+    ) -> Result<(), Error>
+    where
+        T: DeserializeOwned,
+    {
+        Self::verify_from_reader_with_progress::<SIGMA, R>(
+            reader,
+            circuit,
+            public_output,
+            context,
+            &mut NoProgress,
+        )
+    }
+
+    /// Same as `verify_from_reader_with_context`, reporting to `progress` as
+    /// `verify_with_progress` does.
+    pub fn verify_from_reader_with_progress<const SIGMA: usize, R: Read>(
+        reader: &mut R,
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        context: &[u8],
+        progress: &mut impl ProgressObserver,
+    ) -> Result<(), Error>
+    where
+        T: DeserializeOwned,
+    {
+        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        let progress = &mut Tracker::new(progress);
+        Self::check_public_output(circuit, public_output)?;
+        if Proof::<T, D>::read_header(reader)? != (SIGMA, num_of_repetitions) {
+            return Err(Error::VerificationError);
+        }
+
+        let mut all_commitments = Vec::<Commitment<D>>::with_capacity(3 * num_of_repetitions);
+        let mut outputs = Vec::<Vec<GF2Word<T>>>::with_capacity(3 * num_of_repetitions);
+        let mut claimed_trits = Vec::with_capacity(num_of_repetitions);
+
+        progress.report(Phase::Simulate, 0, num_of_repetitions)?;
+        for repetition in 0..num_of_repetitions {
+            let repetition_proof = Proof::<T, D>::read_repetition(reader)?;
+
+            Self::verify_repetition(
+                circuit,
+                public_output,
+                repetition,
+                repetition_proof.claimed_trit,
+                repetition_proof.party_input,
+                repetition_proof.keys,
+                &repetition_proof.view,
+                &repetition_proof.commitment,
+                (&mut all_commitments, &mut outputs),
+            )?;
+
+            claimed_trits.push(repetition_proof.claimed_trit);
+            progress.report(Phase::Simulate, repetition + 1, num_of_repetitions)?;
+        }
+
+        progress.report(Phase::Challenge, 0, 1)?;
+        Self::check_fiat_shamir(
+            SIGMA,
+            public_output,
+            circuit,
+            &outputs,
+            &all_commitments,
+            &claimed_trits,
+            &mut F::from_context(context),
+        )?;
+        progress.report(Phase::Challenge, 1, 1)
+    }
+
+    /// Verify a single repetition of a proof, e.g. one obtained from `Proof::split`.
+    ///
+    /// This checks everything but the Fiat-Shamir challenge, so repetitions can be
+    /// verified independently; `finalize` then checks the challenge once over the
+    /// transcripts of all repetitions.
+    pub fn verify_repetition_proof(
+        circuit: &impl Circuit<T>,
+        public_output: &[GF2Word<T>],
+        repetition: usize,
+        repetition_proof: &RepetitionProof<T, D>,
+    ) -> Result<RepetitionTranscript<T, D>, Error> {
+        Self::check_public_output(circuit, public_output)?;
+        let mut commitments = Vec::with_capacity(3);
+        let mut outputs = Vec::with_capacity(3);
+
+        Self::verify_repetition(
+            circuit,
+            public_output,
+            repetition,
+            repetition_proof.claimed_trit,
+            repetition_proof.party_input.clone(),
+            repetition_proof.keys,
+            &repetition_proof.view,
+            &repetition_proof.commitment,
+            (&mut commitments, &mut outputs),
+        )?;
+
+        Ok(RepetitionTranscript {
+            repetition,
+            claimed_trit: repetition_proof.claimed_trit,
+            commitments,
+            outputs,
+        })
+    }
+
+    /// Check the Fiat-Shamir challenge over the transcripts of all repetitions.
+    ///
+    /// Transcripts may come in any order, but every repetition must be present
+    /// exactly once.
+    pub fn finalize<const SIGMA: usize>(
+        circuit: &impl Circuit<T>,
+        public_output: &Vec<GF2Word<T>>,
+        mut transcripts: Vec<RepetitionTranscript<T, D>>,
+        context: &[u8],
+    ) -> Result<(), Error> {
+        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        Self::check_public_output(circuit, public_output)?;
+        if transcripts.len() != num_of_repetitions {
+            return Err(Error::VerificationError);
+        }
+
+        transcripts.sort_by_key(|transcript| transcript.repetition);
+
+        let mut all_commitments = Vec::<Commitment<D>>::with_capacity(3 * num_of_repetitions);
+        let mut outputs = Vec::<Vec<GF2Word<T>>>::with_capacity(3 * num_of_repetitions);
+        let mut claimed_trits = Vec::with_capacity(num_of_repetitions);
+
+        for (repetition, transcript) in transcripts.into_iter().enumerate() {
+            if transcript.repetition != repetition
+                || transcript.commitments.len() != 3
+                || transcript.outputs.len() != 3
+            {
+                return Err(Error::VerificationError);
+            }
+
+            all_commitments.extend(transcript.commitments);
+            outputs.extend(transcript.outputs);
+            claimed_trits.push(transcript.claimed_trit);
+        }
+
+        Self::check_fiat_shamir(
+            SIGMA,
+            public_output,
+            circuit,
+            &outputs,
+            &all_commitments,
+            &claimed_trits,
+            &mut F::from_context(context),
+        )
+    }
+
+    /// Simulate the two opened parties of a single repetition and record the
+    /// commitments and outputs of all three parties, in party order.
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(party_index))
+    )]
+    fn verify_repetition(
+        circuit: &(impl Circuit<T> + ?Sized),
+        public_output: &[GF2Word<T>],
+        repetition: usize,
+        party_index: u8,
+        party_input: Vec<u8>,
+        keys: (Key, Key),
+        view_i1: &View<T>,
+        cm_i2: &Commitment<D>,
+        (all_commitments, outputs): (&mut Vec<Commitment<D>>, &mut Vec<Vec<GF2Word<T>>>),
+    ) -> Result<(), Error> {
+        let simulated = Self::simulate_repetition(
+            circuit,
+            repetition,
+            party_index,
+            party_input,
+            keys,
+            view_i1,
+        )?;
+        let o2 = Self::derive_third_output(
+            public_output,
+            circuit,
+            (&simulated.outputs.0, &simulated.outputs.1),
+        )
+        .map_err(|source| {
+            let i2 = (party_index as usize + 2) % 3;
+            repetition_error(repetition, i2, VerificationCheck::Output, source)
+        })?;
+        simulated.record(o2, cm_i2, (all_commitments, outputs))
+    }
+
+    fn simulate_repetition(
+        circuit: &(impl Circuit<T> + ?Sized),
+        repetition: usize,
+        party_index: u8,
+        party_input: Vec<u8>,
+        (k_i0, k_i1): (Key, Key),
+        view_i1: &View<T>,
+    ) -> Result<SimulatedRepetition<T, D>, Error> {
+        let fail =
+            |party: usize, check| move |source| repetition_error(repetition, party, check, source);
+        if party_index > 2 {
+            return Err(fail(party_index as usize, VerificationCheck::PartyIndex)(
+                Error::VerificationError,
+            ));
+        }
+        let i0 = party_index as usize;
+        let i1 = (i0 + 1) % 3;
+        let [tape_i0, tape_i1] = expand_tapes::<T, TapeR, 2>(
+            [
+                (k_i0, tape_label(repetition, i0)),
+                (k_i1, tape_label(repetition, i1)),
+            ],
+            circuit.num_of_mul_gates(),
+        );
+        let mut p = Party::from_share_and_tape(party_input, tape_i0);
+        let mut p_next = Party::from_tape_and_view(view_i1.clone(), tape_i1);
+
+        let (o0, o1) = circuit
+            .simulate_two_parties(&mut p, &mut p_next)
+            .map_err(fail(i0, VerificationCheck::Simulation))?;
+        #[cfg(feature = "tracing")]
+        crate::party::trace_counters(&[&p, &p_next]);
+        p.tape
+            .check_consumed()
+            .map_err(fail(i0, VerificationCheck::Tape))?;
+        p_next
+            .tape
+            .check_consumed()
+            .map_err(fail(i1, VerificationCheck::Tape))?;
+
+        /*
+            Based on O6 of (https://eprint.iacr.org/2017/279.pdf)
+            Instead of checking view consistency, full view is computed through simulation
+            then security comes from binding property of H used when committing
+        */
+        let view_i0 = &p.view;
+
+        // Based on O4 of (https://eprint.iacr.org/2017/279.pdf)
+        let cm_i0 =
+            C::commit_view(&k_i0, view_i0).map_err(fail(i0, VerificationCheck::Commitment))?;
+
+        // Based on O4 of (https://eprint.iacr.org/2017/279.pdf)
+        let cm_i1 =
+            C::commit_view(&k_i1, view_i1).map_err(fail(i1, VerificationCheck::Commitment))?;
+
+        Ok(SimulatedRepetition {
+            party_index,
+            commitments: (cm_i0, cm_i1),
+            outputs: (o0, o1),
+        })
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    fn check_fiat_shamir(
+        security_param: usize,
+        public_output: &Vec<GF2Word<T>>,
+        circuit: &(impl Circuit<T> + ?Sized),
+        outputs: &Vec<Vec<GF2Word<T>>>,
+        all_commitments: &[Commitment<D>],
+        claimed_trits: &[u8],
+        transcript: &mut impl Transcript<D>,
+    ) -> Result<(), Error> {
+        let pi = PublicInput {
+            outputs,
+            public_output,
+            public_input: &circuit.public_input(),
+            security_param,
+            circuit_id: &circuit.id(),
+        };
+
+        transcript.absorb_public_input(&pi)?;
+        transcript.absorb_commitments(all_commitments)?;
+
+        let opening_indices = transcript.challenge_trits(claimed_trits.len());
+        if opening_indices != claimed_trits {
             return Err(Error::FiatShamirOutputsMatchingError);
         }
 
@@ -142,157 +962,263 @@ where
 
     pub fn derive_third_output(
         public_output: &[GF2Word<T>],
-        circuit: &impl Circuit<T>,
+        circuit: &(impl Circuit<T> + ?Sized),
         circuit_simulation_output: (&Vec<GF2Word<T>>, &Vec<GF2Word<T>>),
-    ) -> Vec<GF2Word<T>> {
+    ) -> Result<Vec<GF2Word<T>>, Error> {
         let party_output_len = circuit.party_output_len();
         let (o1, o2) = circuit_simulation_output;
 
-        // TODO: introduce error here
-        assert_eq!(o1.len(), party_output_len);
-        assert_eq!(o2.len(), party_output_len);
+        // a circuit may output fewer words on malformed views
+        if [o1.len(), o2.len(), public_output.len()]
+            .iter()
+            .any(|&len| len != party_output_len)
+        {
+            return Err(Error::OutputReconstructionError);
+        }
 
-        let mut derived_output = Vec::with_capacity(party_output_len);
+        Ok(xor3_words(o1, o2, public_output))
+    }
+}
 
-        for i in 0..party_output_len {
-            derived_output.push(o1[i] ^ o2[i] ^ public_output[i]);
+fn repetition_error(
+    repetition: usize,
+    party: usize,
+    check: VerificationCheck,
+    source: Error,
+) -> Error {
+    Error::RepetitionError {
+        repetition,
+        party: party as u8,
+        check,
+        source: Box::new(source),
+    }
+}
+
+/// The outcome of every check of `Verifier::verify_with_report`.
+#[derive(Debug)]
+pub struct VerificationReport {
+    pub num_of_repetitions: usize,
+    /// Why the proof or the public output was rejected before simulating any
+    /// repetition, e.g. for a proof of another security level.
+    pub malformed: Option<Error>,
+    /// A `RepetitionError` for every repetition which failed, in order.
+    pub failures: Vec<Error>,
+    /// Whether the claimed challenge is the one derived from the transcript.
+    /// `None` if the proof is malformed or a repetition failed.
+    pub challenge_matches: Option<bool>,
+}
+
+impl VerificationReport {
+    pub fn is_valid(&self) -> bool {
+        self.challenge_matches == Some(true)
+    }
+
+    /// The repetitions which failed.
+    pub fn failed_repetitions(&self) -> Vec<usize> {
+        self.failures
+            .iter()
+            .filter_map(|error| match error {
+                Error::RepetitionError { repetition, .. } => Some(*repetition),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The first failure, as `verify_with_context` would have returned it.
+    pub fn into_result(self) -> Result<(), Error> {
+        if let Some(error) = self.malformed {
+            return Err(error);
+        }
+        if let Some(error) = self.failures.into_iter().next() {
+            return Err(error);
         }
+        match self.challenge_matches {
+            Some(true) => Ok(()),
+            _ => Err(Error::FiatShamirOutputsMatchingError),
+        }
+    }
+}
+
+/// The opened parties of a repetition after simulation. Only the output of the
+/// third party depends on the claimed public output.
+struct SimulatedRepetition<T: Value, D: Default + Digest + Clone> {
+    party_index: u8,
+    commitments: (Commitment<D>, Commitment<D>),
+    outputs: (Vec<GF2Word<T>>, Vec<GF2Word<T>>),
+}
 
-        derived_output
+impl<T: Value, D: Default + Digest + Clone> SimulatedRepetition<T, D> {
+    /// Record the commitments and outputs of all three parties, in party order,
+    /// given the output `o2` derived for the third party.
+    fn record(
+        &self,
+        o2: Vec<GF2Word<T>>,
+        cm_i2: &Commitment<D>,
+        (all_commitments, outputs): (&mut Vec<Commitment<D>>, &mut Vec<Vec<GF2Word<T>>>),
+    ) -> Result<(), Error> {
+        let (cm_i0, cm_i1) = self.commitments.clone();
+        let (o0, o1) = self.outputs.clone();
+
+        match self.party_index {
+            0 => {
+                all_commitments.push(cm_i0);
+                all_commitments.push(cm_i1);
+                all_commitments.push(cm_i2.clone());
+
+                outputs.push(o0);
+                outputs.push(o1);
+                outputs.push(o2);
+            }
+            1 => {
+                all_commitments.push(cm_i2.clone());
+                all_commitments.push(cm_i0);
+                all_commitments.push(cm_i1);
+
+                outputs.push(o2);
+                outputs.push(o0);
+                outputs.push(o1);
+            }
+            2 => {
+                all_commitments.push(cm_i1);
+                all_commitments.push(cm_i2.clone());
+                all_commitments.push(cm_i0);
+
+                outputs.push(o1);
+                outputs.push(o2);
+                outputs.push(o0);
+            }
+            _ => return Err(Error::VerificationError),
+        };
+
+        Ok(())
     }
 }
 
 #[derive(Default)]
-pub struct InteractiveVerifier<T: Value, TapeR, D>
+pub struct InteractiveVerifier<T: Value, TapeR, D, C = HashCommitment>
 where
     D: Default + Digest + FixedOutputReset + Clone,
-    TapeR: SeedableRng<Seed = Key> + RngCore + CryptoRng,
+    TapeR: TapeExpander,
 {
     challenge: Vec<u8>,
-    pd: PhantomData<(T, TapeR, D)>,
+    randomness: Vec<u8>,
+    pd: PhantomData<(T, TapeR, D, C)>,
     all_commitments: Vec<Commitment<D>>,
     outputs: Vec<Vec<GF2Word<T>>>,
+    state: SessionState,
+    security_param: usize,
 }
 
-impl<T, TapeR, D> InteractiveVerifier<T, TapeR, D>
+impl<T, TapeR, D, C> InteractiveVerifier<T, TapeR, D, C>
 where
     T: Value + PartialEq,
-    TapeR: SeedableRng<Seed = Key> + RngCore + CryptoRng,
+    TapeR: TapeExpander,
     D: Clone + Default + Digest + FixedOutputReset,
+    C: CommitmentScheme<D>,
 {
     pub fn new() -> Self {
         InteractiveVerifier {
             challenge: Vec::new(),
-            pd: PhantomData::default(),
+            randomness: Vec::new(),
+            pd: PhantomData,
             all_commitments: Vec::new(),
             outputs: Vec::new(),
+            state: SessionState::Start,
+            security_param: 0,
         }
     }
 
-    pub fn round2<R: RngCore + CryptoRng>(
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    /// The randomness drawn by the verifier in `round2`.
+    pub fn randomness(&self) -> &[u8] {
+        &self.randomness
+    }
+
+    /// Draw the challenge for `fm`, with one trit per repetition at security
+    /// level `SIGMA`. First messages of another size are rejected.
+    ///
+    /// The challenge hashes `fm` with fresh randomness of the verifier, so
+    /// that a recorded session `(fm, randomness, proof)` can be checked with
+    /// `derive_challenge` like a non-interactive proof.
+    pub fn round2<R: RngCore + CryptoRng, const SIGMA: usize>(
+        &mut self,
+        rng: &mut R,
+        fm: FirstMessageA<T, D>,
+    ) -> Result<Vec<u8>, Error> {
+        let mut transcript = SigmaFS::<D>::initialize(&encode_interactive_seed());
+        self.round2_with_transcript::<R, SIGMA>(rng, fm, &mut transcript)
+    }
+
+    /// `round2`, drawing the challenge from `transcript`.
+    pub fn round2_with_transcript<R: RngCore + CryptoRng, const SIGMA: usize>(
         &mut self,
         rng: &mut R,
-        r: usize,
         fm: FirstMessageA<T, D>,
-    ) -> Vec<u8> {
-        let challenge: Vec<u8> = (0..r).map(|_| rng.gen_range(0..3)).collect();
+        transcript: &mut impl Transcript<D>,
+    ) -> Result<Vec<u8>, Error> {
+        if self.state != SessionState::Start {
+            return Err(Error::ProtocolStateError);
+        }
+        // a rejected first message ends the session
+        self.state = SessionState::Finished;
+
+        let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
+        if fm.all_commitments.len() != 3 * num_of_repetitions
+            || fm.outputs.len() != 3 * num_of_repetitions
+        {
+            return Err(Error::VerificationError);
+        }
+
+        let mut randomness = vec![0u8; HASH_LEN];
+        rng.fill_bytes(&mut randomness);
+        let challenge = Self::derive_challenge::<SIGMA>(&randomness, &fm, transcript)?;
         self.challenge = challenge.clone();
+        self.randomness = randomness;
         self.all_commitments = fm.all_commitments;
         self.outputs = fm.outputs;
+        self.security_param = SIGMA;
+        self.state = SessionState::Committed;
 
-        challenge
+        Ok(challenge)
     }
 
+    /// Check the prover's answer to the challenge. Only one answer is checked
+    /// per session.
     pub fn verify<const SIGMA: usize>(
-        &self,
-        proof: &Proof<T, D, SIGMA>,
+        &mut self,
+        proof: &Proof<T, D>,
         circuit: &impl Circuit<T>,
-        public_output: &Vec<GF2Word<T>>,
+        public_output: &[GF2Word<T>],
     ) -> Result<(), Error> {
+        if self.state != SessionState::Committed || self.security_param != SIGMA {
+            return Err(Error::ProtocolStateError);
+        }
+        self.state = SessionState::Finished;
+
         let num_of_repetitions = num_of_repetitions_given_desired_security(SIGMA);
 
         // Based on O3 and O5 of (https://eprint.iacr.org/2017/279.pdf)
-        assert_eq!(proof.party_inputs.len(), num_of_repetitions);
-        assert_eq!(proof.commitments.len(), num_of_repetitions);
-        assert_eq!(proof.views.len(), num_of_repetitions);
-        assert_eq!(proof.claimed_trits.len(), num_of_repetitions);
-        assert_eq!(proof.keys.len(), 2 * num_of_repetitions);
+        Verifier::<T, TapeR, D, C>::check_params(proof, &ProofParams::from_security(SIGMA))?;
+        Verifier::<T, TapeR, D, C>::check_public_output(circuit, public_output)?;
 
         let mut all_commitments = Vec::<Commitment<D>>::with_capacity(3 * num_of_repetitions);
         let mut outputs = Vec::<Vec<GF2Word<T>>>::with_capacity(3 * num_of_repetitions);
 
         for (repetition, &party_index) in proof.claimed_trits.iter().enumerate() {
-            let k_i0 = proof.keys[2 * repetition];
-            let mut p = Party::new::<TapeR>(
+            Verifier::<T, TapeR, D, C>::verify_repetition(
+                circuit,
+                public_output,
+                repetition,
+                party_index,
                 proof.party_inputs[repetition].clone(),
-                k_i0,
-                circuit.num_of_mul_gates(),
-            );
-
-            let k_i1 = proof.keys[2 * repetition + 1];
-            let view_i1 = &proof.views[repetition];
-
-            let tape_i1 = Tape::from_key::<TapeR>(k_i1, circuit.num_of_mul_gates());
-            let mut p_next = Party::from_tape_and_view(view_i1.clone(), tape_i1);
-
-            let (o0, o1) = circuit.simulate_two_parties(&mut p, &mut p_next)?;
-            let o2 = Self::derive_third_output(public_output, circuit, (&o0, &o1));
-
-            /*
-                Based on O6 of (https://eprint.iacr.org/2017/279.pdf)
-                Instead of checking view consistency, full view is computed through simulation
-                then security comes from binding property of H used when committing
-            */
-            let view_i0 = &p.view;
-
-            let pi0_execution = PartyExecution {
-                key: &k_i0,
-                view: view_i0,
-            };
-
-            // Based on O4 of (https://eprint.iacr.org/2017/279.pdf)
-            let cm_i0 = pi0_execution.commit::<D>()?;
-
-            let pi1_execution = PartyExecution {
-                key: &k_i1,
-                view: view_i1,
-            };
-
-            // Based on O4 of (https://eprint.iacr.org/2017/279.pdf)
-            let cm_i1 = pi1_execution.commit::<D>()?;
-
-            let cm_i2 = &proof.commitments[repetition];
-
-            match party_index {
-                0 => {
-                    all_commitments.push(cm_i0);
-                    all_commitments.push(cm_i1);
-                    all_commitments.push(cm_i2.clone());
-
-                    outputs.push(o0);
-                    outputs.push(o1);
-                    outputs.push(o2);
-                }
-                1 => {
-                    all_commitments.push(cm_i2.clone());
-                    all_commitments.push(cm_i0);
-                    all_commitments.push(cm_i1);
-
-                    outputs.push(o2);
-                    outputs.push(o0);
-                    outputs.push(o1);
-                }
-                2 => {
-                    all_commitments.push(cm_i1);
-                    all_commitments.push(cm_i2.clone());
-                    all_commitments.push(cm_i0);
-
-                    outputs.push(o1);
-                    outputs.push(o2);
-                    outputs.push(o0);
-                }
-                _ => panic!("Not trit"),
-            };
+                (proof.keys[2 * repetition], proof.keys[2 * repetition + 1]),
+                &proof.views[repetition],
+                &proof.commitments[repetition],
+                (&mut all_commitments, &mut outputs),
+            )?;
         }
 
         let opening_indices = self.challenge.clone();
@@ -300,16 +1226,16 @@ where
             return Err(Error::FiatShamirOutputsMatchingError);
         }
 
-        let _ = all_commitments
-            .iter()
-            .zip(self.all_commitments.iter())
-            .map(|(a, b)| {
-                if a.data != b.data {
-                    return Err(Error::VerificationError);
-                } else {
-                    Ok(())
-                }
-            });
+        // the first message must hold exactly the recomputed commitments, a
+        // shorter one would otherwise pass a pairwise comparison
+        if all_commitments.len() != self.all_commitments.len()
+            || all_commitments
+                .iter()
+                .zip(self.all_commitments.iter())
+                .any(|(a, b)| a.data != b.data)
+        {
+            return Err(Error::VerificationError);
+        }
         if outputs != self.outputs {
             return Err(Error::VerificationError);
         }
@@ -317,24 +1243,38 @@ where
         Ok(())
     }
 
+    /// The challenge drawn from `transcript` for the first message `fm` and
+    /// the randomness `randomness` of the verifier.
+    pub fn derive_challenge<const SIGMA: usize>(
+        randomness: &[u8],
+        fm: &FirstMessageA<T, D>,
+        transcript: &mut impl Transcript<D>,
+    ) -> Result<Vec<u8>, Error> {
+        // the public output and the circuit are only known to `verify`, which
+        // binds them through the reconstructed outputs
+        let pi = PublicInput {
+            security_param: SIGMA,
+            public_output: &Vec::new(),
+            public_input: &[],
+            outputs: &fm.outputs,
+            circuit_id: &[],
+        };
+        transcript.absorb_verifier_randomness(randomness)?;
+        transcript.absorb_public_input(&pi)?;
+        transcript.absorb_commitments(&fm.all_commitments)?;
+
+        Ok(transcript.challenge_trits(num_of_repetitions_given_desired_security(SIGMA)))
+    }
+
     pub fn derive_third_output(
         public_output: &[GF2Word<T>],
         circuit: &impl Circuit<T>,
         circuit_simulation_output: (&Vec<GF2Word<T>>, &Vec<GF2Word<T>>),
-    ) -> Vec<GF2Word<T>> {
-        let party_output_len = circuit.party_output_len();
-        let (o1, o2) = circuit_simulation_output;
-
-        // TODO: introduce error here
-        assert_eq!(o1.len(), party_output_len);
-        assert_eq!(o2.len(), party_output_len);
-
-        let mut derived_output = Vec::with_capacity(party_output_len);
-
-        for i in 0..party_output_len {
-            derived_output.push(o1[i] ^ o2[i] ^ public_output[i]);
-        }
-
-        derived_output
+    ) -> Result<Vec<GF2Word<T>>, Error> {
+        Verifier::<T, TapeR, D, C>::derive_third_output(
+            public_output,
+            circuit,
+            circuit_simulation_output,
+        )
     }
 }
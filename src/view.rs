@@ -1,14 +1,33 @@
+use std::io::{self, Write};
+
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
 
-use crate::gf2_word::{GF2Word, Value};
+use crate::{
+    error::Error,
+    gf2_word::{GF2Word, Value},
+};
 
 /// A party's `View` consists of:
 /// - input: the party's initial share of the witness; and
 /// - messages: the messages sent to the party.
+///
+/// Only AND gates send messages, since linear gates are computed locally. A
+/// proof opens the first party of a repetition by its key and input share
+/// alone, and ships the view of the second, whose messages depend on the tape
+/// of the unopened party. A view thus already holds only the words a verifier
+/// cannot recompute, and serializes their values without their size.
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
 pub struct View<T: Value> {
+    #[serde(skip)]
     offset: usize,
     pub input: Vec<u8>,
+    #[serde(
+        serialize_with = "T::serialize_messages",
+        deserialize_with = "T::deserialize_messages"
+    )]
     pub messages: Vec<GF2Word<T>>,
 }
 
@@ -25,10 +44,191 @@ impl<T: Value> View<T> {
         self.messages.push(msg);
     }
 
-    /// Read the message at the current `offset`.
-    pub fn read_next(&mut self) -> GF2Word<T> {
-        let msg_i = self.messages[self.offset];
+    /// Number of messages read so far.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Read the message at the current `offset`, which the view of a malformed
+    /// proof may not hold.
+    pub fn read_next(&mut self) -> Result<GF2Word<T>, Error> {
+        let msg_i = *self
+            .messages
+            .get(self.offset)
+            .ok_or(Error::ViewOffsetOutOfBoundsError(self.offset))?;
         self.offset += 1;
-        msg_i
+        Ok(msg_i)
+    }
+
+    /// Length of the encodings of the view, see `to_bytes_be`.
+    pub fn encoded_len(&self) -> usize {
+        8 + self.input.len() + self.messages.len() * T::bytes_len()
+    }
+
+    /// The canonical encoding of the view, hashed into its commitment:
+    /// `len: u64 || input || messages`, the length in little-endian and each
+    /// message in `T::bytes_len()` big-endian bytes.
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.encoded_len());
+        // writing to a `Vec` does not fail
+        self.write_be(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Like `to_bytes_be` with each message in little-endian bytes.
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        let mut bytes = self.input_header();
+        bytes.extend(self.messages.iter().flat_map(GF2Word::to_bytes_le));
+        bytes
+    }
+
+    /// Write `to_bytes_be` to `writer` without gathering it first.
+    pub fn write_be<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.input_header())?;
+        for msg in &self.messages {
+            writer.write_all(&msg.to_bytes_be())?;
+        }
+        Ok(())
+    }
+
+    pub fn from_bytes_be(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_bytes(bytes, GF2Word::from_bytes_be)
+    }
+
+    pub fn from_bytes_le(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_bytes(bytes, GF2Word::from_bytes_le)
+    }
+
+    fn input_header(&self) -> Vec<u8> {
+        let mut bytes = (self.input.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&self.input);
+        bytes
+    }
+
+    fn from_bytes(
+        bytes: &[u8],
+        word: fn(&[u8]) -> Result<GF2Word<T>, Error>,
+    ) -> Result<Self, Error> {
+        let (len, rest) = bytes.split_at_checked(8).ok_or(Error::SerializationError)?;
+        // safe to unwrap since the split leaves 8 bytes
+        let len = u64::from_le_bytes(len.try_into().unwrap());
+        let len = usize::try_from(len).map_err(|_| Error::SerializationError)?;
+        let (input, messages) = rest
+            .split_at_checked(len)
+            .ok_or(Error::SerializationError)?;
+        if messages.len() % T::bytes_len() != 0 {
+            return Err(Error::SerializationError);
+        }
+
+        let mut view = Self::new(input.to_vec());
+        view.messages = messages
+            .chunks(T::bytes_len())
+            .map(word)
+            .collect::<Result<_, _>>()?;
+        Ok(view)
+    }
+}
+
+/// Message buffers of views which are no longer needed, handed to the views of
+/// later repetitions instead of allocating new ones.
+///
+/// A view holds one message per AND gate, so the buffers of a large circuit are
+/// large. A store which writes the views out, such as `DiskViewStore`, gives
+/// their buffers back with `ViewStore::push_reclaiming`, so that the prover
+/// allocates the buffers of a single repetition.
+pub struct ViewArena<T: Value> {
+    buffers: Vec<Vec<GF2Word<T>>>,
+}
+
+impl<T: Value> Default for ViewArena<T> {
+    fn default() -> Self {
+        Self { buffers: vec![] }
+    }
+}
+
+impl<T: Value> ViewArena<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A view of `input` with room for `capacity` messages, in a reclaimed
+    /// buffer if there is one.
+    pub fn view(&mut self, input: Vec<u8>, capacity: usize) -> View<T> {
+        let mut messages = self.buffers.pop().unwrap_or_default();
+        messages.reserve_exact(capacity);
+        View {
+            offset: 0,
+            input,
+            messages,
+        }
+    }
+
+    /// Keep the message buffer of `view`, emptied and wiped, for a later view.
+    pub fn reclaim(&mut self, mut view: View<T>) {
+        let mut messages = std::mem::take(&mut view.messages);
+        #[cfg(feature = "zeroize")]
+        messages.zeroize();
+        messages.clear();
+        self.buffers.push(messages);
+    }
+
+    /// Number of buffers held for later views.
+    pub fn len(&self) -> usize {
+        self.buffers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty()
+    }
+}
+
+/// The input share and messages of a party are wiped when the view is dropped.
+#[cfg(feature = "zeroize")]
+impl<T: Value> Drop for View<T> {
+    fn drop(&mut self) {
+        self.input.zeroize();
+        self.messages.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod view_tests {
+    use super::View;
+    use crate::gf2_word::{Bit, GF2Word};
+
+    #[test]
+    fn test_view_encoding() {
+        let mut view = View::<u32>::new(vec![9, 8]);
+        view.send_msg(0x01020304u32.into());
+        view.send_msg(5u32.into());
+
+        let be = view.to_bytes_be();
+        assert_eq!(be.len(), view.encoded_len());
+        assert_eq!(&be[..10], &[2, 0, 0, 0, 0, 0, 0, 0, 9, 8]);
+        assert_eq!(&be[10..14], &[1, 2, 3, 4]);
+        let le = view.to_bytes_le();
+        assert_eq!(&le[10..14], &[4, 3, 2, 1]);
+
+        for decoded in [
+            View::<u32>::from_bytes_be(&be).unwrap(),
+            View::<u32>::from_bytes_le(&le).unwrap(),
+        ] {
+            assert_eq!(decoded.input, view.input);
+            assert_eq!(decoded.messages, view.messages);
+        }
+
+        // truncated, or with a partial message
+        assert!(View::<u32>::from_bytes_be(&be[..5]).is_err());
+        assert!(View::<u32>::from_bytes_be(&be[..be.len() - 1]).is_err());
+        assert!(View::<u32>::from_bytes_be(&[255; 8]).is_err());
+
+        let mut view = View::<Bit>::new(vec![]);
+        view.send_msg(GF2Word::from(Bit::from(1)));
+        let bytes = view.to_bytes_be();
+        assert_eq!(
+            View::<Bit>::from_bytes_le(&bytes).unwrap().messages,
+            view.messages
+        );
+        assert!(View::<Bit>::from_bytes_le(&[0, 0, 0, 0, 0, 0, 0, 0, 2]).is_err());
     }
 }
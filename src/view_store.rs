@@ -0,0 +1,405 @@
+//! Storage for the views produced while proving.
+//!
+//! The prover commits to the views of all parties of all repetitions before
+//! the challenge is known, and opens a few of them afterwards. Both phases go
+//! through the views in order, so they can live outside of memory when they do
+//! not fit in it.
+//!
+//! A `ProverMemoryBudget` bounds the memory the views and the tapes take while
+//! proving, see `Prover::prove_with_budget`.
+
+use std::mem::size_of;
+
+use crate::{
+    circuit::Circuit,
+    error::Error,
+    gf2_word::{GF2Word, Value},
+    tape::chunk_bytes,
+    view::{View, ViewArena},
+};
+
+pub trait ViewStore<T: Value> {
+    /// Store `view` under the next index, starting from 0.
+    fn push(&mut self, view: View<T>) -> Result<(), Error>;
+
+    /// Take the view stored under `index` out of the store.
+    fn take(&mut self, index: usize) -> Result<View<T>, Error>;
+
+    /// Like `push`, giving the message buffer of `view` to `arena` if the
+    /// store does not keep it.
+    fn push_reclaiming(&mut self, view: View<T>, arena: &mut ViewArena<T>) -> Result<(), Error> {
+        let _ = arena;
+        self.push(view)
+    }
+
+    /// Whether the store keeps the views in memory until they are taken.
+    fn in_memory(&self) -> bool {
+        true
+    }
+}
+
+/// A cap on the bytes the prover holds views and tapes in.
+///
+/// Views take a message per AND gate and party. A store keeping them in memory
+/// holds those of every repetition until the challenge is known, while a store
+/// writing them out only holds those of the repetition being run. Tapes are
+/// generated a chunk at a time, see `tape::set_chunk_bytes`. Other allocations,
+/// such as those of the circuit itself, are not counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProverMemoryBudget {
+    pub max_bytes: usize,
+}
+
+impl ProverMemoryBudget {
+    pub fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(usize::MAX)
+    }
+
+    /// Bytes the prover holds views and tapes in to prove `circuit` on a
+    /// witness of `input_len` bytes over `num_of_repetitions` repetitions,
+    /// with views kept in memory or not.
+    pub fn needed<T: Value>(
+        circuit: &(impl Circuit<T> + ?Sized),
+        input_len: usize,
+        num_of_repetitions: usize,
+        views_in_memory: bool,
+    ) -> usize {
+        let num_of_mul_gates = circuit.num_of_mul_gates();
+        let word = size_of::<GF2Word<T>>();
+        let view = num_of_mul_gates
+            .saturating_mul(word)
+            .saturating_add(input_len);
+        let chunk = (chunk_bytes() / T::bytes_len())
+            .max(1)
+            .min(num_of_mul_gates);
+
+        let views = if views_in_memory {
+            num_of_repetitions.saturating_mul(3 * view)
+        } else {
+            3 * view
+        };
+        views.saturating_add(3 * chunk * word)
+    }
+
+    /// Fails with the bytes needed and the budget if `needed` exceeds it.
+    pub fn check(&self, needed: usize) -> Result<(), Error> {
+        if needed > self.max_bytes {
+            return Err(Error::MemoryBudgetError(needed, self.max_bytes));
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the views in memory.
+pub struct MemoryViewStore<T: Value> {
+    views: Vec<View<T>>,
+}
+
+impl<T: Value> MemoryViewStore<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            views: Vec::with_capacity(capacity),
+        }
+    }
+}
+
+impl<T: Value> ViewStore<T> for MemoryViewStore<T> {
+    fn push(&mut self, view: View<T>) -> Result<(), Error> {
+        self.views.push(view);
+        Ok(())
+    }
+
+    fn take(&mut self, index: usize) -> Result<View<T>, Error> {
+        self.views
+            .get_mut(index)
+            .map(std::mem::take)
+            .ok_or(Error::ViewStoreError)
+    }
+}
+
+#[cfg(feature = "disk-views")]
+pub use disk::DiskViewStore;
+
+#[cfg(feature = "disk-views")]
+mod disk {
+    use std::{
+        fs::File,
+        io::{BufWriter, Write},
+        marker::PhantomData,
+        path::Path,
+    };
+
+    use memmap2::Mmap;
+    use rand::{CryptoRng, RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+    use serde::de::DeserializeOwned;
+    use zeroize::Zeroize;
+
+    use crate::{
+        error::Error,
+        gf2_word::Value,
+        key::Key,
+        view::{View, ViewArena},
+    };
+
+    use super::ViewStore;
+
+    /// Writes the views to an anonymous temporary file, encrypted with
+    /// ChaCha20 under a key which only lives in memory.
+    ///
+    /// Views are appended while committing; the first `take` maps the file and
+    /// no view can be pushed afterwards.
+    pub struct DiskViewStore<T: Value> {
+        writer: Option<BufWriter<File>>,
+        map: Option<Mmap>,
+        records: Vec<(usize, usize)>,
+        len: usize,
+        key: Key,
+        _marker: PhantomData<T>,
+    }
+
+    impl<T: Value> DiskViewStore<T> {
+        /// Create a store in the default temporary directory.
+        pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> Result<Self, Error> {
+            Self::from_file(
+                tempfile::tempfile().map_err(|_| Error::ViewStoreError)?,
+                rng,
+            )
+        }
+
+        /// Create a store in `dir`.
+        pub fn new_in<P: AsRef<Path>, R: RngCore + CryptoRng>(
+            dir: P,
+            rng: &mut R,
+        ) -> Result<Self, Error> {
+            Self::from_file(
+                tempfile::tempfile_in(dir).map_err(|_| Error::ViewStoreError)?,
+                rng,
+            )
+        }
+
+        fn from_file<R: RngCore + CryptoRng>(file: File, rng: &mut R) -> Result<Self, Error> {
+            let mut key = Key::default();
+            rng.fill_bytes(&mut key);
+
+            Ok(Self {
+                writer: Some(BufWriter::new(file)),
+                map: None,
+                records: vec![],
+                len: 0,
+                key,
+                _marker: PhantomData,
+            })
+        }
+
+        /// Encrypts or decrypts the record of view `index`.
+        fn apply_keystream(&self, index: usize, bytes: &mut [u8]) {
+            let mut cipher = ChaCha20Rng::from_seed(self.key);
+            cipher.set_stream(index as u64);
+
+            let mut keystream = vec![0u8; bytes.len()];
+            cipher.fill_bytes(&mut keystream);
+            for (b, k) in bytes.iter_mut().zip(keystream.iter()) {
+                *b ^= k;
+            }
+        }
+
+        fn write(&mut self, view: &View<T>) -> Result<(), Error> {
+            let mut bytes = bincode::serialize(view).map_err(|_| Error::SerializationError)?;
+            self.apply_keystream(self.records.len(), &mut bytes);
+
+            let writer = self.writer.as_mut().ok_or(Error::ViewStoreError)?;
+            writer
+                .write_all(&bytes)
+                .map_err(|_| Error::ViewStoreError)?;
+
+            self.records.push((self.len, bytes.len()));
+            self.len += bytes.len();
+            Ok(())
+        }
+
+        fn map(&mut self) -> Result<&Mmap, Error> {
+            if let Some(writer) = self.writer.take() {
+                let file = writer.into_inner().map_err(|_| Error::ViewStoreError)?;
+                // safety: the file is anonymous, so no other process can modify it
+                let map = unsafe { Mmap::map(&file) }.map_err(|_| Error::ViewStoreError)?;
+                self.map = Some(map);
+            }
+            self.map.as_ref().ok_or(Error::ViewStoreError)
+        }
+    }
+
+    impl<T: Value + DeserializeOwned> ViewStore<T> for DiskViewStore<T> {
+        fn push(&mut self, view: View<T>) -> Result<(), Error> {
+            self.write(&view)
+        }
+
+        fn push_reclaiming(
+            &mut self,
+            view: View<T>,
+            arena: &mut ViewArena<T>,
+        ) -> Result<(), Error> {
+            self.write(&view)?;
+            arena.reclaim(view);
+            Ok(())
+        }
+
+        fn in_memory(&self) -> bool {
+            false
+        }
+
+        fn take(&mut self, index: usize) -> Result<View<T>, Error> {
+            let &(offset, len) = self.records.get(index).ok_or(Error::ViewStoreError)?;
+
+            let mut bytes = self.map()?[offset..offset + len].to_vec();
+            self.apply_keystream(index, &mut bytes);
+            let view = bincode::deserialize(&bytes).map_err(|_| Error::SerializationError);
+            bytes.zeroize();
+
+            view
+        }
+    }
+
+    impl<T: Value> Drop for DiskViewStore<T> {
+        fn drop(&mut self) {
+            self.key.zeroize();
+        }
+    }
+}
+
+#[cfg(test)]
+mod view_store_tests {
+    use rand::thread_rng;
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+
+    use crate::{
+        circuit::Circuit,
+        error::Error,
+        gadgets::sha256::Sha256Circuit,
+        gf2_word::GF2Word,
+        num_of_repetitions_given_desired_security,
+        prover::Prover,
+        verifier::Verifier,
+        view::{View, ViewArena},
+    };
+
+    use super::{MemoryViewStore, ProverMemoryBudget, ViewStore};
+
+    fn views() -> Vec<View<u32>> {
+        (0..4u32)
+            .map(|i| {
+                let mut view = View::new(vec![i as u8; 3]);
+                view.send_msg(GF2Word::from(i));
+                view
+            })
+            .collect()
+    }
+
+    fn roundtrip(store: &mut impl ViewStore<u32>) {
+        for view in views() {
+            store.push(view).unwrap();
+        }
+
+        for index in [2, 0, 3] {
+            let view = store.take(index).unwrap();
+            assert_eq!(view.input, vec![index as u8; 3]);
+            assert_eq!(view.messages[0].value, index as u32);
+        }
+        assert!(store.take(4).is_err());
+    }
+
+    #[test]
+    fn test_memory_store() {
+        roundtrip(&mut MemoryViewStore::with_capacity(4));
+    }
+
+    #[test]
+    fn test_arena() {
+        let mut arena = ViewArena::<u32>::new();
+        let mut view = arena.view(vec![1], 8);
+        view.send_msg(GF2Word::from(7u32));
+        let mut store = MemoryViewStore::with_capacity(1);
+
+        // a store keeping the view does not give its buffer back
+        store.push_reclaiming(view.clone(), &mut arena).unwrap();
+        assert!(arena.is_empty());
+
+        arena.reclaim(view);
+        assert_eq!(arena.len(), 1);
+        let view = arena.view(vec![2], 8);
+        assert!(arena.is_empty());
+        assert!(view.messages.is_empty());
+        assert!(view.messages.capacity() >= 8);
+    }
+
+    #[test]
+    fn test_memory_budget() {
+        let input = b"budget";
+        let circuit = Sha256Circuit::new(input.len());
+        let output = circuit.compute(input);
+
+        let needed = ProverMemoryBudget::needed(
+            &circuit,
+            input.len(),
+            num_of_repetitions_given_desired_security(40),
+            true,
+        );
+        let streamed = ProverMemoryBudget::needed(
+            &circuit,
+            input.len(),
+            num_of_repetitions_given_desired_security(40),
+            false,
+        );
+        assert!(streamed < needed);
+
+        let prove = |budget| {
+            Prover::<u32, ChaCha20Rng, Keccak256>::prove_with_budget::<_, _, 40>(
+                &mut thread_rng(),
+                input,
+                &circuit,
+                &output,
+                &[],
+                &mut MemoryViewStore::with_capacity(0),
+                &ProverMemoryBudget::new(budget),
+            )
+        };
+        assert!(matches!(
+            prove(needed - 1),
+            Err(Error::MemoryBudgetError(n, b)) if n == needed && b == needed - 1
+        ));
+        let proof = prove(needed).unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<40>(&proof, &circuit, &output).unwrap();
+        assert!(ProverMemoryBudget::unlimited().check(needed).is_ok());
+    }
+
+    #[cfg(feature = "disk-views")]
+    #[test]
+    fn test_disk_store() {
+        use super::DiskViewStore;
+        use crate::gadgets::add_mod::AdderCircuit;
+
+        let mut rng = thread_rng();
+        roundtrip(&mut DiskViewStore::new(&mut rng).unwrap());
+
+        let circuit = AdderCircuit::<u32>::new();
+        let input = [5u32.to_le_bytes(), 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&input);
+
+        let mut store = DiskViewStore::new(&mut rng).unwrap();
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove_with_store::<_, _, 40>(
+            &mut rng,
+            &input,
+            &circuit,
+            &output,
+            &[],
+            &mut store,
+        )
+        .unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<40>(&proof, &circuit, &output).unwrap();
+    }
+}
@@ -0,0 +1,25 @@
+//! `wasm-bindgen` bindings, behind the `wasm` feature.
+//!
+//! Randomness comes from `getrandom`, which uses `crypto.getRandomValues` in
+//! the browser.
+
+use rand::rngs::OsRng;
+use wasm_bindgen::prelude::*;
+
+use crate::serialized;
+
+fn to_js_error(err: crate::error::Error) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+/// See `serialized::prove_bytes`.
+#[wasm_bindgen(js_name = proveBytes)]
+pub fn prove_bytes(statement_blob: &[u8], witness: &[u8]) -> Result<Vec<u8>, JsError> {
+    serialized::prove_bytes(&mut OsRng, statement_blob, witness).map_err(to_js_error)
+}
+
+/// See `serialized::verify_bytes`.
+#[wasm_bindgen(js_name = verifyBytes)]
+pub fn verify_bytes(statement_blob: &[u8], proof_bytes: &[u8]) -> Result<(), JsError> {
+    serialized::verify_bytes(statement_blob, proof_bytes).map_err(to_js_error)
+}
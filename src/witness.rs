@@ -0,0 +1,313 @@
+//! Secret witness handling.
+//!
+//! The plaintext witness is only read while it is split into shares. `Witness`
+//! keeps it out of logs and wipes it from memory when dropped, and the prover
+//! wipes the shares it does not hand out in the proof.
+//!
+//! Circuits may also declare the values some witness bytes are limited to, as
+//! `WitnessRange`s, so that malformed witnesses are rejected before proving
+//! rather than yielding proofs of a false statement which fail to verify.
+//!
+//! A `WitnessMask` selects witness bytes which are made public, such as a tag
+//! prefixed to a secret preimage.
+
+use std::{
+    fmt::{self, Debug},
+    ops::{Range, RangeInclusive},
+};
+
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::error::Error;
+
+/// A witness which is zeroized on drop and redacted when formatted.
+///
+/// The wrapper does not implement `Clone`, so the plaintext is never copied
+/// implicitly.
+pub struct Witness(Vec<u8>);
+
+impl Witness {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    /// Access to the plaintext, for sharing it among the parties.
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Zeroize for Witness {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for Witness {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for Witness {}
+
+impl From<Vec<u8>> for Witness {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl Debug for Witness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Witness([REDACTED; {} bytes])", self.0.len())
+    }
+}
+
+/// The witness bytes at `bytes` all lie in `allowed`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WitnessRange {
+    pub bytes: Range<usize>,
+    pub allowed: RangeInclusive<u8>,
+}
+
+impl WitnessRange {
+    pub fn new(bytes: Range<usize>, allowed: RangeInclusive<u8>) -> Self {
+        Self { bytes, allowed }
+    }
+
+    pub fn ascii_digits(bytes: Range<usize>) -> Self {
+        Self::new(bytes, b'0'..=b'9')
+    }
+
+    pub fn ascii_printable(bytes: Range<usize>) -> Self {
+        Self::new(bytes, b' '..=b'~')
+    }
+
+    /// Fails with the index of the first byte of `witness` which is out of
+    /// range, or with the end of the range if `witness` is too short.
+    pub fn check(&self, witness: &[u8]) -> Result<(), Error> {
+        let bytes = witness
+            .get(self.bytes.clone())
+            .ok_or(Error::WitnessRangeError(self.bytes.end))?;
+
+        match bytes.iter().position(|b| !self.allowed.contains(b)) {
+            Some(i) => Err(Error::WitnessRangeError(self.bytes.start + i)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Witness bytes which are disclosed, in the order of `ranges`, while the
+/// others stay secret, see `circuit::disclose`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WitnessMask {
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl WitnessMask {
+    pub fn new(ranges: impl IntoIterator<Item = Range<usize>>) -> Self {
+        Self {
+            ranges: ranges.into_iter().collect(),
+        }
+    }
+
+    /// Discloses the witness bytes at `bytes`.
+    pub fn range(bytes: Range<usize>) -> Self {
+        Self::new(std::iter::once(bytes))
+    }
+
+    /// Discloses the first `len` bytes of the witness.
+    pub fn prefix(len: usize) -> Self {
+        Self::range(0..len)
+    }
+
+    /// Discloses the last `len` bytes of a witness of `witness_len` bytes.
+    pub fn suffix(witness_len: usize, len: usize) -> Self {
+        Self::range(witness_len.saturating_sub(len)..witness_len)
+    }
+
+    /// Number of disclosed bytes.
+    pub fn len(&self) -> usize {
+        self.ranges.iter().map(|range| range.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The disclosed bytes of `witness`. Fails with the end of the first range
+    /// which does not fit in `witness`.
+    pub fn disclosed(&self, witness: &[u8]) -> Result<Vec<u8>, Error> {
+        self.ranges.iter().try_fold(vec![], |mut bytes, range| {
+            let disclosed = witness
+                .get(range.clone())
+                .ok_or(Error::WitnessRangeError(range.end))?;
+            bytes.extend_from_slice(disclosed);
+            Ok(bytes)
+        })
+    }
+
+    /// The start and end of every range, as little-endian `u64`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.ranges
+            .iter()
+            .flat_map(|range| [range.start as u64, range.end as u64])
+            .flat_map(u64::to_le_bytes)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod witness_tests {
+    use rand::{rngs::ThreadRng, thread_rng};
+    use rand_chacha::ChaCha20Rng;
+    use sha3::Keccak256;
+    use zeroize::Zeroize;
+
+    use crate::{
+        circuit::{Circuit, Output, TwoThreeDecOutput},
+        error::Error,
+        gadgets::add_mod::AdderCircuit,
+        gf2_word::GF2Word,
+        party::Party,
+        prover::Prover,
+        verifier::Verifier,
+    };
+
+    use super::{Witness, WitnessRange};
+
+    /// Adds a number written as four ASCII digits to a word.
+    struct DigitsAdderCircuit(AdderCircuit<u32>);
+
+    impl Circuit<u32> for DigitsAdderCircuit {
+        fn compute(&self, input: &[u8]) -> Vec<GF2Word<u32>> {
+            self.0.compute(input)
+        }
+
+        fn compute_23_decomposition(
+            &self,
+            p1: &mut Party<u32>,
+            p2: &mut Party<u32>,
+            p3: &mut Party<u32>,
+        ) -> TwoThreeDecOutput<u32> {
+            self.0.compute_23_decomposition(p1, p2, p3)
+        }
+
+        fn simulate_two_parties(
+            &self,
+            p: &mut Party<u32>,
+            p_next: &mut Party<u32>,
+        ) -> Result<(Output<u32>, Output<u32>), Error> {
+            self.0.simulate_two_parties(p, p_next)
+        }
+
+        fn party_input_len(&self) -> usize {
+            self.0.party_input_len()
+        }
+
+        fn party_output_len(&self) -> usize {
+            self.0.party_output_len()
+        }
+
+        fn num_of_mul_gates(&self) -> usize {
+            self.0.num_of_mul_gates()
+        }
+
+        fn witness_ranges(&self) -> Vec<WitnessRange> {
+            vec![WitnessRange::ascii_digits(0..4)]
+        }
+    }
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let witness = Witness::new(b"super secret".to_vec());
+        let formatted = format!("{:?}", witness);
+
+        assert_eq!(formatted, "Witness([REDACTED; 12 bytes])");
+        assert!(!formatted.contains("secret"));
+    }
+
+    #[test]
+    fn test_zeroize() {
+        let mut witness = Witness::new(vec![0xff; 8]);
+        witness.zeroize();
+        // the buffer is wiped and then truncated
+        assert!(witness.is_empty());
+    }
+
+    #[test]
+    fn test_prove_witness() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+
+        let circuit = AdderCircuit::<u32>::new();
+        let witness = Witness::new([5u32.to_le_bytes(), 6u32.to_le_bytes()].concat());
+        let output = circuit.compute(witness.expose_secret());
+
+        let proof = Prover::<u32, ChaCha20Rng, Keccak256>::prove_witness::<ThreadRng, SIGMA>(
+            &mut rng, &witness, &circuit, &output,
+        )
+        .unwrap();
+
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_witness_range() {
+        let range = WitnessRange::new(2..4, b'a'..=b'f');
+        range.check(b"..af..").unwrap();
+        assert!(matches!(
+            range.check(b"..ag"),
+            Err(Error::WitnessRangeError(3))
+        ));
+        assert!(matches!(
+            range.check(b"..a"),
+            Err(Error::WitnessRangeError(4))
+        ));
+    }
+
+    #[test]
+    fn test_prove_checked() {
+        let mut rng = thread_rng();
+        const SIGMA: usize = 40;
+        type P = Prover<u32, ChaCha20Rng, Keccak256>;
+
+        let circuit = DigitsAdderCircuit(AdderCircuit::new());
+
+        let witness = [*b"2024", 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&witness);
+        let proof =
+            P::prove_checked::<ThreadRng, SIGMA>(&mut rng, &witness, &circuit, &output).unwrap();
+        Verifier::<u32, ChaCha20Rng, Keccak256>::verify::<SIGMA>(&proof, &circuit, &output)
+            .unwrap();
+
+        let witness = [*b"20x4", 6u32.to_le_bytes()].concat();
+        let output = circuit.compute(&witness);
+        assert!(matches!(
+            P::prove_checked::<ThreadRng, SIGMA>(&mut rng, &witness, &circuit, &output),
+            Err(Error::WitnessRangeError(2))
+        ));
+        // the unchecked prover does not look at the ranges
+        P::prove::<ThreadRng, SIGMA>(&mut rng, &witness, &circuit, &output).unwrap();
+
+        // a witness for another output is caught before proving
+        let witness = [*b"2024", 6u32.to_le_bytes()].concat();
+        assert!(matches!(
+            P::prove_checked::<ThreadRng, SIGMA>(&mut rng, &witness, &circuit, &output),
+            Err(Error::WitnessOutputError(0))
+        ));
+        assert!(matches!(
+            P::check_witness(&witness, &circuit, &[]),
+            Err(Error::PublicOutputLenError(1, 0))
+        ));
+    }
+}